@@ -0,0 +1,304 @@
+// ============================================================================
+// Archive Module - Reading zip, tar and 7z containers
+// ============================================================================
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::fileops;
+
+// Container formats we can read
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZip)
+        } else {
+            None
+        }
+    }
+}
+
+// A single entry inside an archive
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64,
+    /// Stored size, when the format records it per entry (tar does not compress)
+    pub compressed_size: Option<u64>,
+    pub is_dir: bool,
+}
+
+/// List the entries of an archive without extracting anything
+pub fn list_entries(path: &Path) -> Result<(ArchiveFormat, Vec<ArchiveEntry>), String> {
+    let format = ArchiveFormat::from_path(path)
+        .ok_or_else(|| format!("Unsupported archive format: {}", path.display()))?;
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+
+    let entries = match format {
+        ArchiveFormat::Zip => zip_entries(file)?,
+        ArchiveFormat::Tar => tar_entries(BufReader::new(file))?,
+        ArchiveFormat::TarGz => tar_entries(flate2::read::GzDecoder::new(BufReader::new(file)))?,
+        ArchiveFormat::SevenZip => seven_zip_entries(path)?,
+    };
+    Ok((format, entries))
+}
+
+fn zip_entries(file: File) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+    let mut entries = Vec::with_capacity(archive.len());
+
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        entries.push(ArchiveEntry {
+            name: entry.name().to_string(),
+            size: entry.size(),
+            compressed_size: Some(entry.compressed_size()),
+            is_dir: entry.is_dir(),
+        });
+    }
+    Ok(entries)
+}
+
+fn tar_entries<R: Read>(reader: R) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+
+    for entry in archive.entries().map_err(|e| format!("Failed to read archive: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let name = entry
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .map_err(|e| format!("Invalid entry name: {}", e))?;
+        entries.push(ArchiveEntry {
+            name,
+            size: entry.size(),
+            compressed_size: None,
+            is_dir: entry.header().entry_type().is_dir(),
+        });
+    }
+    Ok(entries)
+}
+
+fn seven_zip_entries(path: &Path) -> Result<Vec<ArchiveEntry>, String> {
+    let reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    Ok(reader
+        .archive()
+        .files
+        .iter()
+        .map(|entry| ArchiveEntry {
+            name: entry.name.clone(),
+            size: entry.size,
+            compressed_size: Some(entry.compressed_size).filter(|size| *size > 0),
+            is_dir: entry.is_directory,
+        })
+        .collect())
+}
+
+/// Call `visit` with each entry of an archive and a reader over its
+/// contents, stopping at the first error
+pub fn read_entries(
+    path: &Path,
+    mut visit: impl FnMut(&ArchiveEntry, &mut dyn Read) -> Result<(), String>,
+) -> Result<(), String> {
+    let format = ArchiveFormat::from_path(path)
+        .ok_or_else(|| format!("Unsupported archive format: {}", path.display()))?;
+    let file = File::open(fileops::long_path(path)).map_err(|e| format!("Failed to open archive: {}", e))?;
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+            for index in 0..archive.len() {
+                let mut entry = archive
+                    .by_index(index)
+                    .map_err(|e| format!("Failed to read archive entry: {}", e))?;
+                let info = ArchiveEntry {
+                    name: entry.name().to_string(),
+                    size: entry.size(),
+                    compressed_size: Some(entry.compressed_size()),
+                    is_dir: entry.is_dir(),
+                };
+                visit(&info, &mut entry)?;
+            }
+            Ok(())
+        }
+        ArchiveFormat::Tar => visit_tar(BufReader::new(file), &mut visit),
+        ArchiveFormat::TarGz => visit_tar(flate2::read::GzDecoder::new(BufReader::new(file)), &mut visit),
+        ArchiveFormat::SevenZip => {
+            let mut reader = sevenz_rust::SevenZReader::open(fileops::long_path(path), sevenz_rust::Password::empty())
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+            // The 7z callback can't carry our error type, so stash it and stop
+            let mut failure = None;
+            reader
+                .for_each_entries(|entry, contents| {
+                    let info = ArchiveEntry {
+                        name: entry.name.clone(),
+                        size: entry.size,
+                        compressed_size: Some(entry.compressed_size).filter(|size| *size > 0),
+                        is_dir: entry.is_directory,
+                    };
+                    match visit(&info, contents) {
+                        Ok(()) => Ok(true),
+                        Err(e) => {
+                            failure = Some(e);
+                            Ok(false)
+                        }
+                    }
+                })
+                .map_err(|e| format!("Failed to read archive: {}", e))?;
+            failure.map_or(Ok(()), Err)
+        }
+    }
+}
+
+// Tar entries other than files and folders (links, devices) are skipped
+fn visit_tar<R: Read>(
+    reader: R,
+    visit: &mut impl FnMut(&ArchiveEntry, &mut dyn Read) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().map_err(|e| format!("Failed to read archive: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("Failed to read archive entry: {}", e))?;
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            continue;
+        }
+        let info = ArchiveEntry {
+            name: entry
+                .path()
+                .map(|p| p.to_string_lossy().to_string())
+                .map_err(|e| format!("Invalid entry name: {}", e))?,
+            size: entry.size(),
+            compressed_size: None,
+            is_dir: entry_type.is_dir(),
+        };
+        visit(&info, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// Relative path an entry extracts to, or None for names that would escape
+/// the destination folder (absolute paths, "..")
+pub fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part if part.contains(':') => return None,
+            part => path.push(part),
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+// A file or folder to store in a new archive under `name`
+pub struct ArchiveInput {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Expand a selection of files and folders into archive inputs; folders keep
+/// their own name as the top-level entry
+pub fn collect_inputs(paths: &[PathBuf]) -> Result<Vec<ArchiveInput>, String> {
+    let mut inputs = Vec::new();
+    for path in paths {
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in WalkDir::new(fileops::long_path(path)) {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let entry_path = fileops::display_path(entry.path());
+            let name = entry_path
+                .strip_prefix(base)
+                .unwrap_or(&entry_path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            inputs.push(ArchiveInput {
+                is_dir: entry.file_type().is_dir(),
+                path: entry_path,
+                name,
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+/// Write `inputs` into a new zip at `destination`. `progress` is called after
+/// each entry with (entries done, bytes written so far, current path).
+pub fn create_zip(
+    inputs: &[ArchiveInput],
+    destination: &Path,
+    mut progress: impl FnMut(usize, u64, &Path),
+) -> Result<u64, String> {
+    let file = File::create(fileops::long_path(destination)).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+    let mut bytes = 0u64;
+
+    for (index, input) in inputs.iter().enumerate() {
+        if input.is_dir {
+            writer
+                .add_directory(format!("{}/", input.name.trim_end_matches('/')), options)
+                .map_err(|e| format!("Failed to write archive: {}", e))?;
+        } else {
+            writer
+                .start_file(input.name.as_str(), options)
+                .map_err(|e| format!("Failed to write archive: {}", e))?;
+            let mut source = File::open(fileops::long_path(&input.path))
+                .map_err(|e| format!("Failed to open {}: {}", input.path.display(), e))?;
+            bytes += io::copy(&mut source, &mut writer).map_err(|e| format!("Failed to write archive: {}", e))?;
+        }
+        progress(index + 1, bytes, &input.path);
+    }
+
+    writer.finish().map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(bytes)
+}
+
+/// Re-read every file entry of a zip (checking CRCs) and compare sizes with
+/// the inputs it was built from
+pub fn verify_zip(archive_path: &Path, inputs: &[ArchiveInput]) -> Result<(), String> {
+    let file = File::open(fileops::long_path(archive_path)).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    for input in inputs.iter().filter(|input| !input.is_dir) {
+        let mut entry = archive
+            .by_name(&input.name)
+            .map_err(|e| format!("Archive is missing {}: {}", input.name, e))?;
+        // Reading to the end makes the zip reader check the CRC
+        let read = io::copy(&mut entry, &mut io::sink())
+            .map_err(|e| format!("Archive entry {} is corrupt: {}", input.name, e))?;
+        let expected = std::fs::metadata(fileops::long_path(&input.path))
+            .map_err(|e| format!("Failed to read metadata: {}", e))?
+            .len();
+        if read != expected {
+            return Err(format!("Archive entry {} has the wrong size", input.name));
+        }
+    }
+    Ok(())
+}
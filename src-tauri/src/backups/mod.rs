@@ -0,0 +1,197 @@
+// ============================================================================
+// Backups Module - Recognizing folders that are intentional backups
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fileops;
+use crate::projects;
+
+/// Folder names backup tools give the root of their backup sets
+const BACKUP_FOLDER_NAMES: &[&str] = &["Backups.backupdb", "WindowsImageBackup", "MobileSync"];
+
+/// Entries that Time Machine leaves in its backup destinations
+const TIME_MACHINE_MARKERS: &[&str] = &[
+    ".com.apple.timemachine.supported",
+    ".com.apple.timemachine.donotpresent",
+    "com.apple.TimeMachine.MachineID.plist",
+];
+
+/// Entries backup tools write next to the data they manage
+const TOOL_MARKERS: &[&str] = &[
+    // Windows Backup
+    "MediaID.bin",
+    // iPhone and iPad backups made by Finder or iTunes
+    "Manifest.db",
+    "Manifest.mbdb",
+    ".duplicacy",
+    "backup.manifest",
+    "rsnapshot.conf",
+];
+
+/// Extensions of backup images and archives from imaging tools
+const TOOL_MARKER_EXTENSIONS: &[&str] = &["tib", "tibx", "mrimg", "mrbak", "vbk", "vib", "bkf"];
+
+/// Subfolders a folder of dated snapshots needs before it counts as one
+const MIN_SNAPSHOTS: usize = 2;
+
+// "Backup", "backups", "bak", and the word in a few other languages
+static BACKUP_WORD: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:^|[^a-z])(backups?|bak|bkp|sicherung|sauvegarde|copia de seguridad|respaldo)(?:$|[^a-z])")
+        .unwrap()
+});
+
+// A date in a folder name: 2024-03-01, 2024_03_01, 20240301
+static NAME_DATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|[^0-9])(?:19|20)\d{2}[-_.]?(?:0[1-9]|1[0-2])[-_.]?(?:0[1-9]|[12]\d|3[01])")
+        .unwrap()
+});
+
+// Snapshot names: a date and time, as Time Machine and rsync-based scripts
+// use, or rsnapshot's rotating daily.0, weekly.1, ...
+static SNAPSHOT_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^((?:19|20)\d{2}-?\d{2}-?\d{2}[-_T ]?\d{2}[-:]?\d{2}([-:]?\d{2})?|(hourly|daily|weekly|monthly|yearly)\.\d+)$")
+        .unwrap()
+});
+
+// A bare date, which only names a snapshot next to a "latest" link: photo
+// imports are filed into folders named like this too
+static DATE_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:19|20)\d{2}-?\d{2}-?\d{2}$").unwrap());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    /// Time Machine destinations and folders of dated full copies
+    Snapshots,
+    /// A single copy named as a backup, with the date it was taken
+    DatedCopy,
+    /// A repository, image or manifest written by a backup tool
+    ToolData,
+}
+
+impl BackupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupKind::Snapshots => "snapshots",
+            BackupKind::DatedCopy => "dated_copy",
+            BackupKind::ToolData => "tool_data",
+        }
+    }
+}
+
+// A folder recognized as a backup, and what gave it away
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSet {
+    pub path: String,
+    pub kind: BackupKind,
+    pub marker: String,
+}
+
+/// Whether the folder at `path` is itself a backup set
+pub fn backup_set(path: &Path) -> Option<BackupSet> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let folder = fileops::long_path(path);
+    if !folder.is_dir() {
+        return None;
+    }
+    let found = |kind: BackupKind, marker: String| {
+        Some(BackupSet {
+            path: path.to_string_lossy().to_string(),
+            kind,
+            marker,
+        })
+    };
+
+    if BACKUP_FOLDER_NAMES.contains(&name.as_str()) {
+        let kind = if name == "Backups.backupdb" {
+            BackupKind::Snapshots
+        } else {
+            BackupKind::ToolData
+        };
+        return found(kind, name);
+    }
+    if name.ends_with(".backupbundle") {
+        return found(BackupKind::Snapshots, name);
+    }
+    if BACKUP_WORD.is_match(&name) && NAME_DATE.is_match(&name) {
+        return found(BackupKind::DatedCopy, name);
+    }
+    if let Some(marker) = projects::marker_in(path, TIME_MACHINE_MARKERS, &[]) {
+        return found(BackupKind::Snapshots, marker);
+    }
+    if let Some(marker) = projects::marker_in(path, TOOL_MARKERS, TOOL_MARKER_EXTENSIONS) {
+        return found(BackupKind::ToolData, marker);
+    }
+    if let Some(marker) = repository_marker(&folder) {
+        return found(BackupKind::ToolData, marker);
+    }
+    snapshot_marker(&folder).and_then(|marker| found(BackupKind::Snapshots, marker))
+}
+
+// restic and Borg repositories: a config file next to the folders they
+// keep their packs in
+fn repository_marker(folder: &Path) -> Option<String> {
+    if !folder.join("config").is_file() {
+        return None;
+    }
+    if ["keys", "snapshots", "data"]
+        .iter()
+        .all(|name| folder.join(name).is_dir())
+    {
+        return Some("restic repository".to_string());
+    }
+    let has_hints = fs::read_dir(folder)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("hints."));
+    (has_hints && folder.join("data").is_dir()).then(|| "borg repository".to_string())
+}
+
+// A folder whose subfolders are mostly snapshot names, like the dated
+// copies rsync scripts make; the oldest snapshot is given as the marker
+fn snapshot_marker(folder: &Path) -> Option<String> {
+    let subfolders: Vec<String> = fs::read_dir(folder)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    let latest_link =
+        fs::symlink_metadata(folder.join("latest")).is_ok_and(|m| m.file_type().is_symlink());
+    let mut snapshots: Vec<&String> = subfolders
+        .iter()
+        .filter(|name| SNAPSHOT_NAME.is_match(name) || (latest_link && DATE_NAME.is_match(name)))
+        .collect();
+    if snapshots.len() < MIN_SNAPSHOTS || snapshots.len() * 2 < subfolders.len() {
+        return None;
+    }
+    snapshots.sort();
+    snapshots.first().map(|name| name.to_string())
+}
+
+/// Finds the backup set a path lies in, remembering what each folder is
+#[derive(Default)]
+pub struct BackupDetector {
+    sets: HashMap<PathBuf, Option<BackupSet>>,
+}
+
+impl BackupDetector {
+    /// The innermost backup set containing `path`, or `path` itself when it
+    /// is one
+    pub fn backup_of(&mut self, path: &Path) -> Option<BackupSet> {
+        path.ancestors().find_map(|folder| {
+            if let Some(set) = self.sets.get(folder) {
+                return set.clone();
+            }
+            let set = backup_set(folder);
+            self.sets.insert(folder.to_path_buf(), set.clone());
+            set
+        })
+    }
+}
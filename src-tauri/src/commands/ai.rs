@@ -8,11 +8,17 @@ use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager};
+use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
 
+use crate::fileops;
+use crate::shutdown::{self, OperationKind};
+use crate::storage;
+use crate::throttle::{self, IoClass};
+
 // Model configuration
 const MODEL_URL: &str = "https://huggingface.co/bartowski/SmolLM2-135M-Instruct-GGUF/resolve/main/SmolLM2-135M-Instruct-Q4_K_M.gguf";
-const MODEL_FILENAME: &str = "SmolLM2-135M-Instruct-Q4_K_M.gguf";
+pub const MODEL_FILENAME: &str = "SmolLM2-135M-Instruct-Q4_K_M.gguf";
 
 // AI Status enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -33,6 +39,91 @@ pub struct DownloadProgress {
     pub progress: f32,
 }
 
+// Generation settings, persisted in preferences
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelConfig {
+    pub max_tokens: u32,
+    pub context_size: u32,
+    pub temperature: f32,
+    pub top_p: f32,
+    /// Wall-clock limit per generation; partial output is returned when hit
+    pub max_generation_ms: u64,
+    pub seed: u32,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            context_size: 512,
+            temperature: 0.7,
+            top_p: 0.9,
+            max_generation_ms: 30_000,
+            seed: 42,
+        }
+    }
+}
+
+const MODEL_CONFIG_KEY: &str = "model_config";
+
+// Per-request generation overrides
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GenerationOptions {
+    /// Greedy sampling, fixed seed, single-threaded decode and no time limit,
+    /// so the same prompt always yields the same output
+    pub deterministic: bool,
+    pub seed: Option<u32>,
+    pub max_duration_ms: Option<u64>,
+    /// Feature the run is counted under in get_ai_usage
+    pub feature: AiFeature,
+}
+
+// What a model run was for, as counted in get_ai_usage
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AiFeature {
+    #[default]
+    Chat,
+    Classification,
+    Embeddings,
+    SemanticSearch,
+    PlanSummary,
+}
+
+impl AiFeature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AiFeature::Chat => "chat",
+            AiFeature::Classification => "classification",
+            AiFeature::Embeddings => "embeddings",
+            AiFeature::SemanticSearch => "semantic_search",
+            AiFeature::PlanSummary => "plan_summary",
+        }
+    }
+}
+
+// Why generation stopped
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    EndOfText,
+    MaxTokens,
+    TimeLimit,
+}
+
+// Generated text and how it ended
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationResult {
+    pub text: String,
+    /// Output was cut short by the token or time limit
+    pub truncated: bool,
+    pub stop_reason: StopReason,
+    pub tokens_generated: u32,
+    pub elapsed_ms: u64,
+}
+
 // Chat message for inference
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -62,7 +153,7 @@ static AI_STATE: Lazy<Arc<RwLock<AiState>>> = Lazy::new(|| {
 });
 
 /// Get model directory path
-fn get_model_dir(app: &AppHandle) -> Result<PathBuf, String> {
+pub fn get_model_dir(app: &AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app.path().app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
     let model_dir = app_data_dir.join("models");
@@ -112,6 +203,7 @@ pub async fn check_model_status(app: AppHandle) -> Result<AiStatus, String> {
 #[tauri::command]
 pub async fn download_model(app: AppHandle) -> Result<(), String> {
     let model_path = get_model_path(&app)?;
+    let operation = shutdown::begin(OperationKind::Download, "Downloading the AI model");
 
     // Update status to downloading
     {
@@ -128,8 +220,16 @@ pub async fn download_model(app: AppHandle) -> Result<(), String> {
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
+    // Pick up a download that was paused at exit
+    let temp_path = model_path.with_extension("gguf.downloading");
+    let partial = tokio::fs::metadata(&temp_path).await.map(|m| m.len()).unwrap_or(0);
+
     // Start download
-    let response = client.get(MODEL_URL)
+    let mut request = client.get(MODEL_URL);
+    if partial > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", partial));
+    }
+    let response = request
         .send()
         .await
         .map_err(|e| format!("Failed to start download: {}", e))?;
@@ -142,21 +242,31 @@ pub async fn download_model(app: AppHandle) -> Result<(), String> {
         return Err(error_msg);
     }
 
-    let total_size = response.content_length().unwrap_or(0);
+    // Servers that ignore the range send the whole file again
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut downloaded: u64 = if resumed { partial } else { 0 };
+    let total_size = response.content_length().map(|len| len + downloaded).unwrap_or(0);
 
     // Create temp file for download
-    let temp_path = model_path.with_extension("gguf.downloading");
-    let mut file = tokio::fs::File::create(&temp_path)
-        .await
-        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new().append(true).open(&temp_path).await
+    } else {
+        tokio::fs::File::create(&temp_path).await
+    }
+    .map_err(|e| format!("Failed to create temp file: {}", e))?;
 
-    let mut downloaded: u64 = 0;
     let mut stream = response.bytes_stream();
 
     use futures_util::StreamExt;
 
     while let Some(chunk) = stream.next().await {
+        // Keep what we have; the next download resumes from it
+        if operation.should_stop() {
+            file.flush().await.map_err(|e| format!("Failed to flush file: {}", e))?;
+            return Err("Download paused for exit".to_string());
+        }
         let chunk = chunk.map_err(|e| format!("Download error: {}", e))?;
+        throttle::consume_async(IoClass::Network, chunk.len()).await;
 
         file.write_all(&chunk)
             .await
@@ -208,6 +318,205 @@ pub async fn download_model(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+const MODEL_ASSEMBLY_KEY: &str = "model_assembly";
+
+/// Event emitted after each part appended by assemble_model
+pub const MODEL_ASSEMBLY_EVENT: &str = "model-assembly-progress";
+
+// A multi-part model being joined, saved after every part so an interrupted
+// assembly resumes instead of starting over
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ModelAssembly {
+    target: PathBuf,
+    parts: Vec<PathBuf>,
+    /// Parts appended so far, in order
+    appended: usize,
+    /// Length of the output after the last appended part; anything past it
+    /// is a part that was cut off and gets written again
+    bytes: u64,
+}
+
+// Assembly progress event
+#[derive(Debug, Clone, Serialize)]
+pub struct AssemblyProgress {
+    pub appended: usize,
+    pub total: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssemblyResult {
+    pub path: String,
+    pub size: u64,
+    /// Parts found already appended by an earlier, interrupted run
+    pub resumed_parts: usize,
+    /// Parts deleted once the model was verified
+    pub deleted_parts: Vec<String>,
+}
+
+/// Join a model shipped in parts ("model.gguf.part1of3", ...) into the
+/// models folder, in the order given. Progress is saved after each part, so
+/// calling again with the same parts resumes an interrupted run. The result
+/// must start with the GGUF magic and, when `sha256` is given, match it;
+/// with `delete_parts`, the parts are removed only after that check passes.
+#[tauri::command]
+pub async fn assemble_model(
+    app: AppHandle,
+    parts: Vec<String>,
+    sha256: Option<String>,
+    delete_parts: Option<bool>,
+) -> Result<AssemblyResult, String> {
+    let parts: Vec<PathBuf> = parts.into_iter().map(PathBuf::from).collect();
+    let first_name = parts
+        .first()
+        .and_then(|part| part.file_name())
+        .ok_or("No model parts given")?
+        .to_string_lossy()
+        .to_string();
+    // "model.gguf.part1of3" and "model.gguf.001" both become "model.gguf"
+    let name = match first_name.find(".gguf") {
+        Some(end) => first_name[..end + ".gguf".len()].to_string(),
+        None => format!("{}.gguf", first_name.split('.').next().unwrap_or(&first_name)),
+    };
+    let target = get_model_dir(&app)?.join(name);
+    let delete_parts = delete_parts.unwrap_or(false);
+    let app_handle = app.clone();
+
+    let result = tokio::task::spawn_blocking(move || {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let operation = shutdown::begin(OperationKind::Download, "Assembling the AI model");
+        let saved = storage::get_preference(MODEL_ASSEMBLY_KEY)?
+            .and_then(|json| serde_json::from_str::<ModelAssembly>(&json).ok())
+            .filter(|saved| saved.target == target && saved.parts == parts);
+        let resumed_parts = saved.as_ref().map_or(0, |saved| saved.appended);
+        let mut assembly = saved.unwrap_or_else(|| ModelAssembly {
+            target: target.clone(),
+            parts: parts.clone(),
+            appended: 0,
+            bytes: 0,
+        });
+
+        let temp_path = target.with_extension("gguf.assembling");
+        let mut output = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&temp_path)
+            .map_err(|e| format!("Failed to open assembly file: {}", e))?;
+        output
+            .set_len(assembly.bytes)
+            .and_then(|_| output.seek(SeekFrom::End(0)))
+            .map_err(|e| format!("Failed to resume assembly: {}", e))?;
+
+        for part in &parts[assembly.appended..] {
+            if operation.should_stop() {
+                return Err("Assembly paused for exit".to_string());
+            }
+            let input = std::fs::File::open(part)
+                .map_err(|e| format!("Failed to open {}: {}", part.display(), e))?;
+            let mut reader = throttle::ThrottledReader::new(input, IoClass::Copying);
+            std::io::copy(&mut reader, &mut output)
+                .and_then(|_| output.flush())
+                .and_then(|_| output.sync_data())
+                .map_err(|e| format!("Failed to append {}: {}", part.display(), e))?;
+
+            assembly.appended += 1;
+            assembly.bytes = output
+                .stream_position()
+                .map_err(|e| format!("Failed to read assembly length: {}", e))?;
+            let json = serde_json::to_string(&assembly).map_err(|e| e.to_string())?;
+            storage::set_preference(MODEL_ASSEMBLY_KEY, &json)?;
+            let _ = app_handle.emit(
+                MODEL_ASSEMBLY_EVENT,
+                AssemblyProgress {
+                    appended: assembly.appended,
+                    total: parts.len(),
+                    bytes: assembly.bytes,
+                },
+            );
+        }
+        drop(output);
+
+        // A wrong part order or a damaged part shows up here; the parts are
+        // left alone so the assembly can be redone from them
+        let verified = verify_model(&temp_path, &parts, sha256.as_deref());
+        if let Err(e) = verified {
+            let _ = std::fs::remove_file(&temp_path);
+            storage::delete_preference(MODEL_ASSEMBLY_KEY)?;
+            return Err(e);
+        }
+
+        std::fs::rename(&temp_path, &target).map_err(|e| format!("Failed to finish assembly: {}", e))?;
+        storage::delete_preference(MODEL_ASSEMBLY_KEY)?;
+
+        let mut deleted_parts = Vec::new();
+        if delete_parts {
+            for part in &parts {
+                match std::fs::remove_file(part) {
+                    Ok(()) => deleted_parts.push(part.to_string_lossy().to_string()),
+                    Err(e) => eprintln!("Failed to delete model part {}: {}", part.display(), e),
+                }
+            }
+        }
+
+        Ok::<_, String>(AssemblyResult {
+            path: target.to_string_lossy().to_string(),
+            size: assembly.bytes,
+            resumed_parts,
+            deleted_parts,
+        })
+    })
+    .await
+    .map_err(|e| format!("Assembly task error: {}", e))??;
+
+    {
+        let mut state = AI_STATE.write();
+        state.model_path = Some(PathBuf::from(&result.path));
+        state.status = AiStatus::Loading;
+    }
+    let _ = app.emit("ai-status", AiStatus::Loading);
+
+    Ok(result)
+}
+
+// The assembled file is as long as its parts together, is a GGUF model and
+// matches the expected hash, if one was given
+fn verify_model(path: &std::path::Path, parts: &[PathBuf], sha256: Option<&str>) -> Result<(), String> {
+    use std::io::Read;
+
+    let expected_size = parts
+        .iter()
+        .map(|part| std::fs::metadata(part).map(|m| m.len()))
+        .sum::<std::io::Result<u64>>()
+        .map_err(|e| format!("Failed to read model part: {}", e))?;
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read assembled model: {}", e))?
+        .len();
+    if size != expected_size {
+        return Err(format!(
+            "Assembled model is {} bytes, but its parts add up to {}",
+            size, expected_size
+        ));
+    }
+
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| format!("Failed to read assembled model: {}", e))?;
+    if &magic != b"GGUF" {
+        return Err("Assembled file is not a GGUF model; check the part order".to_string());
+    }
+
+    if let Some(expected) = sha256 {
+        let actual = fileops::hash_file_contents(path)?;
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!("Assembled model hash {} does not match {}", actual, expected));
+        }
+    }
+    Ok(())
+}
+
 /// Load the AI model into memory
 #[tauri::command]
 pub async fn load_model(app: AppHandle) -> Result<(), String> {
@@ -273,9 +582,51 @@ pub async fn load_model(app: AppHandle) -> Result<(), String> {
     }
 }
 
-/// Generate AI response
+/// Load the generation settings, falling back to defaults
+pub fn load_model_config() -> ModelConfig {
+    storage::get_preference(MODEL_CONFIG_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Get the generation settings
+#[tauri::command]
+pub async fn get_model_config() -> Result<ModelConfig, String> {
+    Ok(load_model_config())
+}
+
+/// Save the generation settings
+#[tauri::command]
+pub async fn set_model_config(config: ModelConfig) -> Result<(), String> {
+    if config.max_tokens == 0 || config.context_size == 0 {
+        return Err("max_tokens and context_size must be greater than zero".to_string());
+    }
+    let value = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    storage::set_preference(MODEL_CONFIG_KEY, &value)
+}
+
+/// Generate AI response.
+///
+/// Generation stops at the configured token or wall-clock limit
+/// (`options.max_duration_ms` overrides the latter); whatever was produced so
+/// far is returned with `truncated` set.
 #[tauri::command]
-pub async fn generate_response(prompt: String) -> Result<String, String> {
+pub async fn generate_response(
+    prompt: String,
+    options: Option<GenerationOptions>,
+) -> Result<GenerationResult, String> {
+    let config = load_model_config();
+    let options = options.unwrap_or_default();
+    let seed = options.seed.unwrap_or(config.seed);
+    let time_limit = match (options.max_duration_ms, options.deterministic) {
+        (Some(ms), _) => Some(Duration::from_millis(ms)),
+        // A wall-clock cut-off would make output depend on machine speed
+        (None, true) => None,
+        (None, false) => Some(Duration::from_millis(config.max_generation_ms)),
+    };
+
     // Check if model is ready
     let state = AI_STATE.read();
 
@@ -288,6 +639,8 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
         .ok_or("Model path not set")?;
     drop(state);
 
+    let feature = options.feature;
+
     // Format prompt for SmolLM2-Instruct
     let formatted_prompt = format!(
         "<|im_start|>system\nYou are a helpful AI assistant for Smart Storage AI, a privacy-first file organization app. You help users organize their files by type, date, or size. Be concise and helpful. You run 100% locally on the user's device.<|im_end|>\n<|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
@@ -304,9 +657,15 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
         let model = llama_cpp_2::LlamaModel::load_from_file(&backend, &model_path, &model_params)
             .map_err(|e| format!("Model load error: {}", e))?;
 
+        let started = Instant::now();
+
         // Create context
-        let ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
-            .with_n_ctx(std::num::NonZeroU32::new(512));
+        let mut ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
+            .with_n_ctx(std::num::NonZeroU32::new(config.context_size));
+        if options.deterministic {
+            // Multi-threaded decode can reorder float reductions between runs
+            ctx_params = ctx_params.with_n_threads(1).with_n_threads_batch(1);
+        }
 
         let mut ctx = model.new_context(&backend, ctx_params)
             .map_err(|e| format!("Context error: {}", e))?;
@@ -316,7 +675,7 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
             .map_err(|e| format!("Tokenize error: {}", e))?;
 
         // Create batch
-        let mut batch = llama_cpp_2::llama_batch::LlamaBatch::new(512, 1);
+        let mut batch = llama_cpp_2::llama_batch::LlamaBatch::new(config.context_size as usize, 1);
 
         // Add tokens to batch
         for (i, token) in tokens.iter().enumerate() {
@@ -331,22 +690,35 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
         // Generate response
         let mut output = String::new();
         let mut n_cur = batch.n_tokens();
-        let n_len = 256; // Max tokens to generate
+        // Max tokens to generate, bounded by the context window
+        let n_len = (batch.n_tokens() + config.max_tokens as i32).min(config.context_size as i32);
+        let mut stop_reason = StopReason::MaxTokens;
+        let mut tokens_generated: u32 = 0;
 
         // Sampler setup
         let mut sampler = llama_cpp_2::sampling::LlamaSampler::chain_simple(
             llama_cpp_2::sampling::params::LlamaSamplerChainParams::default(),
         );
-        sampler.add_temp(0.7);
-        sampler.add_top_p(0.9, 1);
-        sampler.add_dist(42);
+        if options.deterministic {
+            sampler.add_greedy();
+        } else {
+            sampler.add_temp(config.temperature);
+            sampler.add_top_p(config.top_p, 1);
+            sampler.add_dist(seed);
+        }
+
+        while n_cur < n_len {
+            if time_limit.is_some_and(|limit| started.elapsed() >= limit) {
+                stop_reason = StopReason::TimeLimit;
+                break;
+            }
 
-        while n_cur < n_len as i32 {
             // Sample next token
             let new_token_id = sampler.sample(&ctx, batch.n_tokens() - 1);
 
             // Check for end of sequence
             if model.is_eog_token(new_token_id) {
+                stop_reason = StopReason::EndOfText;
                 break;
             }
 
@@ -356,10 +728,12 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
 
             // Check for end markers
             if token_str.contains("<|im_end|>") || token_str.contains("<|endoftext|>") {
+                stop_reason = StopReason::EndOfText;
                 break;
             }
 
             output.push_str(&token_str);
+            tokens_generated += 1;
 
             // Prepare next batch
             batch.clear();
@@ -373,14 +747,327 @@ pub async fn generate_response(prompt: String) -> Result<String, String> {
             n_cur += 1;
         }
 
-        Ok::<_, String>(output.trim().to_string())
+        Ok::<_, String>(GenerationResult {
+            text: output.trim().to_string(),
+            truncated: stop_reason != StopReason::EndOfText,
+            stop_reason,
+            tokens_generated,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?;
 
+    if let Ok(generated) = &result {
+        record_usage(feature, generated.tokens_generated as u64, 1, generated.elapsed_ms);
+    }
     result
 }
 
+/// Embed texts with the GGUF model at `model_path`, counting the run under
+/// `feature` in get_ai_usage.
+///
+/// Vectors are mean-pooled by llama.cpp and L2-normalized here.
+pub fn embed_texts(
+    model_path: &std::path::Path,
+    texts: &[String],
+    feature: AiFeature,
+) -> Result<Vec<Vec<f32>>, String> {
+    let started = Instant::now();
+    let backend = llama_cpp_2::llama_backend::LlamaBackend::init()
+        .map_err(|e| format!("Backend init error: {}", e))?;
+    let model_params = llama_cpp_2::model::params::LlamaModelParams::default();
+    let model = llama_cpp_2::LlamaModel::load_from_file(&backend, model_path, &model_params)
+        .map_err(|e| format!("Model load error: {}", e))?;
+
+    let ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
+        .with_n_ctx(std::num::NonZeroU32::new(512))
+        .with_embeddings(true);
+    let mut ctx = model.new_context(&backend, ctx_params)
+        .map_err(|e| format!("Context error: {}", e))?;
+
+    let mut batch = llama_cpp_2::llama_batch::LlamaBatch::new(512, 1);
+    let mut vectors = Vec::with_capacity(texts.len());
+    let mut tokens_embedded = 0;
+
+    for text in texts {
+        let mut tokens = model.str_to_token(text, llama_cpp_2::model::AddBos::Always)
+            .map_err(|e| format!("Tokenize error: {}", e))?;
+        tokens.truncate(512);
+        tokens_embedded += tokens.len() as u64;
+
+        ctx.clear_kv_cache();
+        batch.clear();
+        batch.add_sequence(&tokens, 0, false)
+            .map_err(|e| format!("Batch add error: {}", e))?;
+        ctx.decode(&mut batch)
+            .map_err(|e| format!("Decode error: {}", e))?;
+
+        let embedding = ctx.embeddings_seq_ith(0)
+            .map_err(|e| format!("Embedding error: {}", e))?;
+        let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+        vectors.push(if norm > 0.0 {
+            embedding.iter().map(|v| v / norm).collect()
+        } else {
+            embedding.to_vec()
+        });
+    }
+
+    record_usage(feature, tokens_embedded, texts.len() as u64, started.elapsed().as_millis() as u64);
+    Ok(vectors)
+}
+
+// One classified item from a batch
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassifiedItem {
+    pub item: String,
+    /// None when the model's answer for this item could not be parsed
+    pub category: Option<String>,
+}
+
+// Result of a batch classification
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchClassification {
+    pub results: Vec<ClassifiedItem>,
+    pub chunks: usize,
+    pub elapsed_ms: u64,
+    pub items_per_second: f32,
+}
+
+const DEFAULT_CATEGORIES: &[&str] = &[
+    "document", "pdf", "spreadsheet", "presentation", "image", "video", "audio", "archive", "code",
+    "other",
+];
+
+/// Context window used for batch classification
+const CLASSIFY_CONTEXT_SIZE: u32 = 2048;
+
+/// Output tokens budgeted per item ("12: spreadsheet\n")
+const CLASSIFY_TOKENS_PER_ITEM: usize = 8;
+
+/// Classify many file names, packing as many as fit into each prompt.
+///
+/// The model and context are created once and reused for every chunk; the
+/// answer is expected as one "<number>: <category>" line per item.
+#[tauri::command]
+pub async fn classify_batch(
+    items: Vec<String>,
+    categories: Option<Vec<String>>,
+) -> Result<BatchClassification, String> {
+    let categories: Vec<String> = categories
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| DEFAULT_CATEGORIES.iter().map(|c| c.to_string()).collect());
+
+    let state = AI_STATE.read();
+    if state.model.is_none() {
+        return Err("Model not loaded".to_string());
+    }
+    let model_path = state.model_path.clone()
+        .ok_or("Model path not set")?;
+    drop(state);
+
+    tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+
+        let backend = llama_cpp_2::llama_backend::LlamaBackend::init()
+            .map_err(|e| format!("Backend init error: {}", e))?;
+        let model_params = llama_cpp_2::model::params::LlamaModelParams::default();
+        let model = llama_cpp_2::LlamaModel::load_from_file(&backend, &model_path, &model_params)
+            .map_err(|e| format!("Model load error: {}", e))?;
+
+        let ctx_params = llama_cpp_2::context::params::LlamaContextParams::default()
+            .with_n_ctx(std::num::NonZeroU32::new(CLASSIFY_CONTEXT_SIZE));
+        let mut ctx = model.new_context(&backend, ctx_params)
+            .map_err(|e| format!("Context error: {}", e))?;
+
+        let count_tokens = |text: &str| {
+            model.str_to_token(text, llama_cpp_2::model::AddBos::Never)
+                .map(|tokens| tokens.len())
+                .unwrap_or(text.len())
+        };
+
+        // Pack items into chunks that fit the context with room for the answers
+        let overhead = count_tokens(&classification_prompt(&categories, &[]));
+        let budget = CLASSIFY_CONTEXT_SIZE as usize;
+        let mut chunks: Vec<Vec<String>> = Vec::new();
+        let mut current: Vec<String> = Vec::new();
+        let mut used = overhead;
+        for item in items {
+            let cost = count_tokens(&item) + 4 + CLASSIFY_TOKENS_PER_ITEM;
+            if !current.is_empty() && used + cost > budget {
+                chunks.push(std::mem::take(&mut current));
+                used = overhead;
+            }
+            used += cost;
+            current.push(item);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+
+        let mut results = Vec::new();
+        let mut tokens_generated = 0;
+        let mut batch = llama_cpp_2::llama_batch::LlamaBatch::new(budget, 1);
+
+        for chunk in &chunks {
+            // Reuse the context; only the KV cache is reset between chunks
+            ctx.clear_kv_cache();
+            batch.clear();
+
+            let prompt = classification_prompt(&categories, chunk);
+            let tokens = model.str_to_token(&prompt, llama_cpp_2::model::AddBos::Always)
+                .map_err(|e| format!("Tokenize error: {}", e))?;
+            for (i, token) in tokens.iter().enumerate() {
+                batch.add(*token, i as i32, &[0], i == tokens.len() - 1)
+                    .map_err(|e| format!("Batch add error: {}", e))?;
+            }
+            ctx.decode(&mut batch)
+                .map_err(|e| format!("Decode error: {}", e))?;
+
+            let mut sampler = llama_cpp_2::sampling::LlamaSampler::chain_simple(
+                llama_cpp_2::sampling::params::LlamaSamplerChainParams::default(),
+            );
+            sampler.add_greedy();
+
+            let mut output = String::new();
+            let mut n_cur = batch.n_tokens();
+            let n_len = (n_cur as usize + chunk.len() * CLASSIFY_TOKENS_PER_ITEM).min(budget) as i32;
+
+            while n_cur < n_len {
+                let new_token_id = sampler.sample(&ctx, batch.n_tokens() - 1);
+                if model.is_eog_token(new_token_id) {
+                    break;
+                }
+                let token_str = model.token_to_str(new_token_id, llama_cpp_2::model::Special::Tokenize)
+                    .map_err(|e| format!("Token convert error: {}", e))?;
+                if token_str.contains("<|im_end|>") || token_str.contains("<|endoftext|>") {
+                    break;
+                }
+                output.push_str(&token_str);
+                tokens_generated += 1;
+
+                batch.clear();
+                batch.add(new_token_id, n_cur, &[0], true)
+                    .map_err(|e| format!("Batch add error: {}", e))?;
+                ctx.decode(&mut batch)
+                    .map_err(|e| format!("Decode error: {}", e))?;
+                n_cur += 1;
+            }
+
+            results.extend(parse_classification(&output, chunk, &categories));
+        }
+
+        let elapsed = started.elapsed();
+        record_usage(
+            AiFeature::Classification,
+            tokens_generated,
+            results.len() as u64,
+            elapsed.as_millis() as u64,
+        );
+        let items_per_second = if elapsed.as_secs_f32() > 0.0 {
+            results.len() as f32 / elapsed.as_secs_f32()
+        } else {
+            0.0
+        };
+
+        Ok::<_, String>(BatchClassification {
+            results,
+            chunks: chunks.len(),
+            elapsed_ms: elapsed.as_millis() as u64,
+            items_per_second,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+// Numbered list prompt for SmolLM2-Instruct
+fn classification_prompt(categories: &[String], items: &[String]) -> String {
+    let list: String = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| format!("{}. {}\n", i + 1, item))
+        .collect();
+
+    format!(
+        "<|im_start|>system\nYou classify file names. Allowed categories: {}. Answer with one line per file in the form \"<number>: <category>\" and nothing else.<|im_end|>\n<|im_start|>user\n{}<|im_end|>\n<|im_start|>assistant\n",
+        categories.join(", "),
+        list
+    )
+}
+
+// Map "<number>: <category>" lines back onto the chunk's items
+fn parse_classification(output: &str, items: &[String], categories: &[String]) -> Vec<ClassifiedItem> {
+    let mut assigned: Vec<Option<String>> = vec![None; items.len()];
+
+    for line in output.lines() {
+        let Some((number, answer)) = line.split_once(':').or_else(|| line.split_once('.')) else {
+            continue;
+        };
+        let Ok(index) = number.trim().trim_start_matches('-').trim().parse::<usize>() else {
+            continue;
+        };
+        if index == 0 || index > items.len() {
+            continue;
+        }
+
+        let answer = answer.trim().trim_end_matches('.').to_lowercase();
+        if let Some(category) = categories.iter().find(|c| c.to_lowercase() == answer) {
+            assigned[index - 1] = Some(category.clone());
+        }
+    }
+
+    items
+        .iter()
+        .zip(assigned)
+        .map(|(item, category)| ClassifiedItem {
+            item: item.clone(),
+            category,
+        })
+        .collect()
+}
+
+/// Days get_ai_usage covers by default
+const DEFAULT_USAGE_DAYS: u32 = 7;
+
+// Log a model run; a failure to log never fails the run itself
+fn record_usage(feature: AiFeature, tokens: u64, items: u64, elapsed_ms: u64) {
+    if let Err(e) = storage::record_ai_usage(feature.as_str(), tokens, items, elapsed_ms) {
+        eprintln!("Failed to record AI usage: {}", e);
+    }
+}
+
+// Model use over a period, by feature
+#[derive(Debug, Serialize)]
+pub struct AiUsageReport {
+    pub since: String,
+    pub features: Vec<storage::AiUsage>,
+    pub total_runs: u64,
+    pub total_tokens: u64,
+    /// Wall-clock time spent running the model
+    pub total_elapsed_ms: u64,
+}
+
+/// How much model time each feature (chat, classification, embeddings,
+/// plan summaries, ...) used over the last `days` days, heaviest first, so
+/// background work can be tuned
+#[tauri::command]
+pub async fn get_ai_usage(days: Option<u32>) -> Result<AiUsageReport, String> {
+    let days = days.unwrap_or(DEFAULT_USAGE_DAYS).max(1);
+    let since = (chrono::Utc::now() - chrono::Duration::days(days as i64))
+        .format(storage::INDEX_TIME_FORMAT)
+        .to_string();
+    let features = storage::ai_usage_since(&since)?;
+
+    Ok(AiUsageReport {
+        since,
+        total_runs: features.iter().map(|usage| usage.runs).sum(),
+        total_tokens: features.iter().map(|usage| usage.tokens).sum(),
+        total_elapsed_ms: features.iter().map(|usage| usage.elapsed_ms).sum(),
+        features,
+    })
+}
+
 /// Initialize AI on app startup
 #[tauri::command]
 pub async fn init_ai(app: AppHandle) -> Result<AiStatus, String> {
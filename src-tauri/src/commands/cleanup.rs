@@ -0,0 +1,1014 @@
+// ============================================================================
+// Cleanup Commands
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+use walkdir::WalkDir;
+
+use crate::backups::{self, BackupDetector};
+use crate::commands::files::{empty_folders, get_file_type, EmptyFolders};
+use crate::events::{self, FsChange};
+use crate::fileops::{self, CollisionPolicy, MoveOutcome};
+use crate::health::{self, SuspectReason};
+use crate::libraries;
+use crate::scan::{self, DerivedKind, ScanFilter};
+use crate::staging;
+use crate::storage;
+use crate::throttle;
+
+const DEFAULT_REPORT_FILES: usize = 50;
+const DEFAULT_UNTOUCHED_DAYS: u64 = 365;
+const DEFAULT_STALE_MONTHS: u64 = 6;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+const HEALTH_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// What kind of link a broken link is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Symlink,
+    /// Windows .lnk shortcut
+    Shortcut,
+}
+
+// A symlink or shortcut whose target no longer exists
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    pub path: String,
+    /// Where the link points; None for shortcuts whose target can't be read
+    pub target: Option<String>,
+    pub kind: LinkKind,
+}
+
+// What to do with one broken link
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LinkFix {
+    Delete { path: String },
+    Retarget { path: String, target: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixBrokenLinksResult {
+    pub batch_id: String,
+    pub deleted: Vec<String>,
+    pub retargeted: Vec<String>,
+    /// Links that were fixed meanwhile, are protected, or could not be changed
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Everything under a folder that can be tidied away
+#[derive(Debug, Serialize)]
+pub struct CleanupReport {
+    pub path: String,
+    pub empty_folders: EmptyFolders,
+    pub broken_links: Vec<BrokenLink>,
+}
+
+// Which timestamp says when a file was last used
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LastUsedSource {
+    Accessed,
+    /// No usable access time (noatime mounts, access updates turned off)
+    Modified,
+}
+
+// A file listed in the large/old file and stale file reports
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportedFile {
+    pub path: String,
+    pub size: u64,
+    pub file_type: String,
+    /// Last access, or last modification when that is later, as RFC 3339
+    pub last_used_at: String,
+    pub last_used_source: LastUsedSource,
+}
+
+// Files of a report section that share a folder or a type
+#[derive(Debug, Clone, Serialize)]
+pub struct FileGroup {
+    pub key: String,
+    pub file_count: u64,
+    pub size: u64,
+}
+
+// One list of the report with its breakdowns, largest groups first
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSection {
+    pub files: Vec<ReportedFile>,
+    pub by_folder: Vec<FileGroup>,
+    pub by_type: Vec<FileGroup>,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StaleFilesReport {
+    pub path: String,
+    /// Files not used since `stale_since`, oldest first
+    pub stale: ReportSection,
+    pub stale_since: String,
+    pub files_scanned: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LargeOldFilesReport {
+    pub path: String,
+    /// The largest files, biggest first
+    pub largest: ReportSection,
+    /// Files not used since `untouched_since`, oldest first
+    pub untouched: ReportSection,
+    pub untouched_since: String,
+    pub files_scanned: u64,
+}
+
+// A file that looks empty, mislabeled or damaged
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspectFile {
+    pub path: String,
+    pub size: u64,
+    #[serde(flatten)]
+    pub reason: SuspectReason,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuspectFiles {
+    pub files: Vec<SuspectFile>,
+    pub files_scanned: u64,
+}
+
+// Progress of a health scan, emitted as "health-scan-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthScanProgress {
+    pub root: String,
+    pub files_scanned: u64,
+    pub issues: usize,
+    pub current_path: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuarantineResult {
+    pub batch_id: String,
+    pub quarantined: Vec<MoveOutcome>,
+    pub errors: Vec<String>,
+}
+
+// A dependency, build or cache folder and what it takes up
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivedFolder {
+    pub path: String,
+    pub kind: DerivedKind,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DerivedDataReport {
+    /// Largest first; folders inside another derived folder are counted
+    /// with it
+    pub folders: Vec<DerivedFolder>,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CleanDerivedResult {
+    pub batch_id: String,
+    pub removed: Vec<String>,
+    /// Bytes moved to the trash, or to the staging area
+    pub freed: u64,
+    pub errors: Vec<String>,
+}
+
+// One indexed copy of a duplicated file
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCopy {
+    pub path: String,
+    /// Name of the library holding the copy, if any
+    pub library: Option<String>,
+    /// False while the copy's device is not connected
+    pub available: bool,
+}
+
+// Files with the same content hash
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    /// Size of one copy
+    pub size: u64,
+    pub copies: Vec<DuplicateCopy>,
+    /// Libraries holding a copy, sorted by name
+    pub libraries: Vec<String>,
+}
+
+// Content held by every one of a set of libraries
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryOverlap {
+    pub libraries: Vec<String>,
+    pub groups: u64,
+    /// Bytes of one copy of each group
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    /// Largest first
+    pub groups: Vec<DuplicateGroup>,
+    /// Largest first
+    pub overlaps: Vec<LibraryOverlap>,
+    /// Bytes taken by every copy beyond the first
+    pub wasted_size: u64,
+}
+
+/// Symlinks and .lnk shortcuts under `path` whose targets don't exist.
+/// Shortcuts are only checked on Windows, where their targets resolve.
+/// Links inside protected folders are left out.
+#[tauri::command]
+pub async fn find_broken_links(path: String) -> Result<Vec<BrokenLink>, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        Ok::<_, String>(
+            broken_links(&root)
+                .into_iter()
+                .filter(|link| !fileops::is_protected(Path::new(&link.path), &protected))
+                .collect(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Broken link task error: {}", e))?
+}
+
+/// Delete or retarget broken links, logging each as a "delete_link" or
+/// "retarget_link" change. Links are checked again first, so one whose
+/// target came back since `find_broken_links` is kept as it is.
+#[tauri::command]
+pub async fn fix_broken_links(app: AppHandle, fixes: Vec<LinkFix>) -> Result<FixBrokenLinksResult, String> {
+    let (result, changes) = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let batch_id = storage::create_history_batch("Fix broken links", &format!("{} link(s)", fixes.len()))?;
+        let mut result = FixBrokenLinksResult {
+            batch_id,
+            deleted: Vec::new(),
+            retargeted: Vec::new(),
+            skipped: Vec::new(),
+            errors: Vec::new(),
+        };
+        let mut changes = Vec::new();
+
+        for fix in fixes {
+            let path = match &fix {
+                LinkFix::Delete { path } | LinkFix::Retarget { path, .. } => PathBuf::from(path),
+            };
+            let Some(link) = broken_link(&path) else {
+                result.skipped.push(path.to_string_lossy().to_string());
+                continue;
+            };
+            if fileops::is_protected(&path, &protected) {
+                result.skipped.push(path.to_string_lossy().to_string());
+                continue;
+            }
+            let display = path.to_string_lossy().to_string();
+
+            match fix {
+                LinkFix::Delete { .. } => {
+                    if let Err(e) = fs::remove_file(fileops::long_path(&path)) {
+                        result.errors.push(format!("{}: {}", display, e));
+                        continue;
+                    }
+                    storage::record_change(&result.batch_id, "delete_link", &display, link.target.as_deref(), None)?;
+                    storage::remove_indexed_path(&display)?;
+                    changes.push(FsChange::removed(&path));
+                    result.deleted.push(display);
+                }
+                LinkFix::Retarget { target, .. } => {
+                    if !fileops::long_path(Path::new(&target)).exists() {
+                        result.errors.push(format!("{}: new target does not exist: {}", display, target));
+                        continue;
+                    }
+                    if let Err(e) = fileops::retarget_link(&path, Path::new(&target)) {
+                        result.errors.push(format!("{}: {}", display, e));
+                        continue;
+                    }
+                    storage::record_change(&result.batch_id, "retarget_link", &display, Some(&target), None)?;
+                    changes.push(FsChange::modified(&path));
+                    result.retargeted.push(display);
+                }
+            }
+        }
+        Ok::<_, String>((result, changes))
+    })
+    .await
+    .map_err(|e| format!("Broken link task error: {}", e))??;
+
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Empty folders and broken links under `path`, in one pass for the
+/// cleanup view. Those inside backup sets are left out unless
+/// `include_backups`, since a restore expects them where they are.
+#[tauri::command]
+pub async fn get_cleanup_report(
+    path: String,
+    include_backups: Option<bool>,
+) -> Result<CleanupReport, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let mut detector = BackupDetector::default();
+        let mut in_backup = |path: &Path| !include_backups && detector.backup_of(path).is_some();
+        let empty: Vec<PathBuf> = empty_folders(&root)
+            .into_iter()
+            .filter(|folder| !fileops::is_protected(folder, &protected) && !in_backup(folder))
+            .collect();
+        let folders = empty
+            .iter()
+            .filter(|folder| !folder.parent().is_some_and(|parent| empty.iter().any(|e| e == parent)))
+            .map(|folder| folder.to_string_lossy().to_string())
+            .collect();
+        let broken_links = broken_links(&root)
+            .into_iter()
+            .filter(|link| {
+                !fileops::is_protected(Path::new(&link.path), &protected) && !in_backup(Path::new(&link.path))
+            })
+            .collect();
+
+        Ok::<_, String>(CleanupReport {
+            path,
+            empty_folders: EmptyFolders {
+                folders,
+                total: empty.len(),
+            },
+            broken_links,
+        })
+    })
+    .await
+    .map_err(|e| format!("Cleanup report task error: {}", e))?
+}
+
+/// The `top_n` largest files under `path` and the `top_n` files longest
+/// unused for more than `untouched_days`, each grouped by folder and type.
+/// Protected folders and ignored paths are left out, and so are backup sets
+/// unless `include_backups`.
+#[tauri::command]
+pub async fn get_large_old_files(
+    path: String,
+    top_n: Option<usize>,
+    untouched_days: Option<u64>,
+    include_backups: Option<bool>,
+) -> Result<LargeOldFilesReport, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let top_n = top_n.unwrap_or(DEFAULT_REPORT_FILES);
+    let cutoff = days_ago(untouched_days.unwrap_or(DEFAULT_UNTOUCHED_DAYS));
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let filter = ScanFilter::new(&root, false)?;
+        let mut largest: BinaryHeap<Reverse<(u64, PathBuf, LastUsed)>> = BinaryHeap::new();
+        let mut untouched: BinaryHeap<(LastUsed, PathBuf, u64)> = BinaryHeap::new();
+        let mut files_scanned = 0u64;
+
+        for entry in cleanup_files(&root, &filter, &protected, include_backups) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            files_scanned += 1;
+            let size = metadata.len();
+            let last_used = last_used(&metadata);
+
+            // Keep only the top N of each list while walking
+            largest.push(Reverse((size, entry.path().to_path_buf(), last_used)));
+            if largest.len() > top_n {
+                largest.pop();
+            }
+            if last_used.0 < cutoff {
+                untouched.push((last_used, entry.path().to_path_buf(), size));
+                if untouched.len() > top_n {
+                    untouched.pop();
+                }
+            }
+        }
+
+        // Ascending order of the Reverse puts the biggest first
+        let largest: Vec<ReportedFile> = largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path, last_used))| reported_file(&path, size, last_used))
+            .collect();
+        let untouched: Vec<ReportedFile> = untouched
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(last_used, path, size)| reported_file(&path, size, last_used))
+            .collect();
+
+        Ok::<_, String>(LargeOldFilesReport {
+            path,
+            largest: report_section(largest),
+            untouched: report_section(untouched),
+            untouched_since: chrono::DateTime::<chrono::Utc>::from(cutoff).to_rfc3339(),
+            files_scanned,
+        })
+    })
+    .await
+    .map_err(|e| format!("File report task error: {}", e))?
+}
+
+/// Files under `path` not opened for `months` months (30 days each), oldest
+/// first, for archiving cold files. Access times are used where the
+/// filesystem keeps them, modification times otherwise. Backup sets are
+/// left out unless `include_backups`.
+#[tauri::command]
+pub async fn find_stale_files(
+    path: String,
+    months: Option<u64>,
+    include_backups: Option<bool>,
+) -> Result<StaleFilesReport, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let cutoff = days_ago(months.unwrap_or(DEFAULT_STALE_MONTHS) * 30);
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let filter = ScanFilter::new(&root, false)?;
+        let mut stale: Vec<(LastUsed, PathBuf, u64)> = Vec::new();
+        let mut files_scanned = 0u64;
+
+        for entry in cleanup_files(&root, &filter, &protected, include_backups) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            files_scanned += 1;
+            let last_used = last_used(&metadata);
+            if last_used.0 < cutoff {
+                stale.push((last_used, entry.into_path(), metadata.len()));
+            }
+        }
+        stale.sort();
+
+        let files = stale
+            .into_iter()
+            .map(|(last_used, path, size)| reported_file(&path, size, last_used))
+            .collect();
+        Ok::<_, String>(StaleFilesReport {
+            path,
+            stale: report_section(files),
+            stale_since: chrono::DateTime::<chrono::Utc>::from(cutoff).to_rfc3339(),
+            files_scanned,
+        })
+    })
+    .await
+    .map_err(|e| format!("Stale file task error: {}", e))?
+}
+
+// When a file was last used and which timestamp said so
+type LastUsed = (SystemTime, LastUsedSource);
+
+// The access time, unless the filesystem doesn't keep it current: reads
+// always come after the last write, so an access time before the
+// modification time means access updates are off
+fn last_used(metadata: &fs::Metadata) -> LastUsed {
+    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    match metadata.accessed() {
+        Ok(accessed) if accessed > modified => (accessed, LastUsedSource::Accessed),
+        _ => (modified, LastUsedSource::Modified),
+    }
+}
+
+fn days_ago(days: u64) -> SystemTime {
+    SystemTime::now().checked_sub(DAY * days as u32).unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+// Files under `root` a cleanup scan looks at: not ignored, not protected,
+// and outside backup sets unless `include_backups`
+fn cleanup_files<'a>(
+    root: &Path,
+    filter: &'a ScanFilter,
+    protected: &'a [String],
+    include_backups: bool,
+) -> impl Iterator<Item = walkdir::DirEntry> + 'a {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_dir();
+            entry.depth() == 0
+                || (filter.allows(entry.path(), is_dir)
+                    && !fileops::is_protected(entry.path(), protected)
+                    && (include_backups || !is_dir || backups::backup_set(entry.path()).is_none()))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+}
+
+fn reported_file(path: &Path, size: u64, (last_used, source): LastUsed) -> ReportedFile {
+    ReportedFile {
+        path: path.to_string_lossy().to_string(),
+        size,
+        file_type: path
+            .extension()
+            .map(|e| get_file_type(&e.to_string_lossy()))
+            .unwrap_or_else(|| "other".to_string()),
+        last_used_at: chrono::DateTime::<chrono::Utc>::from(last_used).to_rfc3339(),
+        last_used_source: source,
+    }
+}
+
+// Group a list of files by parent folder and by type
+fn report_section(files: Vec<ReportedFile>) -> ReportSection {
+    let mut by_folder: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut by_type: HashMap<String, (u64, u64)> = HashMap::new();
+    for file in &files {
+        let folder = Path::new(&file.path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for (groups, key) in [(&mut by_folder, folder), (&mut by_type, file.file_type.clone())] {
+            let totals = groups.entry(key).or_default();
+            totals.0 += 1;
+            totals.1 += file.size;
+        }
+    }
+
+    let into_groups = |groups: HashMap<String, (u64, u64)>| {
+        let mut groups: Vec<FileGroup> = groups
+            .into_iter()
+            .map(|(key, (file_count, size))| FileGroup { key, file_count, size })
+            .collect();
+        groups.sort_by(|a, b| b.size.cmp(&a.size));
+        groups
+    };
+    ReportSection {
+        total_size: files.iter().map(|f| f.size).sum(),
+        by_folder: into_groups(by_folder),
+        by_type: into_groups(by_type),
+        files,
+    }
+}
+
+/// Zero-byte files, images and PDFs whose contents don't match their
+/// extension, and archives that can't be read to the end, so they can be
+/// quarantined before being filed with good data. Backup sets are left out
+/// unless `include_backups`.
+#[tauri::command]
+pub async fn find_suspect_files(path: String, include_backups: Option<bool>) -> Result<SuspectFiles, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let _foreground = throttle::foreground();
+        let protected = storage::protected_paths()?;
+        let filter = ScanFilter::new(&root, false)?;
+        let mut result = SuspectFiles {
+            files: Vec::new(),
+            files_scanned: 0,
+        };
+
+        for entry in cleanup_files(&root, &filter, &protected, include_backups) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            result.files_scanned += 1;
+            if let Some(reason) = health::check(entry.path(), &metadata) {
+                result.files.push(SuspectFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: metadata.len(),
+                    reason,
+                });
+            }
+        }
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Suspect file task error: {}", e))?
+}
+
+/// Read every file under `path` and list the ones to repair or review
+/// before archiving: zero-byte files, truncated images and videos, and
+/// files with read errors, along with everything `find_suspect_files` flags.
+/// Backup sets are left out unless `include_backups`.
+#[tauri::command]
+pub async fn run_health_scan(
+    app: AppHandle,
+    path: String,
+    include_backups: Option<bool>,
+) -> Result<SuspectFiles, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let filter = ScanFilter::new(&root, false)?;
+        let mut result = SuspectFiles {
+            files: Vec::new(),
+            files_scanned: 0,
+        };
+        let mut last_emit = Instant::now();
+
+        for entry in cleanup_files(&root, &filter, &protected, include_backups) {
+            throttle::yield_to_foreground();
+            result.files_scanned += 1;
+            let reason = match entry.metadata() {
+                Ok(metadata) => health::check_thoroughly(entry.path(), &metadata),
+                Err(e) => Some(SuspectReason::Unreadable {
+                    message: format!("Failed to read metadata: {}", e),
+                }),
+            };
+            if let Some(reason) = reason {
+                result.files.push(SuspectFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    reason,
+                });
+            }
+
+            if last_emit.elapsed() >= HEALTH_PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = app.emit(
+                    "health-scan-progress",
+                    HealthScanProgress {
+                        root: path.clone(),
+                        files_scanned: result.files_scanned,
+                        issues: result.files.len(),
+                        current_path: entry.path().to_string_lossy().to_string(),
+                        done: false,
+                    },
+                );
+            }
+        }
+
+        let _ = app.emit(
+            "health-scan-progress",
+            HealthScanProgress {
+                root: path.clone(),
+                files_scanned: result.files_scanned,
+                issues: result.files.len(),
+                current_path: path.clone(),
+                done: true,
+            },
+        );
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Health scan task error: {}", e))?
+}
+
+/// Move files into `destination` to keep them out of organizing, logging
+/// each as a "quarantine" change so it can be undone from history
+#[tauri::command]
+pub async fn quarantine_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    destination: String,
+) -> Result<QuarantineResult, String> {
+    let (result, changes) = tokio::task::spawn_blocking(move || {
+        let quarantine = PathBuf::from(&destination);
+        fs::create_dir_all(fileops::long_path(&quarantine))
+            .map_err(|e| format!("Failed to create quarantine folder: {}", e))?;
+        let protected = storage::protected_paths()?;
+        let batch_id = storage::create_history_batch("Quarantine files", &format!("{} file(s)", paths.len()))?;
+        let mut result = QuarantineResult {
+            batch_id,
+            quarantined: Vec::new(),
+            errors: Vec::new(),
+        };
+        let mut changes = Vec::new();
+
+        for path in paths {
+            let source = PathBuf::from(&path);
+            if fileops::is_protected(&source, &protected) {
+                result.errors.push(format!("Path is protected: {}", path));
+                continue;
+            }
+            let Some(name) = source.file_name() else {
+                result.errors.push(format!("Not a file: {}", path));
+                continue;
+            };
+            let target = quarantine.join(name);
+            let outcome = match fileops::move_with_policy(&source, &target, CollisionPolicy::RenameWithSuffix) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    result.errors.push(format!("{}: {}", path, e));
+                    continue;
+                }
+            };
+            storage::record_change(
+                &result.batch_id,
+                "quarantine",
+                &outcome.source,
+                Some(&outcome.destination),
+                Some(outcome.resolution.as_str()),
+            )?;
+            if let Err(e) = storage::rename_path_records(&outcome.source, &outcome.destination) {
+                eprintln!("Failed to carry file records to new path: {}", e);
+            }
+            changes.push(FsChange::renamed(&source, Path::new(&outcome.destination)));
+            result.quarantined.push(outcome);
+        }
+        Ok::<_, String>((result, changes))
+    })
+    .await
+    .map_err(|e| format!("Quarantine task error: {}", e))??;
+
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Dependency installs, build output, caches and virtual environments
+/// under `path`, with their sizes, so they can be told apart from the
+/// user's own files. Protected folders are left out, and so are backup
+/// sets unless `include_backups`.
+#[tauri::command]
+pub async fn find_derived_data(path: String, include_backups: Option<bool>) -> Result<DerivedDataReport, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let include_backups = include_backups.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let mut found = Vec::new();
+        // Ignore patterns are not applied: node_modules is ignored by default
+        let mut walker = WalkDir::new(&root).min_depth(1).into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            let path = fileops::display_path(entry.path());
+            if fileops::is_protected(&path, &protected)
+                || path.file_name().is_some_and(|n| n == ".git")
+                || (!include_backups && backups::backup_set(&path).is_some())
+            {
+                walker.skip_current_dir();
+                continue;
+            }
+            if let Some(kind) = scan::derived_kind(&path) {
+                walker.skip_current_dir();
+                found.push((path, kind));
+            }
+        }
+
+        let mut folders: Vec<DerivedFolder> = found
+            .into_iter()
+            .map(|(path, kind)| {
+                let (size, file_count) = folder_totals(&path);
+                DerivedFolder {
+                    path: path.to_string_lossy().to_string(),
+                    kind,
+                    size,
+                    file_count,
+                }
+            })
+            .collect();
+        folders.sort_by(|a, b| b.size.cmp(&a.size));
+        Ok::<_, String>(DerivedDataReport {
+            total_size: folders.iter().map(|f| f.size).sum(),
+            folders,
+        })
+    })
+    .await
+    .map_err(|e| format!("Derived data task error: {}", e))?
+}
+
+/// Move derived-data folders to the trash, logging each as a "trash"
+/// change, or with `stage` to the staging area, where they wait
+/// STAGING_RETENTION_DAYS before being purged. Folders that are protected
+/// or no longer look like derived data are refused, and so are folders
+/// inside backup sets unless `include_backups`.
+#[tauri::command]
+pub async fn clean_derived_data(
+    app: AppHandle,
+    paths: Vec<String>,
+    include_backups: Option<bool>,
+    stage: Option<bool>,
+) -> Result<CleanDerivedResult, String> {
+    let include_backups = include_backups.unwrap_or(false);
+    let stage = stage.unwrap_or(false);
+    let (result, changes) = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let mut detector = BackupDetector::default();
+        let batch_id = storage::create_history_batch("Clean derived data", &format!("{} folder(s)", paths.len()))?;
+        let mut result = CleanDerivedResult {
+            batch_id,
+            removed: Vec::new(),
+            freed: 0,
+            errors: Vec::new(),
+        };
+        let mut changes = Vec::new();
+
+        for path in paths {
+            let folder = PathBuf::from(&path);
+            if fileops::is_protected(&folder, &protected) {
+                result.errors.push(format!("Path is protected: {}", path));
+                continue;
+            }
+            if !include_backups {
+                if let Some(set) = detector.backup_of(&folder) {
+                    result.errors.push(format!("Inside a backup ({}): {}", set.path, path));
+                    continue;
+                }
+            }
+            let Some(kind) = scan::derived_kind(&folder) else {
+                result.errors.push(format!("Not a dependency, build or cache folder: {}", path));
+                continue;
+            };
+            let (size, _) = folder_totals(&folder);
+            if stage {
+                if let Err(e) = staging::stage(&folder, &result.batch_id) {
+                    result.errors.push(e);
+                    continue;
+                }
+            } else {
+                if let Err(e) = fileops::move_to_trash(&fileops::long_path(&folder)) {
+                    result.errors.push(e);
+                    continue;
+                }
+                storage::record_change(&result.batch_id, "trash", &path, None, Some(kind.as_str()))?;
+                if let Err(e) = storage::remove_indexed_path(&path) {
+                    eprintln!("Failed to unindex {}: {}", path, e);
+                }
+            }
+            changes.push(FsChange::removed(&folder));
+            result.freed += size;
+            result.removed.push(path);
+        }
+        Ok::<_, String>((result, changes))
+    })
+    .await
+    .map_err(|e| format!("Derived data task error: {}", e))??;
+
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Files with identical content across every registered library, from the
+/// content hashes persisted in the index, so copies on a laptop, an
+/// external drive and a NAS are found together even while some of those
+/// devices are disconnected. With `path`, only groups with a copy under it
+/// are reported. `min_size` defaults to one byte to skip empty files.
+/// Copies inside backup sets are intentional and left out unless
+/// `include_backups`.
+#[tauri::command]
+pub async fn find_duplicates(
+    path: Option<String>,
+    min_size: Option<u64>,
+    include_backups: Option<bool>,
+) -> Result<DuplicateReport, String> {
+    let include_backups = include_backups.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        let mut detector = BackupDetector::default();
+        let libraries = libraries::list();
+        let online: HashMap<&str, bool> = libraries
+            .iter()
+            .map(|library| (library.id.as_str(), library.is_online()))
+            .collect();
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        for (content_hash, file) in storage::duplicate_files(min_size.unwrap_or(1))? {
+            let file_path = PathBuf::from(&file.path);
+            let library = libraries::library_of(&file_path, &libraries);
+            let available = match library {
+                Some(library) if !online[library.id.as_str()] => false,
+                // A reachable copy that is gone is a stale index row
+                _ if !fileops::long_path(&file_path).is_file() => continue,
+                _ => true,
+            };
+            if available && !include_backups && detector.backup_of(&file_path).is_some() {
+                continue;
+            }
+            let copy = DuplicateCopy {
+                path: file.path,
+                library: library.map(|library| library.name.clone()),
+                available,
+            };
+            match groups.last_mut() {
+                Some(group) if group.content_hash == content_hash => group.copies.push(copy),
+                _ => groups.push(DuplicateGroup {
+                    content_hash,
+                    size: file.size,
+                    copies: vec![copy],
+                    libraries: Vec::new(),
+                }),
+            }
+        }
+
+        groups.retain(|group| group.copies.len() > 1);
+        if let Some(scope) = path.map(PathBuf::from) {
+            groups.retain(|group| group.copies.iter().any(|copy| Path::new(&copy.path).starts_with(&scope)));
+        }
+
+        let mut overlaps: HashMap<Vec<String>, LibraryOverlap> = HashMap::new();
+        for group in &mut groups {
+            let mut names: Vec<String> = group.copies.iter().filter_map(|copy| copy.library.clone()).collect();
+            names.sort();
+            names.dedup();
+            if names.len() > 1 {
+                let overlap = overlaps.entry(names.clone()).or_insert_with(|| LibraryOverlap {
+                    libraries: names.clone(),
+                    groups: 0,
+                    size: 0,
+                });
+                overlap.groups += 1;
+                overlap.size += group.size;
+            }
+            group.libraries = names;
+        }
+        let mut overlaps: Vec<LibraryOverlap> = overlaps.into_values().collect();
+        overlaps.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.libraries.cmp(&b.libraries)));
+
+        Ok::<_, String>(DuplicateReport {
+            wasted_size: groups
+                .iter()
+                .map(|group| group.size * (group.copies.len() as u64 - 1))
+                .sum(),
+            groups,
+            overlaps,
+        })
+    })
+    .await
+    .map_err(|e| format!("Duplicate scan task error: {}", e))?
+}
+
+// Bytes and number of files below `folder`
+fn folder_totals(folder: &Path) -> (u64, u64) {
+    WalkDir::new(fileops::long_path(folder))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .fold((0, 0), |(size, count), metadata| (size + metadata.len(), count + 1))
+}
+
+// Every broken link under `root`; links are not followed
+fn broken_links(root: &Path) -> Vec<BrokenLink> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| broken_link(entry.path()))
+        .collect()
+}
+
+// `path` as a broken link, or None if it isn't a link or its target exists.
+// A .lnk copied to another OS points at Windows paths that never exist
+// there, so shortcuts only count on Windows.
+fn broken_link(path: &Path) -> Option<BrokenLink> {
+    let metadata = fs::symlink_metadata(fileops::long_path(path)).ok()?;
+    let kind = if metadata.file_type().is_symlink() {
+        LinkKind::Symlink
+    } else if cfg!(windows)
+        && metadata.is_file()
+        && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+    {
+        LinkKind::Shortcut
+    } else {
+        return None;
+    };
+
+    let target = fileops::link_target(path);
+    match &target {
+        Some(target) if fileops::long_path(target).exists() => return None,
+        // A shortcut we can't parse may point at a network location or a
+        // shell item; only call it broken when its path is known to be gone
+        None if kind == LinkKind::Shortcut => return None,
+        _ => {}
+    }
+    Some(BrokenLink {
+        path: path.to_string_lossy().to_string(),
+        target: target.map(|t| t.to_string_lossy().to_string()),
+        kind,
+    })
+}
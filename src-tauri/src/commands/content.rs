@@ -0,0 +1,116 @@
+// ============================================================================
+// Content Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::content::{self, ExtractedText};
+use crate::fileops::{fingerprint, is_placeholder};
+use crate::libraries;
+use crate::scan::ScanFilter;
+use crate::shutdown::{self, OperationKind};
+use crate::storage;
+use crate::throttle;
+
+#[derive(Debug, Serialize)]
+pub struct ContentIndexReport {
+    pub extracted: usize,
+    /// Documents whose stored text is still current
+    pub unchanged: usize,
+    /// Documents left out by their library's index policy
+    pub skipped_by_policy: usize,
+    pub failed: Vec<String>,
+}
+
+/// Get the text of a document, extracting and storing it if needed
+#[tauri::command]
+pub async fn get_file_text(path: String, refresh: Option<bool>) -> Result<ExtractedText, String> {
+    tokio::task::spawn_blocking(move || {
+        let file = Path::new(&path);
+        let metadata = fs::metadata(file).map_err(|e| format!("Failed to read metadata: {}", e))?;
+        let (size, mtime) = fingerprint(&metadata);
+
+        if !refresh.unwrap_or(false) && storage::has_fresh_content(&path, size, mtime)? {
+            if let Some((text, truncated)) = storage::get_content(&path)? {
+                return Ok(ExtractedText { path, text, truncated });
+            }
+        }
+
+        extract_and_store(file, size, mtime)
+    })
+    .await
+    .map_err(|e| format!("Extraction task error: {}", e))?
+}
+
+/// Extract text from every supported document in a folder that its
+/// library's index policy lets through
+#[tauri::command]
+pub async fn index_contents(path: String, recursive: bool) -> Result<ContentIndexReport, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let filter = ScanFilter::new(&root, false)?;
+
+    tokio::task::spawn_blocking(move || {
+        let operation = shutdown::begin(OperationKind::Index, &format!("Indexing text in {}", path));
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        let mut report = ContentIndexReport {
+            extracted: 0,
+            unchanged: 0,
+            skipped_by_policy: 0,
+            failed: Vec::new(),
+        };
+        let libraries = libraries::list();
+
+        let files = WalkDir::new(&root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && content::is_supported(e.path()));
+
+        for entry in files {
+            // Stored text is kept per file, so the next run continues from here
+            if operation.should_stop() {
+                break;
+            }
+            throttle::yield_to_foreground();
+            let key = entry.path().to_string_lossy().to_string();
+            let result = entry
+                .metadata()
+                .map_err(|e| format!("Failed to read metadata: {}", e))
+                .and_then(|metadata| {
+                    let (size, mtime) = fingerprint(&metadata);
+                    if !libraries::policy_for(entry.path(), &libraries).allows_content(size) {
+                        return Ok(None);
+                    }
+                    // Online-only files are left alone rather than downloaded
+                    if storage::has_fresh_content(&key, size, mtime)? || is_placeholder(entry.path(), &metadata) {
+                        return Ok(Some(false));
+                    }
+                    extract_and_store(entry.path(), size, mtime).map(|_| Some(true))
+                });
+
+            match result {
+                Ok(Some(true)) => report.extracted += 1,
+                Ok(Some(false)) => report.unchanged += 1,
+                Ok(None) => report.skipped_by_policy += 1,
+                Err(_) => report.failed.push(key),
+            }
+        }
+
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Extraction task error: {}", e))?
+}
+
+fn extract_and_store(path: &Path, size: u64, mtime: i64) -> Result<ExtractedText, String> {
+    let extracted = content::extract_text(path)?;
+    storage::store_content(&extracted.path, size, mtime, &extracted.text, extracted.truncated)?;
+    Ok(extracted)
+}
@@ -0,0 +1,38 @@
+// ============================================================================
+// Digest Commands
+// ============================================================================
+
+use crate::digest::{self, Digest, DigestSettings};
+use crate::storage;
+
+const DEFAULT_DIGEST_LIST: usize = 20;
+
+/// Summarize what ran since the last digest; scheduled jobs call this when
+/// they finish, with their name as `trigger`
+#[tauri::command]
+pub async fn generate_digest(trigger: Option<String>, since: Option<String>) -> Result<Digest, String> {
+    let trigger = trigger.unwrap_or_else(|| "manual".to_string());
+    tokio::task::spawn_blocking(move || digest::generate(&trigger, since))
+        .await
+        .map_err(|e| format!("Digest task error: {}", e))?
+}
+
+/// Stored digests, newest first
+#[tauri::command]
+pub async fn list_digests(limit: Option<usize>) -> Result<Vec<serde_json::Value>, String> {
+    storage::list_digests(limit.unwrap_or(DEFAULT_DIGEST_LIST))?
+        .iter()
+        .map(|json| serde_json::from_str(json).map_err(|e| format!("Failed to read digest: {}", e)))
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_digest_settings() -> Result<DigestSettings, String> {
+    Ok(digest::load_settings())
+}
+
+/// Where digests are delivered: an HTML folder and/or a local SMTP relay
+#[tauri::command]
+pub async fn set_digest_settings(settings: DigestSettings) -> Result<(), String> {
+    digest::save_settings(&settings)
+}
@@ -0,0 +1,785 @@
+// ============================================================================
+// Disk Usage Commands
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use walkdir::WalkDir;
+
+use crate::commands::files::get_file_type;
+use crate::events::{self, FsChange};
+use crate::fileops::{self, CollisionPolicy};
+use crate::scan::ScanFilter;
+use crate::shutdown::{self, OperationKind};
+use crate::staging;
+use crate::storage::{self, ExtensionStats};
+use crate::summary;
+use crate::throttle;
+
+const DEFAULT_TREE_DEPTH: usize = 3;
+const DEFAULT_TOP_FILES: usize = 50;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Cached folder totals older than this are recomputed even without a
+/// change event, since unwatched folders change silently
+const FOLDER_SIZE_MAX_AGE_SECS: i64 = 60 * 60;
+
+// A file present on both sides whose contents differ
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDifference {
+    /// Path relative to both roots
+    pub path: String,
+    pub size_a: u64,
+    pub size_b: u64,
+    /// "size", or "content" when sizes match but hashes don't
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderComparison {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub different: Vec<FileDifference>,
+    pub identical: usize,
+    /// Same-size files that couldn't be hashed, so weren't compared
+    pub unreadable: Vec<String>,
+}
+
+/// Compare two folder trees by relative path: files only in A, only in B,
+/// and files on both sides that differ by size or, with `compare_contents`
+/// (the default), by hash
+#[tauri::command]
+pub async fn compare_folders(a: String, b: String, compare_contents: Option<bool>) -> Result<FolderComparison, String> {
+    let root_a = PathBuf::from(&a);
+    let root_b = PathBuf::from(&b);
+    for root in [&root_a, &root_b] {
+        if !fileops::long_path(root).is_dir() {
+            return Err(format!("Path is not a directory: {}", root.display()));
+        }
+    }
+    let compare_contents = compare_contents.unwrap_or(true);
+
+    tokio::task::spawn_blocking(move || {
+        let files_a = relative_files(&root_a)?;
+        let mut files_b = relative_files(&root_b)?;
+        let mut comparison = FolderComparison {
+            only_in_a: Vec::new(),
+            only_in_b: Vec::new(),
+            different: Vec::new(),
+            identical: 0,
+            unreadable: Vec::new(),
+        };
+
+        for (relative, size_a) in files_a {
+            let Some(size_b) = files_b.remove(&relative) else {
+                comparison.only_in_a.push(relative.to_string_lossy().to_string());
+                continue;
+            };
+            let path = relative.to_string_lossy().to_string();
+            if size_a != size_b {
+                comparison.different.push(FileDifference {
+                    path,
+                    size_a,
+                    size_b,
+                    reason: "size".to_string(),
+                });
+                continue;
+            }
+            if !compare_contents {
+                comparison.identical += 1;
+                continue;
+            }
+
+            match (
+                fileops::cached_file_hash(&root_a.join(&relative)),
+                fileops::cached_file_hash(&root_b.join(&relative)),
+            ) {
+                (Ok((hash_a, _)), Ok((hash_b, _))) if hash_a == hash_b => comparison.identical += 1,
+                (Ok(_), Ok(_)) => comparison.different.push(FileDifference {
+                    path,
+                    size_a,
+                    size_b,
+                    reason: "content".to_string(),
+                }),
+                _ => comparison.unreadable.push(path),
+            }
+        }
+        comparison.only_in_b = files_b.into_keys().map(|p| p.to_string_lossy().to_string()).collect();
+
+        Ok::<_, String>(comparison)
+    })
+    .await
+    .map_err(|e| format!("Compare task error: {}", e))?
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MirrorOptions {
+    /// Move destination files that have no counterpart in the source to the
+    /// staging area
+    pub prune: bool,
+    /// Report what would change without touching the destination
+    pub dry_run: bool,
+    /// Also re-copy same-size files whose hashes differ, not only newer ones
+    pub compare_contents: bool,
+}
+
+// Progress of a mirror, emitted as "mirror-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorProgress {
+    pub source: String,
+    pub files_done: usize,
+    pub total_files: usize,
+    pub bytes_copied: u64,
+    pub current_path: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MirrorReport {
+    /// None for dry runs, which log nothing
+    pub batch_id: Option<String>,
+    pub dry_run: bool,
+    /// Relative paths copied because they were missing from the destination
+    pub added: Vec<String>,
+    /// Relative paths copied over an older or different destination file
+    pub updated: Vec<String>,
+    /// Relative paths removed from the destination (with `prune`)
+    pub pruned: Vec<String>,
+    pub unchanged: usize,
+    pub failed: Vec<String>,
+    /// Destination files neither overwritten nor pruned because they are
+    /// marked do-not-touch
+    pub protected: Vec<String>,
+    pub bytes_copied: u64,
+    /// One-sentence description for screen-reader announcements
+    pub summary: String,
+}
+
+/// One-way mirror of `source` into `destination`: files missing from the
+/// destination or newer (or, with `compare_contents`, different) in the
+/// source are copied over, and with `prune` destination files the source
+/// doesn't have are staged for deletion. `dry_run` reports the same lists without
+/// changing anything.
+#[tauri::command]
+pub async fn mirror_folder(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    options: Option<MirrorOptions>,
+) -> Result<MirrorReport, String> {
+    let options = options.unwrap_or_default();
+    let source_root = PathBuf::from(&source);
+    let destination_root = PathBuf::from(&destination);
+    if !fileops::long_path(&source_root).is_dir() {
+        return Err(format!("Path is not a directory: {}", source));
+    }
+    if destination_root.starts_with(&source_root) || source_root.starts_with(&destination_root) {
+        return Err("Source and destination must not contain each other".to_string());
+    }
+
+    let progress_app = app.clone();
+    let mirror_root = destination_root.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let _foreground = throttle::foreground();
+        let _operation = shutdown::begin(OperationKind::Apply, &format!("Mirroring {}", source));
+        let destination_root = mirror_root;
+        let source_files = relative_files(&source_root)?;
+        let mut destination_files = if fileops::long_path(&destination_root).is_dir() {
+            relative_files(&destination_root)?
+        } else {
+            BTreeMap::new()
+        };
+
+        let batch_id = if options.dry_run {
+            None
+        } else {
+            Some(storage::create_history_batch(
+                "Mirror folder",
+                &format!("{} -> {}", source, destination),
+            )?)
+        };
+        let mut report = MirrorReport {
+            batch_id,
+            dry_run: options.dry_run,
+            added: Vec::new(),
+            updated: Vec::new(),
+            pruned: Vec::new(),
+            unchanged: 0,
+            failed: Vec::new(),
+            protected: Vec::new(),
+            bytes_copied: 0,
+            summary: String::new(),
+        };
+        let total_files = source_files.len();
+        let mut last_emit = Instant::now();
+        let protected = storage::protected_paths()?;
+
+        for (files_done, (relative, size)) in source_files.into_iter().enumerate() {
+            let from = source_root.join(&relative);
+            let to = destination_root.join(&relative);
+            let path = relative.to_string_lossy().to_string();
+            if fileops::is_protected(&to, &protected) {
+                destination_files.remove(&relative);
+                report.protected.push(path);
+                continue;
+            }
+
+            let added = match destination_files.remove(&relative) {
+                None => true,
+                Some(destination_size) => {
+                    if !needs_update(&from, &to, size, destination_size, options.compare_contents) {
+                        report.unchanged += 1;
+                        continue;
+                    }
+                    false
+                }
+            };
+
+            if !options.dry_run {
+                match fileops::copy_with_policy(&from, &to, CollisionPolicy::Overwrite) {
+                    Ok(outcome) => {
+                        if let Some(batch_id) = &report.batch_id {
+                            if let Err(e) = storage::record_change(
+                                batch_id,
+                                "copy",
+                                &outcome.source,
+                                Some(&outcome.destination),
+                                Some(outcome.resolution.as_str()),
+                            ) {
+                                eprintln!("Failed to record copy in change log: {}", e);
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        report.failed.push(path);
+                        continue;
+                    }
+                }
+            }
+            report.bytes_copied += size;
+            if added {
+                report.added.push(path);
+            } else {
+                report.updated.push(path);
+            }
+
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = progress_app.emit(
+                    "mirror-progress",
+                    MirrorProgress {
+                        source: source.clone(),
+                        files_done: files_done + 1,
+                        total_files,
+                        bytes_copied: report.bytes_copied,
+                        current_path: from.to_string_lossy().to_string(),
+                        done: false,
+                    },
+                );
+            }
+        }
+
+        if options.prune {
+            for relative in destination_files.into_keys() {
+                let extra = destination_root.join(&relative);
+                let path = relative.to_string_lossy().to_string();
+                if fileops::is_protected(&extra, &protected) {
+                    report.protected.push(path);
+                    continue;
+                }
+                // Pruned files wait in the staging area, in case the mirror ran the wrong way
+                if let Some(batch_id) = &report.batch_id {
+                    if staging::stage(&extra, batch_id).is_err() {
+                        report.failed.push(path);
+                        continue;
+                    }
+                    remove_empty_parents(&extra, &destination_root);
+                }
+                report.pruned.push(path);
+            }
+        }
+
+        let _ = progress_app.emit(
+            "mirror-progress",
+            MirrorProgress {
+                source: source.clone(),
+                files_done: total_files,
+                total_files,
+                bytes_copied: report.bytes_copied,
+                current_path: destination.clone(),
+                done: true,
+            },
+        );
+        report.summary = summary::describe_mirror(
+            &source,
+            &destination,
+            report.added.len(),
+            report.updated.len(),
+            report.pruned.len(),
+            report.failed.len(),
+            report.dry_run,
+        );
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Mirror task error: {}", e))??;
+
+    if !report.dry_run {
+        let changes = report
+            .added
+            .iter()
+            .map(|path| FsChange::created(&destination_root.join(path)))
+            .chain(report.updated.iter().map(|path| FsChange::modified(&destination_root.join(path))))
+            .chain(report.pruned.iter().map(|path| FsChange::removed(&destination_root.join(path))))
+            .collect();
+        events::emit_fs_changed(&app, changes);
+    }
+
+    Ok(report)
+}
+
+/// Difference in modification times still treated as the same time, for
+/// destinations that round them
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+// Whether a file present on both sides should be copied again: sizes
+// differ, the source is newer, or (with `compare_contents`) hashes differ
+fn needs_update(from: &Path, to: &Path, source_size: u64, destination_size: u64, compare_contents: bool) -> bool {
+    if source_size != destination_size {
+        return true;
+    }
+    let modified = |path: &Path| fs::metadata(fileops::long_path(path)).and_then(|m| m.modified()).ok();
+    if let (Some(source_mtime), Some(destination_mtime)) = (modified(from), modified(to)) {
+        // FAT keeps times to 2 seconds, so a fresh copy can look older
+        if source_mtime > destination_mtime + MTIME_SLACK {
+            return true;
+        }
+    }
+    compare_contents
+        && match (fileops::cached_file_hash(from), fileops::cached_file_hash(to)) {
+            (Ok((hash_from, _)), Ok((hash_to, _))) => hash_from != hash_to,
+            _ => true,
+        }
+}
+
+// Remove folders left empty by pruning, stopping at `root`
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for ancestor in path.ancestors().skip(1) {
+        if ancestor == root || fs::remove_dir(fileops::long_path(ancestor)).is_err() {
+            break;
+        }
+    }
+}
+
+// Files under `root` (ignore patterns applied) by relative path, with sizes
+fn relative_files(root: &Path) -> Result<BTreeMap<PathBuf, u64>, String> {
+    let filter = ScanFilter::new(root, false)?;
+    Ok(WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|entry| {
+            let size = entry.metadata().ok()?.len();
+            let relative = entry.path().strip_prefix(root).ok()?.to_path_buf();
+            Some((relative, size))
+        })
+        .collect())
+}
+
+// Folders whose size is being recomputed, so repeated requests don't pile up walks
+static SIZING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Recursive totals of a folder. `stale` totals come from the index or an old
+// cache entry; a "folder-size-updated" event follows with fresh ones.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderSize {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    pub computed_at: Option<String>,
+    pub stale: bool,
+}
+
+/// Recursive size of a folder, answered from the cache when fresh and
+/// recomputed in the background otherwise
+#[tauri::command]
+pub async fn get_folder_size(app: AppHandle, path: String) -> Result<FolderSize, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    if let Some((size, file_count, computed_at)) = storage::get_folder_size(&path)? {
+        let fresh = chrono::DateTime::parse_from_rfc3339(&computed_at)
+            .is_ok_and(|at| chrono::Utc::now().signed_duration_since(at).num_seconds() < FOLDER_SIZE_MAX_AGE_SECS);
+        if fresh {
+            return Ok(FolderSize {
+                path,
+                size,
+                file_count,
+                computed_at: Some(computed_at),
+                stale: false,
+            });
+        }
+    }
+
+    // Answer with the indexed totals now and walk the folder in the background
+    let (size, file_count) = storage::indexed_folder_totals(&path)?;
+    if SIZING.lock().insert(path.clone()) {
+        let folder = path.clone();
+        tokio::task::spawn_blocking(move || {
+            let fresh = compute_folder_size(&folder);
+            SIZING.lock().remove(&folder);
+            match fresh {
+                Ok(fresh) => {
+                    let _ = app.emit("folder-size-updated", fresh);
+                }
+                Err(e) => eprintln!("Failed to compute folder size: {}", e),
+            }
+        });
+    }
+
+    Ok(FolderSize {
+        path,
+        size,
+        file_count,
+        computed_at: None,
+        stale: true,
+    })
+}
+
+// Walk a folder, summing file sizes, and cache the result
+fn compute_folder_size(path: &str) -> Result<FolderSize, String> {
+    let (mut size, mut file_count) = (0u64, 0u64);
+    for entry in WalkDir::new(path).min_depth(1).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            size += metadata.len();
+            file_count += 1;
+        }
+    }
+
+    let computed_at = chrono::Utc::now().to_rfc3339();
+    storage::store_folder_size(path, size, file_count, &computed_at)?;
+    Ok(FolderSize {
+        path: path.to_string(),
+        size,
+        file_count,
+        computed_at: Some(computed_at),
+        stale: false,
+    })
+}
+
+// Folder in the size tree; `own_size` is the bytes of files directly inside,
+// so a treemap can draw it as a leaf next to the subfolders
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageNode {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub own_size: u64,
+    pub file_count: u64,
+    pub children: Vec<UsageNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeFile {
+    pub path: String,
+    pub size: u64,
+    pub file_type: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeUsage {
+    pub file_type: String,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiskUsageReport {
+    pub tree: UsageNode,
+    pub largest_files: Vec<LargeFile>,
+    pub by_type: Vec<TypeUsage>,
+    pub total_size: u64,
+    pub file_count: u64,
+    pub folder_count: u64,
+    /// Entries that could not be read (permissions, vanished files)
+    pub unreadable: u64,
+    pub elapsed_ms: u64,
+}
+
+// Progress of an analysis, emitted as "disk-usage-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsageProgress {
+    pub root: String,
+    pub files_scanned: u64,
+    pub bytes: u64,
+    pub current_path: String,
+    pub done: bool,
+}
+
+// Totals for one folder while walking
+#[derive(Default)]
+struct FolderTotals {
+    size: u64,
+    own_size: u64,
+    file_count: u64,
+    children: Vec<PathBuf>,
+}
+
+/// Walk `path` and report where the space went: a folder size tree down to
+/// `max_depth`, the largest files and a per-type breakdown.
+///
+/// Ignore patterns are not applied here; big ignored folders such as
+/// node_modules are exactly what users want to find.
+#[tauri::command]
+pub async fn analyze_disk_usage(
+    app: AppHandle,
+    path: String,
+    max_depth: Option<usize>,
+    top_files: Option<usize>,
+) -> Result<DiskUsageReport, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let max_depth = max_depth.unwrap_or(DEFAULT_TREE_DEPTH);
+    let top_files = top_files.unwrap_or(DEFAULT_TOP_FILES);
+
+    tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+        let mut last_emit = Instant::now();
+
+        let mut folders: HashMap<PathBuf, FolderTotals> = HashMap::new();
+        folders.insert(root.clone(), FolderTotals::default());
+        let mut largest: BinaryHeap<Reverse<(u64, String)>> = BinaryHeap::new();
+        let mut by_type: HashMap<String, (u64, u64)> = HashMap::new();
+        let (mut total_size, mut file_count, mut folder_count, mut unreadable) = (0u64, 0u64, 0u64, 0u64);
+
+        for entry in WalkDir::new(&root).min_depth(1) {
+            let Ok(entry) = entry else {
+                unreadable += 1;
+                continue;
+            };
+
+            if entry.file_type().is_dir() {
+                folder_count += 1;
+                if entry.depth() <= max_depth {
+                    if let Some(parent) = entry.path().parent() {
+                        folders
+                            .entry(parent.to_path_buf())
+                            .or_default()
+                            .children
+                            .push(entry.path().to_path_buf());
+                    }
+                    folders.entry(entry.path().to_path_buf()).or_default();
+                }
+                continue;
+            }
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let Ok(metadata) = entry.metadata() else {
+                unreadable += 1;
+                continue;
+            };
+            let size = metadata.len();
+            total_size += size;
+            file_count += 1;
+
+            // Charge the file to every tracked ancestor
+            let mut direct = true;
+            for ancestor in entry.path().ancestors().skip(1) {
+                if let Some(totals) = folders.get_mut(ancestor) {
+                    totals.size += size;
+                    totals.file_count += 1;
+                    if direct {
+                        totals.own_size += size;
+                    }
+                }
+                direct = false;
+                if ancestor == root {
+                    break;
+                }
+            }
+
+            let extension = entry
+                .path()
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let type_totals = by_type.entry(get_file_type(&extension)).or_default();
+            type_totals.0 += size;
+            type_totals.1 += 1;
+
+            if top_files > 0 {
+                largest.push(Reverse((size, entry.path().to_string_lossy().to_string())));
+                if largest.len() > top_files {
+                    largest.pop();
+                }
+            }
+
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = app.emit(
+                    "disk-usage-progress",
+                    DiskUsageProgress {
+                        root: path.clone(),
+                        files_scanned: file_count,
+                        bytes: total_size,
+                        current_path: entry.path().to_string_lossy().to_string(),
+                        done: false,
+                    },
+                );
+            }
+        }
+
+        let _ = app.emit(
+            "disk-usage-progress",
+            DiskUsageProgress {
+                root: path.clone(),
+                files_scanned: file_count,
+                bytes: total_size,
+                current_path: path.clone(),
+                done: true,
+            },
+        );
+
+        let mut largest_files: Vec<LargeFile> = largest
+            .into_iter()
+            .map(|Reverse((size, path))| LargeFile {
+                file_type: Path::new(&path)
+                    .extension()
+                    .map(|e| get_file_type(&e.to_string_lossy()))
+                    .unwrap_or_else(|| "other".to_string()),
+                path,
+                size,
+            })
+            .collect();
+        largest_files.sort_by(|a, b| b.size.cmp(&a.size));
+
+        let mut by_type: Vec<TypeUsage> = by_type
+            .into_iter()
+            .map(|(file_type, (size, file_count))| TypeUsage {
+                file_type,
+                size,
+                file_count,
+            })
+            .collect();
+        by_type.sort_by(|a, b| b.size.cmp(&a.size));
+
+        Ok::<_, String>(DiskUsageReport {
+            tree: build_tree(&root, &mut folders),
+            largest_files,
+            by_type,
+            total_size,
+            file_count,
+            folder_count,
+            unreadable,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    })
+    .await
+    .map_err(|e| format!("Disk usage task error: {}", e))?
+}
+
+// Index totals for one file type, rolled up from its extensions
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTypeStats {
+    pub file_type: String,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub average_size: u64,
+    pub newest_modified_at: Option<String>,
+    pub oldest_modified_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexStats {
+    pub path: String,
+    pub by_extension: Vec<ExtensionStats>,
+    pub by_file_type: Vec<FileTypeStats>,
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+/// Counts, sizes and date ranges of the indexed files under `path`, per
+/// extension and per file type, for the dashboard charts. Reads only the
+/// index, so it is instant but as fresh as the last scan.
+#[tauri::command]
+pub async fn get_index_stats(path: String) -> Result<IndexStats, String> {
+    tokio::task::spawn_blocking(move || {
+        let by_extension = storage::extension_stats(&path)?;
+
+        let mut types: BTreeMap<String, FileTypeStats> = BTreeMap::new();
+        for stats in &by_extension {
+            let totals = types.entry(stats.file_type.clone()).or_insert_with(|| FileTypeStats {
+                file_type: stats.file_type.clone(),
+                file_count: 0,
+                total_size: 0,
+                average_size: 0,
+                newest_modified_at: None,
+                oldest_modified_at: None,
+            });
+            totals.file_count += stats.file_count;
+            totals.total_size += stats.total_size;
+            totals.newest_modified_at = totals.newest_modified_at.clone().max(stats.newest_modified_at.clone());
+            totals.oldest_modified_at = match (&totals.oldest_modified_at, &stats.oldest_modified_at) {
+                (Some(a), Some(b)) => Some(a.min(b).clone()),
+                (a, b) => a.clone().or_else(|| b.clone()),
+            };
+        }
+        let mut by_file_type: Vec<FileTypeStats> = types
+            .into_values()
+            .map(|mut totals| {
+                totals.average_size = totals.total_size / totals.file_count.max(1);
+                totals
+            })
+            .collect();
+        by_file_type.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+
+        Ok::<_, String>(IndexStats {
+            file_count: by_extension.iter().map(|s| s.file_count).sum(),
+            total_size: by_extension.iter().map(|s| s.total_size).sum(),
+            path,
+            by_extension,
+            by_file_type,
+        })
+    })
+    .await
+    .map_err(|e| format!("Stats task error: {}", e))?
+}
+
+// Turn the flat folder totals into a nested tree, largest children first
+fn build_tree(path: &Path, folders: &mut HashMap<PathBuf, FolderTotals>) -> UsageNode {
+    let totals = folders.remove(path).unwrap_or_default();
+    let mut children: Vec<UsageNode> = totals
+        .children
+        .iter()
+        .map(|child| build_tree(child, folders))
+        .collect();
+    children.sort_by(|a, b| b.size.cmp(&a.size));
+
+    UsageNode {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string()),
+        path: path.to_string_lossy().to_string(),
+        size: totals.size,
+        own_size: totals.own_size,
+        file_count: totals.file_count,
+        children,
+    }
+}
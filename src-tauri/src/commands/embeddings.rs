@@ -0,0 +1,151 @@
+// ============================================================================
+// Embedding Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::commands::ai::{get_model_dir, AiFeature};
+use crate::embeddings;
+use crate::fileops;
+use crate::libraries;
+use crate::scan::ScanFilter;
+use crate::shutdown::{self, OperationKind};
+use crate::storage::{self, EmbeddingCoverage};
+use crate::throttle;
+use crate::vector_index;
+
+/// Files embedded per model call
+const EMBED_BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingIndexReport {
+    pub model_id: String,
+    pub embedded: usize,
+    /// Files whose content already has a vector for this model
+    pub unchanged: usize,
+    /// Files in libraries whose policy stops short of embeddings
+    pub skipped_by_policy: usize,
+    pub failed: Vec<String>,
+}
+
+/// Embed the files under `path`, skipping content that is already embedded
+/// with the current model and files their library's policy leaves out
+#[tauri::command]
+pub async fn index_embeddings(app: AppHandle, path: String, recursive: bool) -> Result<EmbeddingIndexReport, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    let model_id = embeddings::current_model_id();
+    let model_path = get_model_dir(&app)?.join(&model_id);
+    if !model_path.exists() {
+        return Err(format!("Embedding model not downloaded: {}", model_id));
+    }
+    let filter = ScanFilter::new(&root, false)?;
+
+    tokio::task::spawn_blocking(move || {
+        let operation = shutdown::begin(OperationKind::Index, &format!("Embedding files in {}", path));
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        let files: Vec<PathBuf> = WalkDir::new(&root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .collect();
+        let libraries = libraries::list();
+        let before = files.len();
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|file| {
+                let size = fs::metadata(fileops::long_path(file)).map_or(0, |metadata| metadata.len());
+                libraries::policy_for(file, &libraries).allows_embeddings(size)
+            })
+            .collect();
+
+        let mut report = EmbeddingIndexReport {
+            model_id: model_id.clone(),
+            embedded: 0,
+            unchanged: 0,
+            skipped_by_policy: before - files.len(),
+            failed: Vec::new(),
+        };
+
+        // Content hashes decide what needs (re-)embedding
+        let mut pending: Vec<(PathBuf, String)> = Vec::new();
+        for file in files {
+            if operation.should_stop() {
+                break;
+            }
+            throttle::yield_to_foreground();
+            match fileops::cached_file_hash(&file) {
+                Ok((hash, _)) => {
+                    if storage::has_embedding(&hash, &model_id)?
+                        || pending.iter().any(|(_, h)| h == &hash)
+                    {
+                        report.unchanged += 1;
+                    } else {
+                        pending.push((file, hash));
+                    }
+                }
+                Err(_) => report.failed.push(file.to_string_lossy().to_string()),
+            }
+        }
+
+        for chunk in pending.chunks(EMBED_BATCH_SIZE) {
+            if operation.should_stop() {
+                break;
+            }
+            throttle::yield_to_foreground();
+            let texts: Vec<String> = chunk
+                .iter()
+                .map(|(file, _)| embeddings::embedding_text(file))
+                .collect();
+            let vectors = crate::commands::ai::embed_texts(&model_path, &texts, AiFeature::Embeddings)?;
+
+            for ((file, hash), vector) in chunk.iter().zip(vectors) {
+                let bytes = embeddings::encode_vector(&vector);
+                match storage::store_embedding(hash, &model_id, vector.len(), &bytes) {
+                    Ok(()) => report.embedded += 1,
+                    Err(_) => report.failed.push(file.to_string_lossy().to_string()),
+                }
+            }
+        }
+
+        // Bring the ANN index up to date with the new vectors
+        if report.embedded > 0 {
+            vector_index::sync(&app)?;
+        }
+
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Embedding task error: {}", e))?
+}
+
+/// How many files under `path` are hashed and embedded with the current model
+#[tauri::command]
+pub async fn embedding_coverage(path: Option<String>) -> Result<EmbeddingCoverage, String> {
+    let model_id = embeddings::current_model_id();
+    storage::embedding_coverage(path.as_deref(), &model_id)
+}
+
+/// Switch the embedding model; only vectors from other models are invalidated
+#[tauri::command]
+pub async fn set_embedding_model(app: AppHandle, model_id: String) -> Result<usize, String> {
+    if Path::new(&model_id).components().count() != 1 {
+        return Err("Model id must be a file name".to_string());
+    }
+    if !get_model_dir(&app)?.join(&model_id).exists() {
+        return Err(format!("Model not found: {}", model_id));
+    }
+    let invalidated = embeddings::set_model_id(&model_id)?;
+    vector_index::reset();
+    Ok(invalidated)
+}
@@ -3,11 +3,32 @@
 // ============================================================================
 
 use serde::{Deserialize, Serialize};
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::archive::{self, ArchiveEntry, ArchiveFormat};
+use crate::events::{self, FsChange};
+use crate::fileops::{self, CollisionPolicy, MoveOutcome};
+use crate::libraries::{self, IndexDepth};
+use crate::network;
+use crate::photos;
+use crate::scan::{self, ScanFilter};
+use crate::screenshot;
+use crate::search::SearchFilters;
+use crate::similar_names;
+use crate::staging;
+use crate::storage::{self, IndexedFile};
+use crate::throttle;
+use crate::xattrs::{self, DataStream, FileTag};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileNode {
     pub id: String,
     pub name: String,
@@ -19,7 +40,27 @@ pub struct FileNode {
     pub modified_at: String,
     pub created_at: String,
     pub extension: Option<String>,
+    /// MIME type from the file's magic bytes, or from the extension
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub permissions: Option<fileops::FilePermissions>,
     pub children: Option<Vec<FileNode>>,
+    #[serde(default)]
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    /// Marked do-not-touch: never part of a plan or cleanup
+    #[serde(default)]
+    pub protected: bool,
+    /// Online-only cloud file; reading it downloads it, so its contents are
+    /// never sniffed and `size` may not be what it takes once local
+    #[serde(default)]
+    pub placeholder: bool,
+    /// On a network share: slower to read, and listed less deeply
+    #[serde(default)]
+    pub network: bool,
+    /// NTFS alternate data streams; only looked up by get_file_info
+    #[serde(default)]
+    pub alternate_streams: Option<Vec<DataStream>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,22 +75,129 @@ pub struct FileListResponse {
     pub files: Vec<FileNode>,
     pub stats: FileStats,
     pub path: String,
+    pub total: usize,
+    pub offset: usize,
+    pub next_offset: Option<usize>,
+    /// Symlinked folders not descended into because they loop or leave the root
+    pub skipped_symlinks: Vec<String>,
+}
+
+// Chunk of a streamed listing, emitted as "file-list-chunk"
+#[derive(Debug, Clone, Serialize)]
+pub struct FileListChunk {
+    pub path: String,
+    pub files: Vec<FileNode>,
+    pub offset: usize,
+    pub done: bool,
+}
+
+const STREAM_CHUNK_SIZE: usize = 500;
+
+// Lightweight directory entry used to sort and page before building FileNodes
+struct ListedEntry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl ListedEntry {
+    fn file_type(&self) -> String {
+        if self.is_dir {
+            return "folder".to_string();
+        }
+        let file_type = self
+            .path
+            .extension()
+            .map(|e| get_file_type(&e.to_string_lossy()))
+            .unwrap_or_else(|| "other".to_string());
+        // Listing doesn't read contents, so only the name can tell
+        if file_type == "image" && screenshot::has_screenshot_name(&self.name) {
+            return screenshot::SCREENSHOT_FILE_TYPE.to_string();
+        }
+        file_type
+    }
+
+    // Filters use the same semantics as search; folders never match
+    fn matches(&self, filters: &SearchFilters) -> bool {
+        if self.is_dir {
+            return false;
+        }
+        filters.matches(&IndexedFile {
+            path: self.path.to_string_lossy().to_string(),
+            name: self.name.clone(),
+            file_type: Some(self.file_type()),
+            size: self.size,
+            modified_at: self
+                .modified
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .unwrap_or_default(),
+            parent_path: None,
+        })
+    }
 }
 
-/// List files in a directory
+// Listing order; folders always come before files
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Type,
+}
+
+// Sorting and filtering applied before paging
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ListView {
+    pub sort_by: SortBy,
+    pub descending: bool,
+    /// Only files matching these filters are listed; folders are left out
+    /// while a filter is set
+    pub filter: Option<SearchFilters>,
+}
+
+/// List files in a directory.
+///
+/// `offset`/`limit` page through the sorted listing; with `stream` set, the
+/// page is delivered as "file-list-chunk" events and `files` is left empty.
+/// Entries matching the ignore patterns, and hidden entries unless
+/// `show_hidden` is set, are skipped. Symlinked folders are only descended
+/// into with `follow_symlinks`, and never when they loop back on an ancestor
+/// or point outside `path`. `view` sorts and filters before paging; stats
+/// describe the filtered listing.
 #[tauri::command]
-pub async fn list_files(path: String, recursive: bool) -> Result<FileListResponse, String> {
+#[allow(clippy::too_many_arguments)]
+pub async fn list_files(
+    app: AppHandle,
+    path: String,
+    recursive: bool,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    stream: Option<bool>,
+    show_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    view: Option<ListView>,
+) -> Result<FileListResponse, String> {
+    let view = view.unwrap_or_default();
     let path_buf = PathBuf::from(&path);
+    let os_path = fileops::long_path(&path_buf);
 
-    if !path_buf.exists() {
+    if !os_path.exists() {
         return Err(format!("Path does not exist: {}", path));
     }
 
-    if !path_buf.is_dir() {
+    if !os_path.is_dir() {
         return Err(format!("Path is not a directory: {}", path));
     }
 
-    let mut files: Vec<FileNode> = Vec::new();
+    let filter = ScanFilter::new(&path_buf, show_hidden.unwrap_or(false))?;
+    let follow_symlinks = follow_symlinks.unwrap_or(false);
+    let mut entries: Vec<ListedEntry> = Vec::new();
+    let mut skipped_symlinks: Vec<String> = Vec::new();
     let mut stats = FileStats {
         total_files: 0,
         total_folders: 0,
@@ -57,97 +205,1369 @@ pub async fn list_files(path: String, recursive: bool) -> Result<FileListRespons
     };
 
     if recursive {
-        // Recursive listing
-        for entry in WalkDir::new(&path_buf).max_depth(10).into_iter().filter_map(|e| e.ok()) {
-            if entry.path() == path_buf {
-                continue;
+        // Recursive listing, scanned in parallel off the async runtime
+        let root = path_buf.clone();
+        let scan_app = app.clone();
+        let (scanned, skipped) = tokio::task::spawn_blocking(move || {
+            scan_recursive(&scan_app, &root, &filter, follow_symlinks)
+        })
+        .await
+        .map_err(|e| format!("Scan task error: {}", e))?;
+        entries = scanned;
+        skipped_symlinks = skipped;
+    } else {
+        // Non-recursive listing
+        if let Ok(read_dir) = fs::read_dir(&os_path) {
+            for entry in read_dir.filter_map(|e| e.ok()) {
+                if let Some(listed) = listed_entry(&fileops::display_path(&entry.path())) {
+                    if filter.allows(&listed.path, listed.is_dir) {
+                        entries.push(listed);
+                    }
+                }
             }
+        }
+    }
 
-            match create_file_node(entry.path()) {
-                Ok(node) => {
-                    if node.node_type == "folder" {
-                        stats.total_folders += 1;
-                    } else {
-                        stats.total_files += 1;
-                        stats.total_size += node.size;
+    if let Some(filter) = &view.filter {
+        entries.retain(|entry| entry.matches(filter));
+    }
+
+    for entry in &entries {
+        if entry.is_dir {
+            stats.total_folders += 1;
+        } else {
+            stats.total_files += 1;
+            stats.total_size += entry.size;
+        }
+    }
+
+    // Sort: folders first, then by the requested key with name as tie-breaker
+    entries.sort_by(|a, b| {
+        if a.is_dir != b.is_dir {
+            return if a.is_dir {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            };
+        }
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let order = match view.sort_by {
+            SortBy::Name => by_name(),
+            SortBy::Size => a.size.cmp(&b.size).then_with(by_name),
+            SortBy::Modified => a.modified.cmp(&b.modified).then_with(by_name),
+            SortBy::Type => a.file_type().cmp(&b.file_type()).then_with(by_name),
+        };
+        if view.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+
+    let total = entries.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let end = match limit {
+        Some(limit) => offset.saturating_add(limit).min(total),
+        None => total,
+    };
+    let next_offset = if end < total { Some(end) } else { None };
+
+    let page = &entries[offset..end];
+
+    if stream.unwrap_or(false) {
+        let mut chunk_offset = offset;
+        for chunk in page.chunks(STREAM_CHUNK_SIZE) {
+            let files = build_nodes(chunk);
+            chunk_offset += chunk.len();
+            let _ = app.emit("file-list-chunk", FileListChunk {
+                path: path.clone(),
+                files,
+                offset: chunk_offset - chunk.len(),
+                done: chunk_offset == end,
+            });
+        }
+        if page.is_empty() {
+            let _ = app.emit("file-list-chunk", FileListChunk {
+                path: path.clone(),
+                files: Vec::new(),
+                offset,
+                done: true,
+            });
+        }
+
+        return Ok(FileListResponse {
+            files: Vec::new(),
+            stats,
+            path,
+            total,
+            offset,
+            next_offset,
+            skipped_symlinks,
+        });
+    }
+
+    let files = build_nodes(page);
+
+    Ok(FileListResponse {
+        files,
+        stats,
+        path,
+        total,
+        offset,
+        next_offset,
+        skipped_symlinks,
+    })
+}
+
+// Progress of a recursive scan, emitted as "scan-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub root: String,
+    pub files_found: u64,
+    pub folders_found: u64,
+    pub bytes: u64,
+    pub current_path: String,
+    pub done: bool,
+}
+
+const MAX_SCAN_DEPTH: usize = 10;
+const SCAN_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Shared counters updated by scan workers
+struct ScanCounters {
+    files: AtomicU64,
+    folders: AtomicU64,
+    bytes: AtomicU64,
+    last_emit: Mutex<Instant>,
+}
+
+impl ScanCounters {
+    fn record(&self, entry: &ListedEntry) {
+        if entry.is_dir {
+            self.folders.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.files.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(entry.size, Ordering::Relaxed);
+        }
+    }
+
+    fn progress(&self, root: &Path, current: &Path, done: bool) -> ScanProgress {
+        ScanProgress {
+            root: root.to_string_lossy().to_string(),
+            files_found: self.files.load(Ordering::Relaxed),
+            folders_found: self.folders.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            current_path: current.to_string_lossy().to_string(),
+            done,
+        }
+    }
+
+    // Emit at most once per interval, from whichever worker gets there first
+    fn maybe_emit(&self, app: &AppHandle, root: &Path, current: &Path) {
+        if let Some(mut last) = self.last_emit.try_lock() {
+            if last.elapsed() >= SCAN_PROGRESS_INTERVAL {
+                *last = Instant::now();
+                let _ = app.emit("scan-progress", self.progress(root, current, false));
+            }
+        }
+    }
+}
+
+/// Walk `root` breadth-first, reading each level's directories in parallel.
+/// Network shares are walked less deeply and by fewer threads, and folders
+/// that fail to list over a flaky connection are retried.
+///
+/// Returns the listed entries and the symlinked folders that were not
+/// descended into because they loop or point outside `root`.
+fn scan_recursive(
+    app: &AppHandle,
+    root: &Path,
+    filter: &ScanFilter,
+    follow_symlinks: bool,
+) -> (Vec<ListedEntry>, Vec<String>) {
+    let canonical_root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let counters = ScanCounters {
+        files: AtomicU64::new(0),
+        folders: AtomicU64::new(0),
+        bytes: AtomicU64::new(0),
+        last_emit: Mutex::new(Instant::now()),
+    };
+
+    let mut entries = Vec::new();
+    let mut skipped_symlinks = Vec::new();
+    let mut visited: HashSet<PathBuf> = HashSet::from([canonical_root.clone()]);
+    let mut level = vec![root.to_path_buf()];
+
+    let network = network::is_network_path(root);
+    let max_depth = if network { network::NETWORK_SCAN_DEPTH } else { MAX_SCAN_DEPTH };
+    let pool = network
+        .then(|| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(network::NETWORK_SCAN_THREADS)
+                .build()
+                .ok()
+        })
+        .flatten();
+
+    for _ in 0..max_depth {
+        if level.is_empty() {
+            break;
+        }
+
+        let read_level = || {
+            level
+                .par_iter()
+                .map(|dir| {
+                    let listed: Vec<ListedEntry> =
+                        network::retrying(network, || fs::read_dir(fileops::long_path(dir)))
+                            .map(|read_dir| {
+                                read_dir
+                                    .filter_map(|e| e.ok())
+                                    .filter_map(|e| listed_entry(&fileops::display_path(&e.path())))
+                                    .filter(|listed| filter.allows(&listed.path, listed.is_dir))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                    for entry in &listed {
+                        counters.record(entry);
+                    }
+                    counters.maybe_emit(app, root, dir);
+                    listed
+                })
+                .collect::<Vec<Vec<ListedEntry>>>()
+        };
+        let results = match &pool {
+            Some(pool) => pool.install(read_level),
+            None => read_level(),
+        };
+
+        let mut next_level = Vec::new();
+        for entry in results.into_iter().flatten() {
+            let is_symlink = entry.is_dir
+                && fs::symlink_metadata(&entry.path)
+                    .map(|m| m.file_type().is_symlink())
+                    .unwrap_or(false);
+
+            // Symlinked folders are listed but only descended into when following links
+            if entry.is_dir && (follow_symlinks || !is_symlink) {
+                let canonical = fs::canonicalize(&entry.path).unwrap_or_else(|_| entry.path.clone());
+                let escapes = is_symlink && !canonical.starts_with(&canonical_root);
+                if escapes || !visited.insert(canonical) {
+                    if is_symlink {
+                        skipped_symlinks.push(entry.path.to_string_lossy().to_string());
                     }
-                    files.push(node);
+                } else {
+                    next_level.push(entry.path.clone());
                 }
-                Err(_) => continue,
             }
+            entries.push(entry);
         }
-    } else {
-        // Non-recursive listing
-        if let Ok(entries) = fs::read_dir(&path_buf) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                match create_file_node(&entry.path()) {
-                    Ok(node) => {
-                        if node.node_type == "folder" {
-                            stats.total_folders += 1;
-                        } else {
-                            stats.total_files += 1;
-                            stats.total_size += node.size;
-                        }
-                        files.push(node);
+
+        level = next_level;
+    }
+
+    let _ = app.emit("scan-progress", counters.progress(root, root, true));
+
+    (entries, skipped_symlinks)
+}
+
+// Describe a path for sorting and paging; symlinks report their target's
+// type and size, broken ones fall back to the link itself
+fn listed_entry(path: &Path) -> Option<ListedEntry> {
+    let os_path = fileops::long_path(path);
+    let metadata = fs::metadata(&os_path).or_else(|_| fs::symlink_metadata(&os_path)).ok()?;
+    Some(ListedEntry {
+        path: path.to_path_buf(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        is_dir: metadata.is_dir(),
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+// Build FileNodes for a slice of entries and record them in the metadata cache
+fn build_nodes(entries: &[ListedEntry]) -> Vec<FileNode> {
+    let protected = storage::protected_paths().unwrap_or_default();
+    let files: Vec<FileNode> = entries
+        .iter()
+        .filter_map(|entry| create_file_node(&entry.path).ok())
+        .map(|mut node| {
+            node.protected = fileops::is_protected(Path::new(&node.path), &protected);
+            node
+        })
+        .collect();
+
+    // Keep the metadata cache warm for rule previews and search
+    if let Err(e) = storage::index_file_nodes(&files) {
+        eprintln!("Failed to index listed files: {}", e);
+    }
+
+    files
+}
+
+/// Get the ignore patterns applied to directory scans
+#[tauri::command]
+pub async fn get_ignore_patterns() -> Result<Vec<String>, String> {
+    Ok(scan::load_ignore_patterns())
+}
+
+/// Replace the ignore patterns applied to directory scans
+#[tauri::command]
+pub async fn set_ignore_patterns(patterns: Vec<String>) -> Result<(), String> {
+    scan::save_ignore_patterns(&patterns)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HashResult {
+    pub path: String,
+    pub hash: String,
+    pub cached: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HashFolderResult {
+    pub results: Vec<HashResult>,
+    pub hashed: usize,
+    pub cached: usize,
+    /// Files in libraries indexed by name only
+    pub skipped_by_policy: usize,
+    pub failed: Vec<String>,
+}
+
+/// Compute (or reuse the cached) SHA-256 of a file
+#[tauri::command]
+pub async fn hash_file(path: String) -> Result<HashResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let (hash, cached) = fileops::cached_file_hash(Path::new(&path))?;
+        Ok::<_, String>(HashResult { path, hash, cached })
+    })
+    .await
+    .map_err(|e| format!("Hash task error: {}", e))?
+}
+
+/// Hash every file in a folder, skipping files unchanged since their last
+/// hash and files in libraries whose policy stops short of hashes
+#[tauri::command]
+pub async fn hash_folder(path: String, recursive: bool) -> Result<HashFolderResult, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let filter = ScanFilter::new(&root, false)?;
+
+    tokio::task::spawn_blocking(move || {
+        let files: Vec<PathBuf> = if recursive {
+            WalkDir::new(&root)
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.into_path())
+                .collect()
+        } else {
+            fs::read_dir(&root)
+                .map_err(|e| format!("Failed to read directory: {}", e))?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && filter.allows(p, false))
+                .collect()
+        };
+
+        let libraries = libraries::list();
+        let before = files.len();
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|file| libraries::policy_for(file, &libraries).allows(IndexDepth::Hashes))
+            .collect();
+        let skipped_by_policy = before - files.len();
+
+        // Hash in parallel; the database lock serializes the cache writes
+        let outcomes: Vec<(PathBuf, Result<(String, bool), String>)> = files
+            .into_par_iter()
+            .map(|file| {
+                throttle::yield_to_foreground();
+                let outcome = fileops::cached_file_hash(&file);
+                (file, outcome)
+            })
+            .collect();
+
+        let mut report = HashFolderResult {
+            results: Vec::new(),
+            hashed: 0,
+            cached: 0,
+            skipped_by_policy,
+            failed: Vec::new(),
+        };
+        for (file, outcome) in outcomes {
+            let path = file.to_string_lossy().to_string();
+            match outcome {
+                Ok((hash, cached)) => {
+                    if cached {
+                        report.cached += 1;
+                    } else {
+                        report.hashed += 1;
                     }
-                    Err(_) => continue,
+                    report.results.push(HashResult { path, hash, cached });
                 }
+                Err(_) => report.failed.push(path),
             }
         }
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Hash task error: {}", e))?
+}
+
+/// Names at least this similar are clustered unless the caller says otherwise
+const DEFAULT_NAME_SIMILARITY: f32 = 0.8;
+
+// A file in a cluster of similar names
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified_at: String,
+}
+
+// Files whose names differ only by copy/version markers or small edits
+#[derive(Debug, Serialize)]
+pub struct SimilarNameCluster {
+    /// Shared name with the markers stripped, e.g. "report" for "report_final(1).docx"
+    pub key: String,
+    /// Lowest similarity between two names that joined the cluster
+    pub similarity: f32,
+    /// Newest first, so the likely current version leads
+    pub files: Vec<SimilarFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarNamesResult {
+    pub clusters: Vec<SimilarNameCluster>,
+    pub files_scanned: usize,
+}
+
+/// Cluster files under `path` whose names are near-identical
+/// ("report_final.docx", "report_final(1).docx", "report final v2.docx")
+/// using edit distance and token overlap. Only files with the same
+/// extension are compared.
+#[tauri::command]
+pub async fn find_similar_names(
+    path: String,
+    recursive: bool,
+    threshold: Option<f32>,
+) -> Result<SimilarNamesResult, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
     }
+    let threshold = threshold.unwrap_or(DEFAULT_NAME_SIMILARITY).clamp(0.0, 1.0);
+    let filter = ScanFilter::new(&root, false)?;
 
-    // Sort: folders first, then by name
-    files.sort_by(|a, b| {
-        if a.node_type != b.node_type {
-            if a.node_type == "folder" {
-                std::cmp::Ordering::Less
+    tokio::task::spawn_blocking(move || {
+        let _foreground = throttle::foreground();
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        let files: Vec<(PathBuf, fs::Metadata)> = WalkDir::new(&root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let metadata = e.metadata().ok()?;
+                Some((e.into_path(), metadata))
+            })
+            .collect();
+
+        let names: Vec<similar_names::NameKey> = files
+            .iter()
+            .map(|(file, _)| similar_names::name_key(&file.file_name().unwrap_or_default().to_string_lossy()))
+            .collect();
+
+        let clusters = similar_names::cluster(&names, threshold)
+            .into_iter()
+            .map(|(mut members, similarity)| {
+                members.sort_by_key(|&i| std::cmp::Reverse(files[i].1.modified().ok()));
+                SimilarNameCluster {
+                    key: names[members[0]].key.clone(),
+                    similarity,
+                    files: members
+                        .into_iter()
+                        .map(|i| &files[i])
+                        .map(|(file, metadata)| SimilarFile {
+                            path: file.to_string_lossy().to_string(),
+                            name: file.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                            size: metadata.len(),
+                            modified_at: metadata
+                                .modified()
+                                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+                                .unwrap_or_default(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Ok::<_, String>(SimilarNamesResult {
+            clusters,
+            files_scanned: files.len(),
+        })
+    })
+    .await
+    .map_err(|e| format!("Similar names task error: {}", e))?
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveContents {
+    pub path: String,
+    pub format: ArchiveFormat,
+    pub entries: Vec<ArchiveEntry>,
+    pub total_size: u64,
+    pub file_count: usize,
+}
+
+/// List what's inside a zip, tar, tar.gz or 7z archive without extracting it
+#[tauri::command]
+pub async fn inspect_archive(path: String) -> Result<ArchiveContents, String> {
+    tokio::task::spawn_blocking(move || {
+        let (format, entries) = archive::list_entries(Path::new(&path))?;
+        let files = entries.iter().filter(|e| !e.is_dir);
+        let total_size = files.clone().map(|e| e.size).sum();
+        let file_count = files.count();
+
+        Ok::<_, String>(ArchiveContents {
+            path,
+            format,
+            entries,
+            total_size,
+            file_count,
+        })
+    })
+    .await
+    .map_err(|e| format!("Archive task error: {}", e))?
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CompressOptions {
+    /// Remove the originals once the archive has been verified
+    pub delete_originals: bool,
+}
+
+// Progress of compress_files, emitted as "compress-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct CompressProgress {
+    pub archive: String,
+    pub entries_done: usize,
+    pub total_entries: usize,
+    pub bytes: u64,
+    pub current_path: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompressResult {
+    pub archive: String,
+    pub batch_id: String,
+    pub entries: usize,
+    pub bytes: u64,
+    pub archive_size: u64,
+    pub originals_deleted: bool,
+}
+
+/// Zip a selection of files and folders into `destination`.
+///
+/// Each selected path is logged as a "compress" operation. Removed originals
+/// go to the staging area and are logged as "stage" changes, so undoing the
+/// batch brings them back and sets the archive aside.
+#[tauri::command]
+pub async fn compress_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    destination: String,
+    options: Option<CompressOptions>,
+) -> Result<CompressResult, String> {
+    let options = options.unwrap_or_default();
+    if paths.is_empty() {
+        return Err("Nothing to compress".to_string());
+    }
+    let sources: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let mut archive_path = PathBuf::from(&destination);
+    if archive_path.extension().is_none() {
+        archive_path.set_extension("zip");
+    }
+    if fileops::long_path(&archive_path).exists() {
+        archive_path = fileops::unique_destination(&archive_path);
+    }
+    // An archive inside a selected folder would be zipped into itself and
+    // deleted along with the originals
+    if let Some(source) = sources.iter().find(|source| archive_path.starts_with(source)) {
+        return Err(format!("The archive can't be saved inside {}", source.display()));
+    }
+
+    let progress_app = app.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let inputs = archive::collect_inputs(&sources)?;
+        let archive_name = archive_path.to_string_lossy().to_string();
+        let total_entries = inputs.len();
+        let mut last_emit = Instant::now();
+
+        let bytes = archive::create_zip(&inputs, &archive_path, |entries_done, bytes, current| {
+            if last_emit.elapsed() >= SCAN_PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = progress_app.emit(
+                    "compress-progress",
+                    CompressProgress {
+                        archive: archive_name.clone(),
+                        entries_done,
+                        total_entries,
+                        bytes,
+                        current_path: current.to_string_lossy().to_string(),
+                        done: false,
+                    },
+                );
+            }
+        })
+        .map_err(|e| {
+            let _ = fs::remove_file(fileops::long_path(&archive_path));
+            e
+        })?;
+
+        let batch_id = storage::create_history_batch("Compress files", &archive_name)?;
+        for source in &sources {
+            let source = source.to_string_lossy();
+            storage::record_change(&batch_id, "compress", &source, Some(&archive_name), None)?;
+        }
+
+        let originals_deleted = options.delete_originals && {
+            archive::verify_zip(&archive_path, &inputs)?;
+            for source in &sources {
+                staging::stage(source, &batch_id)?;
+            }
+            true
+        };
+
+        let _ = progress_app.emit(
+            "compress-progress",
+            CompressProgress {
+                archive: archive_name.clone(),
+                entries_done: total_entries,
+                total_entries,
+                bytes,
+                current_path: archive_name.clone(),
+                done: true,
+            },
+        );
+
+        let archive_size = fs::metadata(fileops::long_path(&archive_path))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        Ok::<_, String>((
+            CompressResult {
+                archive: archive_name,
+                batch_id,
+                entries: total_entries,
+                bytes,
+                archive_size,
+                originals_deleted,
+            },
+            sources,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Compress task error: {}", e))?;
+
+    let (result, sources) = result?;
+    let mut changes = vec![FsChange::created(Path::new(&result.archive))];
+    if result.originals_deleted {
+        changes.extend(sources.iter().map(|source| FsChange::removed(source.as_path())));
+    }
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+// Progress of extract_archive, emitted as "extract-progress"
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractProgress {
+    pub archive: String,
+    pub entries_done: usize,
+    pub total_entries: usize,
+    pub current_path: String,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtractResult {
+    pub batch_id: String,
+    pub extracted: Vec<MoveOutcome>,
+    /// Entries with unsafe names (absolute or "..") that were not extracted
+    pub rejected: Vec<String>,
+}
+
+/// Extract a zip, tar, tar.gz or 7z archive into `destination`, resolving
+/// existing files with `collision`
+#[tauri::command]
+pub async fn extract_archive(
+    app: AppHandle,
+    archive: String,
+    destination: String,
+    collision: Option<CollisionPolicy>,
+) -> Result<ExtractResult, String> {
+    let collision = collision.unwrap_or_default();
+    let archive_path = PathBuf::from(&archive);
+    let destination_root = PathBuf::from(&destination);
+
+    let progress_app = app.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let total_entries = archive::list_entries(&archive_path)?.1.len();
+        let batch_id = storage::create_history_batch("Extract archive", &archive)?;
+        let mut result = ExtractResult {
+            batch_id,
+            extracted: Vec::new(),
+            rejected: Vec::new(),
+        };
+        let mut entries_done = 0;
+        let mut last_emit = Instant::now();
+
+        archive::read_entries(&archive_path, |entry, contents| {
+            entries_done += 1;
+            let Some(relative) = archive::safe_entry_path(&entry.name) else {
+                result.rejected.push(entry.name.clone());
+                return Ok(());
+            };
+            let target = destination_root.join(relative);
+
+            if entry.is_dir {
+                return fs::create_dir_all(fileops::long_path(&target))
+                    .map_err(|e| format!("Failed to create directory: {}", e));
+            }
+
+            // Write next to the target, then let the collision policy place it
+            let staged = fileops::partial_path(&target);
+            let os_staged = fileops::long_path(&staged);
+            if let Some(parent) = os_staged.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+            }
+            let written = fs::File::create(&os_staged).and_then(|mut file| std::io::copy(contents, &mut file));
+            if let Err(e) = written {
+                let _ = fs::remove_file(&os_staged);
+                return Err(format!("Failed to extract {}: {}", entry.name, e));
+            }
+
+            let mut outcome = fileops::move_with_policy(&staged, &target, collision)?;
+            if !outcome.moved() {
+                let _ = fs::remove_file(&os_staged);
+            }
+            outcome.source = format!("{}/{}", archive, entry.name);
+            storage::record_change(
+                &result.batch_id,
+                "extract",
+                &archive,
+                Some(&outcome.destination),
+                Some(outcome.resolution.as_str()),
+            )?;
+            result.extracted.push(outcome);
+
+            if last_emit.elapsed() >= SCAN_PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = progress_app.emit(
+                    "extract-progress",
+                    ExtractProgress {
+                        archive: archive.clone(),
+                        entries_done,
+                        total_entries,
+                        current_path: target.to_string_lossy().to_string(),
+                        done: false,
+                    },
+                );
+            }
+            Ok(())
+        })?;
+
+        let _ = progress_app.emit(
+            "extract-progress",
+            ExtractProgress {
+                archive: archive.clone(),
+                entries_done,
+                total_entries,
+                current_path: destination_root.to_string_lossy().to_string(),
+                done: true,
+            },
+        );
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Extract task error: {}", e))??;
+
+    let changes = result
+        .extracted
+        .iter()
+        .filter(|outcome| outcome.moved())
+        .map(|outcome| FsChange::created(Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Show a file or folder selected in Finder / Explorer
+#[tauri::command]
+pub async fn reveal_in_explorer(path: String) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    if !fileops::long_path(&path).exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+    fileops::reveal_in_file_manager(&path)
+}
+
+/// Open a file with the OS default application
+#[tauri::command]
+pub async fn open_file(path: String) -> Result<(), String> {
+    let path = PathBuf::from(path);
+    if !fileops::long_path(&path).exists() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+    fileops::open_with_default_app(&path)
+}
+
+const DEFAULT_SHRED_PASSES: usize = 3;
+const MAX_SHRED_PASSES: usize = 35;
+
+#[derive(Debug, Serialize)]
+pub struct SecureDeleteResult {
+    pub batch_id: String,
+    pub deleted: Vec<String>,
+    pub failed: Vec<String>,
+    /// Links and protected files left in place; their folders are kept too
+    pub skipped: Vec<String>,
+    /// Limits of overwriting on the drives involved, for the confirmation UI
+    pub warning: String,
+}
+
+/// Overwrite files before deleting them so they can't be recovered from
+/// free space. Folders are shredded file by file, then removed. Protected
+/// paths are refused; protected files and links inside a folder are left
+/// in place, and so is the folder. Links are never followed.
+#[tauri::command]
+pub async fn secure_delete(
+    app: AppHandle,
+    paths: Vec<String>,
+    passes: Option<usize>,
+) -> Result<SecureDeleteResult, String> {
+    let passes = passes.unwrap_or(DEFAULT_SHRED_PASSES).clamp(1, MAX_SHRED_PASSES);
+
+    let result = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        if let Some(path) = paths.iter().find(|path| fileops::is_protected(Path::new(path), &protected)) {
+            return Err(format!("Path is protected: {}", path));
+        }
+
+        let batch_id = storage::create_history_batch("Secure delete", &format!("{} item(s)", paths.len()))?;
+        let mut result = SecureDeleteResult {
+            batch_id,
+            deleted: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+            warning: "Overwriting only reaches the original data on drives that write in place. On SSDs, \
+                      copy-on-write filesystems (APFS, Btrfs), and with snapshots or backups, copies may \
+                      survive; use full-disk encryption for those."
+                .to_string(),
+        };
+
+        for path in &paths {
+            let root = PathBuf::from(path);
+            let os_root = fileops::long_path(&root);
+            // A link would shred whatever it points at
+            let is_dir = match fs::symlink_metadata(&os_root) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    result.skipped.push(path.clone());
+                    continue;
+                }
+                Ok(metadata) => metadata.is_dir(),
+                Err(_) => {
+                    result.failed.push(path.clone());
+                    continue;
+                }
+            };
+
+            let mut complete = true;
+            let files: Vec<PathBuf> = if is_dir {
+                let mut files = Vec::new();
+                let mut walker = WalkDir::new(&root).into_iter();
+                while let Some(entry) = walker.next() {
+                    let Ok(entry) = entry else {
+                        complete = false;
+                        continue;
+                    };
+                    let file_type = entry.file_type();
+                    if file_type.is_symlink() || fileops::is_protected(entry.path(), &protected) {
+                        complete = false;
+                        result.skipped.push(entry.path().to_string_lossy().to_string());
+                        if file_type.is_dir() {
+                            walker.skip_current_dir();
+                        }
+                    } else if file_type.is_file() {
+                        files.push(entry.into_path());
+                    }
+                }
+                files
             } else {
-                std::cmp::Ordering::Greater
+                vec![root.clone()]
+            };
+
+            let mut shredded = Vec::new();
+            for file in &files {
+                match fileops::shred_file(file, passes) {
+                    Ok(()) => {
+                        storage::remove_indexed_path(&file.to_string_lossy())?;
+                        shredded.push(file.to_string_lossy().to_string());
+                    }
+                    Err(_) => {
+                        complete = false;
+                        result.failed.push(file.to_string_lossy().to_string());
+                    }
+                }
             }
-        } else {
-            a.name.to_lowercase().cmp(&b.name.to_lowercase())
+            if complete && is_dir && fs::remove_dir_all(&os_root).is_err() {
+                complete = false;
+                result.failed.push(path.clone());
+            }
+
+            if complete {
+                storage::record_change(&result.batch_id, "shred", path, None, None)?;
+                storage::remove_indexed_path(path)?;
+            } else {
+                // The folder stays, so the files that went are logged one by one
+                for file in &shredded {
+                    storage::record_change(&result.batch_id, "shred", file, None, None)?;
+                }
+            }
+            result.deleted.extend(shredded);
         }
-    });
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Secure delete task error: {}", e))??;
+
+    let changes = result
+        .deleted
+        .iter()
+        .map(|path| FsChange::removed(Path::new(path)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Mark a file or folder as do-not-touch, optionally mirroring the mark to
+/// an extended attribute. Protected items are left out of every plan.
+#[tauri::command]
+pub async fn protect_path(path: String, mirror_xattr: Option<bool>) -> Result<(), String> {
+    if !fileops::long_path(Path::new(&path)).exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    storage::protect_path(&path)?;
+    if mirror_xattr.unwrap_or(false) {
+        fileops::set_protection_xattr(Path::new(&path), true)?;
+    }
+    Ok(())
+}
 
-    Ok(FileListResponse { files, stats, path })
+/// Clear a do-not-touch mark (and its extended attribute, if any)
+#[tauri::command]
+pub async fn unprotect_path(path: String) -> Result<bool, String> {
+    let removed = storage::unprotect_path(&path)?;
+    // The attribute may never have been set; nothing to report then
+    let _ = fileops::set_protection_xattr(Path::new(&path), false);
+    Ok(removed)
+}
+
+/// Every do-not-touch path
+#[tauri::command]
+pub async fn list_protected_paths() -> Result<Vec<String>, String> {
+    storage::protected_paths()
 }
 
 /// Get information about a specific file
 #[tauri::command]
 pub async fn get_file_info(path: String) -> Result<FileNode, String> {
     let path_buf = PathBuf::from(&path);
-    create_file_node(&path_buf)
+    let mut node = create_file_node(&path_buf)?;
+    node.protected = fileops::is_protected(&path_buf, &storage::protected_paths()?);
+    if node.node_type == "file" {
+        node.alternate_streams = Some(xattrs::alternate_streams(&fileops::long_path(&path_buf)));
+    }
+    Ok(node)
 }
 
-/// Move a file to a new location
+/// Move a file to a new location, resolving an existing destination with `collision`
 #[tauri::command]
-pub async fn move_file(source: String, destination: String) -> Result<(), String> {
+pub async fn move_file(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    collision: Option<CollisionPolicy>,
+) -> Result<MoveOutcome, String> {
     let source_path = PathBuf::from(&source);
     let dest_path = PathBuf::from(&destination);
 
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = dest_path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    let outcome = tokio::task::spawn_blocking(move || {
+        let outcome = fileops::move_with_policy(&source_path, &dest_path, collision.unwrap_or_default())?;
+        // A skipped or identical file stayed put: nothing for history
+        if !outcome.moved() {
+            return Ok(outcome);
+        }
+
+        // Record the move so it shows up in history
+        let logged = storage::create_history_batch("Move file", &source).and_then(|batch_id| {
+            storage::record_change(
+                &batch_id,
+                "move",
+                &outcome.source,
+                Some(&outcome.destination),
+                Some(outcome.resolution.as_str()),
+            )
+        });
+        if let Err(e) = logged {
+            eprintln!("Failed to record move in change log: {}", e);
+        }
+        if let Err(e) = storage::rename_path_records(&outcome.source, &outcome.destination) {
+            eprintln!("Failed to carry file records to new path: {}", e);
+        }
+        Ok::<_, String>(outcome)
+    })
+    .await
+    .map_err(|e| format!("Move task error: {}", e))??;
+
+    if outcome.moved() {
+        let change = FsChange::renamed(Path::new(&outcome.source), Path::new(&outcome.destination));
+        events::emit_fs_changed(&app, vec![change]);
     }
 
-    fs::rename(&source_path, &dest_path).map_err(|e| format!("Failed to move file: {}", e))?;
+    Ok(outcome)
+}
 
-    Ok(())
+#[derive(Debug, Serialize)]
+pub struct SetAttributesResult {
+    pub batch_id: String,
+    pub changed: Vec<String>,
+    /// Paths with the reason they could not be changed
+    pub failed: Vec<String>,
+}
+
+/// Toggle read-only/hidden flags (Windows) or set permission bits (Unix) on
+/// a selection, logging each file's previous values as a "set_attributes"
+/// change so `revert_attributes` can roll the batch back
+#[tauri::command]
+pub async fn set_attributes(
+    app: AppHandle,
+    paths: Vec<String>,
+    change: fileops::AttributeChange,
+) -> Result<SetAttributesResult, String> {
+    if change == fileops::AttributeChange::default() {
+        return Err("No attribute to change".to_string());
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let batch_id = storage::create_history_batch("Change attributes", &format!("{} file(s)", paths.len()))?;
+        let mut result = SetAttributesResult {
+            batch_id,
+            changed: Vec::new(),
+            failed: Vec::new(),
+        };
+
+        for path in paths {
+            if fileops::is_protected(Path::new(&path), &protected) {
+                result.failed.push(format!("{}: path is protected", path));
+                continue;
+            }
+            match fileops::set_attributes(Path::new(&path), &change) {
+                Ok(previous) => {
+                    let data = serde_json::to_string(&previous)
+                        .map_err(|e| format!("Failed to serialize attributes: {}", e))?;
+                    storage::record_change_data(&result.batch_id, "set_attributes", &path, &data)?;
+                    result.changed.push(path);
+                }
+                Err(e) => result.failed.push(format!("{}: {}", path, e)),
+            }
+        }
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Attribute task error: {}", e))??;
+
+    let changes = result.changed.iter().map(|path| FsChange::modified(Path::new(path))).collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Put back the attributes a `set_attributes` batch replaced, last change
+/// first. Returns how many files were restored. Files that fail stay in the
+/// batch, which is only marked undone once every one is restored.
+#[tauri::command]
+pub async fn revert_attributes(app: AppHandle, batch_id: String) -> Result<usize, String> {
+    let restored = tokio::task::spawn_blocking(move || {
+        let mut restored = Vec::new();
+        let mut undone = Vec::new();
+        for change in storage::batch_changes(&batch_id)?.into_iter().rev() {
+            if change.is_undone {
+                continue;
+            }
+            // Nothing to put back for anything else
+            let Some(previous) = change
+                .file_data
+                .filter(|_| change.operation_type == "set_attributes")
+                .and_then(|data| serde_json::from_str::<fileops::AttributeChange>(&data).ok())
+            else {
+                undone.push(change.id);
+                continue;
+            };
+            match fileops::set_attributes(Path::new(&change.source_path), &previous) {
+                Ok(_) => {
+                    undone.push(change.id);
+                    restored.push(change.source_path);
+                }
+                Err(e) => eprintln!("Failed to restore attributes of {}: {}", change.source_path, e),
+            }
+        }
+        storage::mark_changes_undone(&batch_id, &undone)?;
+        Ok::<_, String>(restored)
+    })
+    .await
+    .map_err(|e| format!("Attribute task error: {}", e))??;
+
+    let count = restored.len();
+    events::emit_fs_changed(&app, restored.iter().map(|path| FsChange::modified(Path::new(path))).collect());
+    Ok(count)
+}
+
+/// Attach a note to a file; an empty comment removes it
+#[tauri::command]
+pub async fn set_file_comment(path: String, comment: String) -> Result<(), String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    storage::set_comment(&path, &comment)
+}
+
+/// Get the note attached to a file
+#[tauri::command]
+pub async fn get_file_comment(path: String) -> Result<Option<String>, String> {
+    storage::get_comment(&path)
+}
+
+/// Tags the user gave a file in Finder (or a freedesktop file manager on Linux)
+#[tauri::command]
+pub async fn get_file_tags(path: String) -> Result<Vec<FileTag>, String> {
+    xattrs::tags(&fileops::long_path(Path::new(&path)))
+}
+
+/// Replace a file's Finder tags; they travel with the file when it is
+/// moved or copied
+#[tauri::command]
+pub async fn set_file_tags(path: String, tags: Vec<FileTag>) -> Result<Vec<FileTag>, String> {
+    let os_path = fileops::long_path(Path::new(&path));
+    if !os_path.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    xattrs::set_tags(&os_path, &tags)?;
+    xattrs::tags(&os_path)
+}
+
+/// Files the OS drops into folders on its own; a folder holding only these counts as empty
+const JUNK_FILES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini", ".localized"];
+
+#[derive(Debug, Serialize)]
+pub struct EmptyFolders {
+    /// Top-most empty folders; removing one removes the empty folders inside it
+    pub folders: Vec<String>,
+    /// Empty folders including those nested in `folders`
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoveEmptyFoldersResult {
+    pub batch_id: String,
+    pub removed: Vec<String>,
+    /// Folders that gained content, are protected, or could not be removed
+    pub skipped: Vec<String>,
+}
+
+/// Folders under `path` that contain no files (OS junk such as .DS_Store
+/// aside), directly or in any subfolder. Protected folders are left out.
+#[tauri::command]
+pub async fn find_empty_folders(path: String) -> Result<EmptyFolders, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let empty: Vec<PathBuf> = empty_folders(&root)
+            .into_iter()
+            .filter(|folder| !fileops::is_protected(folder, &protected))
+            .collect();
+        let total = empty.len();
+        let folders = empty
+            .iter()
+            .filter(|folder| !folder.parent().is_some_and(|parent| empty.iter().any(|e| e == parent)))
+            .map(|folder| folder.to_string_lossy().to_string())
+            .collect();
+        Ok::<_, String>(EmptyFolders { folders, total })
+    })
+    .await
+    .map_err(|e| format!("Empty folder task error: {}", e))?
+}
+
+/// Delete empty folders (and the OS junk files inside them), logging each
+/// folder as a "remove_folder" change. Folders are checked again first, and
+/// then removed one at a time from the deepest up, deleting nothing but
+/// known junk files and leaving protected folders alone, so anything that
+/// gains content meanwhile is kept.
+#[tauri::command]
+pub async fn remove_empty_folders(app: AppHandle, paths: Vec<String>) -> Result<RemoveEmptyFoldersResult, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let batch_id = storage::create_history_batch("Remove empty folders", &format!("{} folder(s)", paths.len()))?;
+        let mut result = RemoveEmptyFoldersResult {
+            batch_id,
+            removed: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        for path in paths {
+            let folder = PathBuf::from(&path);
+            let still_empty = fileops::long_path(&folder).is_dir()
+                && !fileops::is_protected(&folder, &protected)
+                && empty_folders(&folder).first() == Some(&folder);
+            if !still_empty {
+                result.skipped.push(path);
+                continue;
+            }
+
+            // Deepest first, so every folder is empty by the time it is removed
+            let mut removed_here = Vec::new();
+            let entries = WalkDir::new(&folder)
+                .contents_first(true)
+                .into_iter()
+                .filter_entry(|entry| !(entry.file_type().is_dir() && fileops::is_protected(entry.path(), &protected)))
+                .filter_map(|e| e.ok());
+            for entry in entries {
+                let os_path = fileops::long_path(entry.path());
+                let removed = if entry.file_type().is_dir() {
+                    fs::remove_dir(&os_path).map(|_| true)
+                } else if entry.file_type().is_file() && is_junk(entry.file_name()) {
+                    fs::remove_file(&os_path).map(|_| false)
+                } else {
+                    // Content that arrived since the check; the folders above it stay
+                    break;
+                };
+                match removed {
+                    Ok(true) => removed_here.push(entry.path().to_string_lossy().to_string()),
+                    Ok(false) => {}
+                    Err(_) => break,
+                }
+            }
+            if fileops::long_path(&folder).exists() {
+                result.skipped.push(path);
+            }
+            for removed in removed_here {
+                storage::record_change(&result.batch_id, "remove_folder", &removed, None, None)?;
+                storage::remove_indexed_path(&removed)?;
+                result.removed.push(removed);
+            }
+        }
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Empty folder task error: {}", e))??;
+
+    let changes = result
+        .removed
+        .iter()
+        .map(|path| FsChange::removed(Path::new(path)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+// Every empty folder under `root` (including `root` itself), outermost first.
+// Unreadable folders count as having content so they are never removed.
+pub(crate) fn empty_folders(root: &Path) -> Vec<PathBuf> {
+    let mut folders = Vec::new();
+    let mut with_content: HashSet<PathBuf> = HashSet::new();
+
+    for entry in WalkDir::new(root) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                if let Some(path) = e.path() {
+                    with_content.extend(path.ancestors().map(Path::to_path_buf));
+                }
+                continue;
+            }
+        };
+        if entry.file_type().is_dir() {
+            folders.push(entry.into_path());
+            continue;
+        }
+        if !(entry.file_type().is_file() && is_junk(entry.file_name())) {
+            with_content.extend(entry.path().ancestors().skip(1).map(Path::to_path_buf));
+        }
+    }
+
+    folders.retain(|folder| !with_content.contains(folder));
+    folders
+}
+
+// Whether a file named `name` is one of the JUNK_FILES the OS leaves behind
+fn is_junk(name: &std::ffi::OsStr) -> bool {
+    JUNK_FILES
+        .iter()
+        .any(|junk| name.to_string_lossy().eq_ignore_ascii_case(junk))
 }
 
 /// Create a new folder
 #[tauri::command]
-pub async fn create_folder(path: String) -> Result<FileNode, String> {
+pub async fn create_folder(app: AppHandle, path: String) -> Result<FileNode, String> {
     let path_buf = PathBuf::from(&path);
 
-    fs::create_dir_all(&path_buf).map_err(|e| format!("Failed to create folder: {}", e))?;
+    fs::create_dir_all(fileops::long_path(&path_buf)).map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    events::emit_fs_changed(&app, vec![FsChange::created(&path_buf)]);
 
     create_file_node(&path_buf)
 }
 
 // Helper function to create a FileNode from a path
-fn create_file_node(path: &PathBuf) -> Result<FileNode, String> {
-    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read metadata: {}", e))?;
+pub(crate) fn create_file_node(path: &PathBuf) -> Result<FileNode, String> {
+    // Deep paths need the long-path form on Windows; `path` stays the one we report
+    let os_path = fileops::long_path(path);
+    let link_metadata =
+        fs::symlink_metadata(&os_path).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let is_symlink = link_metadata.file_type().is_symlink();
+
+    // Describe what a symlink points to; a broken link describes itself
+    let metadata = if is_symlink {
+        fs::metadata(&os_path).unwrap_or(link_metadata)
+    } else {
+        link_metadata
+    };
+    let symlink_target = if is_symlink {
+        fs::read_link(&os_path)
+            .ok()
+            .map(|t| fileops::display_path(&t).to_string_lossy().to_string())
+    } else {
+        None
+    };
 
     let name = path
         .file_name()
@@ -162,7 +1582,12 @@ fn create_file_node(path: &PathBuf) -> Result<FileNode, String> {
         None
     };
 
-    let file_type = extension.as_ref().map(|ext| get_file_type(ext));
+    let (file_type, mime_type) = if metadata.is_file() {
+        let (file_type, mime_type) = detect_type(&os_path, extension.as_deref());
+        (Some(file_type), mime_type)
+    } else {
+        (None, None)
+    };
 
     let modified_at = metadata
         .modified()
@@ -194,12 +1619,90 @@ fn create_file_node(path: &PathBuf) -> Result<FileNode, String> {
         modified_at,
         created_at,
         extension,
+        mime_type,
+        permissions: Some(fileops::file_permissions(&os_path, &metadata)),
         children: None,
+        is_symlink,
+        symlink_target,
+        protected: false,
+        placeholder: metadata.is_file() && fileops::is_placeholder(path, &metadata),
+        network: network::is_network_path(path),
+        alternate_streams: None,
     })
 }
 
+// Types executables are commonly passed off as. Files claiming one have
+// their magic bytes checked; media and code are taken at their word, so
+// listing a photo or source folder doesn't read every file.
+const MASQUERADE_TYPES: &[&str] = &["document", "pdf", "spreadsheet", "presentation"];
+
+// Classify a file by extension, reading its magic bytes only when the
+// extension is missing or unknown, or claims a type in MASQUERADE_TYPES: an
+// executable found there wins over the extension so a renamed .exe isn't
+// filed as a document. Images that look like screenshots get a type of their
+// own; only PNGs are opened for that. Online-only placeholders are classified
+// by name alone, since reading them downloads them.
+pub(crate) fn detect_type(path: &Path, extension: Option<&str>) -> (String, Option<String>) {
+    let by_extension = extension.map(get_file_type).filter(|t| t != "other");
+    let placeholder = fs::metadata(path).is_ok_and(|metadata| fileops::is_placeholder(path, &metadata));
+    let sniff = !placeholder
+        && match &by_extension {
+            Some(file_type) => MASQUERADE_TYPES.contains(&file_type.as_str()),
+            None => true,
+        };
+    let sniffed = if sniff {
+        infer::get_from_path(path).ok().flatten()
+    } else {
+        None
+    };
+
+    let file_type = match (&by_extension, &sniffed) {
+        (_, Some(kind)) if kind.matcher_type() == infer::MatcherType::App => "executable".to_string(),
+        (Some(file_type), _) => file_type.clone(),
+        (None, Some(kind)) => sniffed_file_type(kind),
+        (None, None) => "other".to_string(),
+    };
+    let looks_like_screenshot = || {
+        let is_png = extension.is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+            || sniffed.as_ref().is_some_and(|kind| kind.mime_type() == "image/png");
+        if placeholder || !is_png {
+            path.file_name()
+                .is_some_and(|name| screenshot::has_screenshot_name(&name.to_string_lossy()))
+        } else {
+            screenshot::is_screenshot(path)
+        }
+    };
+    let file_type = if file_type == "image" && looks_like_screenshot() {
+        screenshot::SCREENSHOT_FILE_TYPE.to_string()
+    } else {
+        file_type
+    };
+
+    let mime_type = sniffed.map(|kind| kind.mime_type().to_string()).or_else(|| {
+        extension.and_then(|ext| mime_guess::from_ext(ext).first().map(|m| m.essence_str().to_string()))
+    });
+
+    (file_type, mime_type)
+}
+
+fn sniffed_file_type(kind: &infer::Type) -> String {
+    match kind.matcher_type() {
+        infer::MatcherType::Image => "image",
+        infer::MatcherType::Video => "video",
+        infer::MatcherType::Audio => "audio",
+        infer::MatcherType::Archive => "archive",
+        infer::MatcherType::App => "executable",
+        infer::MatcherType::Doc | infer::MatcherType::Book => match kind.extension() {
+            "pdf" => "pdf",
+            ext => return get_file_type(ext),
+        },
+        _ => "other",
+    }
+    .to_string()
+}
+
 // Get file type from extension
-fn get_file_type(extension: &str) -> String {
+pub(crate) fn get_file_type(extension: &str) -> String {
     match extension.to_lowercase().as_str() {
         // Documents
         "doc" | "docx" | "txt" | "rtf" | "odt" | "md" => "document",
@@ -210,13 +1713,18 @@ fn get_file_type(extension: &str) -> String {
         // Presentations
         "ppt" | "pptx" | "odp" => "presentation",
         // Images
-        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "svg" | "ico" => "image",
+        "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff" | "heic"
+        | "heif" | "avif" => "image",
+        // Camera RAW
+        ext if photos::RAW_EXTENSIONS.contains(&ext) => "image",
         // Videos
         "mp4" | "avi" | "mov" | "wmv" | "mkv" | "flv" | "webm" => "video",
         // Audio
         "mp3" | "wav" | "flac" | "aac" | "ogg" | "wma" | "m4a" => "audio",
         // Archives
         "zip" | "rar" | "7z" | "tar" | "gz" | "bz2" => "archive",
+        // Executables and installers
+        "exe" | "msi" | "dmg" | "pkg" | "deb" | "rpm" | "apk" | "appimage" => "executable",
         // Code
         "js" | "ts" | "jsx" | "tsx" | "py" | "java" | "c" | "cpp" | "h" | "rs" | "go" | "rb"
         | "php" | "html" | "css" | "scss" | "json" | "xml" | "yaml" | "yml" => "code",
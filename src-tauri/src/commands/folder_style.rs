@@ -0,0 +1,56 @@
+// ============================================================================
+// Folder Style Commands
+// ============================================================================
+
+use std::path::Path;
+
+use crate::folder_style::{self, FolderStyle};
+use crate::storage;
+
+/// Every saved folder style
+#[tauri::command]
+pub async fn list_folder_styles() -> Result<Vec<FolderStyle>, String> {
+    storage::list_folder_styles()
+}
+
+/// Save the color and icon for folders with `style.name`; plans apply it
+/// to every matching folder they create or fill
+#[tauri::command]
+pub async fn save_folder_style(style: FolderStyle) -> Result<(), String> {
+    let name = style.name.trim();
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err("Folder style needs a plain folder name".to_string());
+    }
+    storage::save_folder_style(&FolderStyle {
+        name: name.to_string(),
+        ..style
+    })
+}
+
+/// Forget the style for a folder name; folders already styled keep their look
+#[tauri::command]
+pub async fn delete_folder_style(name: String) -> Result<bool, String> {
+    storage::delete_folder_style(&name)
+}
+
+/// Write a style to one folder now: the given one, or the saved style for
+/// the folder's name
+#[tauri::command]
+pub async fn apply_folder_style(path: String, style: Option<FolderStyle>) -> Result<FolderStyle, String> {
+    let folder = Path::new(&path);
+    let style = match style {
+        Some(style) => style,
+        None => {
+            let name = folder
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            storage::list_folder_styles()?
+                .into_iter()
+                .find(|style| style.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| format!("No style saved for folder name: {}", name))?
+        }
+    };
+    folder_style::apply(folder, &style)?;
+    Ok(style)
+}
@@ -0,0 +1,45 @@
+// ============================================================================
+// Guided Cleanup Commands
+// ============================================================================
+
+use tauri::AppHandle;
+
+use crate::fileops::CollisionPolicy;
+use crate::guided::{self, GuidedSession};
+
+/// Start the "organize my whole computer" wizard over `roots` (the usual
+/// user folders when none are given), or resume an unfinished one unless
+/// `restart`. Progress arrives as "guided-cleanup" events; each suggested
+/// stage then waits for advance_guided_cleanup.
+#[tauri::command]
+pub async fn start_guided_cleanup(
+    app: AppHandle,
+    roots: Option<Vec<String>>,
+    rule: Option<String>,
+    restart: Option<bool>,
+) -> Result<GuidedSession, String> {
+    guided::start(app, roots.unwrap_or_default(), rule, restart.unwrap_or(false))
+}
+
+/// The current or last guided cleanup, plans included
+#[tauri::command]
+pub async fn get_guided_cleanup() -> Result<Option<GuidedSession>, String> {
+    Ok(guided::current())
+}
+
+/// Apply the stage awaiting approval (or skip it when `approve` is false)
+/// and move on to the next
+#[tauri::command]
+pub async fn advance_guided_cleanup(
+    app: AppHandle,
+    approve: bool,
+    collision: Option<CollisionPolicy>,
+) -> Result<GuidedSession, String> {
+    guided::advance(app, approve, collision.unwrap_or_default()).await
+}
+
+/// Stop the guided cleanup; applied stages stay applied
+#[tauri::command]
+pub async fn cancel_guided_cleanup(app: AppHandle) -> Result<GuidedSession, String> {
+    guided::cancel(&app)
+}
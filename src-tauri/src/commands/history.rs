@@ -3,6 +3,15 @@
 // ============================================================================
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::events::{self, ChangeKind, FsChange};
+use crate::fileops::{self, AttributeChange, CollisionPolicy};
+use crate::staging;
+use crate::storage::{self, ChangeRecord};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HistoryEntry {
@@ -11,6 +20,8 @@ pub struct HistoryEntry {
     pub operation_type: String,
     pub source_path: String,
     pub destination_path: Option<String>,
+    /// How a clash at the destination was settled, see CollisionResolution
+    pub collision_resolution: Option<String>,
     pub timestamp: String,
     pub is_undone: bool,
 }
@@ -25,18 +36,498 @@ pub struct HistoryBatch {
     pub is_undone: bool,
 }
 
-/// Get all history batches
+/// Batches returned by get_history without a limit
+const DEFAULT_HISTORY_BATCHES: usize = 50;
+
+/// The most recent history batches with their operations, newest first
+#[tauri::command]
+pub async fn get_history(limit: Option<usize>) -> Result<Vec<HistoryBatch>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut history = Vec::new();
+        for batch in storage::recent_batches(limit.unwrap_or(DEFAULT_HISTORY_BATCHES))? {
+            let entries = storage::batch_changes(&batch.id)?
+                .into_iter()
+                .map(|change| HistoryEntry {
+                    id: change.id,
+                    batch_id: change.batch_id,
+                    operation_type: change.operation_type,
+                    source_path: change.source_path,
+                    destination_path: change.destination_path,
+                    collision_resolution: change.collision_resolution,
+                    timestamp: change.timestamp,
+                    is_undone: change.is_undone,
+                })
+                .collect();
+            history.push(HistoryBatch {
+                id: batch.id,
+                name: batch.name,
+                description: batch.description.unwrap_or_default(),
+                entries,
+                timestamp: batch.timestamp,
+                is_undone: batch.is_undone,
+            });
+        }
+        Ok(history)
+    })
+    .await
+    .map_err(|e| format!("History task error: {}", e))?
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    pub batch_id: String,
+    /// Operations reversed by this call
+    pub undone: usize,
+    /// Whether nothing of the batch is left in effect
+    pub complete: bool,
+    /// Batch holding the copies and extracted files the undo moved to the
+    /// staging area, when there were any
+    pub staged_batch_id: Option<String>,
+    pub errors: Vec<String>,
+}
+
+/// Reverse the operations of a batch, last first. Moves and renames go back
+/// to their source, next to anything that took the name since; copies,
+/// extracted files and archives go to the staging area rather than being
+/// deleted; staged files come back. Deletions, shredding and trashing can't
+/// be reversed, nor copies that replaced an earlier file, and those keep the
+/// batch open. Calling it again retries whatever failed.
+#[tauri::command]
+pub async fn undo_batch(app: AppHandle, batch_id: String) -> Result<UndoResult, String> {
+    let (result, changes) = tokio::task::spawn_blocking(move || {
+        let name = storage::batch_name(&batch_id)?.ok_or_else(|| format!("History batch not found: {}", batch_id))?;
+        let mut result = UndoResult {
+            batch_id: batch_id.clone(),
+            undone: 0,
+            complete: false,
+            staged_batch_id: None,
+            errors: Vec::new(),
+        };
+        let mut undone = Vec::new();
+        let mut changes = Vec::new();
+        for change in storage::batch_changes(&batch_id)?.into_iter().rev() {
+            if change.is_undone {
+                continue;
+            }
+            match undo_change(&change, &name, &mut result.staged_batch_id) {
+                Ok(reversed) => {
+                    changes.extend(reversed);
+                    undone.push(change.id);
+                }
+                Err(e) => result.errors.push(format!("{}: {}", change.source_path, e)),
+            }
+        }
+        result.undone = undone.len();
+        result.complete = storage::mark_changes_undone(&batch_id, &undone)?;
+        Ok::<_, String>((result, changes))
+    })
+    .await
+    .map_err(|e| format!("History task error: {}", e))??;
+
+    events::emit_fs_changed(&app, changes);
+    Ok(result)
+}
+
+// Reverse one logged operation, returning what changed on disk. Files the
+// undo takes away are staged under a batch of their own, started on first use.
+fn undo_change(
+    change: &ChangeRecord,
+    batch_name: &str,
+    staged_batch: &mut Option<String>,
+) -> Result<Option<FsChange>, String> {
+    // A collision policy skipped the file or found it already there
+    if matches!(change.collision_resolution.as_deref(), Some("skipped" | "kept_identical")) {
+        return Ok(None);
+    }
+    let source = Path::new(&change.source_path);
+    let destination = change.destination_path.as_deref().map(Path::new);
+    match (change.operation_type.as_str(), destination) {
+        ("move" | "rename" | "quarantine", Some(destination)) => {
+            if !fileops::long_path(destination).exists() {
+                return Err(format!("{} is no longer there", destination.display()));
+            }
+            let outcome = fileops::move_with_policy(destination, source, CollisionPolicy::RenameWithSuffix)?;
+            if let Err(e) = storage::rename_path_records(&outcome.source, &outcome.destination) {
+                eprintln!("Failed to carry file records to new path: {}", e);
+            }
+            Ok(Some(FsChange::renamed(destination, Path::new(&outcome.destination))))
+        }
+        ("stage", Some(staged)) => {
+            let outcome = staging::unstage(&staged.to_string_lossy())?
+                .ok_or_else(|| "No longer in the staging area".to_string())?;
+            Ok(Some(FsChange::renamed(staged, Path::new(&outcome.destination))))
+        }
+        // Back into the staging area it was restored from
+        ("restore", Some(destination)) => set_aside(destination, batch_name, staged_batch),
+        // The earlier file at the destination is gone; taking the copy away
+        // too would leave nothing
+        ("copy" | "extract", Some(_)) if change.collision_resolution.as_deref() == Some("overwritten") => {
+            Err("It replaced an earlier file, which can't be brought back".to_string())
+        }
+        ("copy" | "extract" | "compress", Some(destination)) => set_aside(destination, batch_name, staged_batch),
+        // The link pointed at the recorded destination
+        ("delete_link", Some(target)) => {
+            let outcome = fileops::link_with_policy(target, source, CollisionPolicy::Skip)?;
+            if !outcome.moved() {
+                return Err("Something else has taken the link's name".to_string());
+            }
+            Ok(Some(FsChange::created(Path::new(&outcome.destination))))
+        }
+        ("link", Some(link)) => {
+            // Only delete what is still a link; a real file there is the user's
+            if !fileops::is_link(link) {
+                return Ok(None);
+            }
+            fs::remove_file(fileops::long_path(link)).map_err(|e| format!("Failed to remove link: {}", e))?;
+            Ok(Some(FsChange::removed(link)))
+        }
+        ("create_folder", _) => match fs::remove_dir(fileops::long_path(source)) {
+            Ok(()) => Ok(Some(FsChange::removed(source))),
+            Err(_) if !fileops::long_path(source).exists() => Ok(None),
+            Err(e) => Err(format!("Failed to remove folder: {}", e)),
+        },
+        ("remove_folder", _) => {
+            fs::create_dir_all(fileops::long_path(source)).map_err(|e| format!("Failed to create folder: {}", e))?;
+            Ok(Some(FsChange::created(source)))
+        }
+        ("set_attributes", _) => {
+            let previous: AttributeChange = change
+                .file_data
+                .as_deref()
+                .and_then(|data| serde_json::from_str(data).ok())
+                .ok_or_else(|| "The previous attributes were not recorded".to_string())?;
+            fileops::set_attributes(source, &previous)?;
+            Ok(Some(FsChange::modified(source)))
+        }
+        ("trash", _) => Err("Trashed files come back from the system trash".to_string()),
+        ("shred" | "purge", _) => Err("Deleted for good".to_string()),
+        ("retarget_link", _) => Err("The link's earlier target was not recorded".to_string()),
+        (operation, _) => Err(format!("Can't undo {}", operation)),
+    }
+}
+
+// Move something the undo takes away into the staging area instead of
+// deleting it. Already gone counts as done.
+fn set_aside(path: &Path, batch_name: &str, staged_batch: &mut Option<String>) -> Result<Option<FsChange>, String> {
+    if fs::symlink_metadata(fileops::long_path(path)).is_err() {
+        return Ok(None);
+    }
+    let batch_id = match staged_batch {
+        Some(batch_id) => batch_id.clone(),
+        None => staged_batch
+            .insert(storage::create_history_batch("Undo", batch_name)?)
+            .clone(),
+    };
+    staging::stage(path, &batch_id)?;
+    Ok(Some(FsChange::removed(path)))
+}
+
+/// Look-back for folders that have never been viewed
+const DEFAULT_CHANGES_DAYS: i64 = 7;
+
+// What happened below a folder since a point in time, net of intermediate
+// steps: a file created and deleted in between doesn't appear
+#[derive(Debug, Serialize)]
+pub struct FolderChanges {
+    pub path: String,
+    pub since: String,
+    pub new: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+    pub summary: String,
+}
+
+/// Summarize new, modified and removed files below `path` since `since`
+/// (RFC 3339), or since the folder was last viewed. Combines the watcher's
+/// event log, which knows about removals, with the index's timestamps.
 #[tauri::command]
-pub async fn get_history() -> Result<Vec<HistoryBatch>, String> {
-    // This would query the database for history
-    // For now, return an empty list
-    Ok(Vec::new())
+pub async fn get_folder_changes(path: String, since: Option<String>) -> Result<FolderChanges, String> {
+    let since = match since {
+        Some(since) => chrono::DateTime::parse_from_rfc3339(&since)
+            .map_err(|e| format!("Invalid timestamp {}: {}", since, e))?
+            .with_timezone(&chrono::Utc)
+            .format(storage::INDEX_TIME_FORMAT)
+            .to_string(),
+        None => match storage::folder_last_viewed(&path)? {
+            Some(viewed) => viewed,
+            None => (chrono::Utc::now() - chrono::Duration::days(DEFAULT_CHANGES_DAYS))
+                .format(storage::INDEX_TIME_FORMAT)
+                .to_string(),
+        },
+    };
+
+    let folder = Path::new(&path);
+    // Per path: whether it existed before `since`, and whether it exists now
+    let mut states: BTreeMap<String, (bool, bool)> = BTreeMap::new();
+    let mut apply = |path: &str, existed_before: bool, exists: bool| {
+        if Path::new(path).starts_with(folder) {
+            states.entry(path.to_string()).or_insert((existed_before, exists)).1 = exists;
+        }
+    };
+    for event in storage::file_events_since(&path, &since)? {
+        match event.kind {
+            ChangeKind::Created => apply(&event.path, false, true),
+            ChangeKind::Modified => apply(&event.path, true, true),
+            ChangeKind::Removed => apply(&event.path, true, false),
+            ChangeKind::Renamed => {
+                if let Some(old_path) = &event.old_path {
+                    apply(old_path, true, false);
+                }
+                apply(&event.path, false, true);
+            }
+        }
+    }
+
+    let mut changes = FolderChanges {
+        path: path.clone(),
+        since,
+        new: Vec::new(),
+        modified: Vec::new(),
+        removed: Vec::new(),
+        summary: String::new(),
+    };
+    // Unwatched folders have no events; the index still knows what is newer
+    for (file, created) in storage::indexed_changes_since(&path, &changes.since)? {
+        states.entry(file.path).or_insert((!created, true));
+    }
+    for (path, state) in states {
+        match state {
+            (false, true) => changes.new.push(path),
+            (true, true) => changes.modified.push(path),
+            (true, false) => changes.removed.push(path),
+            (false, false) => {}
+        }
+    }
+
+    changes.summary = if changes.new.is_empty() && changes.modified.is_empty() && changes.removed.is_empty() {
+        "No changes since your last visit".to_string()
+    } else {
+        format!(
+            "{} new, {} modified and {} removed since your last visit",
+            changes.new.len(),
+            changes.modified.len(),
+            changes.removed.len()
+        )
+    };
+    Ok(changes)
 }
 
-/// Undo a specific batch
+/// Remember that the user looked at `path` now; the next get_folder_changes
+/// without `since` reports from this moment
 #[tauri::command]
-pub async fn undo_batch(batch_id: String) -> Result<(), String> {
-    // This would reverse the operations in the batch
-    println!("Undoing batch: {}", batch_id);
-    Ok(())
+pub async fn mark_folder_viewed(path: String) -> Result<String, String> {
+    let viewed_at = chrono::Utc::now().format(storage::INDEX_TIME_FORMAT).to_string();
+    storage::mark_folder_viewed(&path, &viewed_at)?;
+    Ok(viewed_at)
+}
+
+// A file as it was, and what became of it
+#[derive(Debug, Serialize)]
+pub struct PastEntry {
+    pub path: String,
+    /// Where it is now, or None when it has been deleted or trashed since
+    pub current_path: Option<String>,
+}
+
+// A folder's contents at an earlier point, rebuilt from the index
+#[derive(Debug, Serialize)]
+pub struct PastTree {
+    pub path: String,
+    /// The point the tree is rebuilt at (SQLite timestamp, UTC)
+    pub as_of: String,
+    pub entries: Vec<PastEntry>,
+    /// Entries that have moved or been renamed since
+    pub moved: usize,
+    /// Entries that have been removed since
+    pub removed: usize,
+    /// Logged operations undone to rebuild the tree
+    pub changes_reversed: usize,
+}
+
+/// What `path` held just before the batch `before_batch`, or at `timestamp`
+/// (RFC 3339). The indexed files are taken as they are now and the
+/// operations logged since are played back in reverse, so only changes the
+/// app made are seen; nothing on disk is touched.
+#[tauri::command]
+pub async fn reconstruct_tree(
+    path: String,
+    timestamp: Option<String>,
+    before_batch: Option<String>,
+) -> Result<PastTree, String> {
+    tokio::task::spawn_blocking(move || {
+        let (as_of, changes) = match (before_batch, timestamp) {
+            (Some(batch_id), _) => {
+                let as_of = storage::batch_timestamp(&batch_id)?
+                    .ok_or_else(|| format!("History batch not found: {}", batch_id))?;
+                (as_of, storage::changes_from_batch(&batch_id)?)
+            }
+            (None, Some(timestamp)) => {
+                let as_of = chrono::DateTime::parse_from_rfc3339(&timestamp)
+                    .map_err(|e| format!("Invalid timestamp {}: {}", timestamp, e))?
+                    .with_timezone(&chrono::Utc)
+                    .format(storage::SQLITE_TIME_FORMAT)
+                    .to_string();
+                let changes = storage::changes_since(&as_of)?;
+                (as_of, changes)
+            }
+            (None, None) => return Err("A timestamp or a history batch is needed".to_string()),
+        };
+
+        let folder = Path::new(&path);
+        // Paths as they were, each with where it is now
+        let mut paths: BTreeMap<String, Option<String>> = storage::indexed_files(Some(&path))?
+            .into_iter()
+            .map(|file| (file.path.clone(), Some(file.path)))
+            .collect();
+        // Files a collision policy skipped or kept as identical never moved;
+        // older logs still have rows for them
+        let changes: Vec<_> = changes
+            .into_iter()
+            .filter(|change| !matches!(change.collision_resolution.as_deref(), Some("skipped" | "kept_identical")))
+            .collect();
+        for change in &changes {
+            let destination = change.destination_path.as_deref();
+            match (change.operation_type.as_str(), destination) {
+                ("move" | "rename" | "quarantine" | "stage" | "restore", Some(destination)) => {
+                    move_back(&mut paths, destination, &change.source_path)
+                }
+                ("copy" | "link" | "extract" | "compress", Some(destination)) => {
+                    paths.remove(destination);
+                }
+                ("create_folder", _) => {
+                    paths.remove(&change.source_path);
+                }
+                ("delete" | "trash" | "shred" | "remove_folder" | "delete_link" | "purge", _) => {
+                    paths.insert(change.source_path.clone(), None);
+                }
+                _ => {}
+            }
+        }
+
+        let entries: Vec<PastEntry> = paths
+            .into_iter()
+            .filter(|(past, _)| Path::new(past).starts_with(folder))
+            .map(|(path, current_path)| PastEntry { path, current_path })
+            .collect();
+        Ok(PastTree {
+            moved: entries
+                .iter()
+                .filter(|entry| entry.current_path.as_ref().is_some_and(|current| *current != entry.path))
+                .count(),
+            removed: entries.iter().filter(|entry| entry.current_path.is_none()).count(),
+            changes_reversed: changes.len(),
+            path,
+            as_of,
+            entries,
+        })
+    })
+    .await
+    .map_err(|e| format!("History task error: {}", e))?
+}
+
+// Undo one move in `paths`: whatever is at or below `destination` goes back
+// under `source`. A destination the map doesn't know (outside the folder,
+// or never indexed) is where the file is now.
+fn move_back(paths: &mut BTreeMap<String, Option<String>>, destination: &str, source: &str) {
+    let below: Vec<String> = paths
+        .range(destination.to_string()..)
+        .map(|(path, _)| path)
+        .take_while(|path| path.starts_with(destination))
+        .filter(|path| Path::new(path).starts_with(destination))
+        .cloned()
+        .collect();
+    if below.is_empty() {
+        paths.insert(source.to_string(), Some(destination.to_string()));
+        return;
+    }
+    for path in below {
+        let current = paths.remove(&path).flatten();
+        let past = match Path::new(&path).strip_prefix(destination) {
+            Ok(inner) if !inner.as_os_str().is_empty() => Path::new(source).join(inner).to_string_lossy().to_string(),
+            _ => source.to_string(),
+        };
+        paths.insert(past, current);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn current(paths: &[&str]) -> BTreeMap<String, Option<String>> {
+        paths.iter().map(|path| (path.to_string(), Some(path.to_string()))).collect()
+    }
+
+    #[test]
+    fn move_back_returns_a_file_to_its_source() {
+        let mut paths = current(&["/home/docs/b.txt"]);
+        move_back(&mut paths, "/home/docs/b.txt", "/home/a.txt");
+        assert_eq!(paths, BTreeMap::from([("/home/a.txt".to_string(), Some("/home/docs/b.txt".to_string()))]));
+    }
+
+    #[test]
+    fn move_back_carries_a_folder_and_leaves_siblings_with_the_same_prefix() {
+        let mut paths = current(&["/home/dst/one", "/home/dst/sub/two", "/home/dstx"]);
+        move_back(&mut paths, "/home/dst", "/home/src");
+        assert_eq!(
+            paths,
+            BTreeMap::from([
+                ("/home/dstx".to_string(), Some("/home/dstx".to_string())),
+                ("/home/src/one".to_string(), Some("/home/dst/one".to_string())),
+                ("/home/src/sub/two".to_string(), Some("/home/dst/sub/two".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn move_back_from_an_unknown_destination_records_where_the_file_is() {
+        let mut paths = BTreeMap::new();
+        move_back(&mut paths, "/elsewhere/a.txt", "/home/a.txt");
+        assert_eq!(paths, BTreeMap::from([("/home/a.txt".to_string(), Some("/elsewhere/a.txt".to_string()))]));
+    }
+
+    fn change(operation_type: &str, destination: Option<&str>, resolution: Option<&str>) -> ChangeRecord {
+        ChangeRecord {
+            id: "change".to_string(),
+            batch_id: "batch".to_string(),
+            operation_type: operation_type.to_string(),
+            source_path: "/home/a.txt".to_string(),
+            destination_path: destination.map(str::to_string),
+            collision_resolution: resolution.map(str::to_string),
+            verified: None,
+            file_data: None,
+            timestamp: String::new(),
+            is_undone: false,
+        }
+    }
+
+    #[test]
+    fn undo_leaves_files_a_collision_policy_never_moved() {
+        let mut staged_batch = None;
+        for resolution in ["skipped", "kept_identical"] {
+            let skipped = change("move", Some("/nowhere/a.txt"), Some(resolution));
+            assert!(undo_change(&skipped, "Organize", &mut staged_batch).unwrap().is_none());
+        }
+        assert!(staged_batch.is_none());
+    }
+
+    #[test]
+    fn undo_refuses_deletions_and_overwriting_copies() {
+        let mut staged_batch = None;
+        for change in [
+            change("shred", None, None),
+            change("trash", None, Some("cache")),
+            change("copy", Some("/nowhere/a.txt"), Some("overwritten")),
+        ] {
+            assert!(undo_change(&change, "Cleanup", &mut staged_batch).is_err());
+        }
+        assert!(staged_batch.is_none());
+    }
+
+    #[test]
+    fn move_back_keeps_a_removed_entry_removed() {
+        let mut paths = BTreeMap::from([("/home/new.txt".to_string(), None)]);
+        move_back(&mut paths, "/home/new.txt", "/home/old.txt");
+        assert_eq!(paths, BTreeMap::from([("/home/old.txt".to_string(), None)]));
+    }
 }
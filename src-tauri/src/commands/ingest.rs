@@ -0,0 +1,371 @@
+// ============================================================================
+// Import Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::commands::files::create_file_node;
+use crate::events::{self, FsChange};
+use crate::fileops::{self, MoveOutcome};
+use crate::ingest::{self, ImportOptions, PendingIngest};
+use crate::libraries::{self, Library};
+use crate::scan::ScanFilter;
+use crate::storage;
+
+// A file left behind because the library already has its content
+#[derive(Debug, Serialize)]
+pub struct DuplicateSkip {
+    pub source: String,
+    pub existing_path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    /// History batch the copies were logged under
+    pub batch_id: String,
+    pub imported: Vec<MoveOutcome>,
+    pub duplicates: Vec<DuplicateSkip>,
+    pub failed: Vec<String>,
+    /// Copies whose hash did not match the source; they were removed
+    pub verification_failed: Vec<String>,
+}
+
+/// Copy files from `source` (a camera card, USB stick, ...) into
+/// `destination`, skipping content the library already has
+#[tauri::command]
+pub async fn import_files(
+    app: AppHandle,
+    source: String,
+    destination: String,
+    options: Option<ImportOptions>,
+) -> Result<ImportReport, String> {
+    let options = options.unwrap_or_default();
+    let source_root = PathBuf::from(&source);
+    if !source_root.is_dir() {
+        return Err(format!("Path is not a directory: {}", source));
+    }
+    if let Some(template) = &options.rename_template {
+        ingest::validate_template(template)?;
+    }
+
+    let report = tokio::task::spawn_blocking(move || {
+        let batch_id = storage::create_history_batch("Import files", &format!("{} → {}", source, destination))?;
+        run_import(&source_root, Path::new(&destination), &options, batch_id)
+    })
+    .await
+    .map_err(|e| format!("Import task error: {}", e))??;
+
+    emit_imported(&app, &report);
+    Ok(report)
+}
+
+// A mounted camera card or phone
+#[derive(Debug, Serialize)]
+pub struct CameraCard {
+    pub root: String,
+    pub dcim_path: String,
+    pub file_count: usize,
+    pub total_size: u64,
+}
+
+/// Check whether `path` is a camera card (has a DCIM folder) and summarize it
+#[tauri::command]
+pub async fn detect_camera_card(path: String) -> Result<Option<CameraCard>, String> {
+    tokio::task::spawn_blocking(move || {
+        let dcim = ingest::find_dcim(Path::new(&path))?;
+        let (file_count, total_size) = WalkDir::new(&dcim)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .fold((0, 0), |(count, size), e| {
+                (count + 1, size + e.metadata().map(|m| m.len()).unwrap_or(0))
+            });
+
+        Some(CameraCard {
+            root: path,
+            dcim_path: dcim.to_string_lossy().to_string(),
+            file_count,
+            total_size,
+        })
+    })
+    .await
+    .map_err(|e| format!("Import task error: {}", e))
+}
+
+/// Import a camera card's DCIM folder into `library` with the photo naming
+/// template, grouped by shooting date.
+///
+/// The ingest is recorded as pending until it completes, so running it again
+/// after the card was removed resumes under the same history batch; files
+/// already copied are recognized by hash and skipped.
+#[tauri::command]
+pub async fn ingest_camera_card(
+    app: AppHandle,
+    card: String,
+    library: String,
+    verify: Option<bool>,
+    naming_template: Option<String>,
+) -> Result<ImportReport, String> {
+    let card_root = PathBuf::from(&card);
+    let dcim = ingest::find_dcim(&card_root).ok_or_else(|| format!("No DCIM folder found on {}", card))?;
+
+    let template = naming_template.unwrap_or_else(|| ingest::PHOTO_NAMING_TEMPLATE.to_string());
+    ingest::validate_template(&template)?;
+    let options = ImportOptions {
+        rename_template: Some(template),
+        recursive: true,
+        skip_duplicates: true,
+        verify: verify.unwrap_or(true),
+        ..Default::default()
+    };
+
+    let report = tokio::task::spawn_blocking(move || {
+        let pending = match ingest::load_pending_ingest() {
+            Some(pending) if pending.card_root == card && pending.library_root == library => pending,
+            _ => {
+                let batch_id = storage::create_history_batch("Camera import", &format!("{} → {}", card, library))?;
+                let pending = PendingIngest {
+                    card_root: card.clone(),
+                    library_root: library.clone(),
+                    batch_id,
+                    started_at: chrono::Utc::now().to_rfc3339(),
+                };
+                ingest::save_pending_ingest(&pending)?;
+                pending
+            }
+        };
+
+        remove_partial_copies(Path::new(&library));
+        let report = run_import(&dcim, Path::new(&library), &options, pending.batch_id)?;
+
+        // Leave the ingest pending if the card went away or files failed
+        if card_root.exists() && report.failed.is_empty() {
+            ingest::clear_pending_ingest()?;
+        }
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Import task error: {}", e))??;
+
+    emit_imported(&app, &report);
+    Ok(report)
+}
+
+/// The camera ingest that was interrupted, if any
+#[tauri::command]
+pub async fn get_pending_ingest() -> Result<Option<PendingIngest>, String> {
+    Ok(ingest::load_pending_ingest())
+}
+
+#[derive(Debug, Serialize)]
+pub struct WipeReport {
+    pub deleted: usize,
+    /// Files kept because no verified copy exists in the library
+    pub kept: Vec<String>,
+}
+
+/// Delete files from a camera card's DCIM folder, but only those whose
+/// content is present in the library right now, outside the card.
+/// Empty files are never deleted since every empty file shares a hash.
+#[tauri::command]
+pub async fn wipe_camera_card(card: String) -> Result<WipeReport, String> {
+    let dcim = ingest::find_dcim(Path::new(&card)).ok_or_else(|| format!("No DCIM folder found on {}", card))?;
+    if ingest::load_pending_ingest().is_some_and(|pending| pending.card_root == card) {
+        return Err("The last import from this card did not finish; resume it first".to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let card_root = PathBuf::from(&card);
+        let libraries = libraries::list();
+        let mut report = WipeReport {
+            deleted: 0,
+            kept: Vec::new(),
+        };
+        let files = WalkDir::new(&dcim)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+
+        for entry in files {
+            let path = entry.path();
+            let empty = entry.metadata().map(|metadata| metadata.len() == 0).unwrap_or(true);
+            let safe = !empty
+                && fileops::hash_file_contents(path)
+                    .and_then(|hash| existing_copy(&hash, &card_root, &libraries))
+                    .map(|existing| existing.is_some())
+                    .unwrap_or(false);
+
+            if safe && fs::remove_file(path).is_ok() {
+                report.deleted += 1;
+            } else {
+                report.kept.push(path.to_string_lossy().to_string());
+            }
+        }
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Import task error: {}", e))?
+}
+
+// Copy every allowed file under `source_root`, logging copies under `batch_id`
+pub(crate) fn run_import(
+    source_root: &Path,
+    destination_root: &Path,
+    options: &ImportOptions,
+    batch_id: String,
+) -> Result<ImportReport, String> {
+    let filter = ScanFilter::new(source_root, false)?;
+    let mut report = ImportReport {
+        batch_id,
+        imported: Vec::new(),
+        duplicates: Vec::new(),
+        failed: Vec::new(),
+        verification_failed: Vec::new(),
+    };
+    // Content copied earlier in this import, for duplicates on the card itself
+    let mut imported_hashes: HashSet<String> = HashSet::new();
+
+    let max_depth = if options.recursive { usize::MAX } else { 1 };
+    let files = WalkDir::new(source_root)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+
+    for (index, entry) in files.enumerate() {
+        let path = entry.path();
+        let result = import_one(
+            path,
+            source_root,
+            destination_root,
+            options,
+            index + 1,
+            &mut imported_hashes,
+        );
+
+        match result {
+            Ok(Imported::Copied(outcome)) => {
+                if let Err(e) = storage::record_change(
+                    &report.batch_id,
+                    "copy",
+                    &outcome.source,
+                    Some(&outcome.destination),
+                    Some(outcome.resolution.as_str()),
+                ) {
+                    eprintln!("Failed to record import in change log: {}", e);
+                }
+                report.imported.push(outcome);
+            }
+            Ok(Imported::Duplicate(existing_path)) => report.duplicates.push(DuplicateSkip {
+                source: path.to_string_lossy().to_string(),
+                existing_path,
+            }),
+            Ok(Imported::Corrupt(destination)) => report.verification_failed.push(destination),
+            Err(_) => report.failed.push(path.to_string_lossy().to_string()),
+        }
+    }
+
+    Ok(report)
+}
+
+enum Imported {
+    Copied(MoveOutcome),
+    /// Content already present at this path
+    Duplicate(String),
+    /// The copy at this path did not match the source and was deleted
+    Corrupt(String),
+}
+
+fn import_one(
+    path: &Path,
+    source_root: &Path,
+    destination_root: &Path,
+    options: &ImportOptions,
+    counter: usize,
+    imported_hashes: &mut HashSet<String>,
+) -> Result<Imported, String> {
+    let metadata = path
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let hash = fileops::hash_file_contents(path)?;
+
+    if options.skip_duplicates && metadata.len() > 0 {
+        if let Some(existing) = existing_copy(&hash, source_root, &libraries::list())? {
+            return Ok(Imported::Duplicate(existing));
+        }
+        if imported_hashes.contains(&hash) {
+            return Ok(Imported::Duplicate(path.to_string_lossy().to_string()));
+        }
+    }
+
+    let relative = match &options.rename_template {
+        Some(template) => {
+            let taken = ingest::capture_time(path, &metadata);
+            ingest::render_template(template, path, taken, counter)
+        }
+        None => path.strip_prefix(source_root).unwrap_or(path).to_path_buf(),
+    };
+    let outcome = fileops::copy_with_policy(path, &destination_root.join(relative), options.collision)?;
+    if !outcome.moved() {
+        return Ok(Imported::Copied(outcome));
+    }
+
+    let copied = PathBuf::from(&outcome.destination);
+    if options.verify && fileops::hash_file_contents(&copied)? != hash {
+        let _ = fs::remove_file(&copied);
+        return Ok(Imported::Corrupt(outcome.destination));
+    }
+    imported_hashes.insert(hash.clone());
+
+    // The copy has the same content, so seed the hash cache instead of rehashing later
+    let node = create_file_node(&copied)?;
+    storage::index_file_nodes(&[node])?;
+    if let Ok(copied_metadata) = copied.metadata() {
+        let (size, mtime) = fileops::fingerprint(&copied_metadata);
+        storage::store_hash(&outcome.destination, size, mtime, &hash)?;
+    }
+
+    Ok(Imported::Copied(outcome))
+}
+
+// An indexed file with this content that still exists on disk, outside
+// `source` (so a card file never counts as its own copy) and inside a
+// library when any are registered
+fn existing_copy(hash: &str, source: &Path, libraries: &[Library]) -> Result<Option<String>, String> {
+    Ok(storage::files_with_hash(hash)?
+        .into_iter()
+        .map(|file| file.path)
+        .filter(|path| !Path::new(path).starts_with(source))
+        .filter(|path| libraries.is_empty() || libraries::library_of(Path::new(path), libraries).is_some())
+        .find(|path| Path::new(path).is_file()))
+}
+
+// Leftovers of copies interrupted by a removed card
+fn remove_partial_copies(root: &Path) {
+    let partials = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file() && e.file_name().to_string_lossy().ends_with(fileops::PARTIAL_SUFFIX)
+        });
+    for entry in partials {
+        let _ = fs::remove_file(entry.path());
+    }
+}
+
+fn emit_imported(app: &AppHandle, report: &ImportReport) {
+    let changes = report
+        .imported
+        .iter()
+        .filter(|outcome| outcome.moved())
+        .map(|outcome| FsChange::created(Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(app, changes);
+}
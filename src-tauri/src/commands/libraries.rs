@@ -0,0 +1,46 @@
+// ============================================================================
+// Library Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::libraries::{self, IndexPolicy, Library};
+
+#[derive(Debug, Serialize)]
+pub struct LibrarySummary {
+    #[serde(flatten)]
+    pub library: Library,
+    pub online: bool,
+}
+
+/// Registered libraries and whether each device is connected
+#[tauri::command]
+pub async fn list_libraries() -> Result<Vec<LibrarySummary>, String> {
+    Ok(libraries::list()
+        .into_iter()
+        .map(|library| LibrarySummary {
+            online: library.is_online(),
+            library,
+        })
+        .collect())
+}
+
+/// Register a folder on a laptop, external drive or NAS as a library
+#[tauri::command]
+pub async fn add_library(name: String, root: String) -> Result<Library, String> {
+    libraries::add(&name, &PathBuf::from(root))
+}
+
+/// Unregister a library; its indexed files are kept
+#[tauri::command]
+pub async fn remove_library(id: String) -> Result<bool, String> {
+    libraries::remove(&id)
+}
+
+/// Set how deeply a library is indexed: names only, hashes, content or
+/// embeddings, plus a size limit for content extraction
+#[tauri::command]
+pub async fn set_library_policy(id: String, policy: IndexPolicy) -> Result<Library, String> {
+    libraries::set_policy(&id, policy)
+}
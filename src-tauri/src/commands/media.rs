@@ -0,0 +1,98 @@
+// ============================================================================
+// Media Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::commands::files::get_file_type;
+use crate::libraries;
+use crate::media::{self, MediaMetadata};
+use crate::scan::ScanFilter;
+use crate::shutdown::{self, OperationKind};
+use crate::storage;
+use crate::throttle;
+
+#[derive(Debug, Serialize)]
+pub struct MediaIndexReport {
+    pub extracted: usize,
+    /// Files left out by their library's index policy
+    pub skipped_by_policy: usize,
+    pub failed: Vec<String>,
+}
+
+/// Get media metadata for a file, extracting and storing it if needed
+#[tauri::command]
+pub async fn get_media_metadata(path: String, refresh: Option<bool>) -> Result<MediaMetadata, String> {
+    if !refresh.unwrap_or(false) {
+        if let Some(metadata) = storage::get_media_metadata(&path)? {
+            return Ok(metadata);
+        }
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let metadata = media::extract(Path::new(&path))?;
+        storage::store_media_metadata(&metadata)?;
+        Ok::<_, String>(metadata)
+    })
+    .await
+    .map_err(|e| format!("Media task error: {}", e))?
+}
+
+/// Extract metadata for every audio/video file in a folder
+#[tauri::command]
+pub async fn index_media(path: String, recursive: bool) -> Result<MediaIndexReport, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let filter = ScanFilter::new(&root, false)?;
+
+    tokio::task::spawn_blocking(move || {
+        let operation = shutdown::begin(OperationKind::Index, &format!("Indexing media in {}", path));
+        let max_depth = if recursive { usize::MAX } else { 1 };
+        let mut report = MediaIndexReport {
+            extracted: 0,
+            skipped_by_policy: 0,
+            failed: Vec::new(),
+        };
+        let libraries = libraries::list();
+
+        let files = WalkDir::new(&root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+
+        for entry in files {
+            if operation.should_stop() {
+                break;
+            }
+            throttle::yield_to_foreground();
+            let is_media = entry
+                .path()
+                .extension()
+                .map(|ext| media::is_media_type(&get_file_type(&ext.to_string_lossy())))
+                .unwrap_or(false);
+            if !is_media {
+                continue;
+            }
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            if !libraries::policy_for(entry.path(), &libraries).allows_content(size) {
+                report.skipped_by_policy += 1;
+                continue;
+            }
+
+            match media::extract(entry.path()).and_then(|m| storage::store_media_metadata(&m)) {
+                Ok(()) => report.extracted += 1,
+                Err(_) => report.failed.push(entry.path().to_string_lossy().to_string()),
+            }
+        }
+
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Media task error: {}", e))?
+}
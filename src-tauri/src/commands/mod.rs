@@ -5,4 +5,27 @@
 pub mod files;
 pub mod organize;
 pub mod history;
+pub mod ingest;
 pub mod ai;
+pub mod cleanup;
+pub mod content;
+pub mod digest;
+pub mod disk;
+pub mod embeddings;
+pub mod folder_style;
+pub mod guided;
+pub mod libraries;
+pub mod media;
+pub mod names;
+pub mod packs;
+pub mod permissions;
+pub mod preview;
+pub mod rules;
+pub mod search;
+pub mod shutdown;
+pub mod staging;
+pub mod startup;
+pub mod storage_sense;
+pub mod throttle;
+pub mod volumes;
+pub mod watcher;
@@ -0,0 +1,186 @@
+// ============================================================================
+// Name Repair Commands
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::events::{self, FsChange};
+use crate::fileops::{self, CollisionPolicy, MoveOutcome};
+use crate::names;
+use crate::scan::ScanFilter;
+use crate::storage;
+
+// A rename proposed by find_broken_names
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameRepair {
+    /// Folder holding the entry
+    pub folder: String,
+    /// Name as it reads now; undecodable bytes show as U+FFFD
+    pub name: String,
+    pub repaired: String,
+    /// How the name was mangled, e.g. "windows-1251" or "UTF-8 read as
+    /// Windows-1252"; None when it is only transliterated
+    pub encoding: Option<String>,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairNamesResult {
+    pub batch_id: String,
+    pub renamed: Vec<MoveOutcome>,
+    pub errors: Vec<String>,
+}
+
+/// Names under `path` that look mis-encoded, with what they decode to, for
+/// the user to review before repair_names. With `transliterate`, names are
+/// also folded to ASCII, so names that are merely non-ASCII are listed too.
+#[tauri::command]
+pub async fn find_broken_names(
+    path: String,
+    recursive: Option<bool>,
+    transliterate: Option<bool>,
+) -> Result<Vec<NameRepair>, String> {
+    let root = PathBuf::from(&path);
+    if !fileops::long_path(&root).is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+    let max_depth = if recursive.unwrap_or(true) {
+        usize::MAX
+    } else {
+        1
+    };
+    let transliterate = transliterate.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let filter = ScanFilter::new(&root, false)?;
+        let repairs = WalkDir::new(fileops::long_path(&root))
+            .min_depth(1)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|entry| {
+                filter.allows(
+                    &fileops::display_path(entry.path()),
+                    entry.file_type().is_dir(),
+                )
+            })
+            .filter_map(|e| e.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let repair = names::repair(entry.file_name());
+                let mut repaired = repair
+                    .as_ref()
+                    .map_or_else(|| name.clone(), |repair| repair.name.clone());
+                if transliterate {
+                    repaired = names::transliterate(&repaired);
+                }
+                if repaired == name {
+                    return None;
+                }
+                Some(NameRepair {
+                    folder: fileops::display_path(entry.path().parent()?)
+                        .to_string_lossy()
+                        .to_string(),
+                    name,
+                    repaired,
+                    encoding: repair.map(|repair| repair.encoding),
+                    is_dir: entry.file_type().is_dir(),
+                })
+            })
+            .collect();
+        Ok::<_, String>(repairs)
+    })
+    .await
+    .map_err(|e| format!("Name scan task error: {}", e))?
+}
+
+/// Rename entries as proposed by find_broken_names, deepest first so
+/// renaming a folder doesn't strand the entries listed inside it. Existing
+/// names are never overwritten; the repaired name gets a suffix instead.
+#[tauri::command]
+pub async fn repair_names(
+    app: AppHandle,
+    mut repairs: Vec<NameRepair>,
+) -> Result<RepairNamesResult, String> {
+    repairs.sort_by_key(|repair| std::cmp::Reverse(Path::new(&repair.folder).components().count()));
+
+    let (result, changes) = tokio::task::spawn_blocking(move || {
+        let protected = storage::protected_paths()?;
+        let batch_id =
+            storage::create_history_batch("Repair names", &format!("{} name(s)", repairs.len()))?;
+        let mut result = RepairNamesResult {
+            batch_id,
+            renamed: Vec::new(),
+            errors: Vec::new(),
+        };
+        let mut changes = Vec::new();
+
+        for repair in repairs {
+            let folder = PathBuf::from(&repair.folder);
+            if !names::is_plain_name(&repair.repaired) {
+                result
+                    .errors
+                    .push(format!("Invalid name: {}", repair.repaired));
+                continue;
+            }
+            // Undecodable names don't survive the trip through a String, so
+            // the entry is looked up by how it reads
+            let Some(source) = find_entry(&folder, &repair.name) else {
+                result.errors.push(format!(
+                    "Not found: {}",
+                    folder.join(&repair.name).display()
+                ));
+                continue;
+            };
+            if fileops::is_protected(&source, &protected) {
+                result
+                    .errors
+                    .push(format!("Path is protected: {}", source.display()));
+                continue;
+            }
+            let target = folder.join(&repair.repaired);
+            let outcome = match fileops::move_with_policy(
+                &source,
+                &target,
+                CollisionPolicy::RenameWithSuffix,
+            ) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    result.errors.push(format!("{}: {}", source.display(), e));
+                    continue;
+                }
+            };
+            storage::record_change(
+                &result.batch_id,
+                "rename",
+                &outcome.source,
+                Some(&outcome.destination),
+                Some(outcome.resolution.as_str()),
+            )?;
+            if let Err(e) = storage::rename_path_records(&outcome.source, &outcome.destination) {
+                eprintln!("Failed to carry file records to new path: {}", e);
+            }
+            changes.push(FsChange::renamed(&source, Path::new(&outcome.destination)));
+            result.renamed.push(outcome);
+        }
+        Ok::<_, String>((result, changes))
+    })
+    .await
+    .map_err(|e| format!("Name repair task error: {}", e))??;
+
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+// The entry in `folder` whose name reads as `name`
+fn find_entry(folder: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(fileops::long_path(folder))
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find(|entry| entry.file_name().to_string_lossy() == name)
+        .map(|entry| folder.join(entry.file_name()))
+}
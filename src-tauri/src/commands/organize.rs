@@ -2,61 +2,1892 @@
 // Organization Commands
 // ============================================================================
 
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::commands::ai::{generate_response, AiFeature, GenerationOptions};
+use crate::commands::files::{self, detect_type};
+use crate::commands::search::run_search;
+use crate::dates::{self, DateFolderFormat, DateFolderSettings, DateSource};
+use crate::events::{self, FsChange};
+use crate::fileops::{self, CollisionPolicy, CollisionResolution, MoveOutcome};
+use crate::folder_style::{self, FolderStyle};
+use crate::ingest;
+use crate::names;
+use crate::projects::{ProjectDetector, ProjectRoot};
+use crate::rules::{DecidedBy, FileFacts, RuleSet};
+use crate::scan::{self, ScanFilter};
+use crate::screenshot;
+use crate::shutdown::{self, OperationKind};
+use crate::search;
+use crate::storage;
+use crate::storage_sense::{self, ManagedFolder};
+use crate::summary;
+use crate::throttle;
+use crate::volumes::{self, FatKind};
+use crate::watcher;
+use crate::xattrs;
+
+/// Upper bound on files selected by a search-driven plan
+const MAX_SEARCH_SELECTION: usize = 10_000;
+/// Folder the "screenshots" plan sorts screenshots into, by year
+const SCREENSHOTS_FOLDER: &str = "Screenshots";
+/// Operations per history batch when applying a plan; larger plans are
+/// applied in sequential parts, each logged under a batch of its own
+const PLAN_PART_SIZE: usize = 5_000;
+/// Preference key prefix of the parts of a plan already applied
+const PLAN_CHECKPOINT_PREFIX: &str = "plan_checkpoint:";
+/// Event emitted after each part of a multi-part plan is applied
+pub const PLAN_PROGRESS_EVENT: &str = "plan-progress";
+/// Event emitted while a move plan's operations are carried out
+pub const PLAN_APPLY_PROGRESS_EVENT: &str = "plan-apply-progress";
+const PLAN_APPLY_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Preference key prefix generated plans are kept under until applied
+const PLAN_KEY_PREFIX: &str = "organization_plan:";
+/// Days a generated plan is kept when it is never fully applied
+const SAVED_PLAN_DAYS: i64 = 7;
+/// Files modified more recently than this may still be being written, and
+/// are left out of plans
+const RECENTLY_MODIFIED: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveOperation {
     pub id: String,
     pub source_path: String,
     pub destination_path: String,
     pub destination_folder: String,
     pub status: String,
+    /// Why this move is expected to fail, e.g. missing permissions
+    #[serde(default)]
+    pub warning: Option<String>,
+}
+
+// How a plan's operations are carried out
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanMode {
+    /// Move the originals into the organized tree
+    #[default]
+    Move,
+    /// Leave the originals in place and build the organized tree out of
+    /// symlinks (shortcuts on Windows) pointing at them
+    Link,
+    /// Leave the originals untouched (e.g. on a read-only volume) and copy
+    /// them into an organized tree under `output_root`
+    Copy,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrganizationPlan {
     pub id: String,
     pub name: String,
     pub description: String,
     pub rule: String,
+    #[serde(default)]
+    pub mode: PlanMode,
+    /// Hash every file before and after it is transferred and halt the
+    /// batch on the first mismatch
+    #[serde(default)]
+    pub paranoid: bool,
     pub operations: Vec<MoveOperation>,
     pub created_at: String,
     pub status: String,
     pub affected_files: usize,
     pub new_folders: Vec<String>,
+    #[serde(default)]
+    pub ambiguities: Vec<PlanAmbiguity>,
+    /// Selected files left out because they are marked do-not-touch
+    #[serde(default)]
+    pub protected: Vec<String>,
+    /// Source-code projects whose files were selected; a project is kept
+    /// whole, so none of its files are planned
+    #[serde(default)]
+    pub projects: Vec<PlanProject>,
+    /// Selected files left out because they sit in derived-data folders
+    #[serde(default)]
+    pub derived_files: usize,
+    /// Downloads in progress and files modified moments ago, left out
+    /// since they may still be being written
+    #[serde(default)]
+    pub in_progress: Vec<String>,
+    /// Online-only files left out of a copy plan, since copying one
+    /// downloads it
+    #[serde(default)]
+    pub placeholders: Vec<String>,
+    /// Files and bytes per bucket, for "bySize" plans
+    #[serde(default)]
+    pub size_buckets: Vec<BucketTotal>,
+    /// Destinations renamed because a FAT or exFAT drive refuses their
+    /// names
+    #[serde(default)]
+    pub fat_renamed: usize,
+    /// One-sentence description for screen-reader announcements
+    #[serde(default)]
+    pub summary: String,
+    /// Colors and icons to give destination folders, by folder path
+    #[serde(default)]
+    pub folder_styles: BTreeMap<String, FolderStyle>,
+}
+
+// A project left whole, and how many of the selected files were inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanProject {
+    #[serde(flatten)]
+    pub root: ProjectRoot,
+    pub selected_files: usize,
+}
+
+// A file matched by several rules of equal priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAmbiguity {
+    pub source_path: String,
+    pub chosen_rule: String,
+    pub competing_rules: Vec<String>,
+    pub decided_by: DecidedBy,
 }
 
-#[derive(Debug, Deserialize)]
+// Which files a plan covers: the files directly inside `path`, or the
+// results of a search query (advanced syntax) or a saved search by name
+#[derive(Debug, Default, Deserialize)]
 pub struct OrganizationConfig {
     pub rule: String,
+    #[serde(default)]
     pub path: String,
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub saved_search: Option<String>,
+    #[serde(default)]
+    pub mode: PlanMode,
+    /// Where copy-mode plans build the organized tree
+    #[serde(default)]
+    pub output_root: Option<String>,
+    #[serde(default)]
+    pub paranoid: bool,
+    /// Also plan files inside dependency, build and cache folders
+    #[serde(default)]
+    pub include_derived: bool,
+    /// How deep the "byDate" plan's folders go
+    #[serde(default)]
+    pub date_format: DateFolderFormat,
+    /// Which date the "byDate" plan files by
+    #[serde(default)]
+    pub date_source: DateSource,
+    /// Folder name pattern for the "byDate" plan, such as
+    /// "{yyyy}/{yyyy}-{mm}"; overrides `date_format` when set
+    #[serde(default)]
+    pub date_pattern: Option<String>,
+    /// Spell new file and folder names in plain ASCII, for destinations
+    /// that reject accents or other scripts
+    #[serde(default)]
+    pub transliterate: bool,
+    /// Buckets for the "bySize" plan, smallest first; Small, Medium and
+    /// Large when unset
+    #[serde(default)]
+    pub size_buckets: Option<Vec<SizeBucket>>,
+}
+
+// A folder files up to a size are sorted into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeBucket {
+    /// Folder name
+    pub name: String,
+    /// Files smaller than this go here; None takes everything larger
+    pub max_size: Option<u64>,
+}
+
+// Files and bytes a size plan puts into one bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketTotal {
+    pub name: String,
+    pub files: usize,
+    pub bytes: u64,
 }
 
 /// Generate an organization plan without applying it
 #[tauri::command]
-pub async fn generate_plan(config: OrganizationConfig) -> Result<OrganizationPlan, String> {
-    // This would analyze files and generate a plan
-    // For now, return a placeholder
+pub async fn generate_plan(app: AppHandle, config: OrganizationConfig) -> Result<OrganizationPlan, String> {
+    let (mut files, source) = select_files(app, &config).await?;
+
+    let protected_roots = storage::protected_paths()?;
+    let mut protected = Vec::new();
+    files.retain(|file| {
+        let keep = !fileops::is_protected(file, &protected_roots);
+        if !keep {
+            protected.push(file.to_string_lossy().to_string());
+        }
+        keep
+    });
+
+    let mut in_progress = Vec::new();
+    files.retain(|file| {
+        let keep = !being_written(file);
+        if !keep {
+            in_progress.push(file.to_string_lossy().to_string());
+        }
+        keep
+    });
+
+    // Generated files belong where their tools put them
+    let mut derived_files = 0;
+    if !config.include_derived {
+        let mut derived: HashMap<PathBuf, bool> = HashMap::new();
+        files.retain(|file| {
+            let inside = file
+                .ancestors()
+                .skip(1)
+                .filter(|ancestor| ancestor.file_name().is_some())
+                .any(|ancestor| {
+                    *derived
+                        .entry(ancestor.to_path_buf())
+                        .or_insert_with(|| scan::derived_kind(ancestor).is_some())
+                });
+            if inside {
+                derived_files += 1;
+            }
+            !inside
+        });
+    }
+
+    // Scattering a repository's files by type would break it
+    let mut detector = ProjectDetector::default();
+    let mut projects: BTreeMap<String, PlanProject> = BTreeMap::new();
+    files.retain(|file| match detector.project_of(file) {
+        Some(root) => {
+            projects
+                .entry(root.path.clone())
+                .or_insert(PlanProject {
+                    root,
+                    selected_files: 0,
+                })
+                .selected_files += 1;
+            false
+        }
+        None => true,
+    });
+    let projects: Vec<PlanProject> = projects.into_values().collect();
+
+    // Only saved rules, type, size, screenshot and date sorting are
+    // implemented so far; other rules return an empty plan
+    let mut size_buckets = Vec::new();
+    let (mut operations, ambiguities) = match config.rule.as_str() {
+        "rules" => rule_operations(&files)?,
+        "byType" | "type" => (type_operations(&files, Path::new(&config.path)), Vec::new()),
+        "screenshots" => (screenshot_operations(&files), Vec::new()),
+        "byDate" | "date" => (date_operations(&files, &config)?, Vec::new()),
+        "bySize" | "size" => {
+            let (operations, totals) = size_operations(&files, &config)?;
+            size_buckets = totals;
+            (operations, Vec::new())
+        }
+        _ => (Vec::new(), Vec::new()),
+    };
+
+    // Reading an online-only file downloads it: copying always reads it, so
+    // it is left out, and a paranoid move skips verifying it
+    let mut placeholders = Vec::new();
+    if config.mode == PlanMode::Copy || (config.mode == PlanMode::Move && config.paranoid) {
+        operations.retain_mut(|op| {
+            let source = Path::new(&op.source_path);
+            let placeholder = fs::metadata(fileops::long_path(source))
+                .is_ok_and(|metadata| fileops::is_placeholder(source, &metadata));
+            if !placeholder {
+                return true;
+            }
+            if config.mode == PlanMode::Copy {
+                placeholders.push(op.source_path.clone());
+                return false;
+            }
+            op.warning
+                .get_or_insert_with(|| "File is online-only; it is moved without verification".to_string());
+            true
+        });
+    }
+
+    if config.mode == PlanMode::Copy {
+        let output_root = config
+            .output_root
+            .as_deref()
+            .ok_or("Copy mode needs an output folder")?;
+        rebase_operations(&mut operations, Path::new(&config.path), Path::new(output_root));
+    }
+
+    if config.transliterate {
+        for op in operations.iter_mut() {
+            rename_new_parts(op, names::transliterate);
+        }
+    }
+
+    // USB sticks and SD cards are mostly FAT: names and sizes it refuses are
+    // dealt with here rather than failing halfway through the apply
+    let fat = fat_destinations(&mut operations, config.mode);
+    let fat_renamed = fat.renamed;
+
+    let new_folders: Vec<String> = operations
+        .iter()
+        .map(|op| op.destination_folder.clone())
+        .filter(|folder| !fileops::long_path(Path::new(folder)).exists())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let folder_styles = folder_style::styles_for(
+        operations.iter().map(|op| op.destination_folder.as_str()),
+        &storage::list_folder_styles()?,
+    );
+
+    let verb = match config.mode {
+        PlanMode::Move => "Will move",
+        PlanMode::Link => "Will link",
+        PlanMode::Copy => "Will copy",
+    };
+    let mut summary = summary::describe_transfer(
+        verb,
+        operations.iter().map(|op| (op.source_path.as_str(), op.destination_path.as_str())),
+        0,
+    );
+    if !protected.is_empty() {
+        summary.push_str(&format!("; {} protected file(s) left out", protected.len()));
+    }
+    if !projects.is_empty() {
+        summary.push_str(&format!("; {} code project(s) left whole", projects.len()));
+    }
+    if derived_files > 0 {
+        summary.push_str(&format!("; {} generated file(s) left out", derived_files));
+    }
+    if !in_progress.is_empty() {
+        summary.push_str(&format!("; {} file(s) still being written left out", in_progress.len()));
+    }
+    if !placeholders.is_empty() {
+        summary.push_str(&format!("; {} online-only file(s) left out", placeholders.len()));
+    }
+    if fat_renamed > 0 {
+        summary.push_str(&format!("; {} name(s) changed for a FAT drive", fat_renamed));
+    }
+    if fat.kinds.contains(&FatKind::Fat) {
+        summary.push_str(&format!(
+            "; FAT keeps modification times to {} seconds",
+            FatKind::Fat.time_resolution().as_secs()
+        ));
+    }
+
     let plan = OrganizationPlan {
         id: uuid::Uuid::new_v4().to_string(),
         name: format!("Organize by {}", config.rule),
-        description: format!("Organize files in {} by {}", config.path, config.rule),
+        description: format!("Organize {} by {}", source, config.rule),
         rule: config.rule,
-        operations: Vec::new(),
+        mode: config.mode,
+        paranoid: config.paranoid,
+        affected_files: operations.len(),
+        operations,
         created_at: chrono::Utc::now().to_rfc3339(),
         status: "preview".to_string(),
-        affected_files: 0,
-        new_folders: Vec::new(),
+        new_folders,
+        ambiguities,
+        protected,
+        projects,
+        derived_files,
+        in_progress,
+        placeholders,
+        size_buckets,
+        fat_renamed,
+        summary,
+        folder_styles,
     };
+    if let Err(e) = prune_saved_plans() {
+        eprintln!("Failed to prune saved plans: {}", e);
+    }
+    save_plan(&plan)?;
 
     Ok(plan)
 }
 
-/// Apply an organization plan
-#[tauri::command]
-pub async fn apply_plan(plan_id: String) -> Result<(), String> {
-    // This would apply the plan by moving files
-    // For now, just return success
-    println!("Applying plan: {}", plan_id);
+// Whether `file` is a download in progress or was modified so recently
+// that something may still be writing it
+fn being_written(file: &Path) -> bool {
+    watcher::is_partial_download(file)
+        || fs::metadata(fileops::long_path(file))
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < RECENTLY_MODIFIED))
+}
+
+// Why an operation of a saved plan must not run now. Paths may have been
+// marked do-not-touch, and files started being written, since the plan was
+// generated, so this is checked again right before each one.
+fn operation_blocked(operation: &MoveOperation, protected: &[String]) -> Option<&'static str> {
+    let source = Path::new(&operation.source_path);
+    let destination = Path::new(&operation.destination_path);
+    if fileops::is_protected(source, protected) || fileops::is_protected(destination, protected) {
+        Some("path is protected")
+    } else if being_written(source) {
+        Some("file may still be being written")
+    } else {
+        None
+    }
+}
+
+// Cancel flag of each move plan being applied, by plan id, set by
+// cancel_apply; that plan stops before its next operation
+static CANCEL_APPLY: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// The cancel flag of plan `plan_id`, created unset when it has none yet
+fn cancel_flag(plan_id: &str) -> Arc<AtomicBool> {
+    CANCEL_APPLY.lock().entry(plan_id.to_string()).or_default().clone()
+}
+
+// Payload of the "plan-apply-progress" event
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanApplyProgress {
+    pub plan_id: String,
+    pub completed: usize,
+    pub total: usize,
+    /// The file being moved; None once the plan has stopped
+    pub current_file: Option<String>,
+    pub errors: usize,
+}
+
+// Keep a generated plan so apply_plan can load it by id
+fn save_plan(plan: &OrganizationPlan) -> Result<(), String> {
+    let json = serde_json::to_string(plan).map_err(|e| e.to_string())?;
+    storage::set_preference(&format!("{}{}", PLAN_KEY_PREFIX, plan.id), &json)
+}
+
+// Forget a saved plan and its checkpoint
+fn delete_plan(plan_id: &str) -> Result<(), String> {
+    storage::delete_preference(&format!("{}{}", PLAN_KEY_PREFIX, plan_id))?;
+    storage::delete_preference(&format!("{}{}", PLAN_CHECKPOINT_PREFIX, plan_id))
+}
+
+// Drop saved plans generated more than SAVED_PLAN_DAYS ago; newer plans of
+// the same folders have superseded them by then
+fn prune_saved_plans() -> Result<(), String> {
+    #[derive(Deserialize)]
+    struct SavedPlan {
+        id: String,
+        created_at: String,
+    }
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(SAVED_PLAN_DAYS);
+    for (_, json) in storage::preferences_with_prefix(PLAN_KEY_PREFIX)? {
+        let Ok(saved) = serde_json::from_str::<SavedPlan>(&json) else {
+            continue;
+        };
+        let expired = chrono::DateTime::parse_from_rfc3339(&saved.created_at).is_ok_and(|created| created < cutoff);
+        if expired {
+            delete_plan(&saved.id)?;
+        }
+    }
     Ok(())
 }
+
+/// Whether applying saved copy plan `plan_id` may replace copies an earlier
+/// run made: any of its files copied before is copied again over the old
+/// copy once it has changed. Unknown plans replace nothing.
+pub(crate) fn copy_plan_replaces_copies(plan_id: &str) -> bool {
+    let Ok(plan) = load_plan(plan_id) else {
+        return false;
+    };
+    plan.mode == PlanMode::Copy
+        && plan
+            .operations
+            .iter()
+            .any(|operation| storage::get_copy_mapping(&operation.source_path).is_ok_and(|mapping| mapping.is_some()))
+}
+
+fn load_plan(plan_id: &str) -> Result<OrganizationPlan, String> {
+    let json = storage::get_preference(&format!("{}{}", PLAN_KEY_PREFIX, plan_id))?
+        .ok_or_else(|| format!("Plan not found: {}", plan_id))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to read plan {}: {}", plan_id, e))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApplyPlanReport {
+    pub plan_id: String,
+    #[serde(flatten)]
+    pub report: MovePlanReport,
+    /// Source folders the moves left empty and that were removed
+    pub removed_folders: Vec<String>,
+}
+
+/// Apply a generated move-mode plan by id. Each move goes through the
+/// collision policy and is logged in the change log under the plan's
+/// history batches. Progress is reported with "plan-apply-progress" events,
+/// and applying a plan again after cancel_apply moves what is left. With
+/// `remove_empty_folders`, source folders the moves leave empty are removed
+/// afterwards, as remove_empty_folders does.
+#[tauri::command]
+pub async fn apply_plan(
+    app: AppHandle,
+    plan_id: String,
+    collision: Option<CollisionPolicy>,
+    remove_empty_folders: Option<bool>,
+) -> Result<ApplyPlanReport, String> {
+    let collision = collision.unwrap_or_default();
+    let mut plan = load_plan(&plan_id)?;
+    if plan.mode != PlanMode::Move {
+        return Err("Plan was not generated in move mode; copy and link plans have commands of their own".to_string());
+    }
+
+    // A fresh flag before the task starts, so a cancel sent right away isn't lost
+    CANCEL_APPLY.lock().insert(plan.id.clone(), Arc::new(AtomicBool::new(false)));
+    let task_app = app.clone();
+    let (plan, report) = tokio::task::spawn_blocking(move || {
+        // A plan cancelled or interrupted earlier continues with what is left
+        let mut pending = plan.clone();
+        pending
+            .operations
+            .retain(|operation| operation.status != "moved" && operation.status != "skipped");
+        // The saved statuses say what is done; parts split from the full plan no longer line up
+        if pending.operations.len() < plan.operations.len() {
+            storage::delete_preference(&format!("{}{}", PLAN_CHECKPOINT_PREFIX, plan.id))?;
+        }
+        let report = apply_move_plan(&task_app, &pending, collision)?;
+        // The saved plan records how far it got, for the UI and for re-runs
+        let statuses: HashMap<&str, OperationStatus> = report
+            .results
+            .iter()
+            .map(|result| (result.operation_id.as_str(), result.status))
+            .collect();
+        for operation in plan.operations.iter_mut() {
+            if let Some(status) = statuses.get(operation.id.as_str()) {
+                operation.status = status.as_str().to_string();
+            }
+        }
+        let unfinished = plan.operations.iter().any(|operation| operation.status == "pending");
+        plan.status = if report.cancelled || unfinished {
+            "partial"
+        } else if report.failed.is_empty() {
+            "applied"
+        } else {
+            "applied_with_errors"
+        }
+        .to_string();
+        // Nothing is left to re-run once every operation went through
+        if plan.status == "applied" {
+            delete_plan(&plan.id)?;
+        } else {
+            save_plan(&plan)?;
+        }
+        Ok::<_, String>((plan, report))
+    })
+    .await
+    .map_err(|e| format!("Apply task error: {}", e))??;
+
+    let mut removed_folders = Vec::new();
+    if remove_empty_folders.unwrap_or(false) {
+        let sources: BTreeSet<PathBuf> = report
+            .moved
+            .iter()
+            .filter(|outcome| outcome.moved())
+            .filter_map(|outcome| Path::new(&outcome.source).parent().map(Path::to_path_buf))
+            .collect();
+        let emptied: Vec<String> = sources
+            .into_iter()
+            .filter(|folder| files::empty_folders(folder).first() == Some(folder))
+            .map(|folder| folder.to_string_lossy().to_string())
+            .collect();
+        if !emptied.is_empty() {
+            removed_folders = files::remove_empty_folders(app, emptied).await?.removed;
+        }
+    }
+
+    Ok(ApplyPlanReport {
+        plan_id: plan.id,
+        report,
+        removed_folders,
+    })
+}
+
+/// Stop move plan `plan_id` after its current operation. What was moved
+/// stays moved and logged; the plan is left "partial". Other plans being
+/// applied carry on.
+#[tauri::command]
+pub async fn cancel_apply(plan_id: String) -> Result<(), String> {
+    match CANCEL_APPLY.lock().get(&plan_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        None => Err(format!("Plan is not being applied: {}", plan_id)),
+    }
+}
+
+/// Time allowed for the model to reword a summary before the template is used
+const SUMMARY_MODEL_TIMEOUT_MS: u64 = 5_000;
+
+// A plan summary and whether the local model or the template produced it
+#[derive(Debug, Serialize)]
+pub struct PlanSummary {
+    pub text: String,
+    /// "model" or "template"
+    pub generated_by: String,
+}
+
+/// Describe a plan in one plain sentence for screen readers. With
+/// `use_model` the loaded local model rewords the template summary; its
+/// answer is only used when it keeps every number, otherwise (or without a
+/// model) the template sentence is returned.
+#[tauri::command]
+pub async fn summarize_plan(plan: OrganizationPlan, use_model: Option<bool>) -> Result<PlanSummary, String> {
+    let template = if plan.summary.is_empty() {
+        summary::describe_transfer(
+            "Will move",
+            plan.operations.iter().map(|op| (op.source_path.as_str(), op.destination_path.as_str())),
+            0,
+        )
+    } else {
+        plan.summary
+    };
+    let fallback = PlanSummary {
+        text: template.clone(),
+        generated_by: "template".to_string(),
+    };
+    if !use_model.unwrap_or(false) {
+        return Ok(fallback);
+    }
+
+    let prompt = format!(
+        "Rewrite this file organization summary as one short, plain sentence for a screen reader. \
+         Keep every number exactly as written.\n\n{}",
+        template
+    );
+    let options = GenerationOptions {
+        deterministic: true,
+        max_duration_ms: Some(SUMMARY_MODEL_TIMEOUT_MS),
+        feature: AiFeature::PlanSummary,
+        ..Default::default()
+    };
+    let Ok(generated) = generate_response(prompt, Some(options)).await else {
+        return Ok(fallback);
+    };
+
+    // Small models drop or invent figures; a summary with wrong numbers is worse than none
+    let numbers = |text: &str| -> BTreeSet<String> {
+        text.split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let text = generated.text.lines().next().unwrap_or_default().trim().to_string();
+    if generated.truncated || text.is_empty() || numbers(&text) != numbers(&template) {
+        return Ok(fallback);
+    }
+    Ok(PlanSummary {
+        text,
+        generated_by: "model".to_string(),
+    })
+}
+
+// One part of a plan applied in parts, logged as its own history batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanPart {
+    pub batch_id: String,
+    /// 1-based
+    pub part: usize,
+    pub parts: usize,
+    /// Index of the part's first operation in the plan
+    pub first_operation: usize,
+    pub operation_count: usize,
+}
+
+// Parts of a plan that finished, so a re-run resumes after them
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PlanCheckpoint {
+    completed: Vec<PlanPart>,
+}
+
+// Payload of the "plan-progress" event
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanProgress {
+    pub plan_id: String,
+    #[serde(flatten)]
+    pub part: PlanPart,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkFarmReport {
+    /// History batch of each part the links were logged under, in order;
+    /// remove_link_farm takes one at a time
+    pub parts: Vec<PlanPart>,
+    /// Parts skipped because an earlier run already finished them
+    pub resumed_parts: usize,
+    pub created: Vec<MoveOutcome>,
+    pub failed: Vec<String>,
+    pub summary: String,
+}
+
+/// Build the organized tree of a link-mode plan, loaded by id, out of links
+/// to the original files, which are not touched
+#[tauri::command]
+pub async fn create_link_farm(
+    app: AppHandle,
+    plan_id: String,
+    collision: Option<CollisionPolicy>,
+) -> Result<LinkFarmReport, String> {
+    let plan = load_plan(&plan_id)?;
+    if plan.mode != PlanMode::Link {
+        return Err("Plan was not generated in link mode".to_string());
+    }
+    let collision = collision.unwrap_or_default();
+
+    let task_app = app.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let app = task_app;
+        let _foreground = throttle::foreground();
+        let _operation = shutdown::begin(OperationKind::Apply, &format!("Linking {}", plan.name));
+        let protected = storage::protected_paths()?;
+        let mut report = LinkFarmReport {
+            parts: Vec::new(),
+            resumed_parts: 0,
+            created: Vec::new(),
+            failed: Vec::new(),
+            summary: String::new(),
+        };
+
+        let (parts, resumed_parts) = apply_in_parts(&app, &plan, "Link farm", |batch_id, operations| {
+            let failed_before = report.failed.len();
+            for operation in operations {
+                if operation_blocked(operation, &protected).is_some() {
+                    report.failed.push(operation.source_path.clone());
+                    continue;
+                }
+                let source = Path::new(&operation.source_path);
+                match fileops::link_with_policy(source, Path::new(&operation.destination_path), collision) {
+                    Ok(outcome) => {
+                        if outcome.moved() {
+                            if let Err(e) = storage::record_change(
+                                batch_id,
+                                "link",
+                                &outcome.source,
+                                Some(&outcome.destination),
+                                Some(outcome.resolution.as_str()),
+                            ) {
+                                eprintln!("Failed to record link in change log: {}", e);
+                            }
+                        }
+                        report.created.push(outcome);
+                    }
+                    Err(_) => report.failed.push(operation.source_path.clone()),
+                }
+            }
+            PartEnd::after(failed_before, report.failed.len())
+        })?;
+        report.parts = parts;
+        report.resumed_parts = resumed_parts;
+        folder_style::apply_planned(&plan.folder_styles);
+        report.summary = summary::describe_transfer(
+            "Linked",
+            report
+                .created
+                .iter()
+                .filter(|outcome| outcome.moved())
+                .map(|outcome| (outcome.source.as_str(), outcome.destination.as_str())),
+            report.failed.len(),
+        );
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Link task error: {}", e))??;
+
+    let changes = report
+        .created
+        .iter()
+        .filter(|outcome| outcome.moved())
+        .map(|outcome| FsChange::created(Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(report)
+}
+
+/// Delete the links created by create_link_farm; originals are never touched.
+/// Links that can't be removed keep the batch open for another try.
+#[tauri::command]
+pub async fn remove_link_farm(app: AppHandle, batch_id: String) -> Result<usize, String> {
+    let removed = tokio::task::spawn_blocking(move || {
+        let mut removed = Vec::new();
+        let mut undone = Vec::new();
+        for change in storage::batch_changes(&batch_id)? {
+            if change.is_undone {
+                continue;
+            }
+            let Some(link) = change.destination_path.filter(|_| change.operation_type == "link") else {
+                undone.push(change.id);
+                continue;
+            };
+            let link = PathBuf::from(link);
+            // Only delete what is still a link; a real file there is the user's
+            if !fileops::is_link(&link) {
+                undone.push(change.id);
+                continue;
+            }
+            match fs::remove_file(fileops::long_path(&link)) {
+                Ok(()) => {
+                    undone.push(change.id);
+                    removed.push(link);
+                }
+                Err(e) => eprintln!("Failed to remove link {}: {}", link.display(), e),
+            }
+        }
+        storage::mark_changes_undone(&batch_id, &undone)?;
+        Ok::<_, String>(removed)
+    })
+    .await
+    .map_err(|e| format!("Link task error: {}", e))??;
+
+    let count = removed.len();
+    events::emit_fs_changed(&app, removed.iter().map(|link| FsChange::removed(link.as_path())).collect());
+    Ok(count)
+}
+
+// Hashes taken around one transfer of a paranoid plan
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    pub source_path: String,
+    pub destination_path: String,
+    pub source_hash: String,
+    pub destination_hash: String,
+    pub matched: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CopyPlanReport {
+    /// History batch of each part, in order
+    pub parts: Vec<PlanPart>,
+    /// Parts skipped because an earlier run already finished them
+    pub resumed_parts: usize,
+    pub copied: Vec<MoveOutcome>,
+    /// Sources already copied and unchanged since
+    pub unchanged: usize,
+    pub failed: Vec<String>,
+    /// Per-file hash checks, for paranoid plans
+    pub verifications: Vec<Verification>,
+    /// Why the batch stopped early, e.g. a copy that didn't match its source
+    pub halted: Option<String>,
+    pub summary: String,
+}
+
+/// Carry out a copy-mode plan, loaded by id. Re-running it copies only
+/// files that are new or changed since the last run; changed files replace
+/// their earlier copy.
+#[tauri::command]
+pub async fn apply_copy_plan(
+    app: AppHandle,
+    plan_id: String,
+    collision: Option<CollisionPolicy>,
+) -> Result<CopyPlanReport, String> {
+    let plan = load_plan(&plan_id)?;
+    if plan.mode != PlanMode::Copy {
+        return Err("Plan was not generated in copy mode".to_string());
+    }
+    let collision = collision.unwrap_or_default();
+
+    let task_app = app.clone();
+    let report = tokio::task::spawn_blocking(move || {
+        let app = task_app;
+        let _foreground = throttle::foreground();
+        let _operation = shutdown::begin(OperationKind::Apply, &format!("Copying {}", plan.name));
+        let protected = storage::protected_paths()?;
+        let mut report = CopyPlanReport {
+            parts: Vec::new(),
+            resumed_parts: 0,
+            copied: Vec::new(),
+            unchanged: 0,
+            failed: Vec::new(),
+            verifications: Vec::new(),
+            halted: None,
+            summary: String::new(),
+        };
+
+        let (parts, resumed_parts) = apply_in_parts(&app, &plan, "Copy", |batch_id, operations| {
+            let failed_before = report.failed.len();
+            for operation in operations {
+                if operation_blocked(operation, &protected).is_some() {
+                    report.failed.push(operation.source_path.clone());
+                    continue;
+                }
+                match copy_operation(operation, collision, plan.paranoid) {
+                    Ok(Some((outcome, verification))) => {
+                        if let Err(e) = storage::record_change(
+                            batch_id,
+                            "copy",
+                            &outcome.source,
+                            Some(&outcome.destination),
+                            Some(outcome.resolution.as_str()),
+                        ) {
+                            eprintln!("Failed to record copy in change log: {}", e);
+                        }
+                        report.copied.push(outcome);
+
+                        if let Some(verification) = verification {
+                            if let Err(e) = storage::record_verification(
+                                batch_id,
+                                &verification.destination_path,
+                                &verification.source_hash,
+                                &verification.destination_hash,
+                            ) {
+                                eprintln!("Failed to record verification in change log: {}", e);
+                            }
+                            let matched = verification.matched;
+                            if !matched {
+                                report.halted = Some(format!(
+                                    "Copy of {} does not match the original",
+                                    verification.source_path
+                                ));
+                            }
+                            report.verifications.push(verification);
+                            if !matched {
+                                return PartEnd::Halted;
+                            }
+                        }
+                    }
+                    Ok(None) => report.unchanged += 1,
+                    Err(_) => report.failed.push(operation.source_path.clone()),
+                }
+            }
+            PartEnd::after(failed_before, report.failed.len())
+        })?;
+        report.parts = parts;
+        report.resumed_parts = resumed_parts;
+        folder_style::apply_planned(&plan.folder_styles);
+        report.summary = summary::describe_transfer(
+            "Copied",
+            report
+                .copied
+                .iter()
+                .filter(|outcome| outcome.moved())
+                .map(|outcome| (outcome.source.as_str(), outcome.destination.as_str())),
+            report.failed.len(),
+        );
+        if report.unchanged > 0 {
+            report.summary.push_str(&format!("; {} unchanged since the last run", report.unchanged));
+        }
+        if report.halted.is_some() {
+            report.summary.push_str("; stopped early after a copy failed verification");
+        }
+        Ok::<_, String>(report)
+    })
+    .await
+    .map_err(|e| format!("Copy task error: {}", e))??;
+
+    let changes = report
+        .copied
+        .iter()
+        .filter(|outcome| outcome.moved())
+        .map(|outcome| FsChange::created(Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(report)
+}
+
+// What happened to one operation of an applied plan
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Moved,
+    /// Left in place by the collision policy, or already at the destination
+    Skipped,
+    Failed,
+}
+
+impl OperationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationStatus::Moved => "moved",
+            OperationStatus::Skipped => "skipped",
+            OperationStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationResult {
+    pub operation_id: String,
+    pub source_path: String,
+    /// Where the file ended up, which differs from the planned destination
+    /// when a collision renamed it
+    pub destination_path: Option<String>,
+    pub status: OperationStatus,
+    pub resolution: Option<CollisionResolution>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovePlanReport {
+    /// History batch of each part, in order
+    pub parts: Vec<PlanPart>,
+    /// Parts skipped because an earlier run already finished them
+    pub resumed_parts: usize,
+    pub moved: Vec<MoveOutcome>,
+    pub failed: Vec<String>,
+    /// One entry per operation attempted in this run, in plan order
+    pub results: Vec<OperationResult>,
+    /// Stopped by cancel_apply before every operation was attempted
+    pub cancelled: bool,
+    /// Per-file hash checks, for paranoid plans
+    pub verifications: Vec<Verification>,
+    /// Why the batch stopped early, e.g. a move that didn't match its source
+    pub halted: Option<String>,
+    pub summary: String,
+}
+
+/// Carry out a move-mode plan on the calling (blocking) thread, logging
+/// every move and emitting the renames. Parts are checkpointed like copy
+/// plans, so a plan interrupted halfway resumes after its finished parts.
+pub(crate) fn apply_move_plan(
+    app: &AppHandle,
+    plan: &OrganizationPlan,
+    collision: CollisionPolicy,
+) -> Result<MovePlanReport, String> {
+    if plan.mode != PlanMode::Move {
+        return Err("Plan was not generated in move mode".to_string());
+    }
+    let _foreground = throttle::foreground();
+    let _operation = shutdown::begin(OperationKind::Apply, &format!("Moving {}", plan.name));
+    let protected = storage::protected_paths()?;
+    let mut report = MovePlanReport {
+        parts: Vec::new(),
+        resumed_parts: 0,
+        moved: Vec::new(),
+        failed: Vec::new(),
+        results: Vec::new(),
+        cancelled: false,
+        verifications: Vec::new(),
+        halted: None,
+        summary: String::new(),
+    };
+    let cancel = cancel_flag(&plan.id);
+    let total = plan.operations.len();
+    let mut last_emit = Instant::now();
+
+    let applied = apply_in_parts(app, plan, "Organize", |batch_id, operations| {
+        // Folders are created up front and logged, so undoing the part can
+        // remove the ones it made
+        let folders: BTreeSet<&str> = operations.iter().map(|op| op.destination_folder.as_str()).collect();
+        for folder in folders {
+            let os_folder = fileops::long_path(Path::new(folder));
+            if os_folder.exists() {
+                continue;
+            }
+            match fs::create_dir_all(&os_folder) {
+                Ok(()) => {
+                    if let Err(e) = storage::record_change(batch_id, "create_folder", folder, None, None) {
+                        eprintln!("Failed to record folder in change log: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Failed to create {}: {}", folder, e),
+            }
+        }
+
+        let failed_before = report.failed.len();
+        for operation in operations {
+            if cancel.load(Ordering::SeqCst) {
+                report.cancelled = true;
+                return PartEnd::Halted;
+            }
+            if last_emit.elapsed() >= PLAN_APPLY_PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                let _ = app.emit(
+                    PLAN_APPLY_PROGRESS_EVENT,
+                    PlanApplyProgress {
+                        plan_id: plan.id.clone(),
+                        completed: report.results.len(),
+                        total,
+                        current_file: Some(operation.source_path.clone()),
+                        errors: report.failed.len(),
+                    },
+                );
+            }
+
+            if let Some(reason) = operation_blocked(operation, &protected) {
+                report.failed.push(format!("{}: {}", operation.source_path, reason));
+                report.results.push(OperationResult {
+                    operation_id: operation.id.clone(),
+                    source_path: operation.source_path.clone(),
+                    destination_path: None,
+                    status: OperationStatus::Failed,
+                    resolution: None,
+                    error: Some(reason.to_string()),
+                });
+                continue;
+            }
+            let source = Path::new(&operation.source_path);
+            let destination = Path::new(&operation.destination_path);
+            let moved = verified_transfer(source, plan.paranoid, || {
+                fileops::move_with_policy(source, destination, collision)
+            });
+            let (outcome, verification) = match moved {
+                Ok(moved) => moved,
+                Err(e) => {
+                    report.failed.push(format!("{}: {}", operation.source_path, e));
+                    report.results.push(OperationResult {
+                        operation_id: operation.id.clone(),
+                        source_path: operation.source_path.clone(),
+                        destination_path: None,
+                        status: OperationStatus::Failed,
+                        resolution: None,
+                        error: Some(e),
+                    });
+                    continue;
+                }
+            };
+            // Skipped and identical files stay where they were: nothing to log or undo
+            if outcome.moved() {
+                if let Err(e) = storage::record_change(
+                    batch_id,
+                    "move",
+                    &outcome.source,
+                    Some(&outcome.destination),
+                    Some(outcome.resolution.as_str()),
+                ) {
+                    eprintln!("Failed to record move in change log: {}", e);
+                }
+                if let Err(e) = storage::rename_path_records(&outcome.source, &outcome.destination) {
+                    eprintln!("Failed to carry file records to new path: {}", e);
+                }
+            }
+            report.results.push(OperationResult {
+                operation_id: operation.id.clone(),
+                source_path: operation.source_path.clone(),
+                destination_path: Some(outcome.destination.clone()),
+                status: if outcome.moved() {
+                    OperationStatus::Moved
+                } else {
+                    OperationStatus::Skipped
+                },
+                resolution: Some(outcome.resolution),
+                error: None,
+            });
+            if outcome.moved() {
+                report.moved.push(outcome);
+            }
+
+            if let Some(verification) = verification {
+                if let Err(e) = storage::record_verification(
+                    batch_id,
+                    &verification.destination_path,
+                    &verification.source_hash,
+                    &verification.destination_hash,
+                ) {
+                    eprintln!("Failed to record verification in change log: {}", e);
+                }
+                let matched = verification.matched;
+                if !matched {
+                    report.halted = Some(format!("Move of {} does not match the original", verification.source_path));
+                }
+                report.verifications.push(verification);
+                if !matched {
+                    return PartEnd::Halted;
+                }
+            }
+        }
+        PartEnd::after(failed_before, report.failed.len())
+    });
+    CANCEL_APPLY.lock().remove(&plan.id);
+    let (parts, resumed_parts) = applied?;
+    report.parts = parts;
+    report.resumed_parts = resumed_parts;
+    folder_style::apply_planned(&plan.folder_styles);
+    report.summary = summary::describe_transfer(
+        "Moved",
+        report
+            .moved
+            .iter()
+            .filter(|outcome| outcome.moved())
+            .map(|outcome| (outcome.source.as_str(), outcome.destination.as_str())),
+        report.failed.len(),
+    );
+    if report.cancelled {
+        report.summary.push_str("; cancelled before the rest");
+    }
+    if report.halted.is_some() {
+        report.summary.push_str("; stopped early after a move failed verification");
+    }
+    let _ = app.emit(
+        PLAN_APPLY_PROGRESS_EVENT,
+        PlanApplyProgress {
+            plan_id: plan.id.clone(),
+            completed: report.results.len(),
+            total,
+            current_file: None,
+            errors: report.failed.len(),
+        },
+    );
+
+    let changes = report
+        .moved
+        .iter()
+        .filter(|outcome| outcome.moved())
+        .map(|outcome| FsChange::renamed(Path::new(&outcome.source), Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(app, changes);
+
+    Ok(report)
+}
+
+// How applying one part of a plan ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartEnd {
+    /// Every operation went through
+    Done,
+    /// Some operations failed; the plan carries on, and the part is applied
+    /// again on the next run
+    Failed,
+    /// The plan stops after this part
+    Halted,
+}
+
+impl PartEnd {
+    // Done unless failures were added to a list that had `before` entries
+    fn after(before: usize, now: usize) -> Self {
+        if now > before {
+            PartEnd::Failed
+        } else {
+            PartEnd::Done
+        }
+    }
+}
+
+// Apply a plan's operations in parts of PLAN_PART_SIZE, each logged under a
+// history batch of its own. Parts that finished without failures are
+// checkpointed, so re-running a plan that was halted or interrupted skips
+// them. Returns the parts applied, including resumed ones, and how many
+// were resumed.
+fn apply_in_parts(
+    app: &AppHandle,
+    plan: &OrganizationPlan,
+    label: &str,
+    mut apply_part: impl FnMut(&str, &[MoveOperation]) -> PartEnd,
+) -> Result<(Vec<PlanPart>, usize), String> {
+    let chunks: Vec<&[MoveOperation]> = plan.operations.chunks(PLAN_PART_SIZE).collect();
+    let count = chunks.len().max(1);
+    let checkpoint_key = format!("{}{}", PLAN_CHECKPOINT_PREFIX, plan.id);
+    let mut checkpoint: PlanCheckpoint = storage::get_preference(&checkpoint_key)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    // A checkpoint only applies to the plan as it was split then
+    checkpoint.completed.retain(|part| part.parts == count);
+    let resumed_parts = checkpoint.completed.len();
+
+    let mut parts = checkpoint.completed.clone();
+    for (index, operations) in chunks.iter().enumerate() {
+        let part_number = index + 1;
+        if checkpoint.completed.iter().any(|part| part.part == part_number) {
+            continue;
+        }
+        // The checkpoint lets the next run pick up from here
+        if shutdown::stopping() {
+            break;
+        }
+        let name = if count > 1 {
+            format!("{}: {} (part {} of {})", label, plan.name, part_number, count)
+        } else {
+            format!("{}: {}", label, plan.name)
+        };
+        let part = PlanPart {
+            batch_id: storage::create_history_batch(&name, &plan.description)?,
+            part: part_number,
+            parts: count,
+            first_operation: index * PLAN_PART_SIZE,
+            operation_count: operations.len(),
+        };
+        let end = apply_part(&part.batch_id, operations);
+        parts.push(part.clone());
+        if end == PartEnd::Halted {
+            break;
+        }
+
+        if end == PartEnd::Done && count > 1 {
+            checkpoint.completed.push(part.clone());
+            let saved = serde_json::to_string(&checkpoint)
+                .map_err(|e| e.to_string())
+                .and_then(|json| storage::set_preference(&checkpoint_key, &json));
+            if let Err(e) = saved {
+                eprintln!("Failed to save plan checkpoint: {}", e);
+            }
+            let _ = app.emit(
+                PLAN_PROGRESS_EVENT,
+                PlanProgress {
+                    plan_id: plan.id.clone(),
+                    part,
+                },
+            );
+        }
+    }
+
+    parts.sort_by_key(|part| part.part);
+    if checkpoint.completed.len() == count {
+        storage::delete_preference(&checkpoint_key)?;
+    }
+    Ok((parts, resumed_parts))
+}
+
+// Copy one operation unless the source is unchanged since its last copy,
+// hashing both sides when `paranoid`
+fn copy_operation(
+    operation: &MoveOperation,
+    collision: CollisionPolicy,
+    paranoid: bool,
+) -> Result<Option<(MoveOutcome, Option<Verification>)>, String> {
+    let source = Path::new(&operation.source_path);
+    let metadata = fs::metadata(fileops::long_path(source)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    // It may have been evicted since the plan was generated
+    if fileops::is_placeholder(source, &metadata) {
+        return Err(format!("File is online-only: {}", operation.source_path));
+    }
+    let (size, mtime) = fileops::fingerprint(&metadata);
+
+    // A changed source replaces the copy it produced before, wherever that went
+    let (destination, policy) = match storage::get_copy_mapping(&operation.source_path)? {
+        Some(previous) if fileops::long_path(Path::new(&previous.destination_path)).exists() => {
+            if previous.size == size && previous.mtime == mtime {
+                return Ok(None);
+            }
+            (PathBuf::from(previous.destination_path), CollisionPolicy::Overwrite)
+        }
+        _ => (PathBuf::from(&operation.destination_path), collision),
+    };
+
+    let (outcome, verification) = verified_transfer(source, paranoid, || {
+        fileops::copy_with_policy(source, &destination, policy)
+    })?;
+    // A copy that failed verification isn't remembered, so the next run retries it
+    if outcome.moved() && !matches!(verification, Some(Verification { matched: false, .. })) {
+        storage::store_copy_mapping(&storage::CopyMapping {
+            source_path: outcome.source.clone(),
+            destination_path: outcome.destination.clone(),
+            size,
+            mtime,
+        })?;
+    }
+    Ok(Some((outcome, verification)))
+}
+
+// Run a move or copy, hashing the source before and the result after when
+// `paranoid`. Skipped and identical files have nothing to verify, and
+// online-only files aren't hashed, since that would download them.
+fn verified_transfer(
+    source: &Path,
+    paranoid: bool,
+    transfer: impl FnOnce() -> Result<MoveOutcome, String>,
+) -> Result<(MoveOutcome, Option<Verification>), String> {
+    let placeholder = fs::metadata(fileops::long_path(source))
+        .is_ok_and(|metadata| fileops::is_placeholder(source, &metadata));
+    if !paranoid || placeholder {
+        return Ok((transfer()?, None));
+    }
+
+    let source_hash = fileops::hash_file_contents(source)?;
+    let outcome = transfer()?;
+    if !outcome.moved() {
+        return Ok((outcome, None));
+    }
+
+    let destination_hash = fileops::hash_file_contents(Path::new(&outcome.destination))?;
+    let verification = Verification {
+        source_path: outcome.source.clone(),
+        destination_path: outcome.destination.clone(),
+        matched: source_hash == destination_hash,
+        source_hash,
+        destination_hash,
+    };
+    Ok((outcome, Some(verification)))
+}
+
+// Move destinations that fall inside the (read-only) source tree over to the
+// same relative place under `output_root`
+fn rebase_operations(operations: &mut [MoveOperation], source_root: &Path, output_root: &Path) {
+    for operation in operations {
+        let destination = PathBuf::from(&operation.destination_path);
+        let relative = if !source_root.as_os_str().is_empty() {
+            destination.strip_prefix(source_root).ok().map(Path::to_path_buf)
+        } else {
+            None
+        };
+        let rebased = match relative {
+            Some(relative) => output_root.join(relative),
+            // Without a common root, keep the destination's last folder and name
+            None => {
+                let mut tail: Vec<_> = destination.components().rev().take(2).collect();
+                tail.reverse();
+                output_root.join(tail.iter().collect::<PathBuf>())
+            }
+        };
+
+        operation.destination_folder = rebased
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        operation.destination_path = rebased.to_string_lossy().to_string();
+    }
+}
+
+// Resolve the plan's file selection and a description of where it came from
+async fn select_files(app: AppHandle, config: &OrganizationConfig) -> Result<(Vec<PathBuf>, String), String> {
+    let query = match (&config.saved_search, &config.query) {
+        (Some(name), _) => {
+            let saved = search::find_saved_search(name)
+                .ok_or_else(|| format!("Saved search not found: {}", name))?;
+            Some((saved.query, format!("saved search \"{}\"", name)))
+        }
+        (None, Some(query)) => Some((query.clone(), format!("files matching \"{}\"", query))),
+        (None, None) => None,
+    };
+
+    let Some((query, source)) = query else {
+        return Ok((direct_files(Path::new(&config.path))?, format!("files in {}", config.path)));
+    };
+
+    let mut query = search::query::parse(&query).into_result()?;
+    query.limit = Some(query.limit.unwrap_or(MAX_SEARCH_SELECTION));
+
+    let files = run_search(app, query)
+        .await?
+        .hits
+        .into_iter()
+        .map(|hit| PathBuf::from(hit.file.path))
+        .filter(|path| fileops::long_path(path).is_file())
+        .collect();
+    Ok((files, source))
+}
+
+// Files directly inside `root` that pass the ignore filter
+fn direct_files(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let filter = ScanFilter::new(root, false)?;
+    let entries = fs::read_dir(fileops::long_path(root)).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .map(|e| fileops::display_path(&e.path()))
+        .filter(|path| fileops::long_path(path).is_file() && filter.allows(path, false))
+        .collect())
+}
+
+// Folder each file type is sorted into, named as the file browser shows them
+fn type_folder(file_type: &str) -> &'static str {
+    match file_type {
+        "document" => "Documents",
+        "pdf" => "PDFs",
+        "spreadsheet" => "Spreadsheets",
+        "presentation" => "Presentations",
+        "image" => "Images",
+        screenshot::SCREENSHOT_FILE_TYPE => SCREENSHOTS_FOLDER,
+        "video" => "Videos",
+        "audio" => "Audio",
+        "archive" => "Archives",
+        "executable" => "Installers",
+        "code" => "Code",
+        _ => "Other Files",
+    }
+}
+
+// Sort the selected files into one folder per file type under the plan's
+// folder, or next to each file when it was selected by a search elsewhere
+fn type_operations(files: &[PathBuf], root: &Path) -> Vec<MoveOperation> {
+    let managed_folders = storage_sense::managed_folders();
+    let mut operations = Vec::new();
+
+    for path in files {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+        let (file_type, _) = detect_type(&fileops::long_path(path), extension.as_deref());
+        let base = if !root.as_os_str().is_empty() && path.starts_with(root) {
+            root
+        } else {
+            match path.parent() {
+                Some(parent) => parent,
+                None => continue,
+            }
+        };
+        let destination = base.join(type_folder(&file_type)).join(name);
+
+        if &destination != path {
+            operations.push(move_operation(path, &destination, &managed_folders));
+        }
+    }
+    operations
+}
+
+// Match the selected files against the saved rules
+fn rule_operations(files: &[PathBuf]) -> Result<(Vec<MoveOperation>, Vec<PlanAmbiguity>), String> {
+    let rule_set = RuleSet::load_active()?;
+    if rule_set.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let mut operations = Vec::new();
+    let mut ambiguities = Vec::new();
+    let mut hits: HashMap<String, i64> = HashMap::new();
+    let managed_folders = storage_sense::managed_folders();
+
+    for path in files {
+        let decision = rule_set.decide(&FileFacts::from_path(path));
+        let (Some(rule_id), Some(destination)) = (decision.winner.clone(), decision.destination.clone())
+        else {
+            continue;
+        };
+        *hits.entry(rule_id).or_insert(0) += 1;
+
+        if decision.ambiguous {
+            let chosen = &decision.candidates[0];
+            ambiguities.push(PlanAmbiguity {
+                source_path: decision.path.clone(),
+                chosen_rule: chosen.rule_name.clone(),
+                competing_rules: decision.candidates[1..]
+                    .iter()
+                    .filter(|c| c.matched && c.priority == chosen.priority)
+                    .map(|c| c.rule_name.clone())
+                    .collect(),
+                decided_by: decision.decided_by.unwrap_or(DecidedBy::Recency),
+            });
+        }
+
+        let destination = PathBuf::from(destination);
+
+        if &destination == path {
+            continue;
+        }
+
+        operations.push(move_operation(path, &destination, &managed_folders));
+    }
+
+    if let Err(e) = storage::record_rule_hits(&hits) {
+        eprintln!("Failed to record rule hits: {}", e);
+    }
+
+    Ok((operations, ambiguities))
+}
+
+// Move screenshots into Screenshots/<year> next to where they are. Files
+// already inside a Screenshots folder are sorted within it rather than
+// nested another level down.
+fn screenshot_operations(files: &[PathBuf]) -> Vec<MoveOperation> {
+    let managed_folders = storage_sense::managed_folders();
+    let mut operations = Vec::new();
+
+    for path in files {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+        let (file_type, _) = detect_type(&fileops::long_path(path), extension.as_deref());
+        if file_type != screenshot::SCREENSHOT_FILE_TYPE {
+            continue;
+        }
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+
+        let is_screenshots = |folder: &Path| {
+            folder
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(SCREENSHOTS_FOLDER))
+        };
+        let is_year = |folder: &Path| {
+            folder
+                .file_name()
+                .is_some_and(|n| n.len() == 4 && n.to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+        };
+        let screenshots = match parent.parent() {
+            Some(grandparent) if is_year(parent) && is_screenshots(grandparent) => grandparent.to_path_buf(),
+            _ if is_screenshots(parent) => parent.to_path_buf(),
+            _ => parent.join(SCREENSHOTS_FOLDER),
+        };
+        let folder = match screenshot::year_taken(path) {
+            Some(year) => screenshots.join(year.to_string()),
+            None => screenshots,
+        };
+        let destination = folder.join(name);
+
+        if &destination != path {
+            operations.push(move_operation(path, &destination, &managed_folders));
+        }
+    }
+    operations
+}
+
+// Small, Medium and Large, split at 10 MB and 1 GB
+fn default_size_buckets() -> Vec<SizeBucket> {
+    vec![
+        SizeBucket {
+            name: "Small (under 10 MB)".to_string(),
+            max_size: Some(10 * 1024 * 1024),
+        },
+        SizeBucket {
+            name: "Medium (10 MB to 1 GB)".to_string(),
+            max_size: Some(1024 * 1024 * 1024),
+        },
+        SizeBucket {
+            name: "Large (over 1 GB)".to_string(),
+            max_size: None,
+        },
+    ]
+}
+
+// Sort files into one folder per size bucket under the plan's folder, or
+// next to each file when it was selected by a search elsewhere. Files
+// larger than every bucket's limit go into the last one.
+fn size_operations(
+    files: &[PathBuf],
+    config: &OrganizationConfig,
+) -> Result<(Vec<MoveOperation>, Vec<BucketTotal>), String> {
+    let buckets = config.size_buckets.clone().unwrap_or_else(default_size_buckets);
+    if buckets.is_empty() {
+        return Err("At least one size bucket is needed".to_string());
+    }
+    for bucket in &buckets {
+        let name = bucket.name.trim();
+        if !names::is_plain_name(name) {
+            return Err(format!("Invalid bucket name: {}", bucket.name));
+        }
+    }
+    let limits: Vec<u64> = buckets.iter().map_while(|bucket| bucket.max_size).collect();
+    if limits.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("Size buckets must go from smallest to largest".to_string());
+    }
+
+    let root = Path::new(&config.path);
+    let managed_folders = storage_sense::managed_folders();
+    let mut totals: Vec<BucketTotal> = buckets
+        .iter()
+        .map(|bucket| BucketTotal {
+            name: bucket.name.clone(),
+            files: 0,
+            bytes: 0,
+        })
+        .collect();
+    let mut operations = Vec::new();
+
+    for path in files {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let Ok(metadata) = fs::metadata(fileops::long_path(path)) else {
+            continue;
+        };
+        let size = metadata.len();
+        let index = buckets
+            .iter()
+            .position(|bucket| bucket.max_size.map_or(true, |max| size < max))
+            .unwrap_or(buckets.len() - 1);
+        let base = if !root.as_os_str().is_empty() && path.starts_with(root) {
+            root
+        } else {
+            match path.parent() {
+                Some(parent) => parent,
+                None => continue,
+            }
+        };
+        let destination = base.join(&buckets[index].name).join(name);
+
+        if &destination != path {
+            totals[index].files += 1;
+            totals[index].bytes += size;
+            operations.push(move_operation(path, &destination, &managed_folders));
+        }
+    }
+    Ok((operations, totals))
+}
+
+// Move files into Year/Month folders under the plan's folder by the
+// configured date, on the local clock; files outside it (search-driven
+// plans) are sorted next to where they are
+fn date_operations(files: &[PathBuf], config: &OrganizationConfig) -> Result<Vec<MoveOperation>, String> {
+    if let Some(pattern) = &config.date_pattern {
+        dates::validate_pattern(pattern)?;
+    }
+    let root = Path::new(&config.path);
+    let managed_folders = storage_sense::managed_folders();
+    let settings = dates::load_settings();
+    let mut operations = Vec::new();
+
+    for path in files {
+        let Some(destination) = date_destination(path, root, config, &settings) else {
+            continue;
+        };
+        if &destination != path {
+            operations.push(move_operation(path, &destination, &managed_folders));
+        }
+    }
+    Ok(operations)
+}
+
+fn date_destination(
+    path: &Path,
+    root: &Path,
+    config: &OrganizationConfig,
+    settings: &DateFolderSettings,
+) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let os_path = fileops::long_path(path);
+    let metadata = fs::metadata(&os_path).ok()?;
+    let modified = metadata.modified().ok()?;
+    let date = match config.date_source {
+        // EXIF would have to be downloaded from an online-only file
+        DateSource::Capture if fileops::is_placeholder(path, &metadata) => dates::local_time(modified),
+        DateSource::Capture => ingest::capture_time(&os_path, &metadata),
+        DateSource::Modified => dates::local_time(modified),
+        DateSource::Created => dates::local_time(metadata.created().unwrap_or(modified)),
+    };
+    let base = if !root.as_os_str().is_empty() && path.starts_with(root) {
+        root
+    } else {
+        path.parent()?
+    };
+    let folder = match &config.date_pattern {
+        Some(pattern) => dates::pattern_folder(date, pattern, settings),
+        None => dates::date_folder(date, config.date_format, settings),
+    };
+    Some(base.join(folder).join(name))
+}
+
+/// Date folder naming used by "byDate" plans and the {month} import placeholder
+#[tauri::command]
+pub async fn get_date_folder_settings() -> Result<DateFolderSettings, String> {
+    Ok(dates::load_settings())
+}
+
+/// Change date folder naming, e.g. to add Spanish month names
+#[tauri::command]
+pub async fn set_date_folder_settings(settings: DateFolderSettings) -> Result<DateFolderSettings, String> {
+    dates::save_settings(&settings)?;
+    Ok(dates::load_settings())
+}
+
+// Rename the parts of a destination that don't exist yet; folders that are
+// already there keep their names
+fn rename_new_parts(op: &mut MoveOperation, rename: impl Fn(&str) -> String) {
+    let destination = PathBuf::from(&op.destination_path);
+    let Some(existing) = destination
+        .ancestors()
+        .skip(1)
+        .find(|folder| fileops::long_path(folder).exists())
+    else {
+        return;
+    };
+    let Ok(rest) = destination.strip_prefix(existing) else {
+        return;
+    };
+    let mut renamed = existing.to_path_buf();
+    for component in rest.components() {
+        renamed.push(rename(&component.as_os_str().to_string_lossy()));
+    }
+    op.destination_folder = renamed
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    op.destination_path = renamed.to_string_lossy().to_string();
+}
+
+// What the FAT pass changed, and which FAT file systems the plan writes to
+#[derive(Default)]
+struct FatDestinations {
+    renamed: usize,
+    kinds: BTreeSet<FatKind>,
+}
+
+// Fit operations bound for FAT or exFAT drives to them: names sanitized,
+// and files FAT32 can't hold or links it can't store flagged. The mount
+// table is only read; without it the check is skipped.
+fn fat_destinations(operations: &mut [MoveOperation], mode: PlanMode) -> FatDestinations {
+    let mut result = FatDestinations::default();
+    let mounted = volumes::mounted_now();
+    if mounted.is_empty() {
+        eprintln!("Could not read the mount table; FAT destinations are not checked");
+        return result;
+    }
+    if !mounted.iter().any(|volume| volume.fat_kind().is_some()) {
+        return result;
+    }
+    for op in operations.iter_mut() {
+        let Some(kind) = volumes::volume_of(Path::new(&op.destination_path), &mounted).and_then(|v| v.fat_kind())
+        else {
+            continue;
+        };
+        result.kinds.insert(kind);
+
+        let before = op.destination_path.clone();
+        rename_new_parts(op, names::fat_safe);
+        if op.destination_path != before {
+            result.renamed += 1;
+        }
+
+        if op.warning.is_some() {
+            continue;
+        }
+        if mode == PlanMode::Link && cfg!(unix) {
+            op.warning = Some("FAT and exFAT drives can't hold symlinks".to_string());
+            continue;
+        }
+        let size = fs::metadata(fileops::long_path(Path::new(&op.source_path))).map_or(0, |m| m.len());
+        if kind.max_file_size().is_some_and(|max| size > max) {
+            op.warning = Some(format!(
+                "File is {:.1} GB, larger than the 4 GB a FAT32 drive can hold",
+                size as f64 / (1u64 << 30) as f64
+            ));
+        }
+    }
+    result
+}
+
+fn move_operation(path: &Path, destination: &Path, managed_folders: &[ManagedFolder]) -> MoveOperation {
+    MoveOperation {
+        id: uuid::Uuid::new_v4().to_string(),
+        source_path: path.to_string_lossy().to_string(),
+        destination_folder: destination
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        destination_path: destination.to_string_lossy().to_string(),
+        status: "pending".to_string(),
+        warning: move_warning(path, destination, managed_folders),
+    }
+}
+
+// Pre-flight check so the plan preview can flag moves that will fail or
+// that conflict with the OS's own cleanup
+fn move_warning(path: &Path, destination: &Path, managed_folders: &[ManagedFolder]) -> Option<String> {
+    let os_path = fileops::long_path(path);
+    let metadata = match fs::metadata(&os_path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Some(format!("Cannot read file: {}", e)),
+    };
+    let permissions = fileops::file_permissions(&os_path, &metadata);
+    if !permissions.movable {
+        return Some("No permission to move files out of this folder".to_string());
+    }
+
+    let source_roots = storage_sense::managing(path, managed_folders);
+    let stays_in_cloud = source_roots.iter().any(|folder| destination.starts_with(&folder.path));
+    if fileops::is_placeholder(path, &metadata) && !stays_in_cloud {
+        return Some("File is online-only; moving it out of its cloud folder downloads it".to_string());
+    }
+    let streams = xattrs::alternate_streams(&os_path);
+    if !streams.is_empty() && !xattrs::supports_streams(&fileops::long_path(destination)) {
+        return Some(format!(
+            "File has {} alternate data stream(s) ({}) that the destination's file system drops",
+            streams.len(),
+            streams.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    storage_sense::destination_warning(destination, managed_folders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_status_names_match_their_serialized_form() {
+        for status in [OperationStatus::Moved, OperationStatus::Skipped, OperationStatus::Failed] {
+            assert_eq!(serde_json::to_value(status).unwrap(), status.as_str());
+        }
+    }
+}
@@ -0,0 +1,157 @@
+// ============================================================================
+// Organization Pack Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::packs::{self, PackValidation, SignatureStatus};
+use crate::rules::{FileFacts, RuleSet};
+use crate::storage::{self, InstalledPack};
+
+const DEFAULT_PREVIEW_LIMIT: usize = 200;
+
+#[derive(Debug, Serialize)]
+pub struct PackMatch {
+    pub path: String,
+    pub rule_name: String,
+    pub destination: String,
+}
+
+// What a pack would do to the indexed files, computed without saving or moving anything
+#[derive(Debug, Serialize)]
+pub struct PackPreview {
+    pub pack_id: String,
+    pub matches: Vec<PackMatch>,
+    pub total_matches: usize,
+    /// Indexed files per pack category
+    pub categories: BTreeMap<String, usize>,
+    pub scanned: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Parse a pack file and report signature status and every problem found,
+/// without importing anything
+#[tauri::command]
+pub async fn validate_pack(json: String) -> Result<PackValidation, String> {
+    Ok(packs::validate(&json))
+}
+
+/// Import a pack with its rules switched off. Packs with a broken signature
+/// are refused; unsigned or untrusted ones need `allow_untrusted`.
+#[tauri::command]
+pub async fn import_pack(json: String, allow_untrusted: Option<bool>) -> Result<InstalledPack, String> {
+    let validation = packs::validate(&json);
+    if !validation.is_valid() {
+        return Err(format!("Pack is not valid: {}", validation.errors.join("; ")));
+    }
+    if validation.signature != SignatureStatus::Trusted && !allow_untrusted.unwrap_or(false) {
+        return Err("Pack is not signed by a trusted key".to_string());
+    }
+    let Some(pack) = validation.pack else {
+        return Err("Pack is not valid".to_string());
+    };
+
+    storage::install_pack(&pack, validation.signature, &packs::rules_of(&pack, false))?;
+    find_pack(&pack.id)
+}
+
+/// Run a pack's rules against the indexed files in a sandbox: nothing is
+/// saved, moved or sent to the model. A pack must be previewed before it can
+/// be activated.
+#[tauri::command]
+pub async fn preview_pack(
+    pack_id: String,
+    scope: Option<String>,
+    limit: Option<usize>,
+) -> Result<PackPreview, String> {
+    let started = Instant::now();
+    let installed = find_pack(&pack_id)?;
+    let rule_set = RuleSet::from_rules(packs::rules_of(&installed.pack, true));
+    let files = storage::indexed_files(scope.as_deref())?;
+    let limit = limit.unwrap_or(DEFAULT_PREVIEW_LIMIT);
+
+    let mut matches = Vec::new();
+    let mut total_matches = 0;
+    let mut categories: BTreeMap<String, usize> = BTreeMap::new();
+    for file in &files {
+        let path = Path::new(&file.path);
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        for (category, extensions) in &installed.pack.category_mappings {
+            if extensions.iter().any(|ext| ext.eq_ignore_ascii_case(&extension)) {
+                *categories.entry(category.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let decision = rule_set.decide(&FileFacts::from_indexed(file));
+        let (Some(winner), Some(destination)) = (decision.candidates.first(), decision.destination.clone()) else {
+            continue;
+        };
+        total_matches += 1;
+        if matches.len() < limit {
+            matches.push(PackMatch {
+                path: file.path.clone(),
+                rule_name: winner.rule_name.clone(),
+                destination,
+            });
+        }
+    }
+
+    storage::mark_pack_previewed(&pack_id)?;
+    Ok(PackPreview {
+        pack_id,
+        matches,
+        total_matches,
+        categories,
+        scanned: files.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Switch on a previewed pack's rules
+#[tauri::command]
+pub async fn activate_pack(pack_id: String) -> Result<InstalledPack, String> {
+    let installed = find_pack(&pack_id)?;
+    if installed.previewed_at.is_none() {
+        return Err("Preview the pack before activating it".to_string());
+    }
+    storage::set_pack_active(&pack_id, true)?;
+    find_pack(&pack_id)
+}
+
+/// Switch a pack's rules off again; the pack stays imported
+#[tauri::command]
+pub async fn deactivate_pack(pack_id: String) -> Result<InstalledPack, String> {
+    find_pack(&pack_id)?;
+    storage::set_pack_active(&pack_id, false)?;
+    find_pack(&pack_id)
+}
+
+/// Every imported pack
+#[tauri::command]
+pub async fn list_packs() -> Result<Vec<InstalledPack>, String> {
+    storage::list_packs()
+}
+
+/// Trust packs signed with a base64 ed25519 public key
+#[tauri::command]
+pub async fn trust_pack_key(public_key: String) -> Result<Vec<String>, String> {
+    let mut keys = packs::trusted_keys();
+    if !keys.contains(&public_key) {
+        keys.push(public_key);
+        packs::save_trusted_keys(&keys)?;
+    }
+    Ok(keys)
+}
+
+fn find_pack(pack_id: &str) -> Result<InstalledPack, String> {
+    storage::list_packs()?
+        .into_iter()
+        .find(|installed| installed.pack.id == pack_id)
+        .ok_or_else(|| format!("Pack not found: {}", pack_id))
+}
@@ -0,0 +1,77 @@
+// ============================================================================
+// Permission Commands
+// ============================================================================
+
+use serde::Serialize;
+
+use crate::permissions::{self, Capability, PermissionSettings};
+
+// A workspace's settings, with its name
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspacePermissions {
+    pub workspace: String,
+    pub active: bool,
+    #[serde(flatten)]
+    pub settings: PermissionSettings,
+}
+
+// A command and what it is allowed to do
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandCapability {
+    pub command: String,
+    pub capability: Capability,
+}
+
+fn workspace_permissions(workspace: String) -> Result<WorkspacePermissions, String> {
+    let settings = permissions::settings(&workspace)?;
+    Ok(WorkspacePermissions {
+        active: workspace == permissions::active_workspace(),
+        workspace,
+        settings,
+    })
+}
+
+/// Settings of a workspace, the active one when none is given
+#[tauri::command]
+pub async fn get_permissions(workspace: Option<String>) -> Result<WorkspacePermissions, String> {
+    workspace_permissions(workspace.unwrap_or_else(permissions::active_workspace))
+}
+
+/// Change what a workspace lets the app do, including read-only mode.
+/// Settings that allow more than before need `confirmed`, given once the
+/// user has agreed to the change.
+#[tauri::command]
+pub async fn set_permissions(
+    workspace: Option<String>,
+    settings: PermissionSettings,
+    confirmed: Option<bool>,
+) -> Result<WorkspacePermissions, String> {
+    let workspace = workspace.unwrap_or_else(permissions::active_workspace);
+    if settings.loosens(&permissions::settings(&workspace)?) && !confirmed.unwrap_or(false) {
+        return Err(format!("Allowing more in workspace {} needs confirmation", workspace));
+    }
+    permissions::set_settings(&workspace, settings)?;
+    workspace_permissions(workspace)
+}
+
+/// Switch to another workspace's settings; unknown workspaces start in
+/// read-only mode. Switching to one that allows more needs `confirmed`.
+#[tauri::command]
+pub async fn set_active_workspace(workspace: String, confirmed: Option<bool>) -> Result<WorkspacePermissions, String> {
+    let workspace = workspace.trim().to_string();
+    let current = permissions::settings(&permissions::active_workspace())?;
+    if permissions::settings(&workspace)?.loosens(&current) && !confirmed.unwrap_or(false) {
+        return Err(format!("Workspace {} allows more than the current one; switching needs confirmation", workspace));
+    }
+    permissions::set_active_workspace(&workspace)?;
+    workspace_permissions(workspace)
+}
+
+/// How every command is classified
+#[tauri::command]
+pub async fn list_command_capabilities() -> Result<Vec<CommandCapability>, String> {
+    Ok(permissions::command_capabilities()
+        .into_iter()
+        .map(|(command, capability)| CommandCapability { command, capability })
+        .collect())
+}
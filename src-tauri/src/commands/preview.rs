@@ -0,0 +1,203 @@
+// ============================================================================
+// Preview Commands
+// ============================================================================
+
+use base64::Engine;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::thumbnails;
+
+#[derive(Debug, Serialize)]
+pub struct Thumbnail {
+    pub path: String,
+    pub mime_type: String,
+    /// Data URL, only filled when requested with `as_base64`
+    pub data: Option<String>,
+}
+
+/// Get a cached thumbnail for an image or video, generating it on first request
+#[tauri::command]
+pub async fn get_thumbnail(
+    app: AppHandle,
+    path: String,
+    size: Option<u32>,
+    as_base64: Option<bool>,
+) -> Result<Thumbnail, String> {
+    let size = size.unwrap_or(thumbnails::DEFAULT_THUMBNAIL_SIZE);
+    let source = PathBuf::from(&path);
+
+    let thumbnail_path = tokio::task::spawn_blocking(move || thumbnails::thumbnail_for(&app, &source, size))
+        .await
+        .map_err(|e| format!("Thumbnail task error: {}", e))??;
+
+    let data = if as_base64.unwrap_or(false) {
+        let bytes = tokio::fs::read(&thumbnail_path)
+            .await
+            .map_err(|e| format!("Failed to read thumbnail: {}", e))?;
+        Some(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    } else {
+        None
+    };
+
+    Ok(Thumbnail {
+        path: thumbnail_path.to_string_lossy().to_string(),
+        mime_type: "image/png".to_string(),
+        data,
+    })
+}
+
+/// Frames of a video's scenes, for telling clips apart in the organize
+/// preview; cached like thumbnails
+#[tauri::command]
+pub async fn get_video_frames(
+    app: AppHandle,
+    path: String,
+    count: Option<u32>,
+    size: Option<u32>,
+) -> Result<Vec<Thumbnail>, String> {
+    let count = count.unwrap_or(DEFAULT_SCENE_FRAMES);
+    let size = size.unwrap_or(thumbnails::DEFAULT_THUMBNAIL_SIZE);
+    let source = PathBuf::from(&path);
+
+    let frames = tokio::task::spawn_blocking(move || thumbnails::scene_frames_for(&app, &source, count, size))
+        .await
+        .map_err(|e| format!("Video frame task error: {}", e))??;
+
+    Ok(frames
+        .into_iter()
+        .map(|frame| Thumbnail {
+            path: frame.to_string_lossy().to_string(),
+            mime_type: "image/png".to_string(),
+            data: None,
+        })
+        .collect())
+}
+
+const DEFAULT_SCENE_FRAMES: u32 = 4;
+const DEFAULT_PREVIEW_KB: usize = 16;
+const MAX_PREVIEW_KB: usize = 1024;
+const HEX_BYTES_PER_LINE: usize = 16;
+
+// Leading content of a file, decoded as text or dumped as hex
+#[derive(Debug, Serialize)]
+pub struct FilePreview {
+    pub path: String,
+    /// "text" or "hex"
+    pub kind: String,
+    /// Detected text encoding, for text previews
+    pub encoding: Option<String>,
+    pub content: String,
+    pub bytes_read: usize,
+    pub file_size: u64,
+    pub truncated: bool,
+}
+
+/// Show the first `max_kb` KB of a file: decoded text for text files, a hex
+/// dump for binaries
+#[tauri::command]
+pub async fn preview_file(path: String, max_kb: Option<usize>) -> Result<FilePreview, String> {
+    let limit = max_kb.unwrap_or(DEFAULT_PREVIEW_KB).clamp(1, MAX_PREVIEW_KB) * 1024;
+
+    tokio::task::spawn_blocking(move || {
+        let file = File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let file_size = file
+            .metadata()
+            .map_err(|e| format!("Failed to read metadata: {}", e))?
+            .len();
+
+        let mut bytes = Vec::with_capacity(limit.min(file_size as usize));
+        file.take(limit as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let truncated = (bytes.len() as u64) < file_size;
+
+        let (kind, encoding, content) = match decode_text(&bytes, truncated) {
+            Some((encoding, text)) => ("text", Some(encoding), text),
+            None => ("hex", None, hex_dump(&bytes)),
+        };
+
+        Ok::<_, String>(FilePreview {
+            path,
+            kind: kind.to_string(),
+            encoding,
+            content,
+            bytes_read: bytes.len(),
+            file_size,
+            truncated,
+        })
+    })
+    .await
+    .map_err(|e| format!("Preview task error: {}", e))?
+}
+
+// Decode bytes as text if they look like text, detecting the encoding from a
+// BOM or the content. `truncated` means the last character may be cut off.
+fn decode_text(bytes: &[u8], truncated: bool) -> Option<(String, String)> {
+    if let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return Some((encoding.name().to_string(), text.into_owned()));
+    }
+
+    if looks_binary(bytes) {
+        return None;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => return Some(("UTF-8".to_string(), text.to_string())),
+        // A multi-byte character split by the read limit is still UTF-8
+        Err(e) if truncated && e.error_len().is_none() => {
+            let text = String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string();
+            return Some(("UTF-8".to_string(), text));
+        }
+        Err(_) => {}
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, !truncated);
+    let encoding = detector.guess(None, true);
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return None;
+    }
+    Some((encoding.name().to_string(), text.into_owned()))
+}
+
+// NUL bytes or a high share of control characters mean binary content
+fn looks_binary(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return true;
+    }
+    let control = bytes
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c | 0x1b))
+        .count();
+    control * 10 > bytes.len()
+}
+
+// Classic offset / hex / ASCII dump, 16 bytes per line
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::with_capacity(bytes.len() * 4);
+    for (line, chunk) in bytes.chunks(HEX_BYTES_PER_LINE).enumerate() {
+        dump.push_str(&format!("{:08x}  ", line * HEX_BYTES_PER_LINE));
+        for index in 0..HEX_BYTES_PER_LINE {
+            match chunk.get(index) {
+                Some(byte) => dump.push_str(&format!("{:02x} ", byte)),
+                None => dump.push_str("   "),
+            }
+            if index == 7 {
+                dump.push(' ');
+            }
+        }
+        dump.push(' ');
+        dump.extend(chunk.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        dump.push('\n');
+    }
+    dump
+}
@@ -0,0 +1,232 @@
+// ============================================================================
+// Rules Commands
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::learning::{self, RuleSuggestionSummary, SuggestionStatus};
+use crate::rules::{
+    CompiledRule, Condition, FileFacts, PatternError, PatternKind, PatternMatcher, Rule, RuleDecision,
+    RuleDraft, RuleSet,
+};
+use crate::storage;
+
+const DEFAULT_PREVIEW_LIMIT: usize = 200;
+
+#[derive(Debug, Serialize)]
+pub struct RuleMatch {
+    pub path: String,
+    pub destination: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulePreview {
+    pub matches: Vec<RuleMatch>,
+    pub total_matches: usize,
+    pub scanned: usize,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatternValidation {
+    pub valid: bool,
+    pub error: Option<PatternError>,
+    pub matches: Vec<String>,
+    pub non_matches: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Compile a pattern, report syntax errors and show which sample paths it matches
+#[tauri::command]
+pub async fn validate_pattern(
+    pattern: String,
+    kind: PatternKind,
+    sample_paths: Vec<String>,
+) -> Result<PatternValidation, String> {
+    let matcher = match PatternMatcher::compile(&pattern, kind) {
+        Ok(matcher) => matcher,
+        Err(error) => {
+            return Ok(PatternValidation {
+                valid: false,
+                error: Some(error),
+                matches: Vec::new(),
+                non_matches: sample_paths,
+                warnings: Vec::new(),
+            });
+        }
+    };
+
+    let (matches, non_matches): (Vec<String>, Vec<String>) = sample_paths
+        .into_iter()
+        .partition(|sample| matcher.is_match(Path::new(sample)));
+
+    let mut warnings = Vec::new();
+    if !matches.is_empty() && non_matches.is_empty() {
+        warnings.push("Pattern matches every sample path".to_string());
+    }
+    if matches.is_empty() && !non_matches.is_empty() {
+        warnings.push("Pattern matches none of the sample paths".to_string());
+    }
+    if pattern != pattern.trim() {
+        warnings.push("Pattern has leading or trailing whitespace".to_string());
+    }
+
+    Ok(PatternValidation {
+        valid: true,
+        error: None,
+        matches,
+        non_matches,
+        warnings,
+    })
+}
+
+/// Run a draft rule against the cached index only, without touching the filesystem
+#[tauri::command]
+pub async fn preview_rule(
+    rule: RuleDraft,
+    scope: Option<String>,
+    limit: Option<usize>,
+) -> Result<RulePreview, String> {
+    let started = Instant::now();
+    let compiled = CompiledRule::compile(&rule.pattern, rule.kind, rule.conditions.as_ref(), &rule.destination)?;
+    let files = storage::indexed_files(scope.as_deref())?;
+    let limit = limit.unwrap_or(DEFAULT_PREVIEW_LIMIT);
+
+    let mut matches = Vec::new();
+    let mut total_matches = 0;
+
+    for file in &files {
+        let facts = FileFacts::from_indexed(file);
+        if !compiled.matches(&facts) {
+            continue;
+        }
+
+        total_matches += 1;
+        if matches.len() < limit {
+            matches.push(RuleMatch {
+                path: file.path.clone(),
+                destination: compiled.destination_for(&facts.path).to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    Ok(RulePreview {
+        matches,
+        total_matches,
+        scanned: files.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleSummary {
+    #[serde(flatten)]
+    pub rule: Rule,
+    pub is_dead: bool,
+    pub shadowed_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RuleInput {
+    pub id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: PatternKind,
+    pub destination: String,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub conditions: Option<Condition>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// List saved rules with hit counts and dead/shadowed flags
+#[tauri::command]
+pub async fn list_rules() -> Result<Vec<RuleSummary>, String> {
+    let rules = storage::list_rules()?;
+    let now = chrono::Utc::now();
+
+    Ok(rules
+        .iter()
+        .map(|rule| RuleSummary {
+            rule: rule.clone(),
+            is_dead: rule.is_dead(now),
+            shadowed_by: rule.shadowed_by(&rules).map(|other| other.id.clone()),
+        })
+        .collect())
+}
+
+/// Create or update a rule
+#[tauri::command]
+pub async fn save_rule(rule: RuleInput) -> Result<Rule, String> {
+    CompiledRule::compile(&rule.pattern, rule.kind, rule.conditions.as_ref(), &rule.destination)?;
+
+    let existing = match &rule.id {
+        Some(id) => storage::list_rules()?.into_iter().find(|r| &r.id == id),
+        None => None,
+    };
+
+    let saved = Rule {
+        id: rule.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name: rule.name,
+        description: rule.description,
+        pattern: rule.pattern,
+        pattern_kind: rule.kind,
+        destination: rule.destination,
+        priority: rule.priority,
+        is_active: rule.is_active,
+        created_at: existing
+            .as_ref()
+            .map(|r| r.created_at.clone())
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        hit_count: existing.as_ref().map(|r| r.hit_count).unwrap_or(0),
+        last_matched_at: existing.and_then(|r| r.last_matched_at),
+        conditions: rule.conditions,
+    };
+
+    storage::save_rule(&saved)?;
+    Ok(saved)
+}
+
+/// Delete a rule
+#[tauri::command]
+pub async fn delete_rule(id: String) -> Result<(), String> {
+    storage::delete_rule(&id)
+}
+
+/// Show how the active rules resolve for a single file
+#[tauri::command]
+pub async fn test_rules(path: String) -> Result<RuleDecision, String> {
+    let rule_set = RuleSet::load_active()?;
+    Ok(rule_set.decide(&FileFacts::from_path(Path::new(&path))))
+}
+
+/// Rules proposed from repeated manual moves that are still awaiting an answer
+#[tauri::command]
+pub async fn list_rule_suggestions() -> Result<Vec<RuleSuggestionSummary>, String> {
+    Ok(storage::list_rule_suggestions(Some(SuggestionStatus::Pending))?
+        .into_iter()
+        .map(RuleSuggestionSummary::from)
+        .collect())
+}
+
+/// Create the rule a suggestion proposes
+#[tauri::command]
+pub async fn accept_rule_suggestion(id: String) -> Result<Rule, String> {
+    learning::accept(&id)
+}
+
+/// Decline a suggestion; it won't be proposed again
+#[tauri::command]
+pub async fn dismiss_rule_suggestion(id: String) -> Result<(), String> {
+    learning::dismiss(&id)
+}
@@ -0,0 +1,452 @@
+// ============================================================================
+// Search Commands
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+use crate::commands::ai::{embed_texts, get_model_dir, AiFeature};
+use crate::commands::files::{create_file_node, get_file_type, FileNode};
+use crate::embeddings;
+use crate::rules::{PatternKind, PatternMatcher};
+use crate::scan::ScanFilter;
+use crate::search::query::ParsedQuery;
+use crate::search::smart::{self, SmartFolder};
+use crate::search::{self, Highlight, SavedSearch, SearchQuery, Snippet};
+use crate::storage::{self, IndexedFile};
+use crate::throttle;
+use crate::vector_index;
+
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const DEFAULT_FILE_SEARCH_PAGE: usize = 100;
+const MAX_SMART_FOLDER_SIZE: usize = 10_000;
+
+#[derive(Debug, Serialize)]
+pub struct SemanticHit {
+    pub path: String,
+    pub name: String,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SemanticSearchResult {
+    pub hits: Vec<SemanticHit>,
+    /// Time spent in the vector index and path lookup, excluding query embedding
+    pub search_ms: u64,
+    pub index_size: usize,
+}
+
+/// Find files whose content is semantically close to `query`
+#[tauri::command]
+pub async fn semantic_search(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<SemanticSearchResult, String> {
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let query_vector = embed_query(app, query).await?;
+
+    let started = Instant::now();
+    let mut hits = Vec::new();
+
+    // Several files can share one content hash, so ask for a few extra
+    for (content_hash, score) in vector_index::search(&query_vector, limit * 2) {
+        for file in storage::files_with_hash(&content_hash)? {
+            hits.push(SemanticHit {
+                path: file.path,
+                name: file.name,
+                score,
+            });
+        }
+        if hits.len() >= limit {
+            break;
+        }
+    }
+    hits.truncate(limit);
+
+    Ok(SemanticSearchResult {
+        hits,
+        search_ms: started.elapsed().as_millis() as u64,
+        index_size: vector_index::len(),
+    })
+}
+
+// Embed a query with the current embedding model, syncing the vector index first
+async fn embed_query(app: AppHandle, query: String) -> Result<Vec<f32>, String> {
+    let model_path = get_model_dir(&app)?.join(embeddings::current_model_id());
+
+    tokio::task::spawn_blocking(move || {
+        vector_index::sync(&app)?;
+        let mut vectors = embed_texts(&model_path, &[query], AiFeature::SemanticSearch)?;
+        vectors.pop().ok_or_else(|| "Failed to embed query".to_string())
+    })
+    .await
+    .map_err(|e| format!("Search task error: {}", e))?
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub file: IndexedFile,
+    pub score: f32,
+    /// Which parts of the query found this file: "keyword", "content",
+    /// "comment", "semantic", "filter"
+    pub matched_by: Vec<String>,
+    /// Byte ranges of keyword matches within the file name
+    pub name_highlights: Vec<Highlight>,
+    /// Matching context from the document text or comment
+    pub snippet: Option<Snippet>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub hits: Vec<SearchHit>,
+    pub elapsed_ms: u64,
+}
+
+/// Hybrid search: keyword terms (full-text), structured filters and a
+/// semantic description in one request, merged with reciprocal rank fusion
+#[tauri::command]
+pub async fn search(app: AppHandle, query: SearchQuery) -> Result<SearchResponse, String> {
+    run_search(app, query).await
+}
+
+// Name/path search over a folder on disk, or over the index when
+// `use_index` is set (or no root is given)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FileSearchRequest {
+    pub root: Option<String>,
+    pub use_index: bool,
+    /// Glob (file name, or full path when it contains '/') or regex over the path
+    pub pattern: Option<String>,
+    pub pattern_kind: PatternKind,
+    pub filters: search::SearchFilters,
+    pub show_hidden: bool,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileSearchResponse {
+    pub files: Vec<FileNode>,
+    pub total: usize,
+    pub offset: usize,
+    pub next_offset: Option<usize>,
+}
+
+/// Find files by glob or regex plus size, date and type filters, returned a
+/// page at a time in path order
+#[tauri::command]
+pub async fn search_files(request: FileSearchRequest) -> Result<FileSearchResponse, String> {
+    let matcher = request
+        .pattern
+        .as_deref()
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| PatternMatcher::compile(pattern, request.pattern_kind))
+        .transpose()
+        .map_err(|e| match e.position {
+            Some(position) => format!("Invalid pattern at {}: {}", position, e.message),
+            None => format!("Invalid pattern: {}", e.message),
+        })?;
+
+    tokio::task::spawn_blocking(move || {
+        let _foreground = throttle::foreground();
+        let mut candidates = match (&request.root, request.use_index) {
+            (Some(root), false) => walk_candidates(Path::new(root), request.show_hidden)?,
+            (root, _) => storage::indexed_files(root.as_deref())?,
+        };
+        candidates.retain(|file| {
+            request.filters.matches(file) && !matcher.as_ref().is_some_and(|m| !m.is_match(Path::new(&file.path)))
+        });
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let total = candidates.len();
+        let offset = request.offset.unwrap_or(0).min(total);
+        let end = offset
+            .saturating_add(request.limit.unwrap_or(DEFAULT_FILE_SEARCH_PAGE))
+            .min(total);
+        let files = candidates[offset..end]
+            .iter()
+            .filter_map(|file| create_file_node(&PathBuf::from(&file.path)).ok())
+            .collect();
+
+        Ok::<_, String>(FileSearchResponse {
+            files,
+            total,
+            offset,
+            next_offset: (end < total).then_some(end),
+        })
+    })
+    .await
+    .map_err(|e| format!("Search task error: {}", e))?
+}
+
+// Files under `root` described like index rows, so the same filters apply.
+// The type comes from the extension only; sniffing every file would be slow.
+fn walk_candidates(root: &Path, show_hidden: bool) -> Result<Vec<IndexedFile>, String> {
+    if !root.is_dir() {
+        return Err(format!("Path is not a directory: {}", root.display()));
+    }
+    let filter = ScanFilter::new(root, show_hidden)?;
+
+    Ok(WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || filter.allows(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let path = entry.path();
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default();
+            Some(IndexedFile {
+                path: path.to_string_lossy().to_string(),
+                name: entry.file_name().to_string_lossy().to_string(),
+                file_type: Some(get_file_type(&extension)),
+                size: metadata.len(),
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .map(|t| chrono::DateTime::<chrono::Utc>::from(t).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                    .unwrap_or_default(),
+                parent_path: path.parent().map(|p| p.to_string_lossy().to_string()),
+            })
+        })
+        .collect())
+}
+
+/// Shared by the search command and search-driven plans
+pub(crate) async fn run_search(app: AppHandle, query: SearchQuery) -> Result<SearchResponse, String> {
+    let _foreground = throttle::foreground();
+    let started = Instant::now();
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    // Fetch deeper candidate lists than requested so fusion has overlap to work with
+    let depth = limit * 3;
+
+    let mut files: HashMap<String, IndexedFile> = HashMap::new();
+    let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+    let mut ranked_lists: Vec<Vec<String>> = Vec::new();
+    let mut snippets: HashMap<String, Snippet> = HashMap::new();
+
+    let fts = query
+        .text
+        .as_deref()
+        .and_then(|text| search::fts_expression(text, &query.excluded));
+    let semantic = query.semantic.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+    if fts.is_some() || semantic.is_none() {
+        let kind = if fts.is_some() { "keyword" } else { "filter" };
+        let keyword_hits = storage::search_indexed_files(fts.as_deref(), &query.filters, depth)?;
+        ranked_lists.push(keyword_hits.iter().map(|f| f.path.clone()).collect());
+        for file in keyword_hits {
+            sources.entry(file.path.clone()).or_default().push(kind.to_string());
+            files.insert(file.path.clone(), file);
+        }
+    }
+
+    // Document text extracted by index_contents is a separate ranked list
+    if let Some(expression) = fts.as_deref() {
+        let content_hits = storage::search_file_contents(expression, &query.filters, depth)?;
+        ranked_lists.push(content_hits.iter().map(|(f, _)| f.path.clone()).collect());
+        for (file, marked) in content_hits {
+            sources.entry(file.path.clone()).or_default().push("content".to_string());
+            snippets.insert(
+                file.path.clone(),
+                search::snippet_from_marked(&marked, storage::SNIPPET_OPEN, storage::SNIPPET_CLOSE),
+            );
+            files.entry(file.path.clone()).or_insert(file);
+        }
+    }
+
+    if let Some(expression) = fts.as_deref() {
+        let comment_hits = storage::search_file_comments(expression, &query.filters, depth)?;
+        ranked_lists.push(comment_hits.iter().map(|(f, _)| f.path.clone()).collect());
+        for (file, marked) in comment_hits {
+            sources.entry(file.path.clone()).or_default().push("comment".to_string());
+            snippets.entry(file.path.clone()).or_insert_with(|| {
+                search::snippet_from_marked(&marked, storage::SNIPPET_OPEN, storage::SNIPPET_CLOSE)
+            });
+            files.entry(file.path.clone()).or_insert(file);
+        }
+    }
+
+    if let Some(semantic) = semantic {
+        let query_vector = embed_query(app, semantic.to_string()).await?;
+        let mut semantic_paths = Vec::new();
+        for (content_hash, _) in vector_index::search(&query_vector, depth) {
+            for file in storage::files_with_hash(&content_hash)? {
+                if !query.filters.matches(&file) {
+                    continue;
+                }
+                semantic_paths.push(file.path.clone());
+                sources.entry(file.path.clone()).or_default().push("semantic".to_string());
+                files.entry(file.path.clone()).or_insert(file);
+            }
+        }
+        ranked_lists.push(semantic_paths);
+    }
+
+    let hits = search::reciprocal_rank_fusion(&ranked_lists)
+        .into_iter()
+        .take(limit)
+        .filter_map(|(path, score)| {
+            let file = files.remove(&path)?;
+            let name_highlights = query
+                .text
+                .as_deref()
+                .map(|text| search::highlight_terms(&file.name, text))
+                .unwrap_or_default();
+            Some(SearchHit {
+                file,
+                score,
+                matched_by: sources.remove(&path).unwrap_or_default(),
+                name_highlights,
+                snippet: snippets.remove(&path),
+            })
+        })
+        .collect();
+
+    Ok(SearchResponse {
+        hits,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Parse an advanced search string ("type:image size:>10MB invoice") into a
+/// structured query for `search`
+#[tauri::command]
+pub async fn parse_search_query(input: String) -> Result<ParsedQuery, String> {
+    Ok(search::query::parse(&input))
+}
+
+/// List saved searches
+#[tauri::command]
+pub async fn list_saved_searches() -> Result<Vec<SavedSearch>, String> {
+    Ok(search::load_saved_searches())
+}
+
+/// Save a search under a name, replacing any search with the same name
+#[tauri::command]
+pub async fn save_search(name: String, query: String) -> Result<SavedSearch, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Saved search name cannot be empty".to_string());
+    }
+    search::query::parse(&query).into_result()?;
+
+    let saved = SavedSearch { name, query };
+    let mut searches = search::load_saved_searches();
+    searches.retain(|s| s.name != saved.name);
+    searches.push(saved.clone());
+    search::save_saved_searches(&searches)?;
+    Ok(saved)
+}
+
+/// Delete a saved search by name
+#[tauri::command]
+pub async fn delete_saved_search(name: String) -> Result<(), String> {
+    let mut searches = search::load_saved_searches();
+    searches.retain(|s| s.name != name);
+    search::save_saved_searches(&searches)
+}
+
+/// List smart folders
+#[tauri::command]
+pub async fn list_smart_folders() -> Result<Vec<SmartFolder>, String> {
+    Ok(smart::load_smart_folders())
+}
+
+/// Create or update a smart folder; a new folder gets an id when `id` is empty
+#[tauri::command]
+pub async fn save_smart_folder(mut folder: SmartFolder) -> Result<SmartFolder, String> {
+    if folder.name.trim().is_empty() {
+        return Err("Smart folder name cannot be empty".to_string());
+    }
+    match &folder.query {
+        Some(query) => {
+            search::query::parse(query).into_result()?;
+        }
+        None if folder.rule_ids.is_empty() => {
+            return Err("A smart folder needs a search query or at least one rule".to_string());
+        }
+        None => {}
+    }
+    if folder.id.is_empty() {
+        folder.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    let mut folders = smart::load_smart_folders();
+    folders.retain(|f| f.id != folder.id);
+    folders.push(folder.clone());
+    smart::save_smart_folders(&folders)?;
+    Ok(folder)
+}
+
+/// Delete a smart folder; the files it showed are untouched
+#[tauri::command]
+pub async fn delete_smart_folder(id: String) -> Result<(), String> {
+    let mut folders = smart::load_smart_folders();
+    folders.retain(|f| f.id != id);
+    smart::save_smart_folders(&folders)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmartFolderListing {
+    pub folder: SmartFolder,
+    pub files: Vec<IndexedFile>,
+    pub total: usize,
+    pub offset: usize,
+}
+
+/// Materialize a smart folder's current contents, paged like list_files
+#[tauri::command]
+pub async fn list_smart_folder(
+    app: AppHandle,
+    id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<SmartFolderListing, String> {
+    let folder = smart::load_smart_folders()
+        .into_iter()
+        .find(|f| f.id == id)
+        .ok_or_else(|| format!("Smart folder not found: {}", id))?;
+
+    let mut files = match &folder.query {
+        Some(query) => {
+            let mut query = search::query::parse(query).into_result()?;
+            if let Some(scope) = &folder.scope {
+                query.filters.path_prefix = Some(scope.clone());
+            }
+            query.limit = Some(MAX_SMART_FOLDER_SIZE);
+            run_search(app, query).await?.hits.into_iter().map(|hit| hit.file).collect()
+        }
+        None => {
+            let rules: Vec<_> = storage::list_rules()?
+                .into_iter()
+                .filter(|rule| folder.rule_ids.contains(&rule.id))
+                .collect();
+            smart::files_matching_rules(&rules, folder.scope.as_deref())?
+        }
+    };
+    // Files may have been removed since they were indexed
+    files.retain(|file| std::path::Path::new(&file.path).exists());
+
+    let total = files.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let end = limit.map_or(total, |limit| (offset + limit).min(total));
+    let files = files.drain(offset..end).collect();
+
+    Ok(SmartFolderListing {
+        folder,
+        files,
+        total,
+        offset,
+    })
+}
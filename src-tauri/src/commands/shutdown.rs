@@ -0,0 +1,35 @@
+// ============================================================================
+// Shutdown Commands
+// ============================================================================
+
+use tauri::AppHandle;
+
+use crate::shutdown::{self, Operation};
+
+/// Long-running operations in progress, e.g. to warn before closing
+#[tauri::command]
+pub async fn get_running_operations() -> Result<Vec<Operation>, String> {
+    Ok(shutdown::in_flight())
+}
+
+/// Operations the last exit cut short, so they can be offered for resuming;
+/// each is returned only once
+#[tauri::command]
+pub async fn get_interrupted_operations() -> Result<Vec<Operation>, String> {
+    tokio::task::spawn_blocking(shutdown::take_interrupted)
+        .await
+        .map_err(|e| format!("Shutdown task error: {}", e))?
+}
+
+/// Whether closing the app waits for running applies to finish
+#[tauri::command]
+pub async fn set_wait_on_exit(wait: bool) -> Result<(), String> {
+    shutdown::set_waits_for_operations(wait)
+}
+
+/// Stop waiting for running operations and exit now
+#[tauri::command]
+pub async fn force_exit(app: AppHandle) -> Result<(), String> {
+    shutdown::force_exit(&app);
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// ============================================================================
+// Staging Commands
+// ============================================================================
+
+use serde::Serialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::events::{self, FsChange};
+use crate::fileops::MoveOutcome;
+use crate::staging::{self, PurgeReport};
+use crate::storage::{self, StagedFile};
+
+#[derive(Debug, Serialize)]
+pub struct RestoreStagedResult {
+    pub batch_id: String,
+    pub restored: Vec<MoveOutcome>,
+    pub errors: Vec<String>,
+}
+
+/// Files and folders pending deletion, next to be purged first
+#[tauri::command]
+pub async fn list_staged_files() -> Result<Vec<StagedFile>, String> {
+    tokio::task::spawn_blocking(storage::list_staged_files)
+        .await
+        .map_err(|e| format!("Staging task error: {}", e))?
+}
+
+/// Move staged entries back to where they were deleted from
+#[tauri::command]
+pub async fn restore_staged_files(app: AppHandle, ids: Vec<String>) -> Result<RestoreStagedResult, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        let batch_id = storage::create_history_batch("Restore from staging", &format!("{} item(s)", ids.len()))?;
+        let mut result = RestoreStagedResult {
+            batch_id,
+            restored: Vec::new(),
+            errors: Vec::new(),
+        };
+        for id in ids {
+            match staging::restore(&id, &result.batch_id) {
+                Ok(outcome) => result.restored.push(outcome),
+                Err(e) => result.errors.push(e),
+            }
+        }
+        Ok::<_, String>(result)
+    })
+    .await
+    .map_err(|e| format!("Staging task error: {}", e))??;
+
+    let changes = result
+        .restored
+        .iter()
+        .map(|outcome| FsChange::renamed(Path::new(&outcome.source), Path::new(&outcome.destination)))
+        .collect();
+    events::emit_fs_changed(&app, changes);
+
+    Ok(result)
+}
+
+/// Delete staged entries for good without waiting for the automatic purge
+#[tauri::command]
+pub async fn purge_staged_files(ids: Vec<String>) -> Result<PurgeReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        let mut missing = Vec::new();
+        for id in ids {
+            match storage::staged_file(&id)? {
+                Some(staged) => files.push(staged),
+                None => missing.push(format!("Not in the staging area: {}", id)),
+            }
+        }
+        let mut report = staging::purge_all(&files, "Empty staging area")?;
+        report.errors.extend(missing);
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Staging task error: {}", e))?
+}
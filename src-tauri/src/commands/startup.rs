@@ -0,0 +1,12 @@
+// ============================================================================
+// Startup Commands
+// ============================================================================
+
+use crate::startup::{self, StartupStatus};
+
+/// Which subsystems have finished initializing, for a frontend that
+/// missed the early "startup-status" events
+#[tauri::command]
+pub async fn get_startup_status() -> Result<StartupStatus, String> {
+    Ok(startup::status())
+}
@@ -0,0 +1,27 @@
+// ============================================================================
+// Storage Sense Commands
+// ============================================================================
+
+use std::path::PathBuf;
+
+use crate::storage_sense::{self, CleanupStatus, ManagedFolder};
+
+/// Folders that Storage Sense / Optimize Storage currently clean up or evict
+#[tauri::command]
+pub async fn list_os_managed_folders() -> Result<Vec<ManagedFolder>, String> {
+    tokio::task::spawn_blocking(storage_sense::managed_folders)
+        .await
+        .map_err(|e| format!("Storage Sense task error: {}", e))
+}
+
+/// Whether the OS manages `path` (or evicted it), with an explanation for
+/// library settings
+#[tauri::command]
+pub async fn get_os_cleanup_status(path: String) -> Result<CleanupStatus, String> {
+    tokio::task::spawn_blocking(move || {
+        let folders = storage_sense::managed_folders();
+        storage_sense::status(&PathBuf::from(path), &folders)
+    })
+    .await
+    .map_err(|e| format!("Storage Sense task error: {}", e))
+}
@@ -0,0 +1,18 @@
+// ============================================================================
+// Throttle Commands
+// ============================================================================
+
+use crate::throttle::{self, IoLimits};
+
+/// Current throughput caps for hashing, copying and downloads
+#[tauri::command]
+pub async fn get_io_limits() -> Result<IoLimits, String> {
+    Ok(throttle::limits())
+}
+
+/// Change the throughput caps; takes effect on running operations
+#[tauri::command]
+pub async fn set_io_limits(limits: IoLimits) -> Result<IoLimits, String> {
+    throttle::set_limits(limits)?;
+    Ok(throttle::limits())
+}
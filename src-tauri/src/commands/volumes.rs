@@ -0,0 +1,51 @@
+// ============================================================================
+// Volume Commands
+// ============================================================================
+
+use crate::storage::{self, IndexedFile};
+use crate::volumes::{self, VolumeSummary};
+
+/// Every drive seen so far, mounted ones first, with what the catalog holds
+/// for each
+#[tauri::command]
+pub async fn list_volumes() -> Result<Vec<VolumeSummary>, String> {
+    tokio::task::spawn_blocking(|| {
+        let mounted = volumes::refresh()?;
+        volumes::summaries(&mounted)
+    })
+    .await
+    .map_err(|e| format!("Volume task error: {}", e))?
+}
+
+/// Browse a volume's catalog, even while the drive is unplugged: the
+/// entries indexed directly inside `path`, or inside its root by default.
+/// The root of a drive whose mount point another drive has taken since is
+/// its parked root.
+#[tauri::command]
+pub async fn list_volume_catalog(volume_id: String, path: Option<String>) -> Result<Vec<IndexedFile>, String> {
+    tokio::task::spawn_blocking(move || {
+        let volume = storage::list_volumes()?
+            .into_iter()
+            .find(|volume| volume.id == volume_id)
+            .ok_or_else(|| format!("Unknown volume: {}", volume_id))?;
+        if let Some(path) = path {
+            return storage::volume_entries(&volume.id, &path);
+        }
+        let entries = storage::volume_entries(&volume.id, &volume.mount_point)?;
+        if !entries.is_empty() {
+            return Ok(entries);
+        }
+        storage::volume_entries(&volume.id, &volumes::parked_root(&volume.id))
+    })
+    .await
+    .map_err(|e| format!("Volume task error: {}", e))?
+}
+
+/// Forget a drive and drop its catalog; it is added again, with an empty
+/// catalog, the next time it is plugged in
+#[tauri::command]
+pub async fn forget_volume(volume_id: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || storage::delete_volume(&volume_id))
+        .await
+        .map_err(|e| format!("Volume task error: {}", e))?
+}
@@ -0,0 +1,62 @@
+// ============================================================================
+// Watcher Commands
+// ============================================================================
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+use crate::watcher::{self, DuplicatePolicy};
+
+/// Watch folders for changes, keeping the index and open views up to date
+#[tauri::command]
+pub async fn start_watching(app: AppHandle, paths: Vec<String>) -> Result<Vec<String>, String> {
+    for path in &paths {
+        watcher::watch(&app, &PathBuf::from(path))?;
+    }
+    Ok(watcher::watched_paths())
+}
+
+/// Stop watching the given folders, or all folders when none are given
+#[tauri::command]
+pub async fn stop_watching(paths: Option<Vec<String>>) -> Result<Vec<String>, String> {
+    match paths {
+        Some(paths) => {
+            for path in &paths {
+                watcher::unwatch(&PathBuf::from(path))?;
+            }
+        }
+        None => watcher::unwatch_all(),
+    }
+    Ok(watcher::watched_paths())
+}
+
+/// Folders currently being watched
+#[tauri::command]
+pub async fn get_watched_paths() -> Result<Vec<String>, String> {
+    Ok(watcher::watched_paths())
+}
+
+/// Duplicate policy of each watched folder that doesn't just report duplicates
+#[tauri::command]
+pub async fn get_duplicate_policies() -> Result<BTreeMap<String, DuplicatePolicy>, String> {
+    Ok(watcher::duplicate_policies())
+}
+
+/// Choose what happens when a file arrives in `path` whose contents are
+/// already in the library
+#[tauri::command]
+pub async fn set_duplicate_policy(path: String, policy: DuplicatePolicy) -> Result<(), String> {
+    watcher::set_duplicate_policy(&PathBuf::from(path), policy)
+}
+
+/// Seconds a new file must stay unchanged before "file-ready" is emitted
+#[tauri::command]
+pub async fn get_settle_seconds() -> Result<u64, String> {
+    Ok(watcher::settle_seconds())
+}
+
+#[tauri::command]
+pub async fn set_settle_seconds(seconds: u64) -> Result<(), String> {
+    watcher::set_settle_seconds(seconds)
+}
@@ -0,0 +1,121 @@
+// ============================================================================
+// Content Module - Plain-text extraction from documents
+// ============================================================================
+
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::fileops;
+
+/// Upper bound on stored text per file, in characters
+pub const MAX_EXTRACTED_CHARS: usize = 100_000;
+
+// Text pulled out of a document
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractedText {
+    pub path: String,
+    pub text: String,
+    /// The document had more text than MAX_EXTRACTED_CHARS
+    pub truncated: bool,
+}
+
+// Plain-text formats read as-is
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "csv", "log", "rtf", "json", "xml", "html", "htm"];
+
+/// Whether text can be extracted from a file with this extension
+pub fn is_supported(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(extension.as_str(), "pdf" | "docx" | "odt") || TEXT_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Extract plain text from a PDF, DOCX, ODT or text file
+pub fn extract_text(path: &Path) -> Result<ExtractedText, String> {
+    fileops::ensure_local(path)?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let raw = match extension.as_str() {
+        "pdf" => pdf_extract::extract_text(path).map_err(|e| format!("Failed to read PDF: {}", e))?,
+        "docx" => zipped_xml_text(path, "word/document.xml", "w:p")?,
+        "odt" => zipped_xml_text(path, "content.xml", "text:p")?,
+        ext if TEXT_EXTENSIONS.contains(&ext) => {
+            let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+        _ => return Err(format!("Unsupported document type: {}", path.display())),
+    };
+
+    let (text, truncated) = bounded(&normalize_whitespace(&raw));
+    Ok(ExtractedText {
+        path: path.to_string_lossy().to_string(),
+        text,
+        truncated,
+    })
+}
+
+// Text of an XML part inside a zip container (DOCX, ODT), one line per paragraph
+fn zipped_xml_text(path: &Path, entry: &str, paragraph_tag: &str) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Failed to read document: {}", e))?;
+    let mut xml = String::new();
+    archive
+        .by_name(entry)
+        .map_err(|e| format!("Document is missing {}: {}", entry, e))?
+        .read_to_string(&mut xml)
+        .map_err(|e| format!("Failed to read document: {}", e))?;
+
+    Ok(xml_text(&xml, paragraph_tag))
+}
+
+// Strip tags, breaking lines at the end of each paragraph element
+fn xml_text(xml: &str, paragraph_tag: &str) -> String {
+    let paragraph_end = format!("/{}", paragraph_tag);
+    let mut text = String::with_capacity(xml.len() / 4);
+    let mut rest = xml;
+
+    while let Some(open) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..open]));
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        if tag == paragraph_end || tag.starts_with("w:br") || tag.starts_with("text:line-break") {
+            text.push('\n');
+        } else if tag.starts_with("w:tab") || tag.starts_with("text:tab") {
+            text.push(' ');
+        }
+        rest = &rest[open + close + 1..];
+    }
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Collapse runs of blank space while keeping single line breaks
+fn normalize_whitespace(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bounded(text: &str) -> (String, bool) {
+    match text.char_indices().nth(MAX_EXTRACTED_CHARS) {
+        Some((index, _)) => (text[..index].to_string(), true),
+        None => (text.to_string(), false),
+    }
+}
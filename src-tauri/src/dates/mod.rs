@@ -0,0 +1,208 @@
+// ============================================================================
+// Dates Module - Year/Month folder names in the user's own time zone
+// ============================================================================
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::storage;
+
+const DATE_FOLDERS_KEY: &str = "date_folders";
+
+// Month names by language code, for folders like "2024/2024-03 Marzo"
+const MONTH_NAMES: &[(&str, [&str; 12])] = &[
+    (
+        "en",
+        [
+            "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+            "November", "December",
+        ],
+    ),
+    (
+        "es",
+        [
+            "Enero", "Febrero", "Marzo", "Abril", "Mayo", "Junio", "Julio", "Agosto", "Septiembre", "Octubre",
+            "Noviembre", "Diciembre",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "Janvier", "Février", "Mars", "Avril", "Mai", "Juin", "Juillet", "Août", "Septembre", "Octobre",
+            "Novembre", "Décembre",
+        ],
+    ),
+    (
+        "de",
+        [
+            "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober",
+            "November", "Dezember",
+        ],
+    ),
+    (
+        "it",
+        [
+            "Gennaio", "Febbraio", "Marzo", "Aprile", "Maggio", "Giugno", "Luglio", "Agosto", "Settembre", "Ottobre",
+            "Novembre", "Dicembre",
+        ],
+    ),
+    (
+        "pt",
+        [
+            "Janeiro", "Fevereiro", "Março", "Abril", "Maio", "Junho", "Julho", "Agosto", "Setembro", "Outubro",
+            "Novembro", "Dezembro",
+        ],
+    ),
+    (
+        "nl",
+        [
+            "Januari", "Februari", "Maart", "April", "Mei", "Juni", "Juli", "Augustus", "September", "Oktober",
+            "November", "December",
+        ],
+    ),
+];
+
+// How deep date folders go
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateFolderFormat {
+    Year,
+    #[default]
+    YearMonth,
+    YearMonthDay,
+}
+
+// Which of a file's dates it is filed under
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    /// When a photo was taken (EXIF), else when the file was last modified
+    #[default]
+    Capture,
+    Modified,
+    /// When the file was created; file systems that don't record it fall
+    /// back to the modified time
+    Created,
+}
+
+/// Placeholders a date folder pattern may use
+const PATTERN_TOKENS: &[&str] = &["yyyy", "mm", "month", "dd"];
+
+// How date folders are named
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DateFolderSettings {
+    /// Language code ("en", "es", ...) for month names after the month
+    /// number; None names months by number only
+    pub month_names: Option<String>,
+}
+
+/// Date folder naming, from preferences
+pub fn load_settings() -> DateFolderSettings {
+    storage::get_preference(DATE_FOLDERS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Change date folder naming; unknown languages are refused
+pub fn save_settings(settings: &DateFolderSettings) -> Result<(), String> {
+    if let Some(language) = &settings.month_names {
+        if month_names(language).is_none() {
+            return Err(format!("No month names for language: {}", language));
+        }
+    }
+    let json = serde_json::to_string(settings).map_err(|e| format!("Failed to serialize date folders: {}", e))?;
+    storage::set_preference(DATE_FOLDERS_KEY, &json)
+}
+
+fn month_names(language: &str) -> Option<&'static [&'static str; 12]> {
+    // "es-AR" and "es_AR" use the "es" names
+    let base = language.split(['-', '_']).next().unwrap_or(language);
+    MONTH_NAMES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(base))
+        .map(|(_, names)| names)
+}
+
+/// `time` on the local wall clock. Converting the instant rather than a
+/// formatted UTC string applies the offset in effect on that date, so a file
+/// from 00:30 local time on the 1st stays in its month on either side of a
+/// DST change.
+pub fn local_time(time: SystemTime) -> NaiveDateTime {
+    DateTime::<Local>::from(time).naive_local()
+}
+
+/// Name of the month `month` (1-12) in `language`, if there is one
+pub fn month_name(month: u32, language: &str) -> Option<&'static str> {
+    let index = month.checked_sub(1)? as usize;
+    month_names(language)?.get(index).copied()
+}
+
+/// Relative folder for something dated `local` (already on the local wall
+/// clock): "2024", "2024/2024-03" or "2024/2024-03/2024-03-01", with the
+/// month name appended when configured, as in "2024/2024-03 March"
+pub fn date_folder(local: NaiveDateTime, format: DateFolderFormat, settings: &DateFolderSettings) -> PathBuf {
+    let year = format!("{:04}", local.year());
+    let month = match settings
+        .month_names
+        .as_deref()
+        .and_then(|language| month_name(local.month(), language))
+    {
+        Some(name) => format!("{}-{:02} {}", year, local.month(), name),
+        None => format!("{}-{:02}", year, local.month()),
+    };
+    let day = format!("{}-{:02}-{:02}", year, local.month(), local.day());
+
+    match format {
+        DateFolderFormat::Year => PathBuf::from(year),
+        DateFolderFormat::YearMonth => PathBuf::from(year).join(month),
+        DateFolderFormat::YearMonthDay => PathBuf::from(year).join(month).join(day),
+    }
+}
+
+/// Check a folder pattern such as "{yyyy}/{yyyy}-{mm} {month}": only known
+/// placeholders, and relative folders that stay under the plan's folder
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in pattern: {}", pattern))?;
+        let token = &rest[open + 1..open + close];
+        if !PATTERN_TOKENS.contains(&token) {
+            return Err(format!("Unknown placeholder {{{}}} in pattern", token));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    if pattern.trim().is_empty()
+        || std::path::Path::new(pattern).is_absolute()
+        || pattern.split(['/', '\\']).any(|part| part == "..")
+    {
+        return Err("Pattern must name folders inside the plan's folder".to_string());
+    }
+    Ok(())
+}
+
+/// Relative folder for something dated `local` following a validated
+/// pattern; {month} is the configured month name, or the number when names
+/// are off
+pub fn pattern_folder(local: NaiveDateTime, pattern: &str, settings: &DateFolderSettings) -> PathBuf {
+    let month = settings
+        .month_names
+        .as_deref()
+        .and_then(|language| month_name(local.month(), language))
+        .map_or_else(|| format!("{:02}", local.month()), str::to_string);
+    let rendered = pattern
+        .replace("{yyyy}", &format!("{:04}", local.year()))
+        .replace("{mm}", &format!("{:02}", local.month()))
+        .replace("{month}", &month)
+        .replace("{dd}", &format!("{:02}", local.day()));
+    rendered
+        .split(['/', '\\'])
+        .filter(|part| !part.trim().is_empty())
+        .collect()
+}
@@ -0,0 +1,279 @@
+// ============================================================================
+// Digest Module - Reports on what unattended runs did
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::storage::{self, BatchSummary};
+
+const DIGEST_SETTINGS_KEY: &str = "digest_settings";
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Where digests go besides the database
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestSettings {
+    /// Folder to write each digest into as an HTML file
+    pub html_dir: Option<String>,
+    pub smtp: Option<SmtpSettings>,
+}
+
+// A local mail relay; no TLS or authentication
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+// Something in the digest period a person should check
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewItem {
+    pub batch_id: String,
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub id: String,
+    /// The job (or "manual") the digest was generated after
+    pub trigger: String,
+    pub period_start: String,
+    pub period_end: String,
+    /// What ran
+    pub batches: Vec<BatchSummary>,
+    /// What moved: operation counts across all batches
+    pub totals: BTreeMap<String, usize>,
+    pub needs_review: Vec<ReviewItem>,
+    pub html_path: Option<String>,
+    pub emailed: bool,
+    /// Problems writing the HTML file or sending mail; the digest is still stored
+    pub delivery_errors: Vec<String>,
+}
+
+pub fn load_settings() -> DigestSettings {
+    storage::get_preference(DIGEST_SETTINGS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &DigestSettings) -> Result<(), String> {
+    let json = serde_json::to_string(settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    storage::set_preference(DIGEST_SETTINGS_KEY, &json)
+}
+
+/// Summarize history since `since` (default: the end of the previous digest,
+/// or the last 24 hours), deliver it per the settings and store it
+pub fn generate(trigger: &str, since: Option<String>) -> Result<Digest, String> {
+    let now = chrono::Utc::now();
+    let period_end = now.format(storage::SQLITE_TIME_FORMAT).to_string();
+    let period_start = match since {
+        Some(since) => since,
+        None => storage::last_digest_end()?
+            .unwrap_or_else(|| (now - chrono::Duration::hours(24)).format(storage::SQLITE_TIME_FORMAT).to_string()),
+    };
+
+    let batches = storage::batches_between(&period_start, &period_end)?;
+    let mut totals = BTreeMap::new();
+    for batch in batches.iter().filter(|batch| !batch.is_undone) {
+        for (operation, count) in &batch.operations {
+            *totals.entry(operation.clone()).or_insert(0) += count;
+        }
+    }
+    let needs_review = storage::changes_needing_review(&period_start, &period_end)?
+        .into_iter()
+        .map(|change| ReviewItem {
+            reason: if change.verified == Some(false) {
+                "Copy did not match the original".to_string()
+            } else {
+                "Renamed to avoid overwriting an existing file".to_string()
+            },
+            path: change.destination_path.unwrap_or(change.source_path),
+            batch_id: change.batch_id,
+        })
+        .collect();
+
+    let mut digest = Digest {
+        id: uuid::Uuid::new_v4().to_string(),
+        trigger: trigger.to_string(),
+        period_start,
+        period_end,
+        batches,
+        totals,
+        needs_review,
+        html_path: None,
+        emailed: false,
+        delivery_errors: Vec::new(),
+    };
+
+    let settings = load_settings();
+    let html = render_html(&digest);
+    if let Some(dir) = &settings.html_dir {
+        let path = Path::new(dir).join(format!("digest-{}.html", now.format("%Y%m%d-%H%M%S")));
+        match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, &html)) {
+            Ok(()) => digest.html_path = Some(path.to_string_lossy().to_string()),
+            Err(e) => digest.delivery_errors.push(format!("Failed to write digest file: {}", e)),
+        }
+    }
+    if let Some(smtp) = &settings.smtp {
+        match send_mail(smtp, &format!("Smart Storage digest: {}", digest.trigger), &html) {
+            Ok(()) => digest.emailed = true,
+            Err(e) => digest.delivery_errors.push(e),
+        }
+    }
+
+    let content = serde_json::to_string(&digest).map_err(|e| format!("Failed to serialize digest: {}", e))?;
+    storage::store_digest(&digest.id, &digest.trigger, &digest.period_start, &digest.period_end, &content)?;
+    Ok(digest)
+}
+
+/// Self-contained HTML page for a digest
+pub fn render_html(digest: &Digest) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html><html><head><meta charset=\"utf-8\">");
+    html.push_str("<title>Smart Storage digest</title></head><body>");
+    html.push_str(&format!(
+        "<h1>Digest: {}</h1><p>{} to {} (UTC)</p>",
+        escape(&digest.trigger),
+        escape(&digest.period_start),
+        escape(&digest.period_end)
+    ));
+
+    html.push_str("<h2>What ran</h2>");
+    if digest.batches.is_empty() {
+        html.push_str("<p>Nothing ran in this period.</p>");
+    } else {
+        html.push_str("<ul>");
+        for batch in &digest.batches {
+            let operations: Vec<String> = batch
+                .operations
+                .iter()
+                .map(|(operation, count)| format!("{} {}", count, operation))
+                .collect();
+            html.push_str(&format!(
+                "<li>{} &mdash; {}{} ({})</li>",
+                escape(&batch.timestamp),
+                escape(&batch.name),
+                if batch.is_undone { " [undone]" } else { "" },
+                escape(&operations.join(", "))
+            ));
+        }
+        html.push_str("</ul>");
+    }
+
+    html.push_str("<h2>What moved</h2><ul>");
+    for (operation, count) in &digest.totals {
+        html.push_str(&format!("<li>{}: {}</li>", escape(operation), count));
+    }
+    html.push_str("</ul>");
+
+    html.push_str("<h2>Needs review</h2>");
+    if digest.needs_review.is_empty() {
+        html.push_str("<p>Nothing needs review.</p>");
+    } else {
+        html.push_str("<ul>");
+        for item in &digest.needs_review {
+            html.push_str(&format!("<li>{}: {}</li>", escape(&item.path), escape(&item.reason)));
+        }
+        html.push_str("</ul>");
+    }
+
+    html.push_str("</body></html>");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Plain SMTP conversation with a local relay
+fn send_mail(smtp: &SmtpSettings, subject: &str, html: &str) -> Result<(), String> {
+    if smtp.to.is_empty() {
+        return Err("No digest recipients configured".to_string());
+    }
+    let address = (smtp.host.as_str(), smtp.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve SMTP host: {}", e))?
+        .next()
+        .ok_or_else(|| format!("Failed to resolve SMTP host: {}", smtp.host))?;
+    let stream = TcpStream::connect_timeout(&address, SMTP_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to SMTP server: {}", e))?;
+    stream
+        .set_read_timeout(Some(SMTP_TIMEOUT))
+        .map_err(|e| format!("SMTP error: {}", e))?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| format!("SMTP error: {}", e))?);
+    let mut writer = stream;
+
+    expect_reply(&mut reader, 220)?;
+    command(&mut writer, &mut reader, "HELO localhost", 250)?;
+    command(&mut writer, &mut reader, &format!("MAIL FROM:<{}>", smtp.from), 250)?;
+    for recipient in &smtp.to {
+        command(&mut writer, &mut reader, &format!("RCPT TO:<{}>", recipient), 250)?;
+    }
+    command(&mut writer, &mut reader, "DATA", 354)?;
+
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/html; charset=utf-8\r\n\r\n",
+        smtp.from,
+        smtp.to.join(", "),
+        subject
+    );
+    for line in html.lines() {
+        // Dot-stuffing so a line starting with "." doesn't end the message
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    writer
+        .write_all(message.as_bytes())
+        .map_err(|e| format!("SMTP error: {}", e))?;
+    expect_reply(&mut reader, 250)?;
+
+    let _ = command(&mut writer, &mut reader, "QUIT", 221);
+    Ok(())
+}
+
+fn command(writer: &mut TcpStream, reader: &mut impl BufRead, line: &str, expected: u16) -> Result<(), String> {
+    writer
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .map_err(|e| format!("SMTP error: {}", e))?;
+    expect_reply(reader, expected)
+}
+
+// Read a (possibly multi-line) reply and check its code
+fn expect_reply(reader: &mut impl BufRead, expected: u16) -> Result<(), String> {
+    loop {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| format!("SMTP error: {}", e))?;
+        let code: u16 = line.get(..3).and_then(|code| code.parse().ok()).unwrap_or(0);
+        if code != expected {
+            return Err(format!("SMTP server replied: {}", line.trim()));
+        }
+        // "250-" continues a multi-line reply, "250 " ends it
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// ============================================================================
+// Embeddings Module - Vector cache keyed by content hash and model
+// ============================================================================
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::commands::ai::MODEL_FILENAME;
+use crate::storage;
+
+const EMBEDDING_MODEL_KEY: &str = "embedding_model";
+
+/// Bytes of text content read from small text files for embedding
+const CONTENT_SAMPLE_BYTES: usize = 2048;
+
+/// Model file used for embeddings; defaults to the chat model
+pub fn current_model_id() -> String {
+    storage::get_preference(EMBEDDING_MODEL_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| MODEL_FILENAME.to_string())
+}
+
+/// Switch the embedding model, dropping vectors produced by any other model.
+/// Returns how many cached vectors were invalidated.
+pub fn set_model_id(model_id: &str) -> Result<usize, String> {
+    storage::set_preference(EMBEDDING_MODEL_KEY, model_id)?;
+    storage::delete_embeddings_except_model(model_id)
+}
+
+/// Text describing a file for embedding: its name, folder names, and the
+/// start of the content for plain-text files
+pub fn embedding_text(path: &Path) -> String {
+    let mut text = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let folders: Vec<String> = path
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .rev()
+                .take(3)
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !folders.is_empty() {
+        text.push_str("\nFolders: ");
+        text.push_str(&folders.join(" / "));
+    }
+
+    if let Some(sample) = text_sample(path) {
+        text.push('\n');
+        text.push_str(&sample);
+    }
+    text
+}
+
+// First bytes of a file when they decode as UTF-8 text
+fn text_sample(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut buffer = vec![0u8; CONTENT_SAMPLE_BYTES];
+    let read = file.read(&mut buffer).ok()?;
+    buffer.truncate(read);
+    if buffer.contains(&0) {
+        return None;
+    }
+
+    // Allow a multi-byte character cut off at the end of the sample
+    match String::from_utf8(buffer) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            if bytes.len() - valid > 3 {
+                return None;
+            }
+            bytes.truncate(valid);
+            String::from_utf8(bytes).ok()
+        }
+    }
+}
+
+/// Encode a vector as little-endian f32 bytes for storage
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Decode a vector stored by `encode_vector`
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
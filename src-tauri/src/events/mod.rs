@@ -0,0 +1,155 @@
+// ============================================================================
+// Events Module - Consolidated filesystem change notifications for the UI
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use crate::storage;
+
+/// Event name the frontend listens on to refresh open views
+pub const FS_CHANGED_EVENT: &str = "fs-changed";
+
+// Kind of change applied to a path
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Renamed => "renamed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "created" => ChangeKind::Created,
+            "removed" => ChangeKind::Removed,
+            "renamed" => ChangeKind::Renamed,
+            _ => ChangeKind::Modified,
+        }
+    }
+}
+
+// A single change to a path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FsChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old_path: Option<String>,
+}
+
+// Payload of the "fs-changed" event
+#[derive(Debug, Clone, Serialize)]
+pub struct FsChangedEvent {
+    pub changes: Vec<FsChange>,
+    /// Directories whose listing is affected, so views can re-list only these
+    pub directories: Vec<String>,
+    pub timestamp: String,
+}
+
+impl FsChange {
+    pub fn created(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind: ChangeKind::Created,
+            old_path: None,
+        }
+    }
+
+    pub fn modified(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind: ChangeKind::Modified,
+            old_path: None,
+        }
+    }
+
+    pub fn removed(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind: ChangeKind::Removed,
+            old_path: None,
+        }
+    }
+
+    pub fn renamed(from: &Path, to: &Path) -> Self {
+        Self {
+            path: to.to_string_lossy().to_string(),
+            kind: ChangeKind::Renamed,
+            old_path: Some(from.to_string_lossy().to_string()),
+        }
+    }
+}
+
+/// Merge changes that touch the same path so each path appears once.
+///
+/// A path created and then removed within the same batch disappears entirely;
+/// a modification after creation is still reported as a creation.
+pub fn coalesce(changes: Vec<FsChange>) -> Vec<FsChange> {
+    let mut merged: Vec<FsChange> = Vec::with_capacity(changes.len());
+
+    for change in changes {
+        match merged.iter().position(|c| c.path == change.path) {
+            Some(index) => {
+                let previous = merged[index].kind;
+                match (previous, change.kind) {
+                    (ChangeKind::Created, ChangeKind::Removed) => {
+                        merged.remove(index);
+                    }
+                    (ChangeKind::Created, ChangeKind::Modified)
+                    | (ChangeKind::Renamed, ChangeKind::Modified) => {}
+                    _ => merged[index] = change,
+                }
+            }
+            None => merged.push(change),
+        }
+    }
+
+    merged
+}
+
+/// Emit a consolidated "fs-changed" event. Empty batches are dropped.
+pub fn emit_fs_changed(app: &AppHandle, changes: Vec<FsChange>) {
+    let changes = coalesce(changes);
+    if changes.is_empty() {
+        return;
+    }
+
+    if let Err(e) = storage::record_file_events(&changes) {
+        eprintln!("Failed to record file events: {}", e);
+    }
+
+    let mut directories = BTreeSet::new();
+    for change in &changes {
+        let paths = std::iter::once(&change.path).chain(change.old_path.iter());
+        for path in paths {
+            if let Err(e) = storage::invalidate_folder_sizes(path) {
+                eprintln!("Failed to invalidate folder sizes: {}", e);
+            }
+            if let Some(parent) = Path::new(path).parent() {
+                directories.insert(parent.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let _ = app.emit(
+        FS_CHANGED_EVENT,
+        FsChangedEvent {
+            changes,
+            directories: directories.into_iter().collect(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+}
@@ -0,0 +1,980 @@
+// ============================================================================
+// File Operations Module - Collision-aware moves shared by commands
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(windows)]
+use std::path::Component;
+
+use crate::commands::files::create_file_node;
+use crate::network;
+use crate::storage;
+use crate::throttle::{self, IoClass, ThrottledReader};
+use crate::xattrs;
+
+// What to do when the destination of a move already exists
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    Skip,
+    Overwrite,
+    #[default]
+    RenameWithSuffix,
+    KeepIfIdentical,
+}
+
+// How a particular move was resolved
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionResolution {
+    NoConflict,
+    Skipped,
+    Overwritten,
+    Renamed,
+    KeptIdentical,
+}
+
+impl CollisionResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CollisionResolution::NoConflict => "no_conflict",
+            CollisionResolution::Skipped => "skipped",
+            CollisionResolution::Overwritten => "overwritten",
+            CollisionResolution::Renamed => "renamed",
+            CollisionResolution::KeptIdentical => "kept_identical",
+        }
+    }
+}
+
+// Result of a collision-aware move or copy
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveOutcome {
+    pub source: String,
+    pub destination: String,
+    pub resolution: CollisionResolution,
+}
+
+impl MoveOutcome {
+    /// Whether the source was actually moved (or copied) on disk
+    pub fn moved(&self) -> bool {
+        !matches!(
+            self.resolution,
+            CollisionResolution::Skipped | CollisionResolution::KeptIdentical
+        )
+    }
+}
+
+// Access information for a file, used to flag moves that will fail
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilePermissions {
+    pub readonly: bool,
+    /// Unix permission bits, e.g. 0o644
+    pub mode: Option<u32>,
+    pub owner_uid: Option<u32>,
+    pub group_gid: Option<u32>,
+    /// Windows attributes that are set: "readonly", "hidden", "system", "archive"
+    pub attributes: Vec<String>,
+    /// The current process can write to the file
+    pub writable: bool,
+    /// The current process can move the file out of its folder
+    pub movable: bool,
+}
+
+/// Permissions, ownership and effective access for `path`
+pub fn file_permissions(path: &Path, metadata: &fs::Metadata) -> FilePermissions {
+    let mut permissions = FilePermissions {
+        readonly: metadata.permissions().readonly(),
+        ..Default::default()
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        permissions.mode = Some(metadata.mode() & 0o7777);
+        permissions.owner_uid = Some(metadata.uid());
+        permissions.group_gid = Some(metadata.gid());
+        permissions.writable = unix_access(path, libc::W_OK);
+        // Moving needs write and search permission on the containing folder
+        permissions.movable = path
+            .parent()
+            .is_some_and(|parent| unix_access(parent, libc::W_OK | libc::X_OK));
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const ATTRIBUTES: &[(u32, &str)] = &[(0x1, "readonly"), (0x2, "hidden"), (0x4, "system"), (0x20, "archive")];
+        let attributes = metadata.file_attributes();
+        permissions.attributes = ATTRIBUTES
+            .iter()
+            .filter(|(bit, _)| attributes & bit != 0)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        permissions.writable = !permissions.readonly;
+        // Read-only files can still be renamed; the folder decides
+        permissions.movable = path
+            .parent()
+            .and_then(|parent| fs::metadata(parent).ok())
+            .is_some_and(|parent| !parent.permissions().readonly());
+    }
+
+    permissions
+}
+
+// Attribute changes for `set_attributes`; fields left as None are untouched
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeChange {
+    pub readonly: Option<bool>,
+    /// Windows hidden attribute; dot-files are the Unix equivalent
+    pub hidden: Option<bool>,
+    /// Unix permission bits, e.g. 0o644
+    pub mode: Option<u32>,
+}
+
+/// Apply `change` to `path` and return the values it replaced, which undo
+/// the change when applied in turn
+pub fn set_attributes(path: &Path, change: &AttributeChange) -> Result<AttributeChange, String> {
+    let os_path = long_path(path);
+    let metadata = fs::metadata(&os_path).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let current = file_permissions(path, &metadata);
+    let mut previous = AttributeChange::default();
+
+    if cfg!(not(unix)) && change.mode.is_some() {
+        return Err("Permission bits can only be set on Unix".to_string());
+    }
+    if cfg!(not(windows)) && change.hidden.is_some() {
+        return Err("The hidden attribute only exists on Windows".to_string());
+    }
+
+    #[cfg(unix)]
+    if let Some(mode) = change.mode {
+        use std::os::unix::fs::PermissionsExt;
+        previous.mode = current.mode;
+        fs::set_permissions(&os_path, fs::Permissions::from_mode(mode & 0o7777))
+            .map_err(|e| format!("Failed to set permissions: {}", e))?;
+    }
+
+    #[cfg(windows)]
+    if let Some(hidden) = change.hidden {
+        previous.hidden = Some(current.attributes.iter().any(|a| a == "hidden"));
+        let status = Command::new("attrib")
+            .arg(if hidden { "+h" } else { "-h" })
+            .arg(&os_path)
+            .status()
+            .map_err(|e| format!("Failed to run attrib: {}", e))?;
+        if !status.success() {
+            return Err("attrib could not change the hidden attribute".to_string());
+        }
+    }
+
+    if let Some(readonly) = change.readonly {
+        previous.readonly = Some(current.readonly);
+        // Re-read, since a mode change above may have toggled it already
+        let mut permissions = fs::metadata(&os_path)
+            .map_err(|e| format!("Failed to read metadata: {}", e))?
+            .permissions();
+        if permissions.readonly() != readonly {
+            #[cfg(unix)]
+            {
+                // Clearing write bits loses which ones were set; keep the
+                // whole mode so undo restores it exactly
+                use std::os::unix::fs::PermissionsExt;
+                previous.mode = previous.mode.or(current.mode);
+                let mode = permissions.mode();
+                permissions.set_mode(if readonly { mode & !0o222 } else { mode | 0o200 });
+            }
+            #[cfg(not(unix))]
+            permissions.set_readonly(readonly);
+            fs::set_permissions(&os_path, permissions).map_err(|e| format!("Failed to set permissions: {}", e))?;
+        }
+    }
+
+    Ok(previous)
+}
+
+/// Whether a file's contents were evicted to the cloud by the OS (Storage
+/// Sense / OneDrive Files On-Demand, iCloud Optimize Storage); reading it
+/// would download it again
+pub fn is_dehydrated(metadata: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+        const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+        const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+        metadata.file_attributes()
+            & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+            != 0
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt;
+        const SF_DATALESS: u32 = 0x4000_0000;
+        metadata.st_flags() & SF_DATALESS != 0
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Whether `path` is an on-demand placeholder rather than a local file: an
+/// evicted OneDrive, Dropbox or iCloud Drive file (see `is_dehydrated`), or
+/// the ".name.icloud" stub older macOS versions leave in place of a file
+/// that was never downloaded. Placeholders report tiny or misleading sizes,
+/// and reading one downloads the whole file.
+pub fn is_placeholder(path: &Path, metadata: &fs::Metadata) -> bool {
+    is_dehydrated(metadata) || is_icloud_stub(path)
+}
+
+/// Fail for a placeholder, before anything opens it and pulls the file
+/// back down from the cloud
+pub fn ensure_local(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(long_path(path)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if is_placeholder(path, &metadata) {
+        return Err(format!("File is online-only: {}", display_path(path).display()));
+    }
+    Ok(())
+}
+
+fn is_icloud_stub(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.len() > ".icloud".len() + 1 && name.starts_with('.') && name.ends_with(".icloud"))
+}
+
+#[cfg(unix)]
+fn unix_access(path: &Path, mode: libc::c_int) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: c_path is a valid NUL-terminated string for the duration of the call
+    unsafe { libc::access(c_path.as_ptr(), mode) == 0 }
+}
+
+/// Move `source` to `destination`, resolving an existing destination with `policy`
+pub fn move_with_policy(
+    source: &Path,
+    destination: &Path,
+    policy: CollisionPolicy,
+) -> Result<MoveOutcome, String> {
+    transfer(source, destination, policy, Transfer::Move)
+}
+
+/// Copy `source` to `destination`, resolving an existing destination with `policy`
+pub fn copy_with_policy(
+    source: &Path,
+    destination: &Path,
+    policy: CollisionPolicy,
+) -> Result<MoveOutcome, String> {
+    transfer(source, destination, policy, Transfer::Copy)
+}
+
+/// Create a link at `destination` pointing at `source`, leaving the source in
+/// place. Uses a symlink, or a .lnk shortcut on Windows when symlinks are not
+/// permitted; the outcome's destination is the path actually created.
+/// A link never replaces an existing file: `Overwrite` is treated as
+/// `RenameWithSuffix`.
+pub fn link_with_policy(
+    source: &Path,
+    destination: &Path,
+    policy: CollisionPolicy,
+) -> Result<MoveOutcome, String> {
+    let policy = match policy {
+        CollisionPolicy::Overwrite => CollisionPolicy::RenameWithSuffix,
+        other => other,
+    };
+    transfer(source, destination, policy, Transfer::Link)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Move,
+    Copy,
+    Link,
+}
+
+// Shared collision handling for moves, copies and links
+fn transfer(source: &Path, destination: &Path, policy: CollisionPolicy, kind: Transfer) -> Result<MoveOutcome, String> {
+    let source = &long_path(source);
+    let destination = &long_path(destination);
+    if !source.exists() {
+        return Err(format!("Source does not exist: {}", display_path(source).display()));
+    }
+
+    let outcome = |target: &Path, resolution| MoveOutcome {
+        source: display_path(source).to_string_lossy().to_string(),
+        destination: display_path(target).to_string_lossy().to_string(),
+        resolution,
+    };
+
+    // On a case-insensitive file system "readme.MD" already exists as
+    // "README.md": it is the source itself, not a collision
+    if is_case_variant(source, destination) {
+        if kind != Transfer::Move {
+            return Err(format!(
+                "Destination is the source under another case: {}",
+                display_path(destination).display()
+            ));
+        }
+        rename_case(source, destination)?;
+        return Ok(outcome(destination, CollisionResolution::NoConflict));
+    }
+
+    let (target, resolution) = if destination.exists() {
+        match policy {
+            CollisionPolicy::Skip => {
+                return Ok(outcome(destination, CollisionResolution::Skipped));
+            }
+            CollisionPolicy::Overwrite => {
+                if destination.is_dir() {
+                    return Err(format!(
+                        "Refusing to overwrite folder: {}",
+                        display_path(destination).display()
+                    ));
+                }
+                (destination.to_path_buf(), CollisionResolution::Overwritten)
+            }
+            CollisionPolicy::RenameWithSuffix => {
+                (unique_destination(destination), CollisionResolution::Renamed)
+            }
+            CollisionPolicy::KeepIfIdentical => {
+                if files_identical(source, destination)? {
+                    return Ok(outcome(destination, CollisionResolution::KeptIdentical));
+                }
+                (unique_destination(destination), CollisionResolution::Renamed)
+            }
+        }
+    } else {
+        (destination.to_path_buf(), CollisionResolution::NoConflict)
+    };
+
+    // Create parent directory if it doesn't exist
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    // Shares drop connections now and then; those operations get retried
+    let network = network::is_network_path(source) || network::is_network_path(&target);
+    // rename() replaces an existing file in one step (MoveFileEx with
+    // REPLACE_EXISTING on Windows), so an overwritten file stays in place
+    // until its replacement is complete
+    let target = match kind {
+        Transfer::Move => {
+            let renamed = network::retrying_applied(
+                network,
+                || fs::rename(source, &target),
+                || !source.exists() && target.exists(),
+            );
+            match renamed {
+                Ok(()) => {}
+                // rename() can't leave the volume, e.g. onto a network share;
+                // the original only goes once the copy reads back the same
+                Err(e) if crosses_devices(&e) && source.is_file() => {
+                    copy_finished(source, &target, network, true)?;
+                    network::retrying_applied(network, || fs::remove_file(source), || !source.exists())
+                        .map_err(|e| format!("Moved a copy but failed to remove the original: {}", e))?;
+                }
+                Err(e) => return Err(format!("Failed to move file: {}", e)),
+            }
+            target
+        }
+        Transfer::Copy => {
+            copy_finished(source, &target, network, false)?;
+            target
+        }
+        Transfer::Link => create_link(source, &target)?,
+    };
+
+    Ok(outcome(target.as_path(), resolution))
+}
+
+// Whether `destination` names the same entry as `source`, spelled with
+// different case, as happens on Windows, macOS and FAT drives
+fn is_case_variant(source: &Path, destination: &Path) -> bool {
+    if source == destination
+        || !source
+            .to_string_lossy()
+            .to_lowercase()
+            .eq(&destination.to_string_lossy().to_lowercase())
+    {
+        return false;
+    }
+    same_entry(source, destination)
+}
+
+#[cfg(unix)]
+fn same_entry(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+// Without file ids, a path that resolves under another case is taken to be
+// the same entry; Windows folders are case-insensitive unless WSL says so
+#[cfg(not(unix))]
+fn same_entry(_a: &Path, b: &Path) -> bool {
+    b.exists()
+}
+
+// Rename in two steps through a temporary name: some file systems (FAT,
+// SMB shares) treat the new name as taken and leave a one-step rename a
+// no-op or an error
+fn rename_case(source: &Path, destination: &Path) -> Result<(), String> {
+    let mut temporary_name = destination.file_name().unwrap_or_default().to_os_string();
+    temporary_name.push(format!(".{}.rename", uuid::Uuid::new_v4().simple()));
+    let temporary = source.with_file_name(temporary_name);
+    fs::rename(source, &temporary).map_err(|e| format!("Failed to rename: {}", e))?;
+    if let Err(e) = fs::rename(&temporary, destination) {
+        let _ = fs::rename(&temporary, source);
+        return Err(format!("Failed to rename: {}", e));
+    }
+    Ok(())
+}
+
+// Copy under a temporary name so an interrupted copy never looks complete,
+// then rename it over `target`. With `verify`, the copy has to match the
+// source before it replaces anything.
+fn copy_finished(source: &Path, target: &Path, network: bool, verify: bool) -> Result<(), String> {
+    let partial = partial_path(target);
+    network::retrying(network, || copy_file(source, &partial)).map_err(|e| {
+        let _ = fs::remove_file(&partial);
+        format!("Failed to copy file: {}", e)
+    })?;
+    if verify && !files_identical(source, &partial).unwrap_or(false) {
+        let _ = fs::remove_file(&partial);
+        return Err(format!(
+            "Copy of {} could not be verified against the original",
+            display_path(source).display()
+        ));
+    }
+    network::retrying_applied(
+        network,
+        || fs::rename(&partial, target),
+        || !partial.exists() && target.exists(),
+    )
+    .map_err(|e| format!("Failed to finish copy: {}", e))
+}
+
+// EXDEV, or ERROR_NOT_SAME_DEVICE on Windows
+fn crosses_devices(error: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    let code = libc::EXDEV;
+    #[cfg(not(unix))]
+    let code = 17;
+    error.raw_os_error() == Some(code)
+}
+
+// fs::copy unless copying is rate limited, in which case the data goes
+// through a throttled reader. Either way tags, other extended attributes and
+// timestamps are carried over, so a move that has to copy loses nothing.
+fn copy_file(source: &Path, target: &Path) -> std::io::Result<u64> {
+    let copied = if throttle::is_limited(IoClass::Copying) {
+        let mut reader = ThrottledReader::new(fs::File::open(source)?, IoClass::Copying);
+        let mut writer = fs::File::create(target)?;
+        let copied = std::io::copy(&mut reader, &mut writer)?;
+        fs::set_permissions(target, fs::metadata(source)?.permissions())?;
+        copied
+    } else {
+        fs::copy(source, target)?
+    };
+    if let Err(e) = xattrs::copy_metadata(source, target) {
+        eprintln!("Failed to copy metadata of {}: {}", display_path(source).display(), e);
+    }
+    Ok(copied)
+}
+
+#[cfg(unix)]
+fn create_link(source: &Path, target: &Path) -> Result<PathBuf, String> {
+    std::os::unix::fs::symlink(source, target).map_err(|e| format!("Failed to create link: {}", e))?;
+    Ok(target.to_path_buf())
+}
+
+// Symlinks need Developer Mode or admin rights on Windows; fall back to a shortcut
+#[cfg(windows)]
+fn create_link(source: &Path, target: &Path) -> Result<PathBuf, String> {
+    let linked = if source.is_dir() {
+        std::os::windows::fs::symlink_dir(source, target)
+    } else {
+        std::os::windows::fs::symlink_file(source, target)
+    };
+    if linked.is_ok() {
+        return Ok(target.to_path_buf());
+    }
+
+    let mut shortcut = target.as_os_str().to_os_string();
+    shortcut.push(".lnk");
+    let shortcut = PathBuf::from(shortcut);
+    mslnk::ShellLink::new(display_path(source))
+        .map_err(|e| format!("Failed to create shortcut: {}", e))?
+        .create_lnk(&shortcut)
+        .map_err(|e| format!("Failed to create shortcut: {}", e))?;
+    Ok(shortcut)
+}
+
+/// Whether `path` is a link created by `link_with_policy`
+pub fn is_link(path: &Path) -> bool {
+    let is_symlink = fs::symlink_metadata(long_path(path))
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    is_symlink || (cfg!(windows) && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lnk")))
+}
+
+/// Where a symlink or .lnk shortcut points, resolved against the link's
+/// folder for relative symlinks. None for anything that isn't a link or a
+/// shortcut whose target can't be read.
+pub fn link_target(path: &Path) -> Option<PathBuf> {
+    let os_path = long_path(path);
+    let metadata = fs::symlink_metadata(&os_path).ok()?;
+    if metadata.file_type().is_symlink() {
+        let target = display_path(&fs::read_link(&os_path).ok()?);
+        return Some(match path.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        });
+    }
+    if metadata.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lnk")) {
+        return shortcut_target(&fs::read(&os_path).ok()?);
+    }
+    None
+}
+
+// Local base path from a Shell Link (.lnk) file's LinkInfo block; the
+// format is the same on every OS, so shortcuts on shared drives can be
+// checked anywhere
+fn shortcut_target(data: &[u8]) -> Option<PathBuf> {
+    const HEADER_SIZE: usize = 0x4C;
+    const HAS_ID_LIST: u32 = 0x1;
+    const HAS_LINK_INFO: u32 = 0x2;
+    const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+
+    let u16_at = |offset: usize| data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at =
+        |offset: usize| data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    if u32_at(0)? as usize != HEADER_SIZE {
+        return None;
+    }
+    let flags = u32_at(0x14)?;
+    if flags & HAS_LINK_INFO == 0 {
+        return None;
+    }
+    let mut offset = HEADER_SIZE;
+    if flags & HAS_ID_LIST != 0 {
+        offset += 2 + u16_at(offset)? as usize;
+    }
+
+    let info = offset;
+    let header_size = u32_at(info + 4)? as usize;
+    if u32_at(info + 8)? & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+    // Newer shortcuts carry a UTF-16 copy of the path; prefer it
+    if header_size >= 0x24 {
+        let start = info + u32_at(info + 0x1C)? as usize;
+        let units: Vec<u16> = (start..data.len().saturating_sub(1))
+            .step_by(2)
+            .map_while(|at| u16_at(at).filter(|&unit| unit != 0))
+            .collect();
+        return Some(PathBuf::from(String::from_utf16_lossy(&units)));
+    }
+    let start = info + u32_at(info + 0x10)? as usize;
+    let end = start + data.get(start..)?.iter().position(|&b| b == 0)?;
+    Some(PathBuf::from(String::from_utf8_lossy(&data[start..end]).to_string()))
+}
+
+/// Point an existing symlink or .lnk shortcut at `target` instead
+pub fn retarget_link(link: &Path, target: &Path) -> Result<(), String> {
+    let os_link = long_path(link);
+    let is_symlink = fs::symlink_metadata(&os_link)
+        .map(|m| m.file_type().is_symlink())
+        .map_err(|e| format!("Failed to read link: {}", e))?;
+
+    if is_symlink {
+        fs::remove_file(&os_link).map_err(|e| format!("Failed to remove old link: {}", e))?;
+        #[cfg(unix)]
+        let created = std::os::unix::fs::symlink(target, &os_link);
+        #[cfg(windows)]
+        let created = if target.is_dir() {
+            std::os::windows::fs::symlink_dir(target, &os_link)
+        } else {
+            std::os::windows::fs::symlink_file(target, &os_link)
+        };
+        return created.map_err(|e| format!("Failed to create link: {}", e));
+    }
+    retarget_shortcut(&os_link, target)
+}
+
+#[cfg(windows)]
+fn retarget_shortcut(shortcut: &Path, target: &Path) -> Result<(), String> {
+    mslnk::ShellLink::new(display_path(target))
+        .map_err(|e| format!("Failed to create shortcut: {}", e))?
+        .create_lnk(shortcut)
+        .map_err(|e| format!("Failed to create shortcut: {}", e))
+}
+
+#[cfg(not(windows))]
+fn retarget_shortcut(_shortcut: &Path, _target: &Path) -> Result<(), String> {
+    Err("Shortcuts can only be rewritten on Windows".to_string())
+}
+
+/// Move a file or folder to the OS trash / recycle bin
+pub fn move_to_trash(path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|e| format!("Failed to move {} to the trash: {}", path.display(), e))
+}
+
+/// Open `path` with the application the OS associates with it
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    let path = display_path(path);
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        Command::new("explorer")
+    } else {
+        Command::new("xdg-open")
+    };
+    launch(command.arg(&path))
+}
+
+/// Show `path` selected in Finder / Explorer / the desktop file manager
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    let path = display_path(path);
+    if cfg!(target_os = "macos") {
+        return launch(Command::new("open").arg("-R").arg(&path));
+    }
+    if cfg!(windows) {
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(&path);
+        return launch(Command::new("explorer").arg(select));
+    }
+
+    // Most Linux file managers implement the FileManager1 D-Bus interface;
+    // without it, open the parent folder instead
+    let shown = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", file_uri(&path)))
+        .arg("string:")
+        .status()
+        .is_ok_and(|status| status.success());
+    if shown {
+        return Ok(());
+    }
+    launch(Command::new("xdg-open").arg(path.parent().unwrap_or(&path)))
+}
+
+// Start a helper program without waiting for it; file managers keep running
+fn launch(command: &mut Command) -> Result<(), String> {
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to launch {:?}: {}", command.get_program(), e))
+}
+
+// file:// URI with everything outside the unreserved set percent-encoded
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// Whether `path` is, or lies inside, one of the do-not-touch `protected` paths
+pub fn is_protected(path: &Path, protected: &[String]) -> bool {
+    protected.iter().any(|root| path.starts_with(root))
+}
+
+/// Mirror the do-not-touch mark to an extended attribute so other tools
+/// (and a fresh database) can see it
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn set_protection_xattr(path: &Path, protected: bool) -> Result<(), String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let name = if cfg!(target_os = "macos") {
+        "com.smartstorage.protected"
+    } else {
+        "user.smartstorage.protected"
+    };
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|e| format!("Invalid path: {}", e))?;
+    let c_name = std::ffi::CString::new(name).map_err(|e| format!("Invalid attribute name: {}", e))?;
+    let value = b"1";
+
+    // SAFETY: c_path and c_name are valid NUL-terminated strings and value
+    // outlives the call
+    #[cfg(target_os = "macos")]
+    let result = unsafe {
+        if protected {
+            libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), value.as_ptr().cast(), value.len(), 0, 0)
+        } else {
+            libc::removexattr(c_path.as_ptr(), c_name.as_ptr(), 0)
+        }
+    };
+    // SAFETY: as above
+    #[cfg(target_os = "linux")]
+    let result = unsafe {
+        if protected {
+            libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+        } else {
+            libc::removexattr(c_path.as_ptr(), c_name.as_ptr())
+        }
+    };
+    if result != 0 {
+        return Err(format!(
+            "Failed to update extended attribute: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn set_protection_xattr(_path: &Path, _protected: bool) -> Result<(), String> {
+    Err("Extended attributes are not supported on this platform".to_string())
+}
+
+/// Overwrite a file's contents `passes` times (random data, zeros on the
+/// last pass), flushing each pass to disk, then rename and delete it.
+///
+/// This only reaches the original blocks on drives that overwrite in place.
+/// SSDs, copy-on-write filesystems (APFS, Btrfs) and snapshots keep old
+/// copies the OS can't address, so the data may still be recoverable there.
+pub fn shred_file(path: &Path, passes: usize) -> Result<(), String> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let os_path = long_path(path);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(&os_path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let length = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?
+        .len();
+
+    let mut seed = u64::from_le_bytes(uuid::Uuid::new_v4().as_bytes()[..8].try_into().unwrap_or([1; 8])) | 1;
+    let mut buffer = vec![0u8; 64 * 1024];
+    for pass in 0..passes.max(1) {
+        let last = pass + 1 == passes.max(1);
+        file.seek(SeekFrom::Start(0)).map_err(|e| format!("Failed to overwrite file: {}", e))?;
+        let mut remaining = length;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len() as u64) as usize;
+            if last {
+                buffer[..chunk].fill(0);
+            } else {
+                // xorshift64; unpredictability isn't needed, only non-zero noise
+                for bytes in buffer[..chunk].chunks_mut(8) {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    bytes.copy_from_slice(&seed.to_le_bytes()[..bytes.len()]);
+                }
+            }
+            file.write_all(&buffer[..chunk])
+                .map_err(|e| format!("Failed to overwrite file: {}", e))?;
+            remaining -= chunk as u64;
+        }
+        file.sync_all().map_err(|e| format!("Failed to flush file: {}", e))?;
+    }
+    file.set_len(0).map_err(|e| format!("Failed to truncate file: {}", e))?;
+    drop(file);
+
+    // Rename first so the directory entry doesn't keep the original name
+    let anonymous = os_path.with_file_name(uuid::Uuid::new_v4().simple().to_string());
+    let doomed = match fs::rename(&os_path, &anonymous) {
+        Ok(()) => anonymous,
+        Err(_) => os_path,
+    };
+    fs::remove_file(&doomed).map_err(|e| format!("Failed to delete file: {}", e))
+}
+
+/// Suffix of files still being copied
+pub const PARTIAL_SUFFIX: &str = ".smartstorage-partial";
+
+/// Temporary path a copy to `target` is written to
+pub fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    target.with_file_name(name)
+}
+
+/// Find a free "name (n).ext" path next to `path`
+pub fn unique_destination(path: &Path) -> PathBuf {
+    let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string());
+
+    let mut counter = 1u32;
+    loop {
+        let name = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        let candidate = parent.join(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Path form that file APIs accept beyond MAX_PATH (260 characters) on
+/// Windows: absolute paths get the `\\?\` or `\\?\UNC\` prefix. Relative
+/// paths, already-prefixed paths and other platforms are left unchanged.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    if path.as_os_str().to_string_lossy().starts_with(r"\\?\") || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    // The prefix disables normalization, so resolve "." and ".." first
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+
+    let normalized = normalized.to_string_lossy().replace('/', "\\");
+    match normalized.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", normalized)),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Undo `long_path` for paths that are shown to the user or stored
+pub fn display_path(path: &Path) -> PathBuf {
+    let raw = path.to_string_lossy();
+    if let Some(unc) = raw.strip_prefix(r"\\?\UNC\") {
+        PathBuf::from(format!(r"\\{}", unc))
+    } else if let Some(local) = raw.strip_prefix(r"\\?\") {
+        PathBuf::from(local)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Compute the SHA-256 of a file's contents as a lowercase hex string.
+/// Online-only placeholders are refused rather than downloaded.
+pub fn hash_file_contents(path: &Path) -> Result<String, String> {
+    ensure_local(path)?;
+    let mut file = fs::File::open(long_path(path)).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        throttle::consume(IoClass::Hashing, read);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Size and modification time (nanoseconds since the epoch) used to tell
+/// whether a cached hash is still valid
+pub fn fingerprint(metadata: &fs::Metadata) -> (u64, i64) {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0);
+    (metadata.len(), mtime)
+}
+
+/// Content hash of `path`, reusing the cached value when size and mtime are
+/// unchanged. Returns the hash and whether it came from the cache.
+pub fn cached_file_hash(path: &Path) -> Result<(String, bool), String> {
+    let metadata = fs::metadata(long_path(path)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", path.display()));
+    }
+
+    let key = path.to_string_lossy().to_string();
+    let (size, mtime) = fingerprint(&metadata);
+
+    if let Some(hash) = storage::cached_hash(&key, size, mtime)? {
+        return Ok((hash, true));
+    }
+
+    // Refuses an evicted file rather than pulling it back down
+    let hash = hash_file_contents(path)?;
+
+    // Make sure the row exists before attaching the hash to it
+    let node = create_file_node(&path.to_path_buf())?;
+    storage::index_file_nodes(&[node])?;
+    storage::store_hash(&key, size, mtime, &hash)?;
+
+    Ok((hash, false))
+}
+
+// Two regular files with the same size and hash
+fn files_identical(a: &Path, b: &Path) -> Result<bool, String> {
+    let meta_a = fs::metadata(long_path(a)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let meta_b = fs::metadata(long_path(b)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+
+    if !meta_a.is_file() || !meta_b.is_file() || meta_a.len() != meta_b.len() {
+        return Ok(false);
+    }
+
+    Ok(hash_file_contents(a)? == hash_file_contents(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_case_variant_needs_a_different_spelling_of_the_same_name() {
+        let dir = std::env::temp_dir().join(format!("case-variant-{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("README.md");
+        fs::write(&file, b"readme").unwrap();
+
+        assert!(!is_case_variant(&file, &file));
+        assert!(!is_case_variant(&file, &dir.join("NOTES.md")));
+        // Only a case-insensitive file system has the other spelling too
+        assert_eq!(is_case_variant(&file, &dir.join("readme.md")), dir.join("readme.md").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+// ============================================================================
+// Folder Style Module - Colors and icons for organized folders
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::fileops;
+
+// Folder colors, matching the Finder label colors
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderColor {
+    Gray,
+    Green,
+    Purple,
+    Blue,
+    Yellow,
+    Red,
+    Orange,
+}
+
+impl FolderColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FolderColor::Gray => "gray",
+            FolderColor::Green => "green",
+            FolderColor::Purple => "purple",
+            FolderColor::Blue => "blue",
+            FolderColor::Yellow => "yellow",
+            FolderColor::Red => "red",
+            FolderColor::Orange => "orange",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "gray" => Some(FolderColor::Gray),
+            "green" => Some(FolderColor::Green),
+            "purple" => Some(FolderColor::Purple),
+            "blue" => Some(FolderColor::Blue),
+            "yellow" => Some(FolderColor::Yellow),
+            "red" => Some(FolderColor::Red),
+            "orange" => Some(FolderColor::Orange),
+            _ => None,
+        }
+    }
+
+    // Finder label index stored in the folder's FinderInfo (0 is no label)
+    #[cfg(target_os = "macos")]
+    fn finder_label(&self) -> u8 {
+        match self {
+            FolderColor::Gray => 1,
+            FolderColor::Green => 2,
+            FolderColor::Purple => 3,
+            FolderColor::Blue => 4,
+            FolderColor::Yellow => 5,
+            FolderColor::Red => 6,
+            FolderColor::Orange => 7,
+        }
+    }
+}
+
+// Look of every folder with a given name, e.g. all "Finance" folders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderStyle {
+    /// Folder name the style applies to, matched case-insensitively
+    pub name: String,
+    pub color: Option<FolderColor>,
+    /// Path to an .ico/.icns/.png file, or an icon theme name on Linux
+    pub icon: Option<String>,
+}
+
+/// The styled folders among `folders` and their ancestors, by path. A
+/// destination of ".../Finance/2024" styles both "Finance" and "2024" if
+/// either name has a style.
+pub fn styles_for<'a>(
+    folders: impl IntoIterator<Item = &'a str>,
+    styles: &[FolderStyle],
+) -> BTreeMap<String, FolderStyle> {
+    let mut styled = BTreeMap::new();
+    if styles.is_empty() {
+        return styled;
+    }
+    for folder in folders {
+        for ancestor in Path::new(folder).ancestors() {
+            let Some(name) = ancestor.file_name().map(|n| n.to_string_lossy()) else {
+                continue;
+            };
+            if let Some(style) = styles.iter().find(|style| style.name.eq_ignore_ascii_case(&name)) {
+                styled.insert(ancestor.to_string_lossy().to_string(), style.clone());
+            }
+        }
+    }
+    styled
+}
+
+/// Apply planned styles to the folders that exist now, logging failures;
+/// a missing icon must not fail the batch that created the folder
+pub fn apply_planned(styles: &BTreeMap<String, FolderStyle>) {
+    for (folder, style) in styles {
+        let folder = Path::new(folder);
+        if !fileops::long_path(folder).is_dir() {
+            continue;
+        }
+        if let Err(e) = apply(folder, style) {
+            eprintln!("Failed to style {}: {}", folder.display(), e);
+        }
+    }
+}
+
+/// Write a style into the folder's platform metadata: desktop.ini on
+/// Windows, the Finder label on macOS, GIO metadata on Linux desktops.
+///
+/// Parts the platform can't show (colors on Windows and Linux, custom icons
+/// on macOS) are left to the database, so the style still travels with
+/// exported settings.
+pub fn apply(folder: &Path, style: &FolderStyle) -> Result<(), String> {
+    let os_folder = fileops::long_path(folder);
+    if !os_folder.is_dir() {
+        return Err(format!("Path is not a directory: {}", folder.display()));
+    }
+    write_metadata(&os_folder, style)
+}
+
+#[cfg(windows)]
+fn write_metadata(folder: &Path, style: &FolderStyle) -> Result<(), String> {
+    use std::process::Command;
+
+    let Some(icon) = &style.icon else {
+        return Ok(());
+    };
+    let desktop_ini = folder.join("desktop.ini");
+    // Explorer only reads desktop.ini in read-only or system folders, and
+    // the file itself should stay out of sight
+    if desktop_ini.exists() {
+        let _ = Command::new("attrib").args(["-h", "-s"]).arg(&desktop_ini).status();
+    }
+    std::fs::write(
+        &desktop_ini,
+        format!("[.ShellClassInfo]\r\nIconResource={},0\r\n", icon),
+    )
+    .map_err(|e| format!("Failed to write desktop.ini: {}", e))?;
+    let hidden = Command::new("attrib").args(["+h", "+s"]).arg(&desktop_ini).status();
+    let marked = Command::new("attrib").arg("+r").arg(folder).status();
+    if !hidden.is_ok_and(|s| s.success()) || !marked.is_ok_and(|s| s.success()) {
+        return Err("Failed to set folder attributes for desktop.ini".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn write_metadata(folder: &Path, style: &FolderStyle) -> Result<(), String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Some(color) = style.color else {
+        return Ok(());
+    };
+    let c_path =
+        std::ffi::CString::new(folder.as_os_str().as_bytes()).map_err(|e| format!("Invalid path: {}", e))?;
+    let c_name = std::ffi::CString::new("com.apple.FinderInfo").map_err(|e| format!("Invalid attribute name: {}", e))?;
+
+    // Keep the rest of the FinderInfo; the label lives in bits 1-3 of byte 9
+    let mut info = [0u8; 32];
+    // SAFETY: c_path and c_name are valid NUL-terminated strings and info
+    // is a writable buffer of the length passed
+    let read = unsafe {
+        libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), info.as_mut_ptr().cast(), info.len(), 0, 0)
+    };
+    if read != info.len() as isize {
+        info = [0u8; 32];
+    }
+    info[9] = (info[9] & !0x0E) | (color.finder_label() << 1);
+
+    // SAFETY: as above, and info outlives the call
+    let result = unsafe {
+        libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), info.as_ptr().cast(), info.len(), 0, 0)
+    };
+    if result != 0 {
+        return Err(format!("Failed to set Finder label: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn write_metadata(folder: &Path, style: &FolderStyle) -> Result<(), String> {
+    use std::process::Command;
+
+    let Some(icon) = &style.icon else {
+        return Ok(());
+    };
+    // Nautilus and other GIO file managers read these; elsewhere gio is missing
+    let (key, value) = if Path::new(icon).is_absolute() {
+        ("metadata::custom-icon", format!("file://{}", icon))
+    } else {
+        ("metadata::custom-icon-name", icon.clone())
+    };
+    let status = Command::new("gio")
+        .arg("set")
+        .arg(folder)
+        .args([key, value.as_str()])
+        .status()
+        .map_err(|e| format!("Failed to run gio: {}", e))?;
+    if !status.success() {
+        return Err("gio could not set the folder icon".to_string());
+    }
+    Ok(())
+}
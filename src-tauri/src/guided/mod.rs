@@ -0,0 +1,468 @@
+// ============================================================================
+// Guided Module - The "organize my whole computer" wizard as a state machine
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::cleanup::find_duplicates;
+use crate::commands::disk::analyze_disk_usage;
+use crate::commands::organize::{self, generate_plan, OrganizationConfig, OrganizationPlan};
+use crate::fileops::CollisionPolicy;
+use crate::storage;
+
+/// Event emitted on every state transition of the guided cleanup
+pub const GUIDED_CLEANUP_EVENT: &str = "guided-cleanup";
+
+const SESSION_KEY: &str = "guided_cleanup";
+
+/// Rule suggestions are generated with unless the wizard is told otherwise
+const DEFAULT_RULE: &str = "byType";
+
+/// Folders under the user's home the wizard looks at by default
+const DEFAULT_FOLDERS: &[&str] = &["Desktop", "Downloads", "Documents", "Pictures", "Videos", "Music"];
+
+/// Folders a suggestion is generated for: the chosen folders and their
+/// fullest subfolders, up to this many
+const MAX_SUGGESTED_FOLDERS: usize = 20;
+
+// Where the wizard is. Each state is reached from the one before it, except
+// that approval and applying alternate once per stage.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum GuidedState {
+    Analyzing,
+    ScanningDuplicates,
+    Suggesting,
+    /// Waiting for the user to approve or skip stage `stage`
+    AwaitingApproval { stage: usize },
+    Applying { stage: usize },
+    Finished,
+    Cancelled,
+    Failed { message: String },
+}
+
+impl GuidedState {
+    /// Whether the session is over and a new one may start
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, GuidedState::Finished | GuidedState::Cancelled | GuidedState::Failed { .. })
+    }
+}
+
+// Size of one analyzed folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderUsage {
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StageStatus {
+    Pending,
+    Applied {
+        moved: usize,
+        failed: usize,
+        /// History batches the moves were logged under, for undo
+        batch_ids: Vec<String>,
+        summary: String,
+    },
+    Skipped,
+}
+
+// One folder's suggested plan, applied only once approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidedStage {
+    pub folder: String,
+    pub plan: OrganizationPlan,
+    #[serde(flatten)]
+    pub status: StageStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidedSession {
+    pub id: String,
+    pub roots: Vec<String>,
+    pub rule: String,
+    pub state: GuidedState,
+    /// Analyzed folders, largest first
+    pub folders: Vec<FolderUsage>,
+    pub total_size: u64,
+    pub duplicate_groups: usize,
+    /// Bytes taken by every duplicate copy beyond the first
+    pub duplicate_size: u64,
+    pub stages: Vec<GuidedStage>,
+    pub started_at: String,
+    pub updated_at: String,
+}
+
+// Payload of the "guided-cleanup" event; the full session, plans included,
+// comes from get_guided_cleanup
+#[derive(Debug, Clone, Serialize)]
+pub struct GuidedTransition {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub state: GuidedState,
+    pub stages: usize,
+    pub stages_done: usize,
+}
+
+static SESSION: Lazy<Mutex<Option<GuidedSession>>> = Lazy::new(|| Mutex::new(load()));
+static CANCEL: AtomicBool = AtomicBool::new(false);
+/// Set while advance is applying a stage in this process; a session loaded
+/// in the Applying state was interrupted instead
+static APPLYING: AtomicBool = AtomicBool::new(false);
+
+// The session checkpointed by the last run, so the wizard survives a restart
+fn load() -> Option<GuidedSession> {
+    storage::get_preference(SESSION_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// The current or last session
+pub fn current() -> Option<GuidedSession> {
+    SESSION.lock().clone()
+}
+
+/// Start a session over `roots` (the usual user folders when empty), or
+/// pick up an unfinished one unless `restart`. Analysis, the duplicate scan
+/// and suggestions run in the background; the session then waits for each
+/// stage to be approved or skipped with advance.
+pub fn start(app: AppHandle, roots: Vec<String>, rule: Option<String>, restart: bool) -> Result<GuidedSession, String> {
+    let resumed = {
+        let mut session = SESSION.lock();
+        if APPLYING.load(Ordering::SeqCst) {
+            return Err("A stage is being applied; wait for it to finish".to_string());
+        }
+        match session.as_mut() {
+            Some(existing) if !existing.state.is_terminal() && !restart => {
+                if let GuidedState::Applying { stage } = existing.state {
+                    // Applying is checkpointed per part; approving again resumes it
+                    existing.state = GuidedState::AwaitingApproval { stage };
+                }
+                Some(existing.clone())
+            }
+            _ => None,
+        }
+    };
+    if let Some(session) = resumed {
+        CANCEL.store(false, Ordering::SeqCst);
+        save();
+        if matches!(session.state, GuidedState::AwaitingApproval { .. }) {
+            return Ok(session);
+        }
+        // Interrupted before the stages were ready; those steps are redone
+        spawn_preparation(app, session.id.clone());
+        return Ok(session);
+    }
+
+    let roots = if roots.is_empty() { default_roots() } else { roots };
+    if roots.is_empty() {
+        return Err("No folders to organize".to_string());
+    }
+    for root in &roots {
+        if !Path::new(root).is_dir() {
+            return Err(format!("Path is not a directory: {}", root));
+        }
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    let session = GuidedSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        roots,
+        rule: rule.unwrap_or_else(|| DEFAULT_RULE.to_string()),
+        state: GuidedState::Analyzing,
+        folders: Vec::new(),
+        total_size: 0,
+        duplicate_groups: 0,
+        duplicate_size: 0,
+        stages: Vec::new(),
+        started_at: now.clone(),
+        updated_at: now,
+    };
+    {
+        let mut slot = SESSION.lock();
+        if APPLYING.load(Ordering::SeqCst) {
+            return Err("A stage is being applied; wait for it to finish".to_string());
+        }
+        CANCEL.store(false, Ordering::SeqCst);
+        *slot = Some(session.clone());
+    }
+    save();
+    spawn_preparation(app, session.id.clone());
+    Ok(session)
+}
+
+/// Apply (`approve`) or skip the stage awaiting approval, then move on to
+/// the next one, or finish after the last
+pub async fn advance(app: AppHandle, approve: bool, collision: CollisionPolicy) -> Result<GuidedSession, String> {
+    // Checking the state and claiming the stage happen under one lock, so a
+    // second approval can't apply the same stage again
+    let (id, stage, plan) = {
+        let mut session = SESSION.lock();
+        let session = session.as_mut().ok_or("No guided cleanup is running")?;
+        let GuidedState::AwaitingApproval { stage } = session.state else {
+            return Err("The guided cleanup is not waiting for approval".to_string());
+        };
+        if approve {
+            session.state = GuidedState::Applying { stage };
+            session.updated_at = chrono::Utc::now().to_rfc3339();
+            APPLYING.store(true, Ordering::SeqCst);
+        }
+        (session.id.clone(), stage, session.stages[stage].plan.clone())
+    };
+
+    let status = if approve {
+        save();
+        emit(&app);
+        let task_app = app.clone();
+        let applied = tokio::task::spawn_blocking(move || organize::apply_move_plan(&task_app, &plan, collision)).await;
+        APPLYING.store(false, Ordering::SeqCst);
+        match applied.map_err(|e| format!("Guided cleanup task error: {}", e))? {
+            Ok(report) => StageStatus::Applied {
+                moved: report.moved.iter().filter(|outcome| outcome.moved()).count(),
+                failed: report.failed.len(),
+                batch_ids: report.parts.into_iter().map(|part| part.batch_id).collect(),
+                summary: report.summary,
+            },
+            Err(e) => {
+                // The stage can be approved again; finished parts are skipped
+                transition(&app, &id, GuidedState::AwaitingApproval { stage });
+                return Err(e);
+            }
+        }
+    } else {
+        StageStatus::Skipped
+    };
+
+    let next = {
+        let mut session = SESSION.lock();
+        let session = session
+            .as_mut()
+            .filter(|session| session.id == id)
+            .ok_or("No guided cleanup is running")?;
+        session.stages[stage].status = status;
+        if CANCEL.load(Ordering::SeqCst) {
+            GuidedState::Cancelled
+        } else if stage + 1 < session.stages.len() {
+            GuidedState::AwaitingApproval { stage: stage + 1 }
+        } else {
+            GuidedState::Finished
+        }
+    };
+    transition(&app, &id, next);
+    current().ok_or_else(|| "No guided cleanup is running".to_string())
+}
+
+/// Stop the session. Preparation stops at its next step and a stage being
+/// applied finishes first; stages already applied stay applied.
+pub fn cancel(app: &AppHandle) -> Result<GuidedSession, String> {
+    let GuidedSession { id, state, .. } = current().ok_or("No guided cleanup is running")?;
+    if state.is_terminal() {
+        return Err("The guided cleanup has already ended".to_string());
+    }
+    CANCEL.store(true, Ordering::SeqCst);
+    if !matches!(state, GuidedState::Applying { .. }) {
+        transition(app, &id, GuidedState::Cancelled);
+    }
+    current().ok_or_else(|| "No guided cleanup is running".to_string())
+}
+
+// Analysis, duplicate scan and suggestions for session `id`, one after the
+// other. Once another session has replaced it, the results are dropped.
+fn spawn_preparation(app: AppHandle, id: String) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(message) = prepare(&app, &id).await {
+            if !CANCEL.load(Ordering::SeqCst) {
+                transition(&app, &id, GuidedState::Failed { message });
+            }
+        }
+    });
+}
+
+async fn prepare(app: &AppHandle, id: &str) -> Result<(), String> {
+    let Some(session) = current().filter(|session| session.id == id) else {
+        return Ok(());
+    };
+
+    transition(app, id, GuidedState::Analyzing);
+    let mut folders = Vec::new();
+    let mut total_size = 0;
+    for root in &session.roots {
+        check_cancelled(id)?;
+        let report = analyze_disk_usage(app.clone(), root.clone(), Some(1), Some(0)).await?;
+        total_size += report.total_size;
+        folders.push(FolderUsage {
+            path: report.tree.path.clone(),
+            size: report.tree.size,
+            file_count: report.tree.file_count,
+        });
+        folders.extend(report.tree.children.iter().map(|child| FolderUsage {
+            path: child.path.clone(),
+            size: child.size,
+            file_count: child.file_count,
+        }));
+    }
+    folders.sort_by(|a, b| b.size.cmp(&a.size));
+    update(id, |session| {
+        session.folders = folders.clone();
+        session.total_size = total_size;
+    });
+
+    check_cancelled(id)?;
+    transition(app, id, GuidedState::ScanningDuplicates);
+    let mut hashes = HashSet::new();
+    let mut duplicate_size = 0;
+    for root in &session.roots {
+        check_cancelled(id)?;
+        let report = find_duplicates(Some(root.clone()), None, None).await?;
+        for group in report.groups {
+            if hashes.insert(group.content_hash) {
+                duplicate_size += group.size * group.copies.len().saturating_sub(1) as u64;
+            }
+        }
+    }
+    update(id, |session| {
+        session.duplicate_groups = hashes.len();
+        session.duplicate_size = duplicate_size;
+    });
+
+    check_cancelled(id)?;
+    transition(app, id, GuidedState::Suggesting);
+    // The chosen folders first, then the subfolders holding the most files
+    let mut candidates: Vec<String> = session.roots.clone();
+    let mut subfolders: Vec<&FolderUsage> =
+        folders.iter().filter(|folder| !session.roots.contains(&folder.path)).collect();
+    subfolders.sort_by(|a, b| b.file_count.cmp(&a.file_count));
+    candidates.extend(subfolders.into_iter().map(|folder| folder.path.clone()));
+    candidates.truncate(MAX_SUGGESTED_FOLDERS);
+
+    let mut stages = Vec::new();
+    for folder in candidates {
+        check_cancelled(id)?;
+        let config = OrganizationConfig {
+            rule: session.rule.clone(),
+            path: folder.clone(),
+            ..Default::default()
+        };
+        let plan = generate_plan(app.clone(), config).await?;
+        if !plan.operations.is_empty() && !already_organized(&folder, &plan) {
+            stages.push(GuidedStage {
+                folder,
+                plan,
+                status: StageStatus::Pending,
+            });
+        }
+    }
+    let empty = stages.is_empty();
+    update(id, |session| session.stages = stages.clone());
+
+    check_cancelled(id)?;
+    transition(
+        app,
+        id,
+        if empty {
+            GuidedState::Finished
+        } else {
+            GuidedState::AwaitingApproval { stage: 0 }
+        },
+    );
+    Ok(())
+}
+
+// Stop preparing when the session was cancelled or replaced by another
+fn check_cancelled(id: &str) -> Result<(), String> {
+    if CANCEL.load(Ordering::SeqCst) || !current().is_some_and(|session| session.id == id) {
+        Err("Guided cleanup cancelled".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Change session `id` and checkpoint it; a session that has since been
+// replaced is left alone
+fn update(id: &str, change: impl FnOnce(&mut GuidedSession)) {
+    if let Some(session) = SESSION.lock().as_mut().filter(|session| session.id == id) {
+        change(session);
+        session.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+    save();
+}
+
+// Move session `id` to `state`, checkpoint and tell the frontend. A
+// cancelled session stays cancelled, whatever step was still finishing.
+fn transition(app: &AppHandle, id: &str, state: GuidedState) {
+    update(id, |session| {
+        if session.state != GuidedState::Cancelled {
+            session.state = state;
+        }
+    });
+    emit(app);
+}
+
+// Tell the frontend where the current session is
+fn emit(app: &AppHandle) {
+    if let Some(session) = current() {
+        let _ = app.emit(
+            GUIDED_CLEANUP_EVENT,
+            GuidedTransition {
+                session_id: session.id.clone(),
+                state: session.state.clone(),
+                stages: session.stages.len(),
+                stages_done: session
+                    .stages
+                    .iter()
+                    .filter(|stage| stage.status != StageStatus::Pending)
+                    .count(),
+            },
+        );
+    }
+}
+
+fn save() {
+    let Some(session) = current() else {
+        return;
+    };
+    let saved = serde_json::to_string(&session)
+        .map_err(|e| e.to_string())
+        .and_then(|json| storage::set_preference(SESSION_KEY, &json));
+    if let Err(e) = saved {
+        eprintln!("Failed to save guided cleanup: {}", e);
+    }
+}
+
+// Whether `plan` would nest `folder`'s files in a folder of the same name,
+// like Images/Images: the folder already is one of the rule's targets
+fn already_organized(folder: &str, plan: &OrganizationPlan) -> bool {
+    let folder = Path::new(folder);
+    let Some(name) = folder.file_name() else {
+        return false;
+    };
+    let nested = folder.join(name);
+    plan.operations
+        .iter()
+        .any(|op| Path::new(&op.destination_path).starts_with(&nested))
+}
+
+// The usual user folders that exist
+fn default_roots() -> Vec<String> {
+    let Some(home) = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+    else {
+        return Vec::new();
+    };
+    DEFAULT_FOLDERS
+        .iter()
+        .map(|name| home.join(name))
+        .filter(|folder| folder.is_dir())
+        .map(|folder| folder.to_string_lossy().to_string())
+        .collect()
+}
@@ -0,0 +1,282 @@
+// ============================================================================
+// Headless Module - Batch mode for scripts and cron jobs
+// ============================================================================
+//
+// Usage: smart-storage-ai --headless <operation> ['<json arguments>'] [--db <path>]
+//
+// Prints one JSON object to stdout and exits with a code scripts can branch on.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::commands::disk::{compare_folders, FolderComparison};
+use crate::commands::ingest::{run_import, ImportReport};
+use crate::commands::search::{search_files, FileSearchRequest, FileSearchResponse};
+use crate::digest::{self, Digest};
+use crate::ingest::{self, ImportOptions};
+use crate::staging::{self, PurgeReport};
+use crate::storage;
+
+/// Matches "identifier" in tauri.conf.json, which names the app data folder
+const APP_IDENTIFIER: &str = "com.smartstorageai.app";
+
+// How an operation ended, and the process exit code for it
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    /// Everything was done
+    Success = 0,
+    /// Nothing was done: bad input or a fatal error
+    Failed = 1,
+    /// Unknown operation or malformed arguments
+    Usage = 2,
+    /// Some items failed; the rest were done
+    Partial = 3,
+    /// Done, but the result holds conflicts that need a decision
+    Conflicts = 4,
+    /// Interrupted before finishing
+    Cancelled = 5,
+}
+
+// The single JSON object printed for every headless run
+#[derive(Debug, Serialize)]
+pub struct HeadlessOutput {
+    pub operation: String,
+    pub status: ExitStatus,
+    pub exit_code: i32,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// Results that know whether they represent full success
+pub trait Outcome: Serialize {
+    fn status(&self) -> ExitStatus {
+        ExitStatus::Success
+    }
+}
+
+impl Outcome for ImportReport {
+    fn status(&self) -> ExitStatus {
+        if self.failed.is_empty() && self.verification_failed.is_empty() {
+            ExitStatus::Success
+        } else if self.imported.is_empty() {
+            ExitStatus::Failed
+        } else {
+            ExitStatus::Partial
+        }
+    }
+}
+
+impl Outcome for FolderComparison {
+    fn status(&self) -> ExitStatus {
+        if !self.unreadable.is_empty() {
+            ExitStatus::Partial
+        } else if self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.different.is_empty() {
+            ExitStatus::Success
+        } else {
+            ExitStatus::Conflicts
+        }
+    }
+}
+
+impl Outcome for FileSearchResponse {}
+
+impl Outcome for PurgeReport {
+    fn status(&self) -> ExitStatus {
+        if self.errors.is_empty() {
+            ExitStatus::Success
+        } else if self.purged.is_empty() {
+            ExitStatus::Failed
+        } else {
+            ExitStatus::Partial
+        }
+    }
+}
+
+impl Outcome for Digest {
+    fn status(&self) -> ExitStatus {
+        if self.delivery_errors.is_empty() {
+            ExitStatus::Success
+        } else {
+            ExitStatus::Partial
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ImportArgs {
+    source: String,
+    destination: String,
+    #[serde(default)]
+    options: Option<ImportOptions>,
+}
+
+#[derive(Deserialize)]
+struct CompareArgs {
+    a: String,
+    b: String,
+    #[serde(default)]
+    compare_contents: Option<bool>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct DigestArgs {
+    trigger: Option<String>,
+    since: Option<String>,
+}
+
+/// Run a headless operation when the process was started with `--headless`;
+/// returns the exit code, or None to start the GUI
+pub fn run_from_args() -> Option<i32> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("--headless") {
+        return None;
+    }
+    args.remove(0);
+
+    let db_path = match args.iter().position(|arg| arg == "--db") {
+        Some(index) if index + 1 < args.len() => {
+            let path = PathBuf::from(args.remove(index + 1));
+            args.remove(index);
+            Some(path)
+        }
+        _ => None,
+    };
+    let operation = args.first().cloned().unwrap_or_default();
+    let arguments = args.get(1).cloned().unwrap_or_else(|| "{}".to_string());
+
+    let output = match open_database(db_path) {
+        Ok(()) => tauri::async_runtime::block_on(run(&operation, &arguments)),
+        Err(e) => failure(&operation, ExitStatus::Failed, e),
+    };
+    match serde_json::to_string_pretty(&output) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Failed to serialize output: {}", e),
+    }
+    Some(output.exit_code)
+}
+
+async fn run(operation: &str, arguments: &str) -> HeadlessOutput {
+    let arguments: Value = match serde_json::from_str(arguments) {
+        Ok(arguments) => arguments,
+        Err(e) => return failure(operation, ExitStatus::Usage, format!("Invalid JSON arguments: {}", e)),
+    };
+
+    match operation {
+        "import" => match serde_json::from_value::<ImportArgs>(arguments) {
+            Ok(args) => finish(operation, import(args)).await,
+            Err(e) => failure(operation, ExitStatus::Usage, format!("Invalid arguments: {}", e)),
+        },
+        "compare" => match serde_json::from_value::<CompareArgs>(arguments) {
+            Ok(args) => finish(operation, compare_folders(args.a, args.b, args.compare_contents)).await,
+            Err(e) => failure(operation, ExitStatus::Usage, format!("Invalid arguments: {}", e)),
+        },
+        "search_files" => match serde_json::from_value::<FileSearchRequest>(arguments) {
+            Ok(request) => finish(operation, search_files(request)).await,
+            Err(e) => failure(operation, ExitStatus::Usage, format!("Invalid arguments: {}", e)),
+        },
+        "digest" => match serde_json::from_value::<DigestArgs>(arguments) {
+            Ok(args) => {
+                let trigger = args.trigger.unwrap_or_else(|| "headless".to_string());
+                let generated = async move {
+                    tokio::task::spawn_blocking(move || digest::generate(&trigger, args.since))
+                        .await
+                        .map_err(|e| format!("Digest task error: {}", e))?
+                };
+                finish(operation, generated).await
+            }
+            Err(e) => failure(operation, ExitStatus::Usage, format!("Invalid arguments: {}", e)),
+        },
+        "purge_staging" => {
+            let purged = async {
+                tokio::task::spawn_blocking(staging::purge_expired)
+                    .await
+                    .map_err(|e| format!("Staging task error: {}", e))?
+            };
+            finish(operation, purged).await
+        }
+        _ => failure(
+            operation,
+            ExitStatus::Usage,
+            "Unknown operation; expected import, compare, search_files, digest or purge_staging".to_string(),
+        ),
+    }
+}
+
+// Await an operation, or report it as cancelled on Ctrl-C
+async fn finish<T: Outcome>(operation: &str, work: impl Future<Output = Result<T, String>>) -> HeadlessOutput {
+    let result = tokio::select! {
+        result = work => result,
+        _ = tokio::signal::ctrl_c() => {
+            return failure(operation, ExitStatus::Cancelled, "Interrupted".to_string());
+        }
+    };
+
+    match result {
+        Ok(result) => {
+            let status = result.status();
+            HeadlessOutput {
+                operation: operation.to_string(),
+                status,
+                exit_code: status as i32,
+                result: serde_json::to_value(&result).ok(),
+                error: None,
+            }
+        }
+        Err(e) => failure(operation, ExitStatus::Failed, e),
+    }
+}
+
+fn failure(operation: &str, status: ExitStatus, error: String) -> HeadlessOutput {
+    HeadlessOutput {
+        operation: operation.to_string(),
+        status,
+        exit_code: status as i32,
+        result: None,
+        error: Some(error),
+    }
+}
+
+async fn import(args: ImportArgs) -> Result<ImportReport, String> {
+    let options = args.options.unwrap_or_default();
+    if let Some(template) = &options.rename_template {
+        ingest::validate_template(template)?;
+    }
+    tokio::task::spawn_blocking(move || {
+        let batch_id =
+            storage::create_history_batch("Import files", &format!("{} → {}", args.source, args.destination))?;
+        run_import(Path::new(&args.source), Path::new(&args.destination), &options, batch_id)
+    })
+    .await
+    .map_err(|e| format!("Import task error: {}", e))?
+}
+
+// The GUI's database unless `--db` points elsewhere
+fn open_database(db_path: Option<PathBuf>) -> Result<(), String> {
+    let db_path = match db_path {
+        Some(path) => path,
+        None => {
+            let data_dir = app_data_dir().ok_or("Failed to find the app data folder; pass --db")?;
+            std::fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+            data_dir.join("smart_storage.db")
+        }
+    };
+    storage::init_database(&db_path).map_err(|e| format!("Failed to open database: {}", e))
+}
+
+// Same location Tauri's app_data_dir() resolves to
+fn app_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
@@ -0,0 +1,265 @@
+// ============================================================================
+// Health Module - Spotting empty, mislabeled and damaged files
+// ============================================================================
+
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::archive::{self, ArchiveFormat};
+use crate::fileops;
+use crate::throttle::{self, IoClass};
+
+/// Extensions whose contents must sniff as an image
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tif", "tiff", "heic", "heif", "avif", "ico",
+];
+
+// Why a file looks damaged or isn't what its name says
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SuspectReason {
+    ZeroByte,
+    /// The contents are not the kind of file the extension claims;
+    /// `detected` is the sniffed extension, if anything was recognized
+    HeaderMismatch { expected: String, detected: Option<String> },
+    /// The archive's directory or stream can't be read to the end
+    TruncatedArchive { message: String },
+    /// The header is intact but the file ends before its data does
+    Truncated { format: String },
+    /// The file doesn't end with its format's end marker, but one turns up
+    /// earlier: it may be cut short or just carry data after its end
+    PossiblyTruncated { format: String },
+    /// Reading the file failed, e.g. on a bad sector
+    Unreadable { message: String },
+}
+
+/// What, if anything, is wrong with the file at `path`. Only the header is
+/// read, except for archives, whose index is listed.
+///
+/// An image saved under another image extension (a PNG named .jpg) opens
+/// fine everywhere, so only contents of a different kind count as a mismatch.
+pub fn check(path: &Path, metadata: &fs::Metadata) -> Option<SuspectReason> {
+    if metadata.len() == 0 {
+        return Some(SuspectReason::ZeroByte);
+    }
+    // Reading an online-only file would download it
+    if fileops::is_placeholder(path, metadata) {
+        return None;
+    }
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) || extension == "pdf" {
+        let sniffed = infer::get_from_path(fileops::long_path(path)).ok().flatten();
+        let matches = match &sniffed {
+            Some(kind) if extension == "pdf" => kind.extension() == "pdf",
+            Some(kind) => kind.matcher_type() == infer::MatcherType::Image,
+            None => false,
+        };
+        if !matches {
+            return Some(SuspectReason::HeaderMismatch {
+                expected: extension,
+                detected: sniffed.map(|kind| kind.extension().to_string()),
+            });
+        }
+    }
+
+    if ArchiveFormat::from_path(path).is_some() {
+        if let Err(message) = archive::list_entries(&fileops::long_path(path)) {
+            return Some(SuspectReason::TruncatedArchive { message });
+        }
+    }
+    None
+}
+
+/// Like `check`, but also reads the whole file to surface I/O errors and
+/// checks that images and videos end where their structure says they do
+pub fn check_thoroughly(path: &Path, metadata: &fs::Metadata) -> Option<SuspectReason> {
+    if let Some(reason) = check(path, metadata) {
+        return Some(reason);
+    }
+    if fileops::is_placeholder(path, metadata) {
+        return None;
+    }
+    if let Err(message) = read_through(path) {
+        return Some(SuspectReason::Unreadable { message });
+    }
+    // Go by the contents, so a PNG named .jpg isn't held to JPEG's rules
+    let format = infer::get_from_path(fileops::long_path(path))
+        .ok()
+        .flatten()
+        .map(|kind| kind.extension().to_string())
+        .or_else(|| path.extension().map(|e| e.to_string_lossy().to_lowercase()))?;
+    match truncated(path, &format, metadata.len()) {
+        Ok(Ending::Truncated) => Some(SuspectReason::Truncated { format }),
+        Ok(Ending::Unknown) => Some(SuspectReason::PossiblyTruncated { format }),
+        Ok(Ending::Complete) => None,
+        Err(message) => Some(SuspectReason::Unreadable { message }),
+    }
+}
+
+// How a file ends, as far as its format tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ending {
+    Complete,
+    Truncated,
+    /// The end marker is missing from the end but appears earlier, e.g. in
+    /// an embedded thumbnail or before data another app appended
+    Unknown,
+}
+
+impl Ending {
+    fn from_truncated(truncated: bool) -> Self {
+        if truncated {
+            Ending::Truncated
+        } else {
+            Ending::Complete
+        }
+    }
+}
+
+// Read every byte; bad sectors show up as read errors
+fn read_through(path: &Path) -> Result<(), String> {
+    let mut file = File::open(fileops::long_path(path)).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        throttle::consume(IoClass::Hashing, read);
+    }
+}
+
+// Whether a file of a format we know ends before its data does. Formats
+// without an end marker or size field are never reported. A missing end
+// marker only counts as truncation when the marker appears nowhere in the
+// file; phones append data after it and thumbnails carry their own.
+fn truncated(path: &Path, format: &str, len: u64) -> Result<Ending, String> {
+    let mut file = File::open(fileops::long_path(path)).map_err(|e| format!("Failed to open file: {}", e))?;
+    let marker: &[u8] = match format {
+        "jpg" | "jpeg" => &[0xFF, 0xD9],
+        "png" => b"IEND",
+        // A one-byte trailer turns up anywhere, so there is nothing to search for
+        "gif" => {
+            return Ok(if trimmed_tail(&mut file, len)?.ends_with(&[0x3B]) {
+                Ending::Complete
+            } else {
+                Ending::Unknown
+            })
+        }
+        "webp" | "avi" | "wav" => return riff_truncated(&mut file, len).map(Ending::from_truncated),
+        "mp4" | "m4v" | "m4a" | "mov" | "3gp" | "heic" | "heif" | "avif" => {
+            return boxes_truncated(&mut file, len).map(Ending::from_truncated)
+        }
+        _ => return Ok(Ending::Complete),
+    };
+    // Cameras sometimes pad after the end marker, so trailing zeros are
+    // skipped; a PNG ends with the IEND chunk's type and 4-byte CRC
+    let ends_with_marker = match format {
+        "png" => tail(&mut file, len, 8)?.starts_with(marker),
+        _ => trimmed_tail(&mut file, len)?.ends_with(marker),
+    };
+    if ends_with_marker {
+        Ok(Ending::Complete)
+    } else if contains(&mut file, marker)? {
+        Ok(Ending::Unknown)
+    } else {
+        Ok(Ending::Truncated)
+    }
+}
+
+// Whether `pattern` occurs anywhere in the file
+fn contains(file: &mut File, pattern: &[u8]) -> Result<bool, String> {
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    // Bytes carried over so a pattern split between reads is still found
+    let mut carried = 0;
+    loop {
+        let read = file
+            .read(&mut buffer[carried..])
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            return Ok(false);
+        }
+        throttle::consume(IoClass::Hashing, read);
+        let filled = carried + read;
+        if buffer[..filled].windows(pattern.len()).any(|window| window == pattern) {
+            return Ok(true);
+        }
+        carried = (pattern.len() - 1).min(filled);
+        buffer.copy_within(filled - carried..filled, 0);
+    }
+}
+
+fn tail(file: &mut File, len: u64, count: u64) -> Result<Vec<u8>, String> {
+    let count = count.min(len);
+    file.seek(SeekFrom::Start(len - count))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut bytes = vec![0u8; count as usize];
+    file.read_exact(&mut bytes).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(bytes)
+}
+
+// The last 4 KB without trailing zero padding
+fn trimmed_tail(file: &mut File, len: u64) -> Result<Vec<u8>, String> {
+    let mut bytes = tail(file, len, 4096)?;
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    Ok(bytes)
+}
+
+// RIFF files state their size right after the "RIFF" tag
+fn riff_truncated(file: &mut File, len: u64) -> Result<bool, String> {
+    let mut header = [0u8; 8];
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_exact(&mut header))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    if &header[..4] != b"RIFF" {
+        return Ok(false);
+    }
+    let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+    Ok(size + 8 > len)
+}
+
+// ISO base media files (MP4, MOV, HEIF) are a chain of sized boxes; the
+// chain has to fit the file exactly
+fn boxes_truncated(file: &mut File, len: u64) -> Result<bool, String> {
+    const MAX_BOXES: usize = 10_000;
+
+    let mut offset = 0u64;
+    for _ in 0..MAX_BOXES {
+        if offset == len {
+            return Ok(false);
+        }
+        if len - offset < 8 {
+            return Ok(true);
+        }
+        let mut header = [0u8; 16];
+        let available = (len - offset).min(16) as usize;
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut header[..available]))
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64 {
+            // Box runs to the end of the file
+            0 => return Ok(false),
+            // 64-bit size follows the type
+            1 if available == 16 => u64::from_be_bytes(header[8..16].try_into().unwrap_or_default()),
+            1 => return Ok(true),
+            size => size,
+        };
+        if size < 8 {
+            // Not a box chain after all; nothing to judge by
+            return Ok(false);
+        }
+        offset = match offset.checked_add(size) {
+            Some(next) if next <= len => next,
+            _ => return Ok(true),
+        };
+    }
+    Ok(false)
+}
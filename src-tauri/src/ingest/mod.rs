@@ -0,0 +1,192 @@
+// ============================================================================
+// Ingest Module - Options and file naming for imports from cameras and drives
+// ============================================================================
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::files::get_file_type;
+use crate::dates;
+use crate::fileops::CollisionPolicy;
+use crate::photos;
+use crate::storage;
+
+/// Naming used by camera ingests: one folder per shooting day, files
+/// prefixed with the capture time
+pub const PHOTO_NAMING_TEMPLATE: &str = "{yyyy}/{yyyy}-{mm}-{dd}/{yyyy}{mm}{dd}_{hh}{min}{ss}_{name}";
+
+const PENDING_INGEST_KEY: &str = "pending_camera_ingest";
+
+// How an import copies files into the library
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Destination path relative to the import folder, e.g.
+    /// "{yyyy}/{yyyy}-{mm}-{dd}_{name}"; None keeps the source layout
+    pub rename_template: Option<String>,
+    pub recursive: bool,
+    /// Skip files whose content is already somewhere in the library
+    pub skip_duplicates: bool,
+    pub collision: CollisionPolicy,
+    /// Re-hash each copy and compare it with the source
+    pub verify: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            rename_template: None,
+            recursive: true,
+            skip_duplicates: true,
+            collision: CollisionPolicy::RenameWithSuffix,
+            verify: false,
+        }
+    }
+}
+
+/// Validate a rename template before starting an import
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in template: {}", template))?;
+        let token = &rest[open + 1..open + close];
+        if !is_known_token(token) {
+            return Err(format!("Unknown placeholder {{{}}} in template", token));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    if Path::new(template).is_absolute() || template.split(['/', '\\']).any(|part| part == "..") {
+        return Err("Template must stay inside the destination folder".to_string());
+    }
+    Ok(())
+}
+
+fn is_known_token(token: &str) -> bool {
+    matches!(
+        token,
+        "name" | "stem" | "ext" | "type" | "yyyy" | "mm" | "month" | "dd" | "hh" | "min" | "ss" | "counter"
+    )
+}
+
+/// Expand a template for one file captured (or last modified) at `taken`.
+/// `counter` is the file's 1-based position in the import.
+pub fn render_template(template: &str, source: &Path, taken: NaiveDateTime, counter: usize) -> PathBuf {
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = source
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // The configured month name, or the number when names are off
+    let month = template
+        .contains("{month}")
+        .then(|| dates::load_settings().month_names)
+        .flatten()
+        .and_then(|language| dates::month_name(taken.month(), &language))
+        .map(str::to_string)
+        .unwrap_or_else(|| taken.format("%m").to_string());
+
+    let mut rendered = template
+        .replace("{name}", &name)
+        .replace("{stem}", &stem)
+        .replace("{ext}", &extension)
+        .replace("{type}", &get_file_type(&extension))
+        .replace("{yyyy}", &taken.format("%Y").to_string())
+        .replace("{mm}", &taken.format("%m").to_string())
+        .replace("{month}", &month)
+        .replace("{dd}", &taken.format("%d").to_string())
+        .replace("{hh}", &taken.format("%H").to_string())
+        .replace("{min}", &taken.format("%M").to_string())
+        .replace("{ss}", &taken.format("%S").to_string())
+        .replace("{counter}", &format!("{:04}", counter));
+
+    // A template without the extension would produce files the OS can't open
+    if !extension.is_empty() && !template.contains("{name}") && !template.contains("{ext}") {
+        rendered.push('.');
+        rendered.push_str(&extension);
+    }
+
+    PathBuf::from(rendered)
+}
+
+/// When a file was captured: EXIF DateTimeOriginal for photos, otherwise the
+/// modification time in local time
+pub fn capture_time(path: &Path, metadata: &fs::Metadata) -> NaiveDateTime {
+    exif_capture_time(path).unwrap_or_else(|| {
+        metadata
+            .modified()
+            .map(dates::local_time)
+            .unwrap_or_else(|_| Local::now().naive_local())
+    })
+}
+
+fn exif_capture_time(path: &Path) -> Option<NaiveDateTime> {
+    let exif = photos::read_exif(path)?;
+    let field = exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+    let exif::Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let taken = exif::DateTime::from_ascii(values.first()?).ok()?;
+
+    NaiveDate::from_ymd_opt(taken.year as i32, taken.month as u32, taken.day as u32)?.and_hms_opt(
+        taken.hour as u32,
+        taken.minute as u32,
+        taken.second as u32,
+    )
+}
+
+// ============================================================================
+// Camera cards
+// ============================================================================
+
+/// The DCIM folder of a camera card or phone mounted at `root`
+pub fn find_dcim(root: &Path) -> Option<PathBuf> {
+    let entries = fs::read_dir(root).ok()?;
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case("DCIM"))
+        })
+}
+
+// An ingest that started but did not finish, e.g. because the card was removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingIngest {
+    pub card_root: String,
+    pub library_root: String,
+    pub batch_id: String,
+    pub started_at: String,
+}
+
+pub fn load_pending_ingest() -> Option<PendingIngest> {
+    storage::get_preference(PENDING_INGEST_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+}
+
+pub fn save_pending_ingest(pending: &PendingIngest) -> Result<(), String> {
+    let value = serde_json::to_string(pending).map_err(|e| e.to_string())?;
+    storage::set_preference(PENDING_INGEST_KEY, &value)
+}
+
+pub fn clear_pending_ingest() -> Result<(), String> {
+    storage::set_preference(PENDING_INGEST_KEY, "null")
+}
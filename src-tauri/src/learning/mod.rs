@@ -0,0 +1,266 @@
+// ============================================================================
+// Learning Module - Suggesting rules from moves the user makes by hand
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use crate::events::FsChange;
+use crate::fileops;
+use crate::rules::{FileFacts, PatternKind, Rule, RuleSet};
+use crate::storage;
+
+/// Event emitted when repeated manual moves produce a new rule suggestion
+pub const RULE_SUGGESTED_EVENT: &str = "rule-suggested";
+
+/// Moves of one extension into one folder before a rule is suggested
+const MIN_MOVES_FOR_SUGGESTION: i64 = 5;
+
+/// How far back the change log is checked for moves the app made itself
+const LOGGED_MOVE_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionStatus {
+    Pending,
+    Accepted,
+    /// Never suggested again, however many more moves are seen
+    Dismissed,
+}
+
+impl SuggestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionStatus::Pending => "pending",
+            SuggestionStatus::Accepted => "accepted",
+            SuggestionStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "accepted" => SuggestionStatus::Accepted,
+            "dismissed" => SuggestionStatus::Dismissed,
+            _ => SuggestionStatus::Pending,
+        }
+    }
+}
+
+// A file the user moved to another folder, as seen by the watcher
+#[derive(Debug, Clone)]
+pub struct ObservedMove {
+    pub path: String,
+    pub old_path: String,
+    /// Lowercase, without the dot
+    pub extension: String,
+    pub destination_folder: String,
+}
+
+// A rule proposed from repeated manual moves of one extension into one folder
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSuggestion {
+    pub id: String,
+    pub extension: String,
+    pub destination_folder: String,
+    /// Distinct files moved there so far
+    pub move_count: i64,
+    pub status: SuggestionStatus,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl RuleSuggestion {
+    /// e.g. "You've moved 14 .sketch files to Design/ — create a rule?"
+    pub fn message(&self) -> String {
+        let folder = Path::new(&self.destination_folder)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.destination_folder.clone());
+        format!(
+            "You've moved {} .{} files to {}/ — create a rule?",
+            self.move_count, self.extension, folder
+        )
+    }
+
+    /// The rule the suggestion stands for
+    pub fn to_rule(&self) -> Rule {
+        Rule {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: format!("Move .{} files to {}", self.extension, self.destination_folder),
+            description: Some(format!("Learned from {} manual moves", self.move_count)),
+            pattern: format!("*.{}", self.extension),
+            pattern_kind: PatternKind::Glob,
+            destination: self.destination_folder.clone(),
+            priority: 0,
+            is_active: true,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            hit_count: 0,
+            last_matched_at: None,
+            conditions: None,
+        }
+    }
+}
+
+// Payload of the "rule-suggested" event and entry of list_rule_suggestions
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSuggestionSummary {
+    #[serde(flatten)]
+    pub suggestion: RuleSuggestion,
+    pub message: String,
+}
+
+impl From<RuleSuggestion> for RuleSuggestionSummary {
+    fn from(suggestion: RuleSuggestion) -> Self {
+        Self {
+            message: suggestion.message(),
+            suggestion,
+        }
+    }
+}
+
+/// Record the manual moves in a batch of watcher changes and suggest a rule
+/// for every extension and folder that has now seen enough of them
+pub fn observe(app: &AppHandle, changes: &[FsChange]) {
+    let moves = manual_moves(changes);
+    if moves.is_empty() {
+        return;
+    }
+    match record(&moves) {
+        Ok(suggestions) => {
+            for suggestion in suggestions {
+                let _ = app.emit(RULE_SUGGESTED_EVENT, RuleSuggestionSummary::from(suggestion));
+            }
+        }
+        Err(e) => eprintln!("Learning: {}", e),
+    }
+}
+
+// Renames into another folder, minus moves the app logged in a history
+// batch and moves an active rule would have made: those are the app
+// applying a plan, not the user
+fn manual_moves(changes: &[FsChange]) -> Vec<ObservedMove> {
+    let renames: Vec<(&Path, &Path)> = changes
+        .iter()
+        .filter_map(|change| Some((Path::new(change.old_path.as_deref()?), Path::new(&change.path))))
+        .filter(|(old_path, path)| old_path.parent() != path.parent())
+        .collect();
+    if renames.is_empty() {
+        return Vec::new();
+    }
+    let logged = match storage::recent_logged_moves(LOGGED_MOVE_MINUTES) {
+        Ok(logged) => logged,
+        Err(e) => {
+            eprintln!("Learning: {}", e);
+            return Vec::new();
+        }
+    };
+    let renames: Vec<(&Path, &Path)> = renames
+        .into_iter()
+        .filter(|(old_path, path)| {
+            !logged.contains(&(old_path.to_string_lossy().to_string(), path.to_string_lossy().to_string()))
+        })
+        .collect();
+    if renames.is_empty() {
+        return Vec::new();
+    }
+    let rule_set = match RuleSet::load_active() {
+        Ok(rule_set) => rule_set,
+        Err(e) => {
+            eprintln!("Learning: {}", e);
+            return Vec::new();
+        }
+    };
+
+    renames
+        .into_iter()
+        .filter_map(|(old_path, path)| {
+            let extension = path.extension()?.to_string_lossy().to_lowercase();
+            let destination_folder = path.parent()?.to_string_lossy().to_string();
+            if !fileops::long_path(path).is_file() {
+                return None;
+            }
+            let mut facts = FileFacts::from_path(path);
+            facts.path = old_path.to_path_buf();
+            let destination = rule_set.decide(&facts).destination;
+            if destination.as_deref() == Some(&*path.to_string_lossy()) {
+                return None;
+            }
+            Some(ObservedMove {
+                path: path.to_string_lossy().to_string(),
+                old_path: old_path.to_string_lossy().to_string(),
+                extension,
+                destination_folder,
+            })
+        })
+        .collect()
+}
+
+// Store the moves and return suggestions that crossed the threshold just now
+fn record(moves: &[ObservedMove]) -> Result<Vec<RuleSuggestion>, String> {
+    storage::record_observed_moves(moves)?;
+
+    let targets: BTreeSet<(&str, &str)> = moves
+        .iter()
+        .map(|m| (m.extension.as_str(), m.destination_folder.as_str()))
+        .collect();
+    let mut created = Vec::new();
+    for (extension, folder) in targets {
+        let move_count = storage::observed_move_count(extension, folder)?;
+        if move_count < MIN_MOVES_FOR_SUGGESTION {
+            continue;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        match storage::get_rule_suggestion(extension, folder)? {
+            Some(mut suggestion) => {
+                // Accepted and dismissed suggestions are settled
+                if suggestion.status == SuggestionStatus::Pending {
+                    suggestion.move_count = move_count;
+                    suggestion.updated_at = now;
+                    storage::save_rule_suggestion(&suggestion)?;
+                }
+            }
+            None => {
+                let suggestion = RuleSuggestion {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    extension: extension.to_string(),
+                    destination_folder: folder.to_string(),
+                    move_count,
+                    status: SuggestionStatus::Pending,
+                    created_at: now.clone(),
+                    updated_at: now,
+                };
+                storage::save_rule_suggestion(&suggestion)?;
+                created.push(suggestion);
+            }
+        }
+    }
+    Ok(created)
+}
+
+/// Save the suggested rule and mark the suggestion accepted
+pub fn accept(id: &str) -> Result<Rule, String> {
+    let mut suggestion = pending(id)?;
+    let rule = suggestion.to_rule();
+    storage::save_rule(&rule)?;
+    suggestion.status = SuggestionStatus::Accepted;
+    suggestion.updated_at = chrono::Utc::now().to_rfc3339();
+    storage::save_rule_suggestion(&suggestion)?;
+    Ok(rule)
+}
+
+/// Stop suggesting this rule
+pub fn dismiss(id: &str) -> Result<(), String> {
+    let mut suggestion = pending(id)?;
+    suggestion.status = SuggestionStatus::Dismissed;
+    suggestion.updated_at = chrono::Utc::now().to_rfc3339();
+    storage::save_rule_suggestion(&suggestion)
+}
+
+fn pending(id: &str) -> Result<RuleSuggestion, String> {
+    storage::list_rule_suggestions(Some(SuggestionStatus::Pending))?
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("No pending rule suggestion: {}", id))
+}
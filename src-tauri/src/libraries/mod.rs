@@ -0,0 +1,267 @@
+// ============================================================================
+// Libraries Module - Named roots on the devices the user keeps files on
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::fileops;
+use crate::storage;
+
+const LIBRARIES_KEY: &str = "libraries";
+
+// A folder tree on one device, e.g. "Laptop" at ~/Pictures or "NAS" at
+// \\nas\photos. Its indexed files stay known while the device is away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Library {
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    pub added_at: String,
+    #[serde(default)]
+    pub policy: IndexPolicy,
+}
+
+// How far indexing goes inside a library; each level includes the ones
+// before it
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexDepth {
+    /// Names, sizes and dates from the scan, nothing read from the files
+    NamesOnly,
+    /// Content hashes, for duplicates and change detection
+    Hashes,
+    /// Text of documents and tags of audio and video files
+    Content,
+    /// Vectors for semantic search
+    #[default]
+    Embeddings,
+}
+
+// What indexing may do with the files of one library, e.g. names only for
+// a huge media NAS and everything for Documents
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct IndexPolicy {
+    pub depth: IndexDepth,
+    /// Files larger than this are left out of content extraction
+    pub max_content_size: Option<u64>,
+}
+
+impl IndexPolicy {
+    /// Whether a pass at `depth` may run
+    pub fn allows(&self, depth: IndexDepth) -> bool {
+        self.depth >= depth
+    }
+
+    /// Whether text or tags may be read from a file of `size` bytes
+    pub fn allows_content(&self, size: u64) -> bool {
+        self.allows(IndexDepth::Content) && self.max_content_size.map_or(true, |max| size <= max)
+    }
+
+    /// Whether a file of `size` bytes may be embedded; the content size
+    /// limit applies, since embedding reads the file's text
+    pub fn allows_embeddings(&self, size: u64) -> bool {
+        self.allows(IndexDepth::Embeddings) && self.allows_content(size)
+    }
+}
+
+impl Library {
+    /// Whether the device is connected and the root reachable
+    pub fn is_online(&self) -> bool {
+        fileops::long_path(Path::new(&self.root)).is_dir()
+    }
+}
+
+/// Registered libraries, in the order they were added
+pub fn list() -> Vec<Library> {
+    let Some(json) = storage::get_preference(LIBRARIES_KEY).ok().flatten() else {
+        return Vec::new();
+    };
+    let mut saved: serde_json::Value = match serde_json::from_str(&json) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("Failed to read libraries: {}", e);
+            return Vec::new();
+        }
+    };
+    let migrated = migrate_ocr_depth(&mut saved);
+    let libraries: Vec<Library> = match serde_json::from_value(saved) {
+        Ok(libraries) => libraries,
+        Err(e) => {
+            eprintln!("Failed to read libraries: {}", e);
+            return Vec::new();
+        }
+    };
+    for name in &migrated {
+        eprintln!(
+            "Library \"{}\" was set to OCR indexing, which isn't available; it now goes as far as embeddings",
+            name
+        );
+    }
+    if !migrated.is_empty() {
+        if let Err(e) = save(&libraries) {
+            eprintln!("Failed to save migrated libraries: {}", e);
+        }
+    }
+    libraries
+}
+
+// Early builds offered an "ocr" depth that never did anything; policies
+// saved with it are moved to the deepest level there is. Returns the names
+// of the libraries changed.
+fn migrate_ocr_depth(saved: &mut serde_json::Value) -> Vec<String> {
+    let mut migrated = Vec::new();
+    for library in saved.as_array_mut().into_iter().flatten() {
+        let Some(depth) = library.pointer_mut("/policy/depth") else {
+            continue;
+        };
+        if *depth == "ocr" {
+            *depth = serde_json::json!("embeddings");
+            migrated.push(library["name"].as_str().unwrap_or_default().to_string());
+        }
+    }
+    migrated
+}
+
+fn save(libraries: &[Library]) -> Result<(), String> {
+    let json = serde_json::to_string(libraries).map_err(|e| format!("Failed to serialize libraries: {}", e))?;
+    storage::set_preference(LIBRARIES_KEY, &json)
+}
+
+/// Register `root` under `name`; roots may nest, and the innermost wins
+pub fn add(name: &str, root: &Path) -> Result<Library, String> {
+    if name.trim().is_empty() {
+        return Err("Library needs a name".to_string());
+    }
+    if !fileops::long_path(root).is_dir() {
+        return Err(format!("Path is not a directory: {}", root.display()));
+    }
+    let mut libraries = list();
+    let root = root.to_string_lossy().to_string();
+    if libraries.iter().any(|library| library.root == root) {
+        return Err(format!("Already a library: {}", root));
+    }
+
+    let library = Library {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        root,
+        added_at: chrono::Utc::now().to_rfc3339(),
+        policy: IndexPolicy::default(),
+    };
+    libraries.push(library.clone());
+    save(&libraries)?;
+    Ok(library)
+}
+
+/// Forget a library; its files stay in the index
+pub fn remove(id: &str) -> Result<bool, String> {
+    let mut libraries = list();
+    let before = libraries.len();
+    libraries.retain(|library| library.id != id);
+    if libraries.len() == before {
+        return Ok(false);
+    }
+    save(&libraries)?;
+    Ok(true)
+}
+
+/// Change how deeply a library is indexed. Data already gathered beyond
+/// the new depth is kept, but no longer refreshed.
+pub fn set_policy(id: &str, policy: IndexPolicy) -> Result<Library, String> {
+    let mut libraries = list();
+    let library = libraries
+        .iter_mut()
+        .find(|library| library.id == id)
+        .ok_or_else(|| format!("No library with id {}", id))?;
+    library.policy = policy;
+    let updated = library.clone();
+    save(&libraries)?;
+    Ok(updated)
+}
+
+/// The policy of the innermost library containing `path`; files outside
+/// every library are indexed fully
+pub fn policy_for(path: &Path, libraries: &[Library]) -> IndexPolicy {
+    library_of(path, libraries)
+        .map(|library| library.policy)
+        .unwrap_or_default()
+}
+
+/// The innermost library containing `path`
+pub fn library_of<'a>(path: &Path, libraries: &'a [Library]) -> Option<&'a Library> {
+    libraries
+        .iter()
+        .filter(|library| path.starts_with(PathBuf::from(&library.root)))
+        .max_by_key(|library| Path::new(&library.root).components().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(root: &str, depth: IndexDepth) -> Library {
+        Library {
+            id: root.to_string(),
+            name: root.to_string(),
+            root: root.to_string(),
+            added_at: String::new(),
+            policy: IndexPolicy {
+                depth,
+                max_content_size: None,
+            },
+        }
+    }
+
+    #[test]
+    fn policy_for_uses_the_innermost_library() {
+        let libraries = [library("/nas", IndexDepth::NamesOnly), library("/nas/docs", IndexDepth::Content)];
+        assert_eq!(policy_for(Path::new("/nas/movies/a.mkv"), &libraries).depth, IndexDepth::NamesOnly);
+        assert_eq!(policy_for(Path::new("/nas/docs/a.pdf"), &libraries).depth, IndexDepth::Content);
+    }
+
+    #[test]
+    fn policy_for_indexes_files_outside_libraries_fully() {
+        let libraries = [library("/nas", IndexDepth::NamesOnly)];
+        let policy = policy_for(Path::new("/nasty/a.txt"), &libraries);
+        assert_eq!(policy.depth, IndexDepth::Embeddings);
+        assert!(policy.allows_embeddings(u64::MAX));
+    }
+
+    #[test]
+    fn content_size_limit_applies_to_content_and_embeddings() {
+        let policy = IndexPolicy {
+            depth: IndexDepth::Embeddings,
+            max_content_size: Some(100),
+        };
+        assert!(policy.allows(IndexDepth::Hashes));
+        assert!(policy.allows_content(100));
+        assert!(!policy.allows_content(101));
+        assert!(!policy.allows_embeddings(101));
+
+        let hashes_only = IndexPolicy {
+            depth: IndexDepth::Hashes,
+            max_content_size: None,
+        };
+        assert!(!hashes_only.allows_content(1));
+    }
+
+    #[test]
+    fn ocr_depth_is_rejected() {
+        assert!(serde_json::from_str::<IndexPolicy>(r#"{"depth":"ocr"}"#).is_err());
+    }
+
+    #[test]
+    fn saved_ocr_depth_is_migrated_to_embeddings() {
+        let mut saved = serde_json::json!([
+            {"id": "1", "name": "Scans", "root": "/scans", "added_at": "", "policy": {"depth": "ocr"}},
+            {"id": "2", "name": "NAS", "root": "/nas", "added_at": "", "policy": {"depth": "names_only"}},
+            {"id": "3", "name": "Old", "root": "/old", "added_at": ""}
+        ]);
+        assert_eq!(migrate_ocr_depth(&mut saved), vec!["Scans".to_string()]);
+        let libraries: Vec<Library> = serde_json::from_value(saved).unwrap();
+        assert_eq!(libraries[0].policy.depth, IndexDepth::Embeddings);
+        assert_eq!(libraries[1].policy.depth, IndexDepth::NamesOnly);
+    }
+}
@@ -7,43 +7,240 @@
     windows_subsystem = "windows"
 )]
 
+mod archive;
+mod backups;
 mod commands;
+mod content;
+mod dates;
+mod digest;
+mod embeddings;
+mod events;
+mod fileops;
+mod folder_style;
+mod guided;
+mod headless;
+mod health;
+mod ingest;
+mod learning;
+mod libraries;
+mod media;
+mod names;
+mod network;
+mod packs;
+mod permissions;
+mod photos;
+mod projects;
+mod rules;
+mod scan;
+mod screenshot;
+mod search;
+mod shutdown;
+mod similar_names;
+mod staging;
+mod startup;
 mod storage;
+mod storage_sense;
+mod summary;
+mod thumbnails;
+mod throttle;
+mod vector_index;
+mod volumes;
+mod watcher;
+mod xattrs;
 
 use tauri::Manager;
 
 fn main() {
+    if let Some(code) = headless::run_from_args() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![
+        // Every command is checked against the active workspace's permissions
+        .invoke_handler(permissions::guarded(tauri::generate_handler![
             commands::files::list_files,
             commands::files::get_file_info,
             commands::files::move_file,
             commands::files::create_folder,
+            commands::files::get_ignore_patterns,
+            commands::files::set_ignore_patterns,
+            commands::files::hash_file,
+            commands::files::hash_folder,
+            commands::files::find_similar_names,
+            commands::files::inspect_archive,
+            commands::files::compress_files,
+            commands::files::extract_archive,
+            commands::files::reveal_in_explorer,
+            commands::files::open_file,
+            commands::files::set_file_comment,
+            commands::files::get_file_comment,
+            commands::files::get_file_tags,
+            commands::files::set_file_tags,
+            commands::files::set_attributes,
+            commands::files::revert_attributes,
+            commands::files::protect_path,
+            commands::files::unprotect_path,
+            commands::files::list_protected_paths,
+            commands::files::secure_delete,
+            commands::files::find_empty_folders,
+            commands::files::remove_empty_folders,
+            commands::cleanup::find_broken_links,
+            commands::cleanup::fix_broken_links,
+            commands::cleanup::get_cleanup_report,
+            commands::cleanup::get_large_old_files,
+            commands::cleanup::find_stale_files,
+            commands::cleanup::find_suspect_files,
+            commands::cleanup::run_health_scan,
+            commands::cleanup::quarantine_files,
+            commands::cleanup::find_derived_data,
+            commands::cleanup::clean_derived_data,
+            commands::cleanup::find_duplicates,
             commands::organize::generate_plan,
             commands::organize::apply_plan,
+            commands::organize::cancel_apply,
+            commands::organize::create_link_farm,
+            commands::organize::remove_link_farm,
+            commands::organize::apply_copy_plan,
+            commands::organize::summarize_plan,
+            commands::organize::get_date_folder_settings,
+            commands::organize::set_date_folder_settings,
             commands::history::get_history,
             commands::history::undo_batch,
+            commands::history::get_folder_changes,
+            commands::history::mark_folder_viewed,
+            commands::history::reconstruct_tree,
+            commands::ingest::import_files,
+            commands::ingest::detect_camera_card,
+            commands::ingest::ingest_camera_card,
+            commands::ingest::get_pending_ingest,
+            commands::ingest::wipe_camera_card,
+            commands::rules::preview_rule,
+            commands::rules::validate_pattern,
+            commands::rules::list_rules,
+            commands::rules::save_rule,
+            commands::rules::delete_rule,
+            commands::rules::test_rules,
+            commands::rules::list_rule_suggestions,
+            commands::rules::accept_rule_suggestion,
+            commands::rules::dismiss_rule_suggestion,
+            commands::startup::get_startup_status,
+            commands::shutdown::get_running_operations,
+            commands::shutdown::get_interrupted_operations,
+            commands::shutdown::set_wait_on_exit,
+            commands::shutdown::force_exit,
+            commands::staging::list_staged_files,
+            commands::staging::restore_staged_files,
+            commands::staging::purge_staged_files,
+            commands::watcher::start_watching,
+            commands::watcher::stop_watching,
+            commands::watcher::get_watched_paths,
+            commands::watcher::get_duplicate_policies,
+            commands::watcher::set_duplicate_policy,
+            commands::watcher::get_settle_seconds,
+            commands::watcher::set_settle_seconds,
             // AI commands
             commands::ai::check_model_status,
             commands::ai::download_model,
+            commands::ai::assemble_model,
             commands::ai::load_model,
             commands::ai::generate_response,
+            commands::ai::get_model_config,
+            commands::ai::set_model_config,
+            commands::ai::classify_batch,
+            commands::ai::get_ai_usage,
+            commands::content::get_file_text,
+            commands::content::index_contents,
+            commands::digest::generate_digest,
+            commands::digest::list_digests,
+            commands::digest::get_digest_settings,
+            commands::digest::set_digest_settings,
+            commands::disk::analyze_disk_usage,
+            commands::disk::get_index_stats,
+            commands::disk::get_folder_size,
+            commands::disk::compare_folders,
+            commands::disk::mirror_folder,
+            commands::embeddings::index_embeddings,
+            commands::embeddings::embedding_coverage,
+            commands::embeddings::set_embedding_model,
+            commands::folder_style::list_folder_styles,
+            commands::folder_style::save_folder_style,
+            commands::folder_style::delete_folder_style,
+            commands::folder_style::apply_folder_style,
+            commands::guided::start_guided_cleanup,
+            commands::guided::get_guided_cleanup,
+            commands::guided::advance_guided_cleanup,
+            commands::guided::cancel_guided_cleanup,
+            commands::libraries::list_libraries,
+            commands::libraries::add_library,
+            commands::libraries::remove_library,
+            commands::libraries::set_library_policy,
+            commands::media::get_media_metadata,
+            commands::media::index_media,
+            commands::names::find_broken_names,
+            commands::names::repair_names,
+            commands::packs::validate_pack,
+            commands::packs::import_pack,
+            commands::packs::preview_pack,
+            commands::packs::activate_pack,
+            commands::packs::deactivate_pack,
+            commands::packs::list_packs,
+            commands::packs::trust_pack_key,
+            commands::permissions::get_permissions,
+            commands::permissions::set_permissions,
+            commands::permissions::set_active_workspace,
+            commands::permissions::list_command_capabilities,
+            commands::preview::get_thumbnail,
+            commands::preview::get_video_frames,
+            commands::preview::preview_file,
+            commands::search::semantic_search,
+            commands::search::search,
+            commands::search::search_files,
+            commands::search::parse_search_query,
+            commands::search::list_saved_searches,
+            commands::search::save_search,
+            commands::search::delete_saved_search,
+            commands::search::list_smart_folders,
+            commands::search::save_smart_folder,
+            commands::search::delete_smart_folder,
+            commands::search::list_smart_folder,
+            commands::storage_sense::list_os_managed_folders,
+            commands::storage_sense::get_os_cleanup_status,
+            commands::throttle::get_io_limits,
+            commands::throttle::set_io_limits,
+            commands::volumes::list_volumes,
+            commands::volumes::list_volume_catalog,
+            commands::volumes::forget_volume,
             commands::ai::init_ai,
-        ])
+        ]))
         .setup(|app| {
-            // Initialize database
             let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
             std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
 
-            let db_path = app_data_dir.join("smart_storage.db");
-            storage::init_database(&db_path).expect("Failed to initialize database");
-
-            println!("Smart Storage AI initialized");
-            println!("Database: {:?}", db_path);
+            // Everything slow happens after the window is up; the frontend
+            // follows along through "startup-status" events
+            startup::begin(app.handle().clone(), app_data_dir.join("smart_storage.db"));
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Give running work a chance to finish or pause before exiting;
+            // the window stays open to show what it is waiting for
+            tauri::RunEvent::WindowEvent {
+                event: tauri::WindowEvent::CloseRequested { api, .. },
+                ..
+            } => {
+                if !shutdown::request_exit(app) {
+                    api.prevent_close();
+                }
+            }
+            tauri::RunEvent::ExitRequested { api, .. } => {
+                if !shutdown::request_exit(app) {
+                    api.prevent_exit();
+                }
+            }
+            _ => {}
+        });
 }
@@ -0,0 +1,176 @@
+// ============================================================================
+// Media Module - Audio/video tags and stream properties
+// ============================================================================
+
+use lofty::prelude::*;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process::Command;
+
+use crate::fileops;
+
+/// Frames ffmpeg's thumbnail filter compares to pick the most typical one,
+/// so a fade from black or a blurred first second isn't chosen
+const THUMBNAIL_FRAMES: u32 = 100;
+
+/// How much a frame must differ from the last to count as a new scene
+const SCENE_THRESHOLD: f64 = 0.3;
+
+// Whether an ffmpeg binary is on the PATH; looked up once per run
+static FFMPEG: Lazy<bool> = Lazy::new(|| {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+});
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MediaMetadata {
+    pub path: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub duration_ms: Option<u64>,
+    pub bitrate_kbps: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Whether a file type carries media metadata we can read
+pub fn is_media_type(file_type: &str) -> bool {
+    matches!(file_type, "audio" | "video")
+}
+
+/// Read tags (ID3, Vorbis comments, MP4 atoms, ...) and stream properties
+pub fn extract(path: &Path) -> Result<MediaMetadata, String> {
+    fileops::ensure_local(path)?;
+    let mut metadata = MediaMetadata {
+        path: path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    // lofty covers audio containers and the audio side of MP4/MOV
+    if let Ok(tagged_file) = lofty::read_from_path(path) {
+        let properties = tagged_file.properties();
+        let duration = properties.duration();
+        if !duration.is_zero() {
+            metadata.duration_ms = Some(duration.as_millis() as u64);
+        }
+        metadata.bitrate_kbps = properties.overall_bitrate().or(properties.audio_bitrate());
+        metadata.sample_rate = properties.sample_rate();
+        metadata.channels = properties.channels();
+
+        if let Some(tag) = tagged_file.primary_tag().or_else(|| tagged_file.first_tag()) {
+            metadata.title = tag.title().map(|v| v.to_string());
+            metadata.artist = tag.artist().map(|v| v.to_string());
+            metadata.album = tag.album().map(|v| v.to_string());
+            metadata.genre = tag.genre().map(|v| v.to_string());
+            metadata.year = tag.year();
+            metadata.track = tag.track();
+        }
+    }
+
+    // Video resolution from the MP4/MOV track headers
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if matches!(extension.as_str(), "mp4" | "m4v" | "mov") {
+        if let Some((width, height)) = mp4_resolution(path) {
+            metadata.width = Some(width);
+            metadata.height = Some(height);
+        }
+    }
+
+    Ok(metadata)
+}
+
+fn mp4_resolution(path: &Path) -> Option<(u32, u32)> {
+    let file = File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    let reader = mp4::Mp4Reader::read_header(BufReader::new(file), size).ok()?;
+
+    reader
+        .tracks()
+        .values()
+        .find(|track| matches!(track.track_type(), Ok(mp4::TrackType::Video)))
+        .map(|track| (track.width() as u32, track.height() as u32))
+}
+
+/// Whether video frames can be extracted; needs ffmpeg installed
+pub fn can_extract_frames() -> bool {
+    *FFMPEG
+}
+
+/// Write a representative frame of the video at `path` to `target` as PNG,
+/// scaled to fit in `size` x `size`. The frame is picked from the stretch
+/// after the first tenth of the video, which skips intros and title cards.
+pub fn video_frame(path: &Path, target: &Path, size: u32) -> Result<(), String> {
+    let start = extract(path)
+        .ok()
+        .and_then(|metadata| metadata.duration_ms)
+        .map_or(0.0, |ms| ms as f64 / 1000.0 / 10.0);
+    run_ffmpeg(
+        ffmpeg()
+            .args(["-ss", &format!("{:.2}", start)])
+            .arg("-i")
+            .arg(path)
+            .args(["-vf", &format!("thumbnail={},{}", THUMBNAIL_FRAMES, scale_filter(size))])
+            .args(["-frames:v", "1"])
+            .arg(target),
+    )
+}
+
+/// Write the first frame of up to `count` scenes of the video at `path`, as
+/// PNGs named after `pattern` with "%d" numbering them from 1. Screen
+/// recordings rarely cut, so they yield one or two frames where holiday
+/// clips yield several.
+pub fn scene_frames(path: &Path, pattern: &Path, count: u32, size: u32) -> Result<(), String> {
+    run_ffmpeg(
+        ffmpeg()
+            .arg("-i")
+            .arg(path)
+            .args([
+                "-vf",
+                &format!("select='eq(n,0)+gt(scene,{})',{}", SCENE_THRESHOLD, scale_filter(size)),
+            ])
+            .args(["-vsync", "vfr", "-frames:v", &count.to_string()])
+            .arg(pattern),
+    )
+}
+
+fn scale_filter(size: u32) -> String {
+    format!("scale={0}:{0}:force_original_aspect_ratio=decrease", size)
+}
+
+// ffmpeg quiet except for errors, overwriting its output; options after the
+// output file would be ignored, so these go first
+fn ffmpeg() -> Command {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-hide_banner", "-loglevel", "error", "-y"]);
+    command
+}
+
+fn run_ffmpeg(command: &mut Command) -> Result<(), String> {
+    if !can_extract_frames() {
+        return Err("ffmpeg is not installed".to_string());
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ffmpeg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
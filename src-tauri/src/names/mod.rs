@@ -0,0 +1,224 @@
+// ============================================================================
+// Names Module - Repairing mis-encoded file names and folding them to ASCII
+// ============================================================================
+
+use std::ffi::OsStr;
+
+// Latin Extended-A (U+0100 to U+017F) without accents; the ligatures at
+// U+0132/3 and U+0152/3 are expanded separately
+const LATIN_EXTENDED_A: &str =
+    "AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIiIiJjKkkLlLlLlLlLlNnNnNnnNnOoOoOoOoRrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs";
+
+// Russian Cyrillic (U+0410 to U+042F, then U+0430 to U+044F) in a simple
+// romanization
+const CYRILLIC_UPPERCASE: [&str; 32] = [
+    "A", "B", "V", "G", "D", "E", "Zh", "Z", "I", "Y", "K", "L", "M", "N", "O", "P", "R", "S", "T",
+    "U", "F", "Kh", "Ts", "Ch", "Sh", "Shch", "", "Y", "", "E", "Yu", "Ya",
+];
+const CYRILLIC_LOWERCASE: [&str; 32] = [
+    "a", "b", "v", "g", "d", "e", "zh", "z", "i", "y", "k", "l", "m", "n", "o", "p", "r", "s", "t",
+    "u", "f", "kh", "ts", "ch", "sh", "shch", "", "y", "", "e", "yu", "ya",
+];
+
+/// Characters Windows, FAT and exFAT refuse in names; the other file
+/// systems accept everything but '/'
+pub const RESERVED_CHARACTERS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+const UTF8_AS_CP1252: &str = "UTF-8 read as Windows-1252";
+
+/// How many times a name is repaired; names copied through two systems
+/// that each misread them are mangled twice
+const MAX_REPAIR_ROUNDS: usize = 2;
+
+/// A name that decodes to something better, and how it was mangled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub name: String,
+    pub encoding: String,
+}
+
+/// The name `name` was meant to have, if it is mis-encoded: bytes in a
+/// legacy encoding that aren't UTF-8 at all (guessed with chardetng), or
+/// UTF-8 that was read as Windows-1252 and saved again ("CafÃ©" for "Café")
+pub fn repair(name: &OsStr) -> Option<Repair> {
+    let (mut current, mut encoding) = match name.to_str() {
+        Some(name) => (name.to_string(), None),
+        None => {
+            let decoded = decode_legacy(name)?;
+            (decoded.name, Some(decoded.encoding))
+        }
+    };
+    for _ in 0..MAX_REPAIR_ROUNDS {
+        let Some(repaired) = repair_utf8_as_cp1252(&current) else {
+            break;
+        };
+        current = repaired;
+        encoding.get_or_insert_with(|| UTF8_AS_CP1252.to_string());
+    }
+    encoding.map(|encoding| Repair {
+        name: current,
+        encoding,
+    })
+}
+
+// Raw bytes that aren't UTF-8: names written by older systems in their
+// code page, kept byte for byte by Unix file systems
+#[cfg(unix)]
+fn decode_legacy(name: &OsStr) -> Option<Repair> {
+    use std::os::unix::ffi::OsStrExt;
+    let bytes = name.as_bytes();
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (!had_errors && !decoded.contains(RESERVED_CHARACTERS)).then(|| Repair {
+        name: decoded.to_string(),
+        encoding: encoding.name().to_string(),
+    })
+}
+
+// Windows names are UTF-16, so there are no stray bytes to decode
+#[cfg(not(unix))]
+fn decode_legacy(_name: &OsStr) -> Option<Repair> {
+    None
+}
+
+// Mis-decoding only happens to non-ASCII text, and always leaves a lead
+// byte character (Â to ï) behind; encoding back to Windows-1252 must give
+// valid UTF-8 that differs from the name
+fn repair_utf8_as_cp1252(name: &str) -> Option<String> {
+    if !name.chars().any(|c| ('\u{C2}'..='\u{EF}').contains(&c)) {
+        return None;
+    }
+    let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(name);
+    if unmappable {
+        return None;
+    }
+    let repaired = String::from_utf8(bytes.into_owned()).ok()?;
+    (repaired != name && !repaired.contains(RESERVED_CHARACTERS)).then_some(repaired)
+}
+
+/// Whether `name` names an entry within a folder: not empty, not "." or
+/// "..", and without separators or NUL that would make it a path
+pub fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+/// `name` in plain ASCII, for destinations that reject or mangle anything
+/// else: accents dropped, ligatures expanded, Cyrillic romanized, reserved
+/// characters and whatever has no spelling replaced with '_'. Windows
+/// refuses names ending in a dot or space, so those are trimmed.
+pub fn transliterate(name: &str) -> String {
+    let mut ascii = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            c if c.is_ascii_control() => ascii.push('_'),
+            c if RESERVED_CHARACTERS.contains(&c) => ascii.push('_'),
+            c if c.is_ascii() => ascii.push(c),
+            _ => match fold(c) {
+                Some(folded) => ascii.push_str(folded),
+                None => ascii.push('_'),
+            },
+        }
+    }
+    let trimmed = ascii.trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// `name` as FAT and exFAT (and Windows) accept it: reserved and control
+/// characters replaced with '_', trailing dots and spaces trimmed, and
+/// device names like "CON" prefixed. Anything else, accents included, is
+/// kept, since these file systems store names as UTF-16.
+pub fn fat_safe(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| if c.is_control() || RESERVED_CHARACTERS.contains(&c) { '_' } else { c })
+        .collect();
+    let trimmed = replaced.trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        return "_".to_string();
+    }
+    let stem = trimmed.split('.').next().unwrap_or(trimmed);
+    if RESERVED_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+        return format!("_{}", trimmed);
+    }
+    trimmed.to_string()
+}
+
+// ASCII spelling of one non-ASCII character
+fn fold(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'À'..='Å' => "A",
+        'Æ' => "AE",
+        'Ç' => "C",
+        'È'..='Ë' => "E",
+        'Ì'..='Ï' => "I",
+        'Ð' => "D",
+        'Ñ' => "N",
+        'Ò'..='Ö' | 'Ø' => "O",
+        'Ù'..='Ü' => "U",
+        'Ý' => "Y",
+        'Þ' => "Th",
+        'ß' => "ss",
+        'à'..='å' => "a",
+        'æ' => "ae",
+        'ç' => "c",
+        'è'..='ë' => "e",
+        'ì'..='ï' => "i",
+        'ð' => "d",
+        'ñ' => "n",
+        'ò'..='ö' | 'ø' => "o",
+        'ù'..='ü' => "u",
+        'ý' | 'ÿ' => "y",
+        'þ' => "th",
+        '\u{132}' => "IJ",
+        '\u{133}' => "ij",
+        '\u{152}' => "OE",
+        '\u{153}' => "oe",
+        '\u{100}'..='\u{17F}' => {
+            let index = c as usize - 0x100;
+            &LATIN_EXTENDED_A[index..index + 1]
+        }
+        'Ё' => "Yo",
+        'ё' => "yo",
+        'А'..='Я' => CYRILLIC_UPPERCASE[c as usize - 'А' as usize],
+        'а'..='я' => CYRILLIC_LOWERCASE[c as usize - 'а' as usize],
+        '‘' | '’' | '‚' | '′' => "'",
+        '“' | '”' | '„' | '«' | '»' => "'",
+        '–' | '—' | '‐' => "-",
+        '…' => "...",
+        '\u{A0}' => " ",
+        '€' => "EUR",
+        '£' => "GBP",
+        '©' => "(c)",
+        '®' => "(R)",
+        '™' => "TM",
+        '°' => "deg",
+        _ => return None,
+    };
+    Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_plain_name_rejects_paths_and_dot_entries() {
+        assert!(is_plain_name("report.pdf"));
+        assert!(is_plain_name("..hidden"));
+        for name in ["", ".", "..", "a/b", "a\\b", "../up", "nul\0byte"] {
+            assert!(!is_plain_name(name), "{:?}", name);
+        }
+    }
+}
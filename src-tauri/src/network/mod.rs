@@ -0,0 +1,276 @@
+// ============================================================================
+// Network Module - Recognizing network shares and coping with their latency
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// How deep a recursive scan of a network share goes; every folder costs a
+/// round trip, so the local depth would take minutes
+pub const NETWORK_SCAN_DEPTH: usize = 4;
+
+/// Folders read at once on a network share; servers throttle or drop
+/// connections when a whole thread pool lists folders in parallel
+pub const NETWORK_SCAN_THREADS: usize = 4;
+
+/// Attempts made at an operation that fails with a transient network error
+const NETWORK_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry; doubled for each one after it
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+// Mounts whose type is a network file system in /proc/self/mounts; FUSE
+// mounts are only known to be remote by their subtype
+#[cfg(target_os = "linux")]
+const LINUX_NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "ncpfs",
+    "afs",
+    "9p",
+    "ceph",
+    "glusterfs",
+    "davfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "fuse.gvfsd-fuse",
+];
+
+// statfs magic numbers of network file systems, from linux/magic.h
+#[cfg(target_os = "linux")]
+const LINUX_NETWORK_MAGIC: &[u32] = &[
+    0x6969,      // NFS
+    0x517B,      // SMB
+    0xFF53_4D42, // CIFS
+    0xFE53_4D42, // SMB2
+    0x564C,      // NCP
+    0x5346_414F, // AFS
+    0x7375_7245, // Coda
+    0x0102_1997, // 9P
+    0x00C3_6400, // Ceph
+];
+
+#[cfg(target_os = "linux")]
+const FUSE_MAGIC: u32 = 0x6573_5546;
+
+// Answers by device id, so a walk asks the kernel once per mount rather
+// than once per file; cleared whenever the mounted volumes change
+static BY_DEVICE: Lazy<Mutex<HashMap<u64, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[cfg(target_os = "macos")]
+const MACOS_NETWORK_FILE_SYSTEMS: &[&str] = &["smbfs", "nfs", "afpfs", "webdav", "cifs", "ftp"];
+
+/// Whether `path` lives on a network share: a UNC path or mapped drive on
+/// Windows, an SMB, NFS, AFP or WebDAV mount elsewhere. Paths that don't
+/// exist yet are judged by their nearest existing folder.
+pub fn is_network_path(path: &Path) -> bool {
+    #[cfg(windows)]
+    {
+        windows_is_network(path)
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let Some((folder, metadata)) = path
+            .ancestors()
+            .find_map(|folder| std::fs::metadata(folder).ok().map(|metadata| (folder, metadata)))
+        else {
+            return false;
+        };
+        if let Some(&network) = BY_DEVICE.lock().get(&metadata.dev()) {
+            return network;
+        }
+        let network = unix_is_network(folder);
+        BY_DEVICE.lock().insert(metadata.dev(), network);
+        network
+    }
+
+    #[cfg(not(any(windows, unix)))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Forget what is known about mounts, for when drives come and go and
+/// device ids get handed to other file systems
+pub fn forget_mounts() {
+    BY_DEVICE.lock().clear();
+}
+
+#[cfg(windows)]
+fn windows_is_network(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Prefix};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+    }
+    const DRIVE_REMOTE: u32 = 4;
+
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return false;
+    };
+    let letter = match prefix.kind() {
+        Prefix::UNC(..) | Prefix::VerbatimUNC(..) => return true,
+        Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => letter,
+        _ => return false,
+    };
+    let root: Vec<u16> = std::ffi::OsStr::new(&format!("{}:\\", letter as char))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    // SAFETY: root is a valid NUL-terminated wide string for the duration of the call
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+#[cfg(all(unix, target_os = "linux"))]
+fn unix_is_network(folder: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(folder.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statfs is plain old data, for which all zeroes is a valid value
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is writable
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return false;
+    }
+    // The magic is 32 bits wide whatever the width of f_type
+    let magic = stats.f_type as u32;
+    if LINUX_NETWORK_MAGIC.contains(&magic) {
+        return true;
+    }
+    magic == FUSE_MAGIC
+        && mount_file_system(folder)
+            .is_some_and(|fs| LINUX_NETWORK_FILE_SYSTEMS.contains(&fs.as_str()))
+}
+
+// File system type of the innermost mount containing `folder`
+#[cfg(target_os = "linux")]
+fn mount_file_system(folder: &Path) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let folder = std::fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf());
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let mount_point = crate::volumes::unescape_mount_field(fields.next()?);
+            let file_system = fields.next()?.to_string();
+            folder
+                .starts_with(&mount_point)
+                .then(|| (mount_point.len(), file_system))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, file_system)| file_system)
+}
+
+#[cfg(all(unix, target_os = "macos"))]
+fn unix_is_network(folder: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(folder.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statfs is plain old data, for which all zeroes is a valid value
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is writable
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return false;
+    }
+    // SAFETY: the kernel NUL-terminates f_fstypename
+    let file_system = unsafe { std::ffi::CStr::from_ptr(stats.f_fstypename.as_ptr()) };
+    MACOS_NETWORK_FILE_SYSTEMS.contains(&file_system.to_string_lossy().as_ref())
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn unix_is_network(_folder: &Path) -> bool {
+    false
+}
+
+/// Whether an error is the kind a flaky connection produces and a retry
+/// may get past, rather than one that will fail the same way again
+pub fn is_transient(error: &io::Error) -> bool {
+    if matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+    ) {
+        return true;
+    }
+
+    #[cfg(windows)]
+    {
+        // ERROR_BAD_NETPATH, ERROR_NETWORK_BUSY, ERROR_UNEXP_NET_ERR,
+        // ERROR_NETNAME_DELETED, ERROR_SEM_TIMEOUT
+        const TRANSIENT: &[i32] = &[53, 54, 59, 64, 121];
+        error
+            .raw_os_error()
+            .is_some_and(|code| TRANSIENT.contains(&code))
+    }
+
+    #[cfg(unix)]
+    {
+        // A stale NFS handle clears once the client looks the path up again
+        error.raw_os_error().is_some_and(|code| {
+            code == libc::ESTALE || code == libc::EHOSTUNREACH || code == libc::ENETRESET
+        })
+    }
+
+    #[cfg(not(any(windows, unix)))]
+    {
+        false
+    }
+}
+
+/// Run `operation`, retrying with backoff while it fails with a transient
+/// error when `network` is set; local operations run once
+pub fn retrying<T>(network: bool, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let attempts = if network { NETWORK_ATTEMPTS } else { 1 };
+    let mut backoff = RETRY_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                std::thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `retrying` for a rename or removal that may have gone through even
+/// though the attempt timed out. The retry then fails with NotFound, which
+/// counts as success once `landed` confirms the result is in place.
+pub fn retrying_applied(
+    network: bool,
+    mut operation: impl FnMut() -> io::Result<()>,
+    landed: impl Fn() -> bool,
+) -> io::Result<()> {
+    let mut timed_out = false;
+    retrying(network, || match operation() {
+        Err(e) if timed_out && e.kind() == io::ErrorKind::NotFound && landed() => Ok(()),
+        Err(e) => {
+            timed_out |= is_transient(&e);
+            Err(e)
+        }
+        Ok(()) => Ok(()),
+    })
+}
@@ -0,0 +1,308 @@
+// ============================================================================
+// Packs Module - Shareable bundles of rules, templates and prompts
+// ============================================================================
+
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use crate::ingest;
+use crate::rules::{CompiledRule, Condition, PatternKind, Rule};
+use crate::storage;
+
+/// Pack format this build understands
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+const TRUSTED_KEYS_KEY: &str = "pack_trusted_keys";
+/// Prompts longer than this are rejected; they go straight into the model context
+const MAX_PROMPT_CHARS: usize = 4_000;
+
+// A rule as shipped in a pack; destinations are always relative
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackRule {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: PatternKind,
+    pub destination: String,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub conditions: Option<Condition>,
+}
+
+// A named rename template, in the same syntax as import templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamingTemplate {
+    pub name: String,
+    pub template: String,
+}
+
+// A named prompt for the local model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackPrompt {
+    pub name: String,
+    pub prompt: String,
+}
+
+// The shareable contents of an organization pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizationPack {
+    pub format_version: u32,
+    /// Stable identifier chosen by the author, e.g. "com.example.finance"
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<PackRule>,
+    #[serde(default)]
+    pub naming_templates: Vec<NamingTemplate>,
+    /// Category name to the file extensions it covers
+    #[serde(default)]
+    pub category_mappings: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub prompts: Vec<PackPrompt>,
+}
+
+// A pack file: the pack plus an optional ed25519 signature over the `pack`
+// value exactly as it is written in the file, both base64-encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPackFile {
+    pub pack: Box<serde_json::value::RawValue>,
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+// Whether a pack's signature checks out and whose key made it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    Unsigned,
+    /// Valid signature by a key the user trusts
+    Trusted,
+    /// Valid signature by an unknown key
+    Untrusted,
+    /// The signature doesn't match the contents; never installable
+    Invalid,
+}
+
+impl SignatureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureStatus::Unsigned => "unsigned",
+            SignatureStatus::Trusted => "trusted",
+            SignatureStatus::Untrusted => "untrusted",
+            SignatureStatus::Invalid => "invalid",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "trusted" => SignatureStatus::Trusted,
+            "untrusted" => SignatureStatus::Untrusted,
+            "invalid" => SignatureStatus::Invalid,
+            _ => SignatureStatus::Unsigned,
+        }
+    }
+}
+
+// Outcome of checking a pack file before import
+#[derive(Debug, Clone, Serialize)]
+pub struct PackValidation {
+    pub pack: Option<OrganizationPack>,
+    pub signature: SignatureStatus,
+    /// Problems that block installing the pack
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl PackValidation {
+    pub fn is_valid(&self) -> bool {
+        self.pack.is_some() && self.errors.is_empty() && self.signature != SignatureStatus::Invalid
+    }
+}
+
+/// Parse a pack file, check its signature and validate every part of it
+pub fn validate(json: &str) -> PackValidation {
+    let mut validation = PackValidation {
+        pack: None,
+        signature: SignatureStatus::Unsigned,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let file: SignedPackFile = match serde_json::from_str(json) {
+        Ok(file) => file,
+        Err(e) => {
+            validation.errors.push(format!("Not a pack file: {}", e));
+            return validation;
+        }
+    };
+    validation.signature = verify_signature(&file);
+    match validation.signature {
+        SignatureStatus::Invalid => validation
+            .errors
+            .push("Signature does not match the pack contents".to_string()),
+        SignatureStatus::Unsigned => validation.warnings.push("Pack is not signed".to_string()),
+        SignatureStatus::Untrusted => validation
+            .warnings
+            .push("Pack is signed by a key you haven't trusted".to_string()),
+        SignatureStatus::Trusted => {}
+    }
+
+    let pack: OrganizationPack = match serde_json::from_str(file.pack.get()) {
+        Ok(pack) => pack,
+        Err(e) => {
+            validation.errors.push(format!("Invalid pack contents: {}", e));
+            return validation;
+        }
+    };
+    validation.errors.extend(content_errors(&pack));
+    if pack.rules.is_empty() && pack.naming_templates.is_empty() && pack.category_mappings.is_empty() {
+        validation.warnings.push("Pack has no rules, templates or categories".to_string());
+    }
+    validation.pack = Some(pack);
+    validation
+}
+
+// Everything in a parsed pack that would be unsafe or unusable
+fn content_errors(pack: &OrganizationPack) -> Vec<String> {
+    let mut errors = Vec::new();
+    if pack.format_version != PACK_FORMAT_VERSION {
+        errors.push(format!(
+            "Unsupported pack format {} (expected {})",
+            pack.format_version, PACK_FORMAT_VERSION
+        ));
+    }
+    if pack.id.trim().is_empty() || pack.name.trim().is_empty() {
+        errors.push("Pack needs an id and a name".to_string());
+    }
+
+    for rule in &pack.rules {
+        if let Err(e) = CompiledRule::compile(&rule.pattern, rule.kind, rule.conditions.as_ref(), &rule.destination) {
+            errors.push(format!("Rule '{}': {}", rule.name, e));
+        }
+        // Packs only sort files relative to where they are, never elsewhere on disk
+        if !is_contained(&rule.destination) {
+            errors.push(format!(
+                "Rule '{}': destination must be a relative folder without '..'",
+                rule.name
+            ));
+        }
+    }
+    for template in &pack.naming_templates {
+        if let Err(e) = ingest::validate_template(&template.template) {
+            errors.push(format!("Template '{}': {}", template.name, e));
+        }
+    }
+    for (category, extensions) in &pack.category_mappings {
+        if category.trim().is_empty() || extensions.is_empty() {
+            errors.push(format!("Category '{}' needs a name and at least one extension", category));
+        }
+        if let Some(bad) = extensions.iter().find(|ext| ext.is_empty() || ext.contains(['/', '\\', '.'])) {
+            errors.push(format!("Category '{}': invalid extension '{}'", category, bad));
+        }
+    }
+    for prompt in &pack.prompts {
+        if prompt.prompt.chars().count() > MAX_PROMPT_CHARS {
+            errors.push(format!(
+                "Prompt '{}' is longer than {} characters",
+                prompt.name, MAX_PROMPT_CHARS
+            ));
+        }
+    }
+    errors
+}
+
+// Relative path that stays below the folder it is resolved against
+fn is_contained(destination: &str) -> bool {
+    let path = Path::new(destination.trim());
+    !destination.trim().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Check the signature of a pack file against its embedded public key and
+/// the user's trusted keys
+pub fn verify_signature(file: &SignedPackFile) -> SignatureStatus {
+    let (Some(signature), Some(public_key)) = (&file.signature, &file.public_key) else {
+        return SignatureStatus::Unsigned;
+    };
+    let engine = base64::engine::general_purpose::STANDARD;
+
+    let key = engine
+        .decode(public_key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok());
+    let signature = engine
+        .decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok());
+    let (Some(key), Some(signature)) = (key, signature) else {
+        return SignatureStatus::Invalid;
+    };
+
+    // The bytes as signed; parsing and re-serializing could change them
+    if key.verify(file.pack.get().as_bytes(), &signature).is_err() {
+        return SignatureStatus::Invalid;
+    }
+    if trusted_keys().iter().any(|trusted| trusted == public_key) {
+        SignatureStatus::Trusted
+    } else {
+        SignatureStatus::Untrusted
+    }
+}
+
+/// Base64 public keys whose packs install without a warning
+pub fn trusted_keys() -> Vec<String> {
+    storage::get_preference(TRUSTED_KEYS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_trusted_keys(keys: &[String]) -> Result<(), String> {
+    let json = serde_json::to_string(keys).map_err(|e| format!("Failed to serialize keys: {}", e))?;
+    storage::set_preference(TRUSTED_KEYS_KEY, &json)
+}
+
+/// Id of the saved rule created for the `index`-th rule of a pack
+pub fn rule_id(pack_id: &str, index: usize) -> String {
+    format!("pack:{}:{}", pack_id, index)
+}
+
+/// The pack's rules as saved rules, `active` or not
+pub fn rules_of(pack: &OrganizationPack, active: bool) -> Vec<Rule> {
+    let created_at = chrono::Utc::now().to_rfc3339();
+    pack.rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| Rule {
+            id: rule_id(&pack.id, index),
+            name: format!("{} ({})", rule.name, pack.name),
+            description: rule.description.clone(),
+            pattern: rule.pattern.clone(),
+            pattern_kind: rule.kind,
+            destination: rule.destination.trim().to_string(),
+            priority: rule.priority,
+            is_active: active,
+            created_at: created_at.clone(),
+            hit_count: 0,
+            last_matched_at: None,
+            conditions: rule.conditions.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,440 @@
+// ============================================================================
+// Permissions Module - Which commands the frontend may invoke
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::Wry;
+
+use crate::commands::organize;
+use crate::storage;
+
+const ACTIVE_WORKSPACE_KEY: &str = "active_workspace";
+const DEFAULT_WORKSPACE: &str = "default";
+
+/// Commands that change the permissions themselves. They need Mutate but
+/// get through read-only mode when invoked with `confirmed: true`, so it can
+/// be turned off again on purpose.
+const PERMISSION_COMMANDS: &[&str] = &["set_permissions", "set_active_workspace"];
+
+/// Arguments that make a command delete data, at any depth of its payload;
+/// a command given one of them needs Destroy whatever its classification
+const DESTRUCTIVE_FLAGS: &[&str] = &["delete_originals", "deleteOriginals", "delete_parts", "deleteParts"];
+
+// What a command may do to the user's files and the app's state
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Looks only; caches and the index may be updated along the way
+    Read,
+    /// Moves, creates or changes files, or changes settings; can be undone
+    Mutate,
+    /// Deletes or overwrites data in a way that can't be undone
+    Destroy,
+}
+
+impl Capability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Read => "read",
+            Capability::Mutate => "mutate",
+            Capability::Destroy => "destroy",
+        }
+    }
+}
+
+// Every command the frontend can invoke, by the name it is invoked with.
+// A command missing here is refused, so a new one has to be classified
+// before it can be used.
+const COMMAND_CAPABILITIES: &[(&str, Capability)] = &[
+    // Files
+    ("list_files", Capability::Read),
+    ("get_file_info", Capability::Read),
+    ("move_file", Capability::Mutate),
+    ("create_folder", Capability::Mutate),
+    ("get_ignore_patterns", Capability::Read),
+    ("set_ignore_patterns", Capability::Mutate),
+    ("hash_file", Capability::Read),
+    ("hash_folder", Capability::Read),
+    ("find_similar_names", Capability::Read),
+    ("inspect_archive", Capability::Read),
+    ("compress_files", Capability::Mutate),
+    ("extract_archive", Capability::Mutate),
+    ("reveal_in_explorer", Capability::Read),
+    ("open_file", Capability::Read),
+    ("set_file_comment", Capability::Mutate),
+    ("get_file_comment", Capability::Read),
+    ("get_file_tags", Capability::Read),
+    ("set_file_tags", Capability::Mutate),
+    ("set_attributes", Capability::Mutate),
+    ("revert_attributes", Capability::Mutate),
+    ("protect_path", Capability::Mutate),
+    ("unprotect_path", Capability::Mutate),
+    ("list_protected_paths", Capability::Read),
+    ("secure_delete", Capability::Destroy),
+    ("find_empty_folders", Capability::Read),
+    ("remove_empty_folders", Capability::Destroy),
+    // Cleanup
+    ("find_broken_links", Capability::Read),
+    ("fix_broken_links", Capability::Destroy),
+    ("get_cleanup_report", Capability::Read),
+    ("get_large_old_files", Capability::Read),
+    ("find_stale_files", Capability::Read),
+    ("find_suspect_files", Capability::Read),
+    ("run_health_scan", Capability::Read),
+    ("quarantine_files", Capability::Mutate),
+    ("find_derived_data", Capability::Read),
+    ("clean_derived_data", Capability::Destroy),
+    ("find_duplicates", Capability::Read),
+    // Organize
+    ("generate_plan", Capability::Read),
+    ("apply_plan", Capability::Mutate),
+    ("cancel_apply", Capability::Read),
+    ("create_link_farm", Capability::Mutate),
+    ("remove_link_farm", Capability::Mutate),
+    ("apply_copy_plan", Capability::Mutate),
+    ("summarize_plan", Capability::Read),
+    ("get_date_folder_settings", Capability::Read),
+    ("set_date_folder_settings", Capability::Mutate),
+    // History
+    ("get_history", Capability::Read),
+    ("undo_batch", Capability::Mutate),
+    ("get_folder_changes", Capability::Read),
+    ("mark_folder_viewed", Capability::Mutate),
+    ("reconstruct_tree", Capability::Read),
+    // Import
+    ("import_files", Capability::Mutate),
+    ("detect_camera_card", Capability::Read),
+    ("ingest_camera_card", Capability::Mutate),
+    ("get_pending_ingest", Capability::Read),
+    ("wipe_camera_card", Capability::Destroy),
+    // Rules
+    ("preview_rule", Capability::Read),
+    ("validate_pattern", Capability::Read),
+    ("list_rules", Capability::Read),
+    ("save_rule", Capability::Mutate),
+    ("delete_rule", Capability::Mutate),
+    ("test_rules", Capability::Read),
+    ("list_rule_suggestions", Capability::Read),
+    ("accept_rule_suggestion", Capability::Mutate),
+    ("dismiss_rule_suggestion", Capability::Mutate),
+    // Startup and shutdown; exiting is always possible
+    ("get_startup_status", Capability::Read),
+    ("get_running_operations", Capability::Read),
+    ("get_interrupted_operations", Capability::Read),
+    ("set_wait_on_exit", Capability::Mutate),
+    ("force_exit", Capability::Read),
+    // Staging area; purging deletes for good
+    ("list_staged_files", Capability::Read),
+    ("restore_staged_files", Capability::Mutate),
+    ("purge_staged_files", Capability::Destroy),
+    // Watcher; duplicate policies can trash files unattended
+    ("start_watching", Capability::Mutate),
+    ("stop_watching", Capability::Mutate),
+    ("get_watched_paths", Capability::Read),
+    ("get_duplicate_policies", Capability::Read),
+    ("set_duplicate_policy", Capability::Destroy),
+    ("get_settle_seconds", Capability::Read),
+    ("set_settle_seconds", Capability::Mutate),
+    // AI
+    ("check_model_status", Capability::Read),
+    ("download_model", Capability::Mutate),
+    ("assemble_model", Capability::Mutate),
+    ("load_model", Capability::Mutate),
+    ("generate_response", Capability::Read),
+    ("get_model_config", Capability::Read),
+    ("set_model_config", Capability::Mutate),
+    ("classify_batch", Capability::Read),
+    ("get_ai_usage", Capability::Read),
+    ("init_ai", Capability::Mutate),
+    // Content, digests and disk usage
+    ("get_file_text", Capability::Read),
+    ("index_contents", Capability::Read),
+    ("generate_digest", Capability::Read),
+    ("list_digests", Capability::Read),
+    ("get_digest_settings", Capability::Read),
+    ("set_digest_settings", Capability::Mutate),
+    ("analyze_disk_usage", Capability::Read),
+    ("get_index_stats", Capability::Read),
+    ("get_folder_size", Capability::Read),
+    ("compare_folders", Capability::Read),
+    ("mirror_folder", Capability::Destroy),
+    // Embeddings
+    ("index_embeddings", Capability::Read),
+    ("embedding_coverage", Capability::Read),
+    ("set_embedding_model", Capability::Mutate),
+    // Folder styles
+    ("list_folder_styles", Capability::Read),
+    ("save_folder_style", Capability::Mutate),
+    ("delete_folder_style", Capability::Mutate),
+    ("apply_folder_style", Capability::Mutate),
+    // Guided cleanup; starting one saves a session and its plans, approving
+    // a stage moves files
+    ("start_guided_cleanup", Capability::Mutate),
+    ("get_guided_cleanup", Capability::Read),
+    ("advance_guided_cleanup", Capability::Mutate),
+    ("cancel_guided_cleanup", Capability::Read),
+    // Libraries
+    ("list_libraries", Capability::Read),
+    ("add_library", Capability::Mutate),
+    ("remove_library", Capability::Mutate),
+    ("set_library_policy", Capability::Mutate),
+    // Media
+    ("get_media_metadata", Capability::Read),
+    ("index_media", Capability::Read),
+    // Name repair
+    ("find_broken_names", Capability::Read),
+    ("repair_names", Capability::Mutate),
+    // Packs
+    ("validate_pack", Capability::Read),
+    ("import_pack", Capability::Mutate),
+    ("preview_pack", Capability::Read),
+    ("activate_pack", Capability::Mutate),
+    ("deactivate_pack", Capability::Mutate),
+    ("list_packs", Capability::Read),
+    ("trust_pack_key", Capability::Mutate),
+    // Permissions; allowed in read-only mode once confirmed, so it can be
+    // turned off again
+    ("get_permissions", Capability::Read),
+    ("set_permissions", Capability::Mutate),
+    ("set_active_workspace", Capability::Mutate),
+    ("list_command_capabilities", Capability::Read),
+    // Preview
+    ("get_thumbnail", Capability::Read),
+    ("get_video_frames", Capability::Read),
+    ("preview_file", Capability::Read),
+    // Search
+    ("semantic_search", Capability::Read),
+    ("search", Capability::Read),
+    ("search_files", Capability::Read),
+    ("parse_search_query", Capability::Read),
+    ("list_saved_searches", Capability::Read),
+    ("save_search", Capability::Mutate),
+    ("delete_saved_search", Capability::Mutate),
+    ("list_smart_folders", Capability::Read),
+    ("save_smart_folder", Capability::Mutate),
+    ("delete_smart_folder", Capability::Mutate),
+    ("list_smart_folder", Capability::Read),
+    // Storage Sense
+    ("list_os_managed_folders", Capability::Read),
+    ("get_os_cleanup_status", Capability::Read),
+    // Throttling
+    ("get_io_limits", Capability::Read),
+    ("set_io_limits", Capability::Mutate),
+    // Volumes; forgetting one drops the catalog of a drive that may be away
+    ("list_volumes", Capability::Read),
+    ("list_volume_catalog", Capability::Read),
+    ("forget_volume", Capability::Destroy),
+];
+
+// What a workspace lets the frontend do; reading is always allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PermissionSettings {
+    /// Refuse every command that changes anything, whatever else is allowed
+    pub read_only: bool,
+    pub allow_mutating: bool,
+    pub allow_destructive: bool,
+}
+
+impl Default for PermissionSettings {
+    fn default() -> Self {
+        Self {
+            read_only: false,
+            allow_mutating: true,
+            allow_destructive: true,
+        }
+    }
+}
+
+impl PermissionSettings {
+    /// Whether these settings allow something `previous` did not
+    pub fn loosens(&self, previous: &PermissionSettings) -> bool {
+        (previous.read_only && !self.read_only)
+            || (!previous.allow_mutating && self.allow_mutating)
+            || (!previous.allow_destructive && self.allow_destructive)
+    }
+
+    /// Settings of a workspace nobody configured: read-only until it is
+    pub fn unconfigured() -> Self {
+        Self {
+            read_only: true,
+            allow_mutating: true,
+            allow_destructive: false,
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Read => true,
+            Capability::Mutate => !self.read_only && self.allow_mutating,
+            Capability::Destroy => !self.read_only && self.allow_mutating && self.allow_destructive,
+        }
+    }
+}
+
+// The active workspace and its settings, read once rather than per command
+static ACTIVE: Lazy<RwLock<Option<(String, PermissionSettings)>>> = Lazy::new(|| RwLock::new(None));
+
+/// How a command is classified, or None for one nobody classified
+pub fn capability_of(command: &str) -> Option<Capability> {
+    COMMAND_CAPABILITIES
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, capability)| *capability)
+}
+
+/// Every classified command, for the settings screen
+pub fn command_capabilities() -> Vec<(String, Capability)> {
+    COMMAND_CAPABILITIES
+        .iter()
+        .map(|(name, capability)| (name.to_string(), *capability))
+        .collect()
+}
+
+/// Name of the workspace whose settings apply
+pub fn active_workspace() -> String {
+    if let Some((workspace, _)) = ACTIVE.read().as_ref() {
+        return workspace.clone();
+    }
+    storage::get_preference(ACTIVE_WORKSPACE_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+/// Switch to another workspace's settings
+pub fn set_active_workspace(workspace: &str) -> Result<(), String> {
+    if workspace.trim().is_empty() {
+        return Err("Workspace needs a name".to_string());
+    }
+    storage::set_preference(ACTIVE_WORKSPACE_KEY, workspace.trim())?;
+    *ACTIVE.write() = None;
+    Ok(())
+}
+
+/// Settings of `workspace`. The default workspace allows everything until
+/// configured; any other workspace is read-only until configured.
+pub fn settings(workspace: &str) -> Result<PermissionSettings, String> {
+    let saved = storage::get_preference(&settings_key(workspace))?.and_then(|json| serde_json::from_str(&json).ok());
+    Ok(saved.unwrap_or_else(|| {
+        if workspace == DEFAULT_WORKSPACE {
+            PermissionSettings::default()
+        } else {
+            PermissionSettings::unconfigured()
+        }
+    }))
+}
+
+/// Change the settings of `workspace`
+pub fn set_settings(workspace: &str, settings: PermissionSettings) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize permissions: {}", e))?;
+    storage::set_preference(&settings_key(workspace), &json)?;
+    *ACTIVE.write() = None;
+    Ok(())
+}
+
+fn settings_key(workspace: &str) -> String {
+    format!("permissions:{}", workspace)
+}
+
+fn active_settings() -> Result<PermissionSettings, String> {
+    if let Some((_, settings)) = ACTIVE.read().as_ref() {
+        return Ok(*settings);
+    }
+    let workspace = storage::get_preference(ACTIVE_WORKSPACE_KEY)?.unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+    let active = settings(&workspace)?;
+    *ACTIVE.write() = Some((workspace, active));
+    Ok(active)
+}
+
+/// Whether the frontend may invoke `command` with `args` under the active
+/// workspace. Unclassified commands are refused, and so is anything but
+/// reading while the settings can't be loaded (e.g. before the database is
+/// open). Arguments that overwrite or delete data raise the command to
+/// Destroy, and so does a saved plan whose run would replace earlier copies.
+pub fn authorize(command: &str, args: &Value) -> Result<(), String> {
+    let mut capability = capability_of(command).ok_or_else(|| format!("Command not permitted: {}", command))?;
+    if destructive_args(args) || destructive_plan(command, args) {
+        capability = Capability::Destroy;
+    }
+    if capability == Capability::Read {
+        return Ok(());
+    }
+    let settings = active_settings().map_err(|e| format!("Cannot check permissions for {}: {}", command, e))?;
+    if PERMISSION_COMMANDS.contains(&command) {
+        if !settings.allow_mutating {
+            return Err(format!("Changes are turned off for this workspace; {} is not allowed", command));
+        }
+        if settings.read_only && !confirmed(args) {
+            return Err(format!("Read-only mode is on; {} needs confirmation", command));
+        }
+        return Ok(());
+    }
+    if settings.read_only {
+        return Err(format!("Read-only mode is on; {} is not allowed", command));
+    }
+    if !settings.allows(capability) {
+        return Err(format!(
+            "{} commands are turned off for this workspace; {} is not allowed",
+            capability.as_str(),
+            command
+        ));
+    }
+    Ok(())
+}
+
+/// Wrap the command handler so every invocation goes through `authorize`
+/// first; refused commands are rejected with the reason
+pub fn guarded(
+    handler: impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let no_args = Value::Null;
+        let args = match invoke.message.payload() {
+            InvokeBody::Json(args) => args,
+            InvokeBody::Raw(_) => &no_args,
+        };
+        match authorize(invoke.message.command(), args) {
+            Ok(()) => handler(invoke),
+            Err(e) => {
+                invoke.resolver.reject(e);
+                true
+            }
+        }
+    }
+}
+
+// Whether `args` carry `confirmed: true`, the user's go-ahead for a change
+// that loosens permissions
+fn confirmed(args: &Value) -> bool {
+    args.get("confirmed").and_then(Value::as_bool) == Some(true)
+}
+
+// Whether the saved plan `command` runs would overwrite data, which its
+// arguments don't show: re-running a copy plan replaces the earlier copies
+// of changed files
+fn destructive_plan(command: &str, args: &Value) -> bool {
+    let plan_id = args.get("planId").or_else(|| args.get("plan_id")).and_then(Value::as_str);
+    match (command, plan_id) {
+        ("apply_copy_plan", Some(plan_id)) => organize::copy_plan_replaces_copies(plan_id),
+        _ => false,
+    }
+}
+
+// Whether any argument asks for an overwrite collision policy or for data
+// to be deleted afterwards
+fn destructive_args(args: &Value) -> bool {
+    match args {
+        Value::Object(fields) => fields.iter().any(|(key, value)| {
+            (key == "collision" && value.as_str() == Some("overwrite"))
+                || (DESTRUCTIVE_FLAGS.contains(&key.as_str()) && value.as_bool() == Some(true))
+                || destructive_args(value)
+        }),
+        Value::Array(items) => items.iter().any(destructive_args),
+        _ => false,
+    }
+}
@@ -0,0 +1,117 @@
+// ============================================================================
+// Photos Module - EXIF and embedded previews of HEIC, AVIF and camera RAW
+// ============================================================================
+
+use image::codecs::jpeg::JpegDecoder;
+use image::{DynamicImage, ImageDecoder, ImageFormat};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+
+/// HEIF containers (HEIC from phones, AVIF from the web), which the image
+/// crate can't decode on its own
+pub const HEIF_EXTENSIONS: &[&str] = &["heic", "heif", "avif"];
+
+/// Camera RAW formats; all but CR3 and RAF are TIFF underneath
+pub const RAW_EXTENSIONS: &[&str] = &[
+    "cr2", "cr3", "crw", "nef", "nrw", "arw", "srf", "sr2", "dng", "orf", "rw2", "raf", "pef", "srw", "x3f",
+];
+
+/// How much of a file is searched for embedded previews
+const MAX_PREVIEW_SCAN: u64 = 128 * 1024 * 1024;
+
+/// How much of an AVIF or CR3 file is searched for its EXIF block, which
+/// both keep near the start
+const MAX_EXIF_SCAN: u64 = 1024 * 1024;
+
+/// How many embedded JPEGs are measured before picking the largest
+const MAX_PREVIEW_CANDIDATES: usize = 32;
+
+// Markers an EXIF TIFF block follows in containers kamadak-exif doesn't
+// parse: the "Exif" item of AVIF and the CMT1 box of Canon CR3
+const EXIF_MARKERS: &[&[u8]] = &[b"Exif\0\0", b"CMT1"];
+
+/// Whether `extension` is a photo format that needs this module to read
+pub fn is_camera_format(extension: &str) -> bool {
+    let extension = extension.to_lowercase();
+    HEIF_EXTENSIONS.contains(&extension.as_str()) || RAW_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// EXIF of any photo: JPEG, TIFF-based RAW, PNG, WebP and HEIC through
+/// kamadak-exif, and AVIF and CR3 by finding the TIFF block inside. Other
+/// files are never searched, so a zip or PDF can't pass for a photo.
+pub fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let file = File::open(path).ok()?;
+    if let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        return Some(exif);
+    }
+
+    let extension = path.extension()?.to_string_lossy();
+    if !is_camera_format(&extension) {
+        return None;
+    }
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(MAX_EXIF_SCAN).read_to_end(&mut head).ok()?;
+    EXIF_MARKERS.iter().find_map(|marker| {
+        let start = find(&head, marker)? + marker.len();
+        let tiff = &head[start..];
+        if !(tiff.starts_with(b"II*\0") || tiff.starts_with(b"MM\0*")) {
+            return None;
+        }
+        exif::Reader::new().read_raw(tiff.to_vec()).ok()
+    })
+}
+
+/// The largest JPEG preview embedded in a RAW or HEIF file. Cameras store a
+/// full-size or screen-size JPEG next to the sensor data, which is what
+/// their own screens and most viewers show.
+pub fn embedded_preview(path: &Path) -> Option<DynamicImage> {
+    let mut bytes = Vec::new();
+    File::open(path).ok()?.take(MAX_PREVIEW_SCAN).read_to_end(&mut bytes).ok()?;
+
+    let (start, _) = jpeg_starts(&bytes)
+        .take(MAX_PREVIEW_CANDIDATES)
+        .filter_map(|start| {
+            let decoder = JpegDecoder::new(Cursor::new(&bytes[start..])).ok()?;
+            let (width, height) = decoder.dimensions();
+            Some((start, width as u64 * height as u64))
+        })
+        .max_by_key(|(_, area)| *area)?;
+    image::load_from_memory_with_format(&bytes[start..], ImageFormat::Jpeg).ok()
+}
+
+// Offsets of JPEG start-of-image markers followed by a marker segment
+fn jpeg_starts(bytes: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    bytes.windows(4).enumerate().filter_map(|(offset, window)| {
+        (window[0] == 0xFF && window[1] == 0xD8 && window[2] == 0xFF && matches!(window[3], 0xC0..=0xFE))
+            .then_some(offset)
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// A HEIC or AVIF decoded by the OS, for files without an embedded JPEG:
+/// macOS converts them with sips, Linux with libheif's heif-convert
+pub fn system_decode(path: &Path) -> Option<DynamicImage> {
+    let target = std::env::temp_dir().join(format!("photo-{}.png", uuid::Uuid::new_v4()));
+
+    #[cfg(target_os = "macos")]
+    let status = std::process::Command::new("sips")
+        .args(["-s", "format", "png"])
+        .arg(path)
+        .arg("--out")
+        .arg(&target)
+        .output();
+
+    #[cfg(not(target_os = "macos"))]
+    let status = std::process::Command::new("heif-convert").arg(path).arg(&target).output();
+
+    let decoded = status
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|_| image::open(&target).ok());
+    let _ = std::fs::remove_file(&target);
+    decoded
+}
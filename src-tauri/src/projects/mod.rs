@@ -0,0 +1,134 @@
+// ============================================================================
+// Projects Module - Recognizing source-code project roots
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fileops;
+use crate::libraries;
+use crate::watcher;
+
+/// Entries whose presence makes a folder a project root
+const PROJECT_MARKERS: &[&str] = &[
+    ".git",
+    ".hg",
+    ".svn",
+    "Cargo.toml",
+    "package.json",
+    "go.mod",
+    "pyproject.toml",
+    "setup.py",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "CMakeLists.txt",
+    "composer.json",
+    "Gemfile",
+];
+
+/// Extensions of entries that make their folder a project root
+const PROJECT_MARKER_EXTENSIONS: &[&str] = &["sln", "csproj", "xcodeproj", "xcworkspace"];
+
+/// The user's standard folders under the home folder, never projects
+/// themselves whatever stray marker they hold
+const STANDARD_FOLDERS: &[&str] = &[
+    "Desktop", "Documents", "Downloads", "Music", "Movies", "Pictures", "Public", "Videos",
+];
+
+// A project whose files were found in a selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRoot {
+    pub path: String,
+    /// The entry that gave it away, e.g. ".git" or "App.sln"
+    pub marker: String,
+}
+
+/// Finds the project a file belongs to, remembering what each folder holds
+pub struct ProjectDetector {
+    markers: HashMap<PathBuf, Option<String>>,
+    home: Option<PathBuf>,
+    /// Library and watched roots; the search stops at them
+    boundaries: Vec<PathBuf>,
+}
+
+impl Default for ProjectDetector {
+    fn default() -> Self {
+        let boundaries = libraries::list()
+            .into_iter()
+            .map(|library| library.root)
+            .chain(watcher::watched_paths())
+            .map(PathBuf::from)
+            .collect();
+        Self {
+            markers: HashMap::new(),
+            home: std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(PathBuf::from),
+            boundaries,
+        }
+    }
+}
+
+impl ProjectDetector {
+    /// The outermost project `path` lies in, so a workspace or monorepo is
+    /// one unit rather than one per package. The search stops at a library
+    /// or watched root. The home folder and the folders above it never
+    /// count, since a dotfiles repository would otherwise claim every file
+    /// the user owns, and neither do standard folders like Downloads.
+    pub fn project_of(&mut self, path: &Path) -> Option<ProjectRoot> {
+        let mut outermost = None;
+        for folder in path.ancestors().skip(1) {
+            if self.home.as_deref().is_some_and(|home| home.starts_with(folder)) {
+                break;
+            }
+            if !self.is_standard_folder(folder) {
+                if let Some(marker) = self.marker(folder) {
+                    outermost = Some(ProjectRoot {
+                        path: folder.to_string_lossy().to_string(),
+                        marker,
+                    });
+                }
+            }
+            if self.boundaries.iter().any(|boundary| boundary == folder) {
+                break;
+            }
+        }
+        outermost
+    }
+
+    fn is_standard_folder(&self, folder: &Path) -> bool {
+        self.home.as_deref().is_some_and(|home| {
+            folder.parent() == Some(home)
+                && folder
+                    .file_name()
+                    .is_some_and(|name| STANDARD_FOLDERS.iter().any(|standard| name == *standard))
+        })
+    }
+
+    fn marker(&mut self, folder: &Path) -> Option<String> {
+        if let Some(marker) = self.markers.get(folder) {
+            return marker.clone();
+        }
+        let marker = marker_in(folder, PROJECT_MARKERS, PROJECT_MARKER_EXTENSIONS);
+        self.markers.insert(folder.to_path_buf(), marker.clone());
+        marker
+    }
+}
+
+/// Name of the first entry directly in `folder` that is one of `names` or
+/// has one of `extensions` (lowercase, without the dot)
+pub fn marker_in(folder: &Path, names: &[&str], extensions: &[&str]) -> Option<String> {
+    fs::read_dir(fileops::long_path(folder)).ok().and_then(|entries| {
+        entries.filter_map(|e| e.ok()).find_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let extension = Path::new(&name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            (names.contains(&name.as_str()) || extensions.contains(&extension.as_str())).then_some(name)
+        })
+    })
+}
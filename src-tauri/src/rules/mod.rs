@@ -0,0 +1,643 @@
+// ============================================================================
+// Rules Module - Organization rule matching
+// ============================================================================
+
+use globset::{GlobBuilder, GlobMatcher};
+use once_cell::unsync::OnceCell;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::files::detect_type;
+use crate::fileops;
+use crate::screenshot::SCREENSHOT_FILE_TYPE;
+use crate::storage::{self, IndexedFile};
+
+/// Rules without a match for this long are reported as dead
+const DEAD_RULE_DAYS: i64 = 90;
+/// Specificity each condition adds to a rule, on top of its pattern
+const CONDITION_SPECIFICITY: usize = 10;
+
+// Pattern syntax used by a rule
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    #[default]
+    Glob,
+    Regex,
+}
+
+impl PatternKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternKind::Glob => "glob",
+            PatternKind::Regex => "regex",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "regex" => PatternKind::Regex,
+            _ => PatternKind::Glob,
+        }
+    }
+}
+
+// Syntax error in a pattern, with the character offset where it was detected
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternError {
+    pub message: String,
+    pub position: Option<usize>,
+}
+
+// A saved organization rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub pattern: String,
+    pub pattern_kind: PatternKind,
+    pub destination: String,
+    pub priority: i64,
+    pub is_active: bool,
+    pub created_at: String,
+    pub hit_count: i64,
+    pub last_matched_at: Option<String>,
+    /// Further conditions a file must meet besides the pattern
+    #[serde(default)]
+    pub conditions: Option<Condition>,
+}
+
+impl Rule {
+    /// Active rule that has not matched anything for DEAD_RULE_DAYS
+    pub fn is_dead(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        if !self.is_active {
+            return false;
+        }
+        let reference = self.last_matched_at.as_deref().unwrap_or(&self.created_at);
+        match chrono::DateTime::parse_from_rfc3339(reference) {
+            Ok(at) => (now - at.with_timezone(&chrono::Utc)).num_days() >= DEAD_RULE_DAYS,
+            Err(_) => false,
+        }
+    }
+
+    /// Id of a higher-priority active rule that covers the same pattern, if any
+    pub fn shadowed_by<'a>(&self, rules: &'a [Rule]) -> Option<&'a Rule> {
+        if !self.is_active {
+            return None;
+        }
+        rules.iter().find(|other| {
+            let same_pattern = other.pattern_kind == self.pattern_kind
+                && other.pattern.trim().eq_ignore_ascii_case(self.pattern.trim());
+            other.id != self.id
+                && other.is_active
+                && other.priority > self.priority
+                && match &other.conditions {
+                    None => is_catch_all(&other.pattern, other.pattern_kind) || same_pattern,
+                    Some(conditions) => same_pattern && self.conditions.as_ref() == Some(conditions),
+                }
+        })
+    }
+
+    pub fn specificity(&self) -> usize {
+        specificity(&self.pattern, self.pattern_kind)
+            + self.conditions.as_ref().map_or(0, |c| c.leaf_count() * CONDITION_SPECIFICITY)
+    }
+}
+
+// Patterns that match every file
+fn is_catch_all(pattern: &str, kind: PatternKind) -> bool {
+    match kind {
+        PatternKind::Glob => matches!(pattern.trim(), "*" | "**" | "**/*"),
+        PatternKind::Regex => matches!(pattern.trim(), "" | ".*" | "^.*$" | ".+" | "^.+$"),
+    }
+}
+
+// A rule as typed in the rule editor, before it is saved
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleDraft {
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: PatternKind,
+    pub destination: String,
+    #[serde(default)]
+    pub conditions: Option<Condition>,
+}
+
+/// A compiled glob or regex pattern.
+///
+/// Globs without a path separator match the file name only ("*.pdf"); globs
+/// containing one match the full path ("**/Invoices/*.pdf"). Regexes are
+/// searched against the full path.
+pub enum PatternMatcher {
+    Glob {
+        matcher: GlobMatcher,
+        match_full_path: bool,
+    },
+    Regex(Regex),
+}
+
+impl PatternMatcher {
+    pub fn compile(pattern: &str, kind: PatternKind) -> Result<Self, PatternError> {
+        if pattern.is_empty() {
+            return Err(PatternError {
+                message: "Pattern is empty".to_string(),
+                position: None,
+            });
+        }
+
+        match kind {
+            PatternKind::Glob => {
+                let glob = GlobBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| PatternError {
+                        message: e.kind().to_string(),
+                        position: glob_error_position(pattern, e.kind()),
+                    })?;
+                Ok(PatternMatcher::Glob {
+                    matcher: glob.compile_matcher(),
+                    match_full_path: pattern.contains('/'),
+                })
+            }
+            PatternKind::Regex => {
+                // Parse first for a precise error position; the regex crate only
+                // reports a formatted message
+                if let Err(e) = regex_syntax::ast::parse::Parser::new().parse(pattern) {
+                    let offset = e.span().start.offset;
+                    return Err(PatternError {
+                        message: e.kind().to_string(),
+                        position: Some(pattern[..offset].chars().count()),
+                    });
+                }
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| PatternError {
+                        message: e.to_string(),
+                        position: None,
+                    })?;
+                Ok(PatternMatcher::Regex(regex))
+            }
+        }
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        match self {
+            PatternMatcher::Glob {
+                matcher,
+                match_full_path: true,
+            } => matcher.is_match(path),
+            PatternMatcher::Glob { matcher, .. } => path
+                .file_name()
+                .map(|name| matcher.is_match(name))
+                .unwrap_or(false),
+            PatternMatcher::Regex(regex) => regex.is_match(&path.to_string_lossy()),
+        }
+    }
+}
+
+// Best-effort character offset for glob errors, which carry no position
+fn glob_error_position(pattern: &str, kind: &globset::ErrorKind) -> Option<usize> {
+    use globset::ErrorKind;
+
+    let chars: Vec<char> = pattern.chars().collect();
+    match kind {
+        ErrorKind::UnclosedClass => chars.iter().rposition(|&c| c == '['),
+        ErrorKind::UnclosedAlternates => chars.iter().rposition(|&c| c == '{'),
+        ErrorKind::UnopenedAlternates => chars.iter().position(|&c| c == '}'),
+        ErrorKind::NestedAlternates => chars
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c == '{')
+            .nth(1)
+            .map(|(i, _)| i),
+        ErrorKind::DanglingEscape => chars.len().checked_sub(1),
+        ErrorKind::InvalidRange(start, _) => chars.iter().position(|c| c == start),
+        ErrorKind::InvalidRecursive => pattern.find("**").map(|b| pattern[..b].chars().count()),
+        _ => None,
+    }
+}
+
+// How a file's size or age is compared with a condition's threshold
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    GreaterThan,
+    LessThan,
+}
+
+impl Comparison {
+    fn holds(&self, value: u64, threshold: u64) -> bool {
+        match self {
+            Comparison::GreaterThan => value > threshold,
+            Comparison::LessThan => value < threshold,
+        }
+    }
+}
+
+/// A condition tree a file must satisfy, in addition to a rule's pattern.
+///
+/// Serialized with a "type" tag, e.g. PDFs over 1 MB not touched for a year:
+/// `{"type": "all", "conditions": [{"type": "extension", "extensions": ["pdf"]},
+/// {"type": "size", "compare": "greater_than", "bytes": 1048576},
+/// {"type": "age", "compare": "greater_than", "days": 365}]}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    All { conditions: Vec<Condition> },
+    Any { conditions: Vec<Condition> },
+    Not { condition: Box<Condition> },
+    /// Extension without the dot, in any case
+    Extension { extensions: Vec<String> },
+    /// File type as the index classifies it; "image" includes screenshots
+    FileType { file_types: Vec<String> },
+    Size { compare: Comparison, bytes: u64 },
+    /// Days since the file was last modified
+    Age { compare: Comparison, days: u64 },
+    /// Glob or regex, matched the same way as a rule's pattern
+    Path {
+        pattern: String,
+        #[serde(default)]
+        kind: PatternKind,
+    },
+}
+
+impl Condition {
+    fn leaf_count(&self) -> usize {
+        match self {
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                conditions.iter().map(Condition::leaf_count).sum()
+            }
+            Condition::Not { condition } => condition.leaf_count(),
+            _ => 1,
+        }
+    }
+}
+
+/// What a condition is evaluated against. Size and modification time are
+/// None when unknown, and size or age conditions on them don't hold.
+pub struct FileFacts {
+    pub path: PathBuf,
+    pub size: Option<u64>,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+    file_type: OnceCell<String>,
+}
+
+impl FileFacts {
+    /// Facts read from the file on disk
+    pub fn from_path(path: &Path) -> Self {
+        let metadata = fs::metadata(fileops::long_path(path)).ok();
+        Self {
+            path: path.to_path_buf(),
+            size: metadata.as_ref().map(|m| m.len()),
+            modified: metadata
+                .and_then(|m| m.modified().ok())
+                .map(chrono::DateTime::<chrono::Utc>::from),
+            file_type: OnceCell::new(),
+        }
+    }
+
+    /// Facts from the metadata cache, without touching the filesystem
+    pub fn from_indexed(file: &IndexedFile) -> Self {
+        Self {
+            path: PathBuf::from(&file.path),
+            size: Some(file.size),
+            modified: chrono::DateTime::parse_from_rfc3339(&file.modified_at)
+                .ok()
+                .map(|at| at.with_timezone(&chrono::Utc)),
+            file_type: OnceCell::with_value(file.file_type.clone().unwrap_or_else(|| "other".to_string())),
+        }
+    }
+
+    /// The file's type, detected from its contents on first use unless it
+    /// came from the index
+    pub fn file_type(&self) -> &str {
+        self.file_type.get_or_init(|| {
+            let extension = self.path.extension().map(|e| e.to_string_lossy().to_string());
+            detect_type(&fileops::long_path(&self.path), extension.as_deref()).0
+        })
+    }
+}
+
+// A condition with its patterns compiled
+enum CompiledCondition {
+    All(Vec<CompiledCondition>),
+    Any(Vec<CompiledCondition>),
+    Not(Box<CompiledCondition>),
+    Extension(Vec<String>),
+    FileType(Vec<String>),
+    Size(Comparison, u64),
+    Age(Comparison, u64),
+    Path(PatternMatcher),
+}
+
+impl CompiledCondition {
+    fn compile(condition: &Condition) -> Result<Self, String> {
+        Ok(match condition {
+            Condition::All { conditions } | Condition::Any { conditions } => {
+                if conditions.is_empty() {
+                    return Err("Condition group is empty".to_string());
+                }
+                let compiled = conditions
+                    .iter()
+                    .map(CompiledCondition::compile)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if matches!(condition, Condition::All { .. }) {
+                    CompiledCondition::All(compiled)
+                } else {
+                    CompiledCondition::Any(compiled)
+                }
+            }
+            Condition::Not { condition } => CompiledCondition::Not(Box::new(CompiledCondition::compile(condition)?)),
+            Condition::Extension { extensions } => {
+                let extensions: Vec<String> = extensions
+                    .iter()
+                    .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|ext| !ext.is_empty())
+                    .collect();
+                if extensions.is_empty() {
+                    return Err("Extension condition has no extensions".to_string());
+                }
+                CompiledCondition::Extension(extensions)
+            }
+            Condition::FileType { file_types } => {
+                let mut file_types: Vec<String> = file_types
+                    .iter()
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                if file_types.is_empty() {
+                    return Err("File type condition has no file types".to_string());
+                }
+                if file_types.iter().any(|t| t == "image") {
+                    file_types.push(SCREENSHOT_FILE_TYPE.to_string());
+                }
+                CompiledCondition::FileType(file_types)
+            }
+            Condition::Size { compare, bytes } => CompiledCondition::Size(*compare, *bytes),
+            Condition::Age { compare, days } => CompiledCondition::Age(*compare, *days),
+            Condition::Path { pattern, kind } => CompiledCondition::Path(
+                PatternMatcher::compile(pattern.trim(), *kind)
+                    .map_err(|e| format!("Invalid path condition: {}", e.message))?,
+            ),
+        })
+    }
+
+    fn holds(&self, file: &FileFacts, now: chrono::DateTime<chrono::Utc>) -> bool {
+        match self {
+            CompiledCondition::All(conditions) => conditions.iter().all(|c| c.holds(file, now)),
+            CompiledCondition::Any(conditions) => conditions.iter().any(|c| c.holds(file, now)),
+            CompiledCondition::Not(condition) => !condition.holds(file, now),
+            CompiledCondition::Extension(extensions) => file
+                .path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .is_some_and(|ext| extensions.contains(&ext)),
+            CompiledCondition::FileType(file_types) => file_types.iter().any(|t| t == file.file_type()),
+            CompiledCondition::Size(compare, bytes) => file.size.is_some_and(|size| compare.holds(size, *bytes)),
+            CompiledCondition::Age(compare, days) => file.modified.is_some_and(|modified| {
+                let age = (now - modified).num_days().max(0) as u64;
+                compare.holds(age, *days)
+            }),
+            CompiledCondition::Path(matcher) => matcher.is_match(&file.path),
+        }
+    }
+}
+
+/// A rule compiled for matching
+pub struct CompiledRule {
+    matcher: Option<PatternMatcher>,
+    conditions: Option<CompiledCondition>,
+    destination: String,
+}
+
+impl CompiledRule {
+    /// The pattern may be left empty when the rule has conditions
+    pub fn compile(
+        pattern: &str,
+        kind: PatternKind,
+        conditions: Option<&Condition>,
+        destination: &str,
+    ) -> Result<Self, String> {
+        let matcher = if pattern.trim().is_empty() && conditions.is_some() {
+            None
+        } else {
+            Some(
+                PatternMatcher::compile(pattern.trim(), kind)
+                    .map_err(|e| format!("Invalid pattern: {}", e.message))?,
+            )
+        };
+        let conditions = conditions.map(CompiledCondition::compile).transpose()?;
+
+        Ok(Self {
+            matcher,
+            conditions,
+            destination: destination.trim().to_string(),
+        })
+    }
+
+    pub fn matches(&self, file: &FileFacts) -> bool {
+        self.matcher.as_ref().map_or(true, |m| m.is_match(&file.path))
+            && self
+                .conditions
+                .as_ref()
+                .map_or(true, |c| c.holds(file, chrono::Utc::now()))
+    }
+
+    /// Where a matching file would be moved.
+    ///
+    /// Absolute destinations are used as-is; relative ones are resolved
+    /// against the folder the file currently lives in.
+    pub fn destination_for(&self, path: &Path) -> PathBuf {
+        let destination = Path::new(&self.destination);
+        let folder = if destination.is_absolute() {
+            destination.to_path_buf()
+        } else {
+            path.parent()
+                .map(|p| p.join(destination))
+                .unwrap_or_else(|| destination.to_path_buf())
+        };
+
+        match path.file_name() {
+            Some(name) => folder.join(name),
+            None => folder,
+        }
+    }
+}
+
+// How the winning rule was chosen among several matches
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DecidedBy {
+    OnlyMatch,
+    Priority,
+    Specificity,
+    Recency,
+}
+
+// One rule's part in a decision
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleCandidate {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub priority: i64,
+    pub specificity: usize,
+    pub created_at: String,
+    pub matched: bool,
+    pub destination: Option<String>,
+    /// Why the rule was not considered (inactive, invalid pattern)
+    pub skipped_reason: Option<String>,
+}
+
+// Full trace of how a destination was chosen for one path
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleDecision {
+    pub path: String,
+    /// Matching candidates first, in resolution order
+    pub candidates: Vec<RuleCandidate>,
+    pub winner: Option<String>,
+    pub destination: Option<String>,
+    pub decided_by: Option<DecidedBy>,
+    /// Several rules with the same priority matched
+    pub ambiguous: bool,
+}
+
+/// Rough measure of how narrow a pattern is: literal characters count,
+/// and path-aware patterns outrank name-only ones
+pub fn specificity(pattern: &str, kind: PatternKind) -> usize {
+    let pattern = pattern.trim();
+    match kind {
+        PatternKind::Glob => {
+            let literals = pattern
+                .chars()
+                .filter(|c| !matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | ','))
+                .count();
+            let path_bonus = if pattern.contains('/') { 100 } else { 0 };
+            literals + path_bonus
+        }
+        PatternKind::Regex => pattern.chars().filter(|c| c.is_alphanumeric()).count(),
+    }
+}
+
+/// Active rules compiled for matching.
+///
+/// When several rules match a file the winner is picked deterministically:
+/// highest priority, then highest specificity, then most recently created.
+pub struct RuleSet {
+    rules: Vec<(Rule, CompiledRule, usize)>,
+    skipped: Vec<(Rule, String)>,
+}
+
+impl RuleSet {
+    pub fn load_active() -> Result<Self, String> {
+        Ok(Self::from_rules(storage::list_rules()?))
+    }
+
+    /// Compile the active rules among `all_rules` without touching the database,
+    /// e.g. to try out rules that are not saved yet
+    pub fn from_rules(all_rules: Vec<Rule>) -> Self {
+        let mut rules = Vec::new();
+        let mut skipped = Vec::new();
+        for rule in all_rules {
+            if !rule.is_active {
+                skipped.push((rule, "inactive".to_string()));
+                continue;
+            }
+            match CompiledRule::compile(
+                &rule.pattern,
+                rule.pattern_kind,
+                rule.conditions.as_ref(),
+                &rule.destination,
+            ) {
+                Ok(compiled) => {
+                    let score = rule.specificity();
+                    rules.push((rule, compiled, score));
+                }
+                Err(e) => {
+                    eprintln!("Skipping rule '{}': {}", rule.name, e);
+                    skipped.push((rule, e));
+                }
+            }
+        }
+
+        // Resolution order: priority, specificity, recency
+        rules.sort_by(|(a, _, a_score), (b, _, b_score)| {
+            b.priority
+                .cmp(&a.priority)
+                .then(b_score.cmp(a_score))
+                .then(b.created_at.cmp(&a.created_at))
+        });
+
+        Self { rules, skipped }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate every rule against `file` and explain the outcome
+    pub fn decide(&self, file: &FileFacts) -> RuleDecision {
+        let path = file.path.as_path();
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+
+        for (rule, compiled, score) in &self.rules {
+            let is_match = compiled.matches(file);
+            let candidate = RuleCandidate {
+                rule_id: rule.id.clone(),
+                rule_name: rule.name.clone(),
+                priority: rule.priority,
+                specificity: *score,
+                created_at: rule.created_at.clone(),
+                matched: is_match,
+                destination: is_match
+                    .then(|| compiled.destination_for(path).to_string_lossy().to_string()),
+                skipped_reason: None,
+            };
+            if is_match {
+                matched.push(candidate);
+            } else {
+                unmatched.push(candidate);
+            }
+        }
+
+        let decided_by = match (matched.first(), matched.get(1)) {
+            (None, _) => None,
+            (Some(_), None) => Some(DecidedBy::OnlyMatch),
+            (Some(first), Some(second)) => Some(if first.priority != second.priority {
+                DecidedBy::Priority
+            } else if first.specificity != second.specificity {
+                DecidedBy::Specificity
+            } else {
+                DecidedBy::Recency
+            }),
+        };
+        let ambiguous = matches!(decided_by, Some(DecidedBy::Specificity | DecidedBy::Recency));
+        let winner = matched.first().map(|c| c.rule_id.clone());
+        let destination = matched.first().and_then(|c| c.destination.clone());
+
+        let skipped = self.skipped.iter().map(|(rule, reason)| RuleCandidate {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            priority: rule.priority,
+            specificity: rule.specificity(),
+            created_at: rule.created_at.clone(),
+            matched: false,
+            destination: None,
+            skipped_reason: Some(reason.clone()),
+        });
+
+        RuleDecision {
+            path: path.to_string_lossy().to_string(),
+            candidates: matched.into_iter().chain(unmatched).chain(skipped).collect(),
+            winner,
+            destination,
+            decided_by,
+            ambiguous,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// ============================================================================
+// Scan Module - Ignore patterns, hidden files and derived data for directory scans
+// ============================================================================
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::fileops;
+use crate::projects;
+use crate::storage;
+
+/// Exclusions applied until the user edits them
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
+    "node_modules/",
+    ".git/",
+    "$RECYCLE.BIN/",
+    "System Volume Information/",
+];
+
+const IGNORE_PATTERNS_KEY: &str = "ignore_patterns";
+
+// Files a tool generated and can generate again
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DerivedKind {
+    Dependencies,
+    BuildOutput,
+    Cache,
+    VirtualEnv,
+}
+
+impl DerivedKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DerivedKind::Dependencies => "dependencies",
+            DerivedKind::BuildOutput => "build_output",
+            DerivedKind::Cache => "cache",
+            DerivedKind::VirtualEnv => "virtual_env",
+        }
+    }
+}
+
+// Folder names that hold derived data, and the sibling entries that have to
+// be present for the name to count; generic names like "build" only count
+// next to the project file that produces them
+const DERIVED_FOLDERS: &[(&str, DerivedKind, &[&str], &[&str])] = &[
+    ("node_modules", DerivedKind::Dependencies, &[], &[]),
+    ("bower_components", DerivedKind::Dependencies, &[], &[]),
+    ("Pods", DerivedKind::Dependencies, &["Podfile"], &[]),
+    ("vendor", DerivedKind::Dependencies, &["composer.json"], &[]),
+    ("__pycache__", DerivedKind::Cache, &[], &[]),
+    (".pytest_cache", DerivedKind::Cache, &[], &[]),
+    (".mypy_cache", DerivedKind::Cache, &[], &[]),
+    (".ruff_cache", DerivedKind::Cache, &[], &[]),
+    (".tox", DerivedKind::Cache, &[], &[]),
+    (".gradle", DerivedKind::Cache, &[], &[]),
+    (".parcel-cache", DerivedKind::Cache, &[], &[]),
+    (".next", DerivedKind::BuildOutput, &["package.json"], &[]),
+    (".nuxt", DerivedKind::BuildOutput, &["package.json"], &[]),
+    (".svelte-kit", DerivedKind::BuildOutput, &["package.json"], &[]),
+    ("target", DerivedKind::BuildOutput, &["Cargo.toml", "pom.xml"], &[]),
+    (
+        "build",
+        DerivedKind::BuildOutput,
+        &["package.json", "build.gradle", "build.gradle.kts", "CMakeLists.txt", "setup.py", "pyproject.toml"],
+        &[],
+    ),
+    ("dist", DerivedKind::BuildOutput, &["package.json", "setup.py", "pyproject.toml"], &[]),
+    ("obj", DerivedKind::BuildOutput, &[], &["csproj", "fsproj", "vbproj"]),
+    ("bin", DerivedKind::BuildOutput, &[], &["csproj", "fsproj", "vbproj"]),
+];
+
+/// What kind of derived data the folder at `path` holds, if any: dependency
+/// installs, build output, caches and Python virtual environments, which
+/// can be deleted and rebuilt from the project that made them
+pub fn derived_kind(path: &Path) -> Option<DerivedKind> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let folder = fileops::long_path(path);
+    if folder.join("pyvenv.cfg").is_file() {
+        return Some(DerivedKind::VirtualEnv);
+    }
+    // Standard marker tools put in cache folders (Cargo's target/ has one)
+    if folder.join("CACHEDIR.TAG").is_file() {
+        return Some(DerivedKind::Cache);
+    }
+
+    let (_, kind, names, extensions) = DERIVED_FOLDERS.iter().find(|(folder_name, ..)| *folder_name == name)?;
+    if names.is_empty() && extensions.is_empty() {
+        return folder.is_dir().then_some(*kind);
+    }
+    let parent = path.parent()?;
+    (folder.is_dir() && projects::marker_in(parent, names, extensions).is_some()).then_some(*kind)
+}
+
+/// Load the user's ignore patterns, falling back to the defaults
+pub fn load_ignore_patterns() -> Vec<String> {
+    storage::get_preference(IGNORE_PATTERNS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_else(|| DEFAULT_IGNORE_PATTERNS.iter().map(|p| p.to_string()).collect())
+}
+
+/// Validate and persist ignore patterns
+pub fn save_ignore_patterns(patterns: &[String]) -> Result<(), String> {
+    build_gitignore(Path::new(""), patterns)?;
+    let value = serde_json::to_string(patterns).map_err(|e| e.to_string())?;
+    storage::set_preference(IGNORE_PATTERNS_KEY, &value)
+}
+
+// Decides which entries a scan should include
+pub struct ScanFilter {
+    ignore: Gitignore,
+    show_hidden: bool,
+}
+
+impl ScanFilter {
+    /// Filter rooted at `root` using the saved ignore patterns
+    pub fn new(root: &Path, show_hidden: bool) -> Result<Self, String> {
+        Ok(Self {
+            ignore: build_gitignore(root, &load_ignore_patterns())?,
+            show_hidden,
+        })
+    }
+
+    pub fn allows(&self, path: &Path, is_dir: bool) -> bool {
+        if !self.show_hidden && is_hidden(path) {
+            return false;
+        }
+        !self.ignore.matched(path, is_dir).is_ignore()
+    }
+
+    /// Like `allows`, but also rejects paths inside an excluded folder
+    pub fn allows_with_ancestors(&self, path: &Path) -> bool {
+        if !self.allows(path, path.is_dir()) {
+            return false;
+        }
+        path.ancestors()
+            .skip(1)
+            .filter(|ancestor| ancestor.file_name().is_some())
+            .all(|ancestor| self.allows(ancestor, true))
+    }
+}
+
+/// Dot-files everywhere, plus the hidden attribute on Windows
+pub fn is_hidden(path: &Path) -> bool {
+    let dot_file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(false);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+                return true;
+            }
+        }
+    }
+
+    dot_file
+}
+
+fn build_gitignore(root: &Path, patterns: &[String]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| format!("Invalid ignore pattern '{}': {}", pattern, e))?;
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Failed to build ignore list: {}", e))
+}
@@ -0,0 +1,170 @@
+// ============================================================================
+// Screenshot Module - Telling screenshots apart from other images
+// ============================================================================
+
+use chrono::Datelike;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::fileops;
+
+/// File type given to images detected as screenshots
+pub const SCREENSHOT_FILE_TYPE: &str = "screenshot";
+
+/// How much of a PNG is read looking for text chunks before the image data
+const PNG_HEADER_BYTES: u64 = 64 * 1024;
+
+// Names screenshot tools give their files, in the languages the OS tools
+// ship in, plus common third-party tools
+static SCREENSHOT_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(screenshot|screen[ _]shot|screencap|scr_?\d|cleanshot|capture d.(é|e)cran|captura de pantalla|captura de ecr(ã|a)|bildschirmfoto|schermafbeelding|schermata|skärmavbild|skjermbilde|zrzut ekranu|снимок экрана|スクリーンショット|屏幕截图|截屏)",
+    )
+    .unwrap()
+});
+
+// A date in a file name: 2024-03-01, 2024_03_01, 20240301
+static NAME_DATE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^0-9])((?:19|20)\d{2})[-_.]?(0[1-9]|1[0-2])[-_.]?(0[1-9]|[12]\d|3[01])").unwrap());
+
+// Screenshot tools as they name themselves in a PNG's Software chunk
+const SCREENSHOT_SOFTWARE: &[&str] = &[
+    "screenshot",
+    "greenshot",
+    "sharex",
+    "spectacle",
+    "flameshot",
+    "shutter",
+    "ksnip",
+];
+
+// Screen sizes in pixels, landscape; portrait captures are checked swapped
+const SCREEN_RESOLUTIONS: &[(u32, u32)] = &[
+    (1280, 720),
+    (1280, 800),
+    (1366, 768),
+    (1440, 900),
+    (1536, 864),
+    (1600, 900),
+    (1680, 1050),
+    (1920, 1080),
+    (1920, 1200),
+    (2560, 1440),
+    (2560, 1600),
+    (2880, 1800),
+    (3024, 1964),
+    (3456, 2234),
+    (3840, 2160),
+    (5120, 2880),
+    // Phones
+    (2340, 1080),
+    (2400, 1080),
+    (2532, 1170),
+    (2556, 1179),
+    (2778, 1284),
+    (2796, 1290),
+    (1334, 750),
+    (2208, 1242),
+];
+
+/// Whether the name alone looks like a screenshot tool named the file
+pub fn has_screenshot_name(name: &str) -> bool {
+    SCREENSHOT_NAME.is_match(name)
+}
+
+/// Whether an image is a screenshot: named like one, tagged as one by the
+/// tool that saved it, or a PNG at a screen resolution with a date in its
+/// name. The resolution alone isn't enough, since wallpapers share it.
+pub fn is_screenshot(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if has_screenshot_name(&name) {
+        return true;
+    }
+    let Some(png) = png_info(path) else {
+        return false;
+    };
+    png.tagged_as_screenshot || (is_screen_resolution(png.width, png.height) && NAME_DATE.is_match(&name))
+}
+
+/// Year a screenshot was taken: the date in its name, which survives copies,
+/// else its modification time
+pub fn year_taken(path: &Path) -> Option<i32> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    if let Some(year) = NAME_DATE
+        .captures(&name)
+        .and_then(|captures| captures[1].parse().ok())
+    {
+        return Some(year);
+    }
+    let modified = fs::metadata(fileops::long_path(path)).ok()?.modified().ok()?;
+    Some(chrono::DateTime::<chrono::Local>::from(modified).year())
+}
+
+fn is_screen_resolution(width: u32, height: u32) -> bool {
+    SCREEN_RESOLUTIONS
+        .iter()
+        .any(|&(w, h)| (width, height) == (w, h) || (width, height) == (h, w))
+}
+
+struct PngInfo {
+    width: u32,
+    height: u32,
+    tagged_as_screenshot: bool,
+}
+
+// Dimensions from IHDR and whether a text chunk before the image data
+// names a screenshot tool; None for anything that isn't a PNG
+fn png_info(path: &Path) -> Option<PngInfo> {
+    let mut bytes = Vec::new();
+    File::open(fileops::long_path(path))
+        .ok()?
+        .take(PNG_HEADER_BYTES)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") || bytes.len() < 24 || &bytes[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+
+    let mut tagged_as_screenshot = false;
+    let mut offset = 8;
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().ok()?) as usize;
+        let kind = &bytes[offset + 4..offset + 8];
+        let data_end = (offset + 8).saturating_add(length);
+        if kind == b"IDAT" || data_end > bytes.len() {
+            break;
+        }
+        // zTXt is compressed and never used by the tools we look for
+        if kind == b"tEXt" || kind == b"iTXt" {
+            let data = &bytes[offset + 8..data_end];
+            let keyword_end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+            let keyword = String::from_utf8_lossy(&data[..keyword_end]);
+            let text = String::from_utf8_lossy(&data[keyword_end..]).to_lowercase();
+            // macOS stores "Screenshot" as the user comment in its XMP chunk
+            tagged_as_screenshot = match keyword.as_ref() {
+                "Software" => SCREENSHOT_SOFTWARE.iter().any(|tool| text.contains(tool)),
+                "XML:com.adobe.xmp" => text.contains("<exif:usercomment>screenshot"),
+                _ => false,
+            };
+            if tagged_as_screenshot {
+                break;
+            }
+        }
+        // Length, type, data and CRC
+        offset = data_end + 4;
+    }
+
+    Some(PngInfo {
+        width,
+        height,
+        tagged_as_screenshot,
+    })
+}
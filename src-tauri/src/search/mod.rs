@@ -0,0 +1,228 @@
+// ============================================================================
+// Search Module - Query model, filters and rank fusion for hybrid search
+// ============================================================================
+
+pub mod query;
+pub mod smart;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::screenshot::SCREENSHOT_FILE_TYPE;
+use crate::storage::{self, IndexedFile};
+
+/// Rank offset for reciprocal rank fusion; 60 is the usual choice
+const RRF_K: f32 = 60.0;
+
+const SAVED_SEARCHES_KEY: &str = "saved_searches";
+
+// A named query string in the advanced search syntax
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+}
+
+/// Load the user's saved searches
+pub fn load_saved_searches() -> Vec<SavedSearch> {
+    storage::get_preference(SAVED_SEARCHES_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Persist saved searches
+pub fn save_saved_searches(searches: &[SavedSearch]) -> Result<(), String> {
+    let value = serde_json::to_string(searches).map_err(|e| e.to_string())?;
+    storage::set_preference(SAVED_SEARCHES_KEY, &value)
+}
+
+/// Look up a saved search by name
+pub fn find_saved_search(name: &str) -> Option<SavedSearch> {
+    load_saved_searches().into_iter().find(|s| s.name == name)
+}
+
+// Structured filters applied to every part of a search
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub file_types: Vec<String>,
+    pub extensions: Vec<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// ISO-8601 date or timestamp, compared against modified_at
+    pub modified_after: Option<String>,
+    pub modified_before: Option<String>,
+    pub path_prefix: Option<String>,
+}
+
+impl SearchFilters {
+    /// Requested file types, lowercased; screenshots are images too
+    pub fn expanded_file_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self.file_types.iter().map(|t| t.to_lowercase()).collect();
+        if types.iter().any(|t| t == "image") && !types.iter().any(|t| t == SCREENSHOT_FILE_TYPE) {
+            types.push(SCREENSHOT_FILE_TYPE.to_string());
+        }
+        types
+    }
+
+    /// Same semantics as the SQL filters, for results that come from elsewhere
+    pub fn matches(&self, file: &IndexedFile) -> bool {
+        if !self.file_types.is_empty() {
+            let file_type = file.file_type.as_deref().unwrap_or("other");
+            if !self.expanded_file_types().iter().any(|t| t.eq_ignore_ascii_case(file_type)) {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let extension = Path::new(&file.path)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !self.extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(&extension)) {
+                return false;
+            }
+        }
+        if self.min_size.is_some_and(|min| file.size < min) {
+            return false;
+        }
+        if self.max_size.is_some_and(|max| file.size > max) {
+            return false;
+        }
+        if self.modified_after.as_deref().is_some_and(|after| file.modified_at.as_str() < after) {
+            return false;
+        }
+        if self.modified_before.as_deref().is_some_and(|before| file.modified_at.as_str() >= before) {
+            return false;
+        }
+        if let Some(prefix) = &self.path_prefix {
+            if !file.path.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// A hybrid search request: any combination of keywords, filters and a
+// semantic description
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchQuery {
+    pub text: Option<String>,
+    /// Keywords that must not appear
+    pub excluded: Vec<String>,
+    pub semantic: Option<String>,
+    pub filters: SearchFilters,
+    pub limit: Option<usize>,
+}
+
+/// FTS5 expression matching every term as a prefix and none of the
+/// excluded ones, or None when there are no positive terms
+pub fn fts_expression(text: &str, excluded: &[String]) -> Option<String> {
+    let terms = fts_terms(text);
+    if terms.is_empty() {
+        return None;
+    }
+
+    let mut expression = terms.join(" ");
+    for term in excluded.iter().flat_map(|e| fts_terms(e)) {
+        expression.push_str(" NOT ");
+        expression.push_str(&term);
+    }
+    Some(expression)
+}
+
+fn fts_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect()
+}
+
+// Byte range of a matched term within a piece of text
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+// Matching context to show under a result
+#[derive(Debug, Clone, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<Highlight>,
+}
+
+/// Turn text with `open`/`close` markers around matches into plain text and
+/// the byte ranges of the matches
+pub fn snippet_from_marked(marked: &str, open: char, close: char) -> Snippet {
+    let mut text = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut start = None;
+
+    for c in marked.chars() {
+        if c == open {
+            start = Some(text.len());
+        } else if c == close {
+            if let Some(start) = start.take() {
+                highlights.push(Highlight { start, end: text.len() });
+            }
+        } else {
+            text.push(c);
+        }
+    }
+
+    Snippet { text, highlights }
+}
+
+/// Byte ranges in `text` of words starting with any of the query terms,
+/// matching the prefix semantics of the FTS expression
+pub fn highlight_terms(text: &str, terms: &str) -> Vec<Highlight> {
+    let terms: Vec<String> = terms
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut highlights = Vec::new();
+    let mut word_start = None;
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (c.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(index),
+            (false, Some(start)) => {
+                let word = text[start..index].to_lowercase();
+                if let Some(term) = terms.iter().find(|term| word.starts_with(term.as_str())) {
+                    // Highlight only the matched prefix when lowercasing kept byte lengths
+                    let end = if word.len() == index - start { start + term.len() } else { index };
+                    highlights.push(Highlight { start, end });
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    highlights
+}
+
+/// Merge ranked lists of keys with reciprocal rank fusion, best first
+pub fn reciprocal_rank_fusion(lists: &[Vec<String>]) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    for list in lists {
+        for (rank, key) in list.iter().enumerate() {
+            *scores.entry(key.as_str()).or_insert(0.0) += 1.0 / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+
+    let mut merged: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(key, score)| (key.to_string(), score))
+        .collect();
+    merged.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    merged
+}
@@ -0,0 +1,274 @@
+// ============================================================================
+// Query Parser - Advanced search strings into structured queries
+// ============================================================================
+//
+// Supported syntax (terms are ANDed):
+//
+//   invoice "tax return"     keyword terms and phrases
+//   -draft                   exclude a keyword
+//   type:image ext:pdf       file type / extension (repeat for OR)
+//   size:>10MB size:1MB..5MB size bounds, 1024-based units
+//   modified:2023-05         modified within a year, month or day
+//   after:2023 before:2024-06-01
+//   in:/home/me/Documents    path prefix
+//   ~"beach sunset"          semantic description
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use super::SearchQuery;
+
+// Problem with part of the input, with its character offset
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedQuery {
+    pub query: SearchQuery,
+    pub errors: Vec<QueryError>,
+}
+
+impl ParsedQuery {
+    /// The query, or every parse error joined into one message
+    pub fn into_result(self) -> Result<SearchQuery, String> {
+        if self.errors.is_empty() {
+            return Ok(self.query);
+        }
+        let messages: Vec<String> = self
+            .errors
+            .iter()
+            .map(|e| format!("{} (at {})", e.message, e.position))
+            .collect();
+        Err(format!("Invalid search query: {}", messages.join("; ")))
+    }
+}
+
+// A whitespace-separated token; quoted sections may contain spaces
+struct Token {
+    text: String,
+    position: usize,
+}
+
+/// Parse a search string. Invalid parts are reported and skipped, so a
+/// partially typed query still searches on whatever is valid.
+pub fn parse(input: &str) -> ParsedQuery {
+    let mut query = SearchQuery::default();
+    let mut errors = Vec::new();
+    let mut terms: Vec<String> = Vec::new();
+    let mut semantic: Vec<String> = Vec::new();
+
+    for token in tokenize(input) {
+        let text = token.text.as_str();
+
+        if let Some(rest) = text.strip_prefix('~') {
+            semantic.push(unquote(rest));
+            continue;
+        }
+        if let Some(rest) = text.strip_prefix('-').filter(|r| !r.is_empty()) {
+            query.excluded.push(unquote(rest));
+            continue;
+        }
+
+        let Some((key, value)) = text.split_once(':').filter(|(k, _)| is_key(k)) else {
+            terms.push(unquote(text));
+            continue;
+        };
+        let value = unquote(value);
+        let value_position = token.position + key.chars().count() + 1;
+        let error = |message: String| QueryError {
+            message,
+            position: value_position,
+        };
+
+        if value.is_empty() {
+            errors.push(error(format!("Missing value for '{}'", key)));
+            continue;
+        }
+
+        let filters = &mut query.filters;
+        match key.to_lowercase().as_str() {
+            "type" => filters.file_types.push(value.to_lowercase()),
+            "ext" => filters.extensions.push(value.trim_start_matches('.').to_lowercase()),
+            "in" | "path" => filters.path_prefix = Some(value),
+            "size" => match parse_size_filter(&value) {
+                Ok((min, max)) => {
+                    if min.is_some() {
+                        filters.min_size = min;
+                    }
+                    if max.is_some() {
+                        filters.max_size = max;
+                    }
+                }
+                Err(message) => errors.push(error(message)),
+            },
+            "modified" | "date" => match parse_date_filter(&value) {
+                Ok((after, before)) => {
+                    if after.is_some() {
+                        filters.modified_after = after;
+                    }
+                    if before.is_some() {
+                        filters.modified_before = before;
+                    }
+                }
+                Err(message) => errors.push(error(message)),
+            },
+            "after" => match parse_period(&value) {
+                Ok((_, end)) => filters.modified_after = Some(format_date(end)),
+                Err(message) => errors.push(error(message)),
+            },
+            "before" => match parse_period(&value) {
+                Ok((start, _)) => filters.modified_before = Some(format_date(start)),
+                Err(message) => errors.push(error(message)),
+            },
+            "semantic" => semantic.push(value),
+            _ => errors.push(QueryError {
+                message: format!("Unknown filter '{}'", key),
+                position: token.position,
+            }),
+        }
+    }
+
+    if !terms.is_empty() {
+        query.text = Some(terms.join(" "));
+    }
+    if !semantic.is_empty() {
+        query.semantic = Some(semantic.join(" "));
+    }
+
+    ParsedQuery { query, errors }
+}
+
+// "key:" prefixes are plain words; this keeps "C:\\path" and URLs as terms
+fn is_key(key: &str) -> bool {
+    key.len() > 1 && key.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+
+    for (position, c) in input.chars().enumerate() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            if !current.is_empty() {
+                tokens.push(Token {
+                    text: std::mem::take(&mut current),
+                    position: start,
+                });
+            }
+            continue;
+        }
+        if current.is_empty() {
+            start = position;
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        tokens.push(Token {
+            text: current,
+            position: start,
+        });
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches('"').to_string()
+}
+
+/// Parse "10MB", "1.5g", "512" (bytes) into a byte count
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}'", value))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        other => return Err(format!("Unknown size unit '{}'", other)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+// ">10MB", "<1GB", "1MB..5MB", or an exact size
+fn parse_size_filter(value: &str) -> Result<(Option<u64>, Option<u64>), String> {
+    if let Some((min, max)) = value.split_once("..") {
+        let min = if min.is_empty() { None } else { Some(parse_size(min)?) };
+        let max = if max.is_empty() { None } else { Some(parse_size(max)?) };
+        return Ok((min, max));
+    }
+    if let Some(rest) = value.strip_prefix(">=").or_else(|| value.strip_prefix('>')) {
+        return Ok((Some(parse_size(rest)?), None));
+    }
+    if let Some(rest) = value.strip_prefix("<=").or_else(|| value.strip_prefix('<')) {
+        return Ok((None, Some(parse_size(rest)?)));
+    }
+    let exact = parse_size(value)?;
+    Ok((Some(exact), Some(exact)))
+}
+
+/// "2023", "2023-05" or "2023-05-10" as a half-open [start, end) date range
+fn parse_period(value: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let invalid = || format!("Invalid date '{}'", value);
+    let parts: Vec<&str> = value.split('-').collect();
+
+    let year: i32 = parts[0].parse().map_err(|_| invalid())?;
+    match parts.len() {
+        1 => {
+            let start = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(invalid)?;
+            let end = NaiveDate::from_ymd_opt(year + 1, 1, 1).ok_or_else(invalid)?;
+            Ok((start, end))
+        }
+        2 => {
+            let month: u32 = parts[1].parse().map_err(|_| invalid())?;
+            let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+            let end = if month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)
+            }
+            .ok_or_else(invalid)?;
+            Ok((start, end))
+        }
+        3 => {
+            let start = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid())?;
+            let end = start.succ_opt().ok_or_else(invalid)?;
+            Ok((start, end))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+// ">2023" (after the period), "<2023-05" (before it), or within it
+fn parse_date_filter(value: &str) -> Result<(Option<String>, Option<String>), String> {
+    if let Some(rest) = value.strip_prefix('>') {
+        let (_, end) = parse_period(rest)?;
+        return Ok((Some(format_date(end)), None));
+    }
+    if let Some(rest) = value.strip_prefix('<') {
+        let (start, _) = parse_period(rest)?;
+        return Ok((None, Some(format_date(start))));
+    }
+    let (start, end) = parse_period(value)?;
+    Ok((Some(format_date(start)), Some(format_date(end))))
+}
+
+// Dates compare as strings against modified_at ("%Y-%m-%dT%H:%M:%SZ")
+fn format_date(date: NaiveDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
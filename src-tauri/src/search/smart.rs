@@ -0,0 +1,60 @@
+// ============================================================================
+// Smart Folders - Virtual folders backed by a search or a set of rules
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::rules::{PatternMatcher, Rule};
+use crate::storage::{self, IndexedFile};
+
+const SMART_FOLDERS_KEY: &str = "smart_folders";
+
+// A live view over indexed files; nothing is moved. Membership comes from a
+// search query (advanced syntax) or from matching any of the listed rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartFolder {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// Only consider files under this folder
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// Load the user's smart folders
+pub fn load_smart_folders() -> Vec<SmartFolder> {
+    storage::get_preference(SMART_FOLDERS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Persist smart folders
+pub fn save_smart_folders(folders: &[SmartFolder]) -> Result<(), String> {
+    let value = serde_json::to_string(folders).map_err(|e| e.to_string())?;
+    storage::set_preference(SMART_FOLDERS_KEY, &value)
+}
+
+/// Indexed files matching any of `rules` (by pattern only), under `scope`
+pub fn files_matching_rules(rules: &[Rule], scope: Option<&str>) -> Result<Vec<IndexedFile>, String> {
+    let matchers: Vec<PatternMatcher> = rules
+        .iter()
+        .filter_map(|rule| PatternMatcher::compile(&rule.pattern, rule.pattern_kind).ok())
+        .collect();
+    if matchers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(storage::indexed_files(scope)?
+        .into_iter()
+        .filter(|file| {
+            let path = Path::new(&file.path);
+            matchers.iter().any(|matcher| matcher.is_match(path))
+        })
+        .collect())
+}
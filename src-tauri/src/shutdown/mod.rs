@@ -0,0 +1,219 @@
+// ============================================================================
+// Shutdown Module - Letting in-flight work finish or pause before exit
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::storage;
+
+/// Event emitted while exit waits for operations to finish
+pub const SHUTDOWN_PROGRESS_EVENT: &str = "shutdown-progress";
+
+const INTERRUPTED_KEY: &str = "interrupted_operations";
+const WAIT_FOR_OPERATIONS_KEY: &str = "shutdown_wait_for_operations";
+/// How often exit re-checks the operations it is waiting for
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Kinds of long-running work that exit has to account for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// Applying a plan; stopping halfway leaves a half-organized tree
+    Apply,
+    /// Model download; resumes from the partial file
+    Download,
+    /// Content, media or embedding indexing; picks up where it left off
+    Index,
+}
+
+impl OperationKind {
+    /// Whether the work can stop early and be resumed on the next start
+    pub fn resumable(&self) -> bool {
+        !matches!(self, OperationKind::Apply)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: u64,
+    pub kind: OperationKind,
+    pub label: String,
+    pub started_at: String,
+}
+
+// Payload of the "shutdown-progress" event
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownProgress {
+    /// Operations exit is still waiting for
+    pub remaining: Vec<Operation>,
+    /// e.g. "Finishing 3 operations"
+    pub message: String,
+    pub done: bool,
+}
+
+static OPERATIONS: Lazy<Mutex<BTreeMap<u64, Operation>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+/// Resumable operations that stopped because exit began
+static PAUSED: Lazy<Mutex<Vec<Operation>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static STOPPING: AtomicBool = AtomicBool::new(false);
+static EXIT_ALLOWED: AtomicBool = AtomicBool::new(false);
+
+// Registration of a running operation; dropping it marks the work finished
+pub struct OperationGuard {
+    id: u64,
+    kind: OperationKind,
+}
+
+impl OperationGuard {
+    /// Resumable work should stop at its next safe point once exit begins
+    pub fn should_stop(&self) -> bool {
+        self.kind.resumable() && STOPPING.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let operation = OPERATIONS.lock().remove(&self.id);
+        if let Some(operation) = operation.filter(|_| self.should_stop()) {
+            PAUSED.lock().push(operation);
+        }
+    }
+}
+
+/// Register long-running work for the rest of the guard's lifetime
+pub fn begin(kind: OperationKind, label: &str) -> OperationGuard {
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    OPERATIONS.lock().insert(
+        id,
+        Operation {
+            id,
+            kind,
+            label: label.to_string(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    OperationGuard { id, kind }
+}
+
+/// Whether exit has begun; work split into checkpointed steps can stop
+/// between them even when it isn't resumable as a whole
+pub fn stopping() -> bool {
+    STOPPING.load(Ordering::SeqCst)
+}
+
+/// Operations running right now
+pub fn in_flight() -> Vec<Operation> {
+    OPERATIONS.lock().values().cloned().collect()
+}
+
+/// Whether exit should wait for unfinished applies (the default) rather
+/// than leave them cut off
+pub fn waits_for_operations() -> bool {
+    storage::get_preference(WAIT_FOR_OPERATIONS_KEY)
+        .ok()
+        .flatten()
+        .map(|value| value != "false")
+        .unwrap_or(true)
+}
+
+pub fn set_waits_for_operations(wait: bool) -> Result<(), String> {
+    storage::set_preference(WAIT_FOR_OPERATIONS_KEY, if wait { "true" } else { "false" })
+}
+
+/// Called when the app is asked to exit. Returns true if exit may go ahead
+/// now; otherwise exit is postponed and `app.exit` is called once work has
+/// wound down.
+pub fn request_exit(app: &AppHandle) -> bool {
+    if EXIT_ALLOWED.load(Ordering::SeqCst) {
+        return true;
+    }
+    // A second request while already winding down is ignored; force_exit
+    // is how the user skips the wait
+    if STOPPING.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    if in_flight().is_empty() {
+        EXIT_ALLOWED.store(true, Ordering::SeqCst);
+        finish();
+        return true;
+    }
+
+    let app = app.clone();
+    let wait = waits_for_operations();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let remaining: Vec<Operation> = in_flight()
+                .into_iter()
+                .filter(|operation| wait || operation.kind.resumable())
+                .collect();
+            if remaining.is_empty() {
+                break;
+            }
+            let _ = app.emit(SHUTDOWN_PROGRESS_EVENT, progress(remaining, false));
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        exit(&app);
+    });
+    false
+}
+
+/// Exit without waiting for the remaining operations
+pub fn force_exit(app: &AppHandle) {
+    exit(app);
+}
+
+fn exit(app: &AppHandle) {
+    if EXIT_ALLOWED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    finish();
+    let _ = app.emit(SHUTDOWN_PROGRESS_EVENT, progress(Vec::new(), true));
+    app.exit(0);
+}
+
+// Remember what was paused or cut short, then close the database
+fn finish() {
+    let mut interrupted = PAUSED.lock().clone();
+    interrupted.extend(in_flight());
+    if !interrupted.is_empty() {
+        let saved = serde_json::to_string(&interrupted)
+            .map_err(|e| e.to_string())
+            .and_then(|json| storage::set_preference(INTERRUPTED_KEY, &json));
+        if let Err(e) = saved {
+            eprintln!("Shutdown: failed to save interrupted operations: {}", e);
+        }
+    }
+    if let Err(e) = storage::close_database() {
+        eprintln!("Shutdown: {}", e);
+    }
+}
+
+/// Operations cut short by the last exit, cleared once read so they are
+/// offered for resuming only once
+pub fn take_interrupted() -> Result<Vec<Operation>, String> {
+    let Some(json) = storage::get_preference(INTERRUPTED_KEY)? else {
+        return Ok(Vec::new());
+    };
+    storage::set_preference(INTERRUPTED_KEY, "[]")?;
+    Ok(serde_json::from_str(&json).unwrap_or_default())
+}
+
+fn progress(remaining: Vec<Operation>, done: bool) -> ShutdownProgress {
+    let message = match remaining.len() {
+        0 => "Closing".to_string(),
+        1 => format!("Finishing {}", remaining[0].label),
+        count => format!("Finishing {} operations", count),
+    };
+    ShutdownProgress {
+        remaining,
+        message,
+        done,
+    }
+}
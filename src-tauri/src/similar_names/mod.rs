@@ -0,0 +1,179 @@
+// ============================================================================
+// Similar Names Module - Clustering near-identical file names
+// ============================================================================
+
+use std::collections::HashMap;
+
+/// Words that mark a version or copy rather than what the file is
+const NOISE_WORDS: &[&str] = &[
+    "copy", "final", "new", "old", "draft", "edited", "backup", "bak", "version", "rev", "latest", "updated",
+];
+
+// A file name reduced to what identifies it
+#[derive(Debug, Clone)]
+pub struct NameKey {
+    /// Meaningful tokens joined by spaces, e.g. "report" for "report_final(1)"
+    pub key: String,
+    pub tokens: Vec<String>,
+    /// Lowercase extension; names only cluster within one extension
+    pub extension: String,
+}
+
+/// Reduce a file name to its identifying tokens: lowercase, split on
+/// anything that isn't a letter or digit, with copy/version markers such as
+/// "(1)", "v2", "final" and "copy" dropped
+pub fn name_key(file_name: &str) -> NameKey {
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, extension.to_lowercase()),
+        _ => (file_name, String::new()),
+    };
+
+    let tokens: Vec<String> = stem
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty() && !is_noise(token))
+        .map(str::to_string)
+        .collect();
+
+    NameKey {
+        key: tokens.join(" "),
+        tokens,
+        extension,
+    }
+}
+
+// Copy counters ("1", "02"), version tags ("v2", "rev3") and noise words;
+// four-digit numbers stay since they are usually years
+fn is_noise(token: &str) -> bool {
+    if token.chars().all(|c| c.is_ascii_digit()) {
+        return token.len() <= 2;
+    }
+    for prefix in ["v", "rev", "ver"] {
+        if let Some(rest) = token.strip_prefix(prefix) {
+            if !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()) {
+                return true;
+            }
+        }
+    }
+    NOISE_WORDS.contains(&token)
+}
+
+/// Similarity of two keys between 0 and 1: the better of the edit-distance
+/// ratio of the joined keys and the token overlap (Jaccard)
+pub fn similarity(a: &NameKey, b: &NameKey) -> f32 {
+    if a.key == b.key {
+        return 1.0;
+    }
+    if a.key.is_empty() || b.key.is_empty() {
+        return 0.0;
+    }
+
+    let longest = a.key.chars().count().max(b.key.chars().count());
+    let edit_ratio = 1.0 - levenshtein(&a.key, &b.key) as f32 / longest as f32;
+
+    let shared = a.tokens.iter().filter(|token| b.tokens.contains(token)).count();
+    let union = a.tokens.len() + b.tokens.len() - shared;
+    let overlap = if union == 0 { 0.0 } else { shared as f32 / union as f32 };
+
+    edit_ratio.max(overlap)
+}
+
+// Character edit distance, two rows at a time
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Group names whose similarity reaches `threshold`, transitively. Returns
+/// clusters of two or more indices into `names`, each with the lowest
+/// similarity of the links that joined it.
+pub fn cluster(names: &[NameKey], threshold: f32) -> Vec<(Vec<usize>, f32)> {
+    let mut parent: Vec<usize> = (0..names.len()).collect();
+    let mut weakest: Vec<f32> = vec![1.0; names.len()];
+
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    // Identical keys join without comparing; only one name per key is
+    // compared pairwise below
+    let mut by_extension: HashMap<&str, HashMap<&str, Vec<usize>>> = HashMap::new();
+    for (index, name) in names.iter().enumerate() {
+        // Names made only of markers ("copy (1).txt") say nothing about each other
+        if name.key.is_empty() {
+            continue;
+        }
+        by_extension
+            .entry(name.extension.as_str())
+            .or_default()
+            .entry(name.key.as_str())
+            .or_default()
+            .push(index);
+    }
+
+    for keys in by_extension.values() {
+        let representatives: Vec<usize> = keys
+            .values()
+            .map(|indices| {
+                for &other in &indices[1..] {
+                    let (a, b) = (root(&mut parent, indices[0]), root(&mut parent, other));
+                    parent[b] = a;
+                }
+                indices[0]
+            })
+            .collect();
+
+        for (position, &a) in representatives.iter().enumerate() {
+            let length_a = names[a].key.chars().count();
+            for &b in &representatives[position + 1..] {
+                // The edit ratio can't reach the threshold across a big length gap,
+                // and token overlap needs at least one shared token
+                let length_b = names[b].key.chars().count();
+                let gap = length_a.abs_diff(length_b) as f32 / length_a.max(length_b).max(1) as f32;
+                if 1.0 - gap < threshold && !names[a].tokens.iter().any(|t| names[b].tokens.contains(t)) {
+                    continue;
+                }
+
+                let score = similarity(&names[a], &names[b]);
+                if score < threshold {
+                    continue;
+                }
+                let (root_a, root_b) = (root(&mut parent, a), root(&mut parent, b));
+                if root_a != root_b {
+                    parent[root_b] = root_a;
+                    weakest[root_a] = weakest[root_a].min(weakest[root_b]).min(score);
+                } else {
+                    weakest[root_a] = weakest[root_a].min(score);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..names.len() {
+        let group = root(&mut parent, index);
+        groups.entry(group).or_default().push(index);
+    }
+    let mut clusters: Vec<(Vec<usize>, f32)> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(group, members)| (members, weakest[group]))
+        .collect();
+    clusters.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    clusters
+}
@@ -0,0 +1,217 @@
+// ============================================================================
+// Staging Module - Deletions held back for a grace period before the purge
+// ============================================================================
+
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::fileops::{self, CollisionPolicy, MoveOutcome};
+use crate::shutdown;
+use crate::storage::{self, StagedFile};
+use crate::volumes;
+
+/// Hidden folder the staging area lives in, at the top of the home folder
+/// or of the drive the files came from, so staging is a rename
+const STAGING_FOLDER: &str = ".smart-storage-staging";
+
+/// Days a staged file waits before the automatic purge deletes it
+pub const STAGING_RETENTION_DAYS: i64 = 30;
+
+/// How often the background purge looks for expired files
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeReport {
+    pub batch_id: Option<String>,
+    pub purged: Vec<String>,
+    /// Bytes given back to the drives
+    pub freed: u64,
+    pub errors: Vec<String>,
+}
+
+/// Move `path` into the staging area instead of deleting it, logging a
+/// "stage" change under `batch_id`. It is purged after
+/// STAGING_RETENTION_DAYS unless restored first.
+pub fn stage(path: &Path, batch_id: &str) -> Result<StagedFile, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Cannot stage {}", path.display()))?;
+    let metadata = fs::symlink_metadata(fileops::long_path(path))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let size = if metadata.is_dir() {
+        WalkDir::new(fileops::long_path(path))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    } else {
+        metadata.len()
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let target = staging_folder(path)?.join(&id).join(name);
+    let outcome = fileops::move_with_policy(path, &target, CollisionPolicy::RenameWithSuffix)?;
+
+    let now = chrono::Utc::now();
+    let staged = StagedFile {
+        id,
+        original_path: outcome.source.clone(),
+        staged_path: outcome.destination.clone(),
+        size,
+        batch_id: Some(batch_id.to_string()),
+        staged_at: now.format(storage::INDEX_TIME_FORMAT).to_string(),
+        purge_after: (now + chrono::Duration::days(STAGING_RETENTION_DAYS))
+            .format(storage::INDEX_TIME_FORMAT)
+            .to_string(),
+    };
+    storage::insert_staged_file(&staged)?;
+    storage::record_change(batch_id, "stage", &outcome.source, Some(&outcome.destination), None)?;
+    if let Err(e) = storage::remove_indexed_path(&outcome.source) {
+        eprintln!("Failed to unindex {}: {}", outcome.source, e);
+    }
+    Ok(staged)
+}
+
+/// Put a staged file back where it came from, next to anything that has
+/// taken its name since, logging a "restore" change under `batch_id`
+pub fn restore(id: &str, batch_id: &str) -> Result<MoveOutcome, String> {
+    let staged = storage::staged_file(id)?.ok_or_else(|| format!("Not in the staging area: {}", id))?;
+    let outcome = put_back(&staged)?;
+    storage::record_change(
+        batch_id,
+        "restore",
+        &outcome.source,
+        Some(&outcome.destination),
+        Some(outcome.resolution.as_str()),
+    )?;
+    Ok(outcome)
+}
+
+/// Put back whatever was staged at `staged_path` without logging anything,
+/// for undoing the batch that staged it. None when it has left the staging
+/// area since, restored or purged.
+pub fn unstage(staged_path: &str) -> Result<Option<MoveOutcome>, String> {
+    let staged = storage::list_staged_files()?
+        .into_iter()
+        .find(|staged| staged.staged_path == staged_path);
+    staged.map(|staged| put_back(&staged)).transpose()
+}
+
+fn put_back(staged: &StagedFile) -> Result<MoveOutcome, String> {
+    let outcome = fileops::move_with_policy(
+        Path::new(&staged.staged_path),
+        Path::new(&staged.original_path),
+        CollisionPolicy::RenameWithSuffix,
+    )?;
+    storage::delete_staged_file(&staged.id)?;
+    remove_holder(staged);
+    Ok(outcome)
+}
+
+/// Delete a staged file for good, logging a "purge" change under `batch_id`.
+/// While the staging area itself is missing, e.g. on an unplugged drive,
+/// the file is left staged for a later purge.
+pub fn purge(staged: &StagedFile, batch_id: &str) -> Result<(), String> {
+    let path = fileops::long_path(Path::new(&staged.staged_path));
+    let removed = match fs::symlink_metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&path),
+        Ok(_) => fs::remove_file(&path),
+        Err(e) if e.kind() == ErrorKind::NotFound && !staging_area_present(staged) => {
+            return Err(format!("Staging area unavailable for {}", staged.original_path));
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    };
+    removed.map_err(|e| format!("Failed to delete {}: {}", staged.staged_path, e))?;
+    storage::record_change(batch_id, "purge", &staged.staged_path, None, None)?;
+    storage::delete_staged_file(&staged.id)?;
+    remove_holder(staged);
+    Ok(())
+}
+
+/// Purge `files`, all logged under one history batch
+pub fn purge_all(files: &[StagedFile], label: &str) -> Result<PurgeReport, String> {
+    let mut report = PurgeReport::default();
+    if files.is_empty() {
+        return Ok(report);
+    }
+    let batch_id = storage::create_history_batch(label, &format!("{} item(s)", files.len()))?;
+    for staged in files {
+        match purge(staged, &batch_id) {
+            Ok(()) => {
+                report.freed += staged.size;
+                report.purged.push(staged.original_path.clone());
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+    report.batch_id = Some(batch_id);
+    Ok(report)
+}
+
+/// Purge everything whose grace period is over
+pub fn purge_expired() -> Result<PurgeReport, String> {
+    let now = chrono::Utc::now().format(storage::INDEX_TIME_FORMAT).to_string();
+    purge_all(&storage::expired_staged_files(&now)?, "Purge staging area")
+}
+
+/// Purge expired files now and then every PURGE_INTERVAL while the app runs
+pub fn start_purger() {
+    std::thread::spawn(|| {
+        while !shutdown::stopping() {
+            match purge_expired() {
+                Ok(report) if !report.errors.is_empty() => {
+                    eprintln!("Staging: {}", report.errors.join("; "))
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Staging: {}", e),
+            }
+            std::thread::sleep(PURGE_INTERVAL);
+        }
+    });
+}
+
+// The staging area for `path`: in the home folder for files inside it,
+// otherwise at the top of the file's drive, falling back to the home
+// folder when the drive's root isn't writable
+fn staging_folder(path: &Path) -> Result<PathBuf, String> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    let known = storage::list_volumes()?;
+    let drive = volumes::volume_of(path, &known).map(|volume| PathBuf::from(&volume.mount_point));
+
+    let candidates = match &home {
+        Some(home) if path.starts_with(home) => vec![home.clone()],
+        _ => drive.into_iter().chain(home.clone()).collect(),
+    };
+    for root in candidates {
+        let folder = root.join(STAGING_FOLDER);
+        if fs::create_dir_all(fileops::long_path(&folder)).is_ok() {
+            return Ok(folder);
+        }
+    }
+    Err(format!("No staging area available for {}", path.display()))
+}
+
+// Whether the staging folder a file was staged into is there, so a missing
+// file was really deleted rather than being on a drive that is away
+fn staging_area_present(staged: &StagedFile) -> bool {
+    Path::new(&staged.staged_path)
+        .ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == STAGING_FOLDER))
+        .is_some_and(|folder| fileops::long_path(folder).is_dir())
+}
+
+// Remove the per-entry folder a staged file was held in, once empty
+fn remove_holder(staged: &StagedFile) {
+    if let Some(holder) = Path::new(&staged.staged_path).parent() {
+        let _ = fs::remove_dir(fileops::long_path(holder));
+    }
+}
@@ -0,0 +1,183 @@
+// ============================================================================
+// Startup Module - Background initialization with readiness reporting
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::ai::{self, AiStatus};
+use crate::staging;
+use crate::storage;
+use crate::volumes;
+use crate::watcher;
+
+/// Event emitted whenever a subsystem changes state during startup
+pub const STARTUP_STATUS_EVENT: &str = "startup-status";
+
+// Parts of the app that initialize after the window is shown, in order
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    Database,
+    Watchers,
+    Model,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SubsystemState {
+    Pending,
+    Initializing,
+    Ready,
+    /// Nothing to initialize, e.g. no model downloaded yet
+    Skipped { reason: String },
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemStatus {
+    pub subsystem: Subsystem,
+    #[serde(flatten)]
+    pub state: SubsystemState,
+    /// Time spent initializing, once finished
+    pub elapsed_ms: Option<u64>,
+}
+
+// Payload of the "startup-status" event and of get_startup_status
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupStatus {
+    pub subsystems: Vec<SubsystemStatus>,
+    /// Every subsystem finished, whether or not it succeeded
+    pub done: bool,
+    /// The database is usable; most commands fail until then
+    pub database_ready: bool,
+}
+
+static STATUS: Lazy<Mutex<Vec<SubsystemStatus>>> = Lazy::new(|| {
+    Mutex::new(
+        [Subsystem::Database, Subsystem::Watchers, Subsystem::Model]
+            .into_iter()
+            .map(|subsystem| SubsystemStatus {
+                subsystem,
+                state: SubsystemState::Pending,
+                elapsed_ms: None,
+            })
+            .collect(),
+    )
+});
+
+/// Current state of every subsystem
+pub fn status() -> StartupStatus {
+    let subsystems = STATUS.lock().clone();
+    let finished = |state: &SubsystemState| !matches!(state, SubsystemState::Pending | SubsystemState::Initializing);
+    StartupStatus {
+        done: subsystems.iter().all(|s| finished(&s.state)),
+        database_ready: subsystems
+            .iter()
+            .any(|s| s.subsystem == Subsystem::Database && s.state == SubsystemState::Ready),
+        subsystems,
+    }
+}
+
+/// Initialize the database, restore watched folders, start watching for
+/// drives and load the model in the background, so setup returns and the
+/// window appears right away.
+/// Later steps are skipped when the database can't be opened.
+pub fn begin(app: AppHandle, db_path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let database = run(&app, Subsystem::Database, || {
+            let path = db_path.clone();
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    storage::init_database(&path).map_err(|e| format!("Failed to initialize database: {}", e))
+                })
+                .await
+                .map_err(|e| format!("Database task error: {}", e))??;
+                println!("Database: {:?}", db_path);
+                Ok(None)
+            }
+        })
+        .await;
+        if !database {
+            let reason = "Database unavailable".to_string();
+            set(&app, Subsystem::Watchers, SubsystemState::Skipped { reason: reason.clone() }, None);
+            set(&app, Subsystem::Model, SubsystemState::Skipped { reason }, None);
+            return;
+        }
+
+        run(&app, Subsystem::Watchers, || {
+            let app = app.clone();
+            async move {
+                let failed = tokio::task::spawn_blocking(move || watcher::restore(&app))
+                    .await
+                    .map_err(|e| format!("Watcher task error: {}", e))?;
+                if failed.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(format!("Could not watch: {}", failed.join(", ")))
+                }
+            }
+        })
+        .await;
+        volumes::start_monitor(app.clone());
+        staging::start_purger();
+
+        run(&app, Subsystem::Model, || {
+            let app = app.clone();
+            async move {
+                match ai::init_ai(app).await? {
+                    AiStatus::Ready => Ok(None),
+                    AiStatus::Error { message } => Err(message),
+                    _ => Ok(Some("No model downloaded".to_string())),
+                }
+            }
+        })
+        .await;
+
+        println!("Smart Storage AI initialized");
+    });
+}
+
+// Run one step, reporting its progress; Ok(Some(reason)) means skipped.
+// Returns whether the step succeeded.
+async fn run<F, Fut>(app: &AppHandle, subsystem: Subsystem, step: F) -> bool
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<Option<String>, String>>,
+{
+    set(app, subsystem, SubsystemState::Initializing, None);
+    let started = Instant::now();
+    let result = step().await;
+    let elapsed_ms = Some(started.elapsed().as_millis() as u64);
+
+    match result {
+        Ok(None) => {
+            set(app, subsystem, SubsystemState::Ready, elapsed_ms);
+            true
+        }
+        Ok(Some(reason)) => {
+            set(app, subsystem, SubsystemState::Skipped { reason }, elapsed_ms);
+            true
+        }
+        Err(message) => {
+            eprintln!("Startup: {:?} failed: {}", subsystem, message);
+            set(app, subsystem, SubsystemState::Failed { message }, elapsed_ms);
+            false
+        }
+    }
+}
+
+fn set(app: &AppHandle, subsystem: Subsystem, state: SubsystemState, elapsed_ms: Option<u64>) {
+    {
+        let mut subsystems = STATUS.lock();
+        if let Some(entry) = subsystems.iter_mut().find(|s| s.subsystem == subsystem) {
+            entry.state = state;
+            entry.elapsed_ms = elapsed_ms;
+        }
+    }
+    let _ = app.emit(STARTUP_STATUS_EVENT, status());
+}
@@ -2,10 +2,25 @@
 // Storage Module - SQLite database operations
 // ============================================================================
 
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, OptionalExtension, Result};
+use serde::Serialize;
 use std::path::Path;
 use std::sync::Mutex;
 
+use crate::commands::files::FileNode;
+use crate::events::{ChangeKind, FsChange};
+use crate::folder_style::{FolderColor, FolderStyle};
+use crate::learning::{ObservedMove, RuleSuggestion, SuggestionStatus};
+use crate::media::MediaMetadata;
+use crate::packs::{OrganizationPack, SignatureStatus};
+use crate::search::SearchFilters;
+use crate::rules::{PatternKind, Rule};
+use crate::volumes::Volume;
+
+/// Control characters FTS5 wraps around matched terms in snippets
+pub const SNIPPET_OPEN: char = '\u{2}';
+pub const SNIPPET_CLOSE: char = '\u{3}';
+
 lazy_static::lazy_static! {
     static ref DB: Mutex<Option<Connection>> = Mutex::new(None);
 }
@@ -72,20 +87,2307 @@ pub fn init_database(path: &Path) -> Result<()> {
             created_at TEXT DEFAULT CURRENT_TIMESTAMP
         );
 
+        -- Embedding vectors, shared by every file with the same content
+        CREATE TABLE IF NOT EXISTS embeddings (
+            content_hash TEXT NOT NULL,
+            model_id TEXT NOT NULL,
+            dims INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (content_hash, model_id)
+        );
+
+        -- Audio/video tags and stream properties
+        CREATE TABLE IF NOT EXISTS media_metadata (
+            path TEXT PRIMARY KEY,
+            title TEXT,
+            artist TEXT,
+            album TEXT,
+            genre TEXT,
+            year INTEGER,
+            track INTEGER,
+            duration_ms INTEGER,
+            bitrate_kbps INTEGER,
+            sample_rate INTEGER,
+            channels INTEGER,
+            width INTEGER,
+            height INTEGER,
+            extracted_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Plain text extracted from documents, bounded in length
+        CREATE TABLE IF NOT EXISTS file_contents (
+            path TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            mtime INTEGER NOT NULL,
+            text TEXT NOT NULL,
+            truncated INTEGER NOT NULL DEFAULT 0,
+            extracted_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- User notes attached to files; rows follow the file when it moves
+        CREATE TABLE IF NOT EXISTS file_comments (
+            path TEXT PRIMARY KEY,
+            comment TEXT NOT NULL,
+            updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Where copy-mode plans put each source file, for incremental re-runs
+        CREATE TABLE IF NOT EXISTS copy_mappings (
+            source_path TEXT PRIMARY KEY,
+            destination_path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            mtime INTEGER NOT NULL,
+            copied_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Recursive folder totals; rows are dropped when anything below changes
+        CREATE TABLE IF NOT EXISTS folder_sizes (
+            path TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            file_count INTEGER NOT NULL,
+            computed_at TEXT NOT NULL
+        );
+
+        -- Files and folders the user marked as do-not-touch
+        CREATE TABLE IF NOT EXISTS protected_paths (
+            path TEXT PRIMARY KEY,
+            protected_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Colors and icons for folders with a given name
+        CREATE TABLE IF NOT EXISTS folder_styles (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            color TEXT,
+            icon TEXT,
+            updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Imported organization packs; their rules stay inactive until activated
+        CREATE TABLE IF NOT EXISTS organization_packs (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            version TEXT NOT NULL,
+            content TEXT NOT NULL,
+            signature_status TEXT NOT NULL,
+            installed_at TEXT DEFAULT CURRENT_TIMESTAMP,
+            previewed_at TEXT,
+            activated_at TEXT
+        );
+
+        -- Changes seen by the watcher or made by the app, for since-last-visit reports
+        CREATE TABLE IF NOT EXISTS file_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            old_path TEXT,
+            kind TEXT NOT NULL,
+            occurred_at TEXT NOT NULL
+        );
+
+        -- When the user last looked at each folder
+        CREATE TABLE IF NOT EXISTS folder_visits (
+            path TEXT PRIMARY KEY,
+            last_viewed_at TEXT NOT NULL
+        );
+
+        -- Reports summarizing what automated runs did, as JSON
+        CREATE TABLE IF NOT EXISTS digests (
+            id TEXT PRIMARY KEY,
+            trigger TEXT NOT NULL,
+            period_start TEXT NOT NULL,
+            period_end TEXT NOT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT DEFAULT CURRENT_TIMESTAMP
+        );
+
+        -- Files the user moved between folders by hand, seen by the watcher
+        CREATE TABLE IF NOT EXISTS observed_moves (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            old_path TEXT NOT NULL,
+            extension TEXT NOT NULL,
+            destination_folder TEXT NOT NULL,
+            moved_at TEXT NOT NULL
+        );
+
+        -- Rules proposed from repeated manual moves
+        CREATE TABLE IF NOT EXISTS rule_suggestions (
+            id TEXT PRIMARY KEY,
+            extension TEXT NOT NULL,
+            destination_folder TEXT NOT NULL,
+            move_count INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (extension, destination_folder)
+        );
+
+        -- Drives seen mounted, kept after they are unplugged
+        CREATE TABLE IF NOT EXISTS volumes (
+            id TEXT PRIMARY KEY,
+            label TEXT NOT NULL,
+            mount_point TEXT NOT NULL,
+            file_system TEXT NOT NULL,
+            removable INTEGER NOT NULL,
+            total_size INTEGER NOT NULL,
+            first_seen_at TEXT NOT NULL,
+            last_seen_at TEXT NOT NULL
+        );
+
+        -- Model runs by the feature that asked for them
+        CREATE TABLE IF NOT EXISTS ai_usage (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            feature TEXT NOT NULL,
+            tokens INTEGER NOT NULL,
+            items INTEGER NOT NULL,
+            elapsed_ms INTEGER NOT NULL,
+            recorded_at TEXT NOT NULL
+        );
+
+        -- Files and folders set aside for deletion, purged after a grace period
+        CREATE TABLE IF NOT EXISTS staged_files (
+            id TEXT PRIMARY KEY,
+            original_path TEXT NOT NULL,
+            staged_path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            batch_id TEXT,
+            staged_at TEXT NOT NULL,
+            purge_after TEXT NOT NULL
+        );
+
         -- Indexes
         CREATE INDEX IF NOT EXISTS idx_files_path ON files(path);
         CREATE INDEX IF NOT EXISTS idx_files_type ON files(file_type);
         CREATE INDEX IF NOT EXISTS idx_files_parent ON files(parent_path);
         CREATE INDEX IF NOT EXISTS idx_change_log_batch ON change_log(batch_id);
         CREATE INDEX IF NOT EXISTS idx_change_log_timestamp ON change_log(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_files_content_hash ON files(content_hash);
+        CREATE INDEX IF NOT EXISTS idx_media_artist_album ON media_metadata(artist, album);
+        CREATE INDEX IF NOT EXISTS idx_file_events_occurred ON file_events(occurred_at);
+        CREATE INDEX IF NOT EXISTS idx_observed_moves_target ON observed_moves(extension, destination_folder);
+        CREATE INDEX IF NOT EXISTS idx_ai_usage_recorded ON ai_usage(recorded_at);
+        CREATE INDEX IF NOT EXISTS idx_staged_files_purge ON staged_files(purge_after);
         ",
     )?;
 
+    run_migrations(&conn)?;
+
     *DB.lock().unwrap() = Some(conn);
     Ok(())
 }
 
+// Columns added after the initial schema; existing databases get them on startup
+fn run_migrations(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "change_log", "collision_resolution", "TEXT")?;
+    add_column_if_missing(conn, "rules", "pattern_kind", "TEXT NOT NULL DEFAULT 'glob'")?;
+    add_column_if_missing(conn, "rules", "hit_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "rules", "last_matched_at", "TEXT")?;
+    add_column_if_missing(conn, "rules", "conditions", "TEXT")?;
+    add_column_if_missing(conn, "files", "hashed_size", "INTEGER")?;
+    add_column_if_missing(conn, "files", "hashed_mtime", "INTEGER")?;
+    add_column_if_missing(conn, "change_log", "source_hash", "TEXT")?;
+    add_column_if_missing(conn, "change_log", "destination_hash", "TEXT")?;
+    add_column_if_missing(conn, "change_log", "verified", "INTEGER")?;
+    add_column_if_missing(conn, "files", "volume_id", "TEXT")?;
+    add_column_if_missing(conn, "volumes", "online", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_files_volume ON files(volume_id);")?;
+    create_files_fts(conn)?;
+    create_contents_fts(conn)?;
+    create_comments_fts(conn)?;
+    Ok(())
+}
+
+// Full-text index over file names and paths, kept in sync by triggers
+fn create_files_fts(conn: &Connection) -> Result<()> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'files_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    conn.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS files_fts USING fts5(
+            name, path, content='files', content_rowid='rowid', tokenize='unicode61'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS files_fts_insert AFTER INSERT ON files BEGIN
+            INSERT INTO files_fts(rowid, name, path) VALUES (new.rowid, new.name, new.path);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS files_fts_delete AFTER DELETE ON files BEGIN
+            INSERT INTO files_fts(files_fts, rowid, name, path) VALUES ('delete', old.rowid, old.name, old.path);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS files_fts_update AFTER UPDATE OF name, path ON files BEGIN
+            INSERT INTO files_fts(files_fts, rowid, name, path) VALUES ('delete', old.rowid, old.name, old.path);
+            INSERT INTO files_fts(rowid, name, path) VALUES (new.rowid, new.name, new.path);
+        END;
+        ",
+    )?;
+
+    // Index rows that existed before the FTS table
+    if !exists {
+        conn.execute_batch("INSERT INTO files_fts(files_fts) VALUES ('rebuild');")?;
+    }
+    Ok(())
+}
+
+// Full-text index over extracted document text
+fn create_contents_fts(conn: &Connection) -> Result<()> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'contents_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    conn.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS contents_fts USING fts5(
+            text, content='file_contents', content_rowid='rowid', tokenize='unicode61'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS contents_fts_insert AFTER INSERT ON file_contents BEGIN
+            INSERT INTO contents_fts(rowid, text) VALUES (new.rowid, new.text);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS contents_fts_delete AFTER DELETE ON file_contents BEGIN
+            INSERT INTO contents_fts(contents_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS contents_fts_update AFTER UPDATE OF text ON file_contents BEGIN
+            INSERT INTO contents_fts(contents_fts, rowid, text) VALUES ('delete', old.rowid, old.text);
+            INSERT INTO contents_fts(rowid, text) VALUES (new.rowid, new.text);
+        END;
+        ",
+    )?;
+
+    if !exists {
+        conn.execute_batch("INSERT INTO contents_fts(contents_fts) VALUES ('rebuild');")?;
+    }
+    Ok(())
+}
+
+// Full-text index over file comments
+fn create_comments_fts(conn: &Connection) -> Result<()> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'comments_fts'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    conn.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS comments_fts USING fts5(
+            comment, content='file_comments', content_rowid='rowid', tokenize='unicode61'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS comments_fts_insert AFTER INSERT ON file_comments BEGIN
+            INSERT INTO comments_fts(rowid, comment) VALUES (new.rowid, new.comment);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS comments_fts_delete AFTER DELETE ON file_comments BEGIN
+            INSERT INTO comments_fts(comments_fts, rowid, comment) VALUES ('delete', old.rowid, old.comment);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS comments_fts_update AFTER UPDATE OF comment ON file_comments BEGIN
+            INSERT INTO comments_fts(comments_fts, rowid, comment) VALUES ('delete', old.rowid, old.comment);
+            INSERT INTO comments_fts(rowid, comment) VALUES (new.rowid, new.comment);
+        END;
+        ",
+    )?;
+
+    if !exists {
+        conn.execute_batch("INSERT INTO comments_fts(comments_fts) VALUES ('rebuild');")?;
+    }
+    Ok(())
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, definition))?;
+    }
+    Ok(())
+}
+
 /// Get database connection
 pub fn get_connection() -> Option<std::sync::MutexGuard<'static, Option<Connection>>> {
     DB.lock().ok()
 }
+
+/// Close the database once any running transaction has finished; later
+/// calls fail with "Database not initialized"
+pub fn close_database() -> std::result::Result<(), String> {
+    let mut guard = get_connection().ok_or("Database lock poisoned")?;
+    match guard.take() {
+        Some(conn) => conn.close().map_err(|(_, e)| format!("Failed to close database: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Run a closure against the open database connection
+pub fn with_connection<T, F>(f: F) -> std::result::Result<T, String>
+where
+    F: FnOnce(&Connection) -> Result<T>,
+{
+    let guard = get_connection().ok_or("Database lock poisoned")?;
+    let conn = guard.as_ref().ok_or("Database not initialized")?;
+    f(conn).map_err(|e| format!("Database error: {}", e))
+}
+
+/// Create a history batch and return its id
+pub fn create_history_batch(name: &str, description: &str) -> std::result::Result<String, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO history_batches (id, name, description) VALUES (?1, ?2, ?3)",
+            rusqlite::params![id, name, description],
+        )
+    })?;
+    Ok(id)
+}
+
+/// Record a single operation in the change log
+pub fn record_change(
+    batch_id: &str,
+    operation_type: &str,
+    source_path: &str,
+    destination_path: Option<&str>,
+    collision_resolution: Option<&str>,
+) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO change_log (id, batch_id, operation_type, source_path, destination_path, collision_resolution)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                batch_id,
+                operation_type,
+                source_path,
+                destination_path,
+                collision_resolution
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Record an operation whose undo needs more than the paths, e.g. the
+/// attributes a file had before; `file_data` is JSON
+pub fn record_change_data(
+    batch_id: &str,
+    operation_type: &str,
+    source_path: &str,
+    file_data: &str,
+) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO change_log (id, batch_id, operation_type, source_path, file_data)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                batch_id,
+                operation_type,
+                source_path,
+                file_data
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Mark a file or folder as do-not-touch
+pub fn protect_path(path: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO protected_paths (path) VALUES (?1)",
+            rusqlite::params![path],
+        )
+    })?;
+    Ok(())
+}
+
+/// Clear the do-not-touch mark; returns whether the path was marked
+pub fn unprotect_path(path: &str) -> std::result::Result<bool, String> {
+    let removed = with_connection(|conn| {
+        conn.execute("DELETE FROM protected_paths WHERE path = ?1", rusqlite::params![path])
+    })?;
+    Ok(removed > 0)
+}
+
+/// Every do-not-touch path, sorted
+pub fn protected_paths() -> std::result::Result<Vec<String>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT path FROM protected_paths ORDER BY path")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        let paths: Result<Vec<String>> = rows.collect();
+        paths
+    })
+}
+
+/// Every folder style, by name
+pub fn list_folder_styles() -> std::result::Result<Vec<FolderStyle>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT name, color, icon FROM folder_styles ORDER BY name")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(FolderStyle {
+                name: row.get(0)?,
+                color: row.get::<_, Option<String>>(1)?.as_deref().and_then(FolderColor::parse),
+                icon: row.get(2)?,
+            })
+        })?;
+        let styles: Result<Vec<FolderStyle>> = rows.collect();
+        styles
+    })
+}
+
+/// Insert or replace the style for a folder name
+pub fn save_folder_style(style: &FolderStyle) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO folder_styles (name, color, icon, updated_at) VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+             ON CONFLICT(name) DO UPDATE SET
+                color = excluded.color,
+                icon = excluded.icon,
+                updated_at = excluded.updated_at",
+            rusqlite::params![style.name, style.color.map(|c| c.as_str()), style.icon],
+        )
+    })?;
+    Ok(())
+}
+
+/// Delete the style for a folder name; returns whether one existed
+pub fn delete_folder_style(name: &str) -> std::result::Result<bool, String> {
+    let removed = with_connection(|conn| {
+        conn.execute("DELETE FROM folder_styles WHERE name = ?1", rusqlite::params![name])
+    })?;
+    Ok(removed > 0)
+}
+
+/// Store the hashes taken around a verified operation on its change_log row
+pub fn record_verification(
+    batch_id: &str,
+    destination_path: &str,
+    source_hash: &str,
+    destination_hash: &str,
+) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "UPDATE change_log SET source_hash = ?3, destination_hash = ?4, verified = ?5
+             WHERE batch_id = ?1 AND destination_path = ?2",
+            rusqlite::params![
+                batch_id,
+                destination_path,
+                source_hash,
+                destination_hash,
+                source_hash == destination_hash
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+// A change_log row
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRecord {
+    pub id: String,
+    pub batch_id: String,
+    pub operation_type: String,
+    pub source_path: String,
+    pub destination_path: Option<String>,
+    pub collision_resolution: Option<String>,
+    /// Whether the destination's hash matched the source's, for verified
+    /// (paranoid) operations
+    pub verified: Option<bool>,
+    /// Extra undo data as JSON, see `record_change_data`
+    pub file_data: Option<String>,
+    pub timestamp: String,
+    pub is_undone: bool,
+}
+
+/// Operations recorded under a batch, in the order they were applied
+pub fn batch_changes(batch_id: &str) -> std::result::Result<Vec<ChangeRecord>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, batch_id, operation_type, source_path, destination_path, collision_resolution,
+                    timestamp, is_undone, verified, file_data
+             FROM change_log WHERE batch_id = ?1 ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![batch_id], |row| {
+            Ok(ChangeRecord {
+                id: row.get(0)?,
+                batch_id: row.get(1)?,
+                operation_type: row.get(2)?,
+                source_path: row.get(3)?,
+                destination_path: row.get(4)?,
+                collision_resolution: row.get(5)?,
+                verified: row.get::<_, Option<i64>>(8)?.map(|v| v != 0),
+                file_data: row.get(9)?,
+                timestamp: row.get(6)?,
+                is_undone: row.get::<_, i64>(7)? != 0,
+            })
+        })?;
+        let changes: Result<Vec<ChangeRecord>> = rows.collect();
+        changes
+    })
+}
+
+/// Mark operations of a batch as undone, and the batch itself once none of
+/// its operations is left. Returns whether the whole batch is undone; a
+/// partly undone batch stays open so the rest can be retried.
+pub fn mark_changes_undone(batch_id: &str, change_ids: &[String]) -> std::result::Result<bool, String> {
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for id in change_ids {
+            tx.execute(
+                "UPDATE change_log SET is_undone = 1 WHERE batch_id = ?1 AND id = ?2",
+                rusqlite::params![batch_id, id],
+            )?;
+        }
+        let done = tx.execute(
+            "UPDATE history_batches SET is_undone = 1
+             WHERE id = ?1 AND NOT EXISTS (SELECT 1 FROM change_log WHERE batch_id = ?1 AND is_undone = 0)",
+            rusqlite::params![batch_id],
+        )? > 0;
+        tx.commit()?;
+        Ok(done)
+    })
+}
+
+// A history batch with how many operations of each type it holds
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub timestamp: String,
+    pub is_undone: bool,
+    pub operations: std::collections::BTreeMap<String, usize>,
+}
+
+/// Batches started in [start, end), oldest first. Times are SQLite
+/// timestamps ("YYYY-MM-DD HH:MM:SS", UTC).
+pub fn batches_between(start: &str, end: &str) -> std::result::Result<Vec<BatchSummary>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.name, b.description, b.timestamp, b.is_undone, c.operation_type, COUNT(c.id)
+             FROM history_batches b LEFT JOIN change_log c ON c.batch_id = b.id
+             WHERE b.timestamp >= ?1 AND b.timestamp < ?2
+             GROUP BY b.id, c.operation_type
+             ORDER BY b.timestamp, b.id",
+        )?;
+        let rows = stmt.query(rusqlite::params![start, end])?;
+        batch_summaries(rows)
+    })
+}
+
+/// The `limit` most recent batches, newest first
+pub fn recent_batches(limit: usize) -> std::result::Result<Vec<BatchSummary>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.name, b.description, b.timestamp, b.is_undone, c.operation_type, COUNT(c.id)
+             FROM (SELECT * FROM history_batches ORDER BY timestamp DESC, rowid DESC LIMIT ?1) b
+             LEFT JOIN change_log c ON c.batch_id = b.id
+             GROUP BY b.id, c.operation_type
+             ORDER BY b.timestamp DESC, b.id",
+        )?;
+        let rows = stmt.query(rusqlite::params![limit as i64])?;
+        batch_summaries(rows)
+    })
+}
+
+// Fold rows of (batch columns, operation_type, count), grouped by batch
+fn batch_summaries(mut rows: rusqlite::Rows) -> Result<Vec<BatchSummary>> {
+    let mut batches: Vec<BatchSummary> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        if batches.last().map(|b| &b.id) != Some(&id) {
+            batches.push(BatchSummary {
+                id,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                timestamp: row.get(3)?,
+                is_undone: row.get::<_, i64>(4)? != 0,
+                operations: Default::default(),
+            });
+        }
+        if let Some(operation) = row.get::<_, Option<String>>(5)? {
+            let count = row.get::<_, i64>(6)? as usize;
+            if let Some(batch) = batches.last_mut() {
+                batch.operations.insert(operation, count);
+            }
+        }
+    }
+    Ok(batches)
+}
+
+/// Name of a history batch, None when there is no such batch
+pub fn batch_name(batch_id: &str) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT name FROM history_batches WHERE id = ?1",
+            rusqlite::params![batch_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Operations in [start, end) a person should look at: copies that failed
+/// verification and files renamed to avoid a collision
+pub fn changes_needing_review(start: &str, end: &str) -> std::result::Result<Vec<ChangeRecord>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, batch_id, operation_type, source_path, destination_path, collision_resolution,
+                    timestamp, is_undone, verified, file_data
+             FROM change_log
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND is_undone = 0
+               AND (verified = 0 OR collision_resolution = 'renamed')
+             ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![start, end], |row| {
+            Ok(ChangeRecord {
+                id: row.get(0)?,
+                batch_id: row.get(1)?,
+                operation_type: row.get(2)?,
+                source_path: row.get(3)?,
+                destination_path: row.get(4)?,
+                collision_resolution: row.get(5)?,
+                verified: row.get::<_, Option<i64>>(8)?.map(|v| v != 0),
+                file_data: row.get(9)?,
+                timestamp: row.get(6)?,
+                is_undone: row.get::<_, i64>(7)? != 0,
+            })
+        })?;
+        let changes: Result<Vec<ChangeRecord>> = rows.collect();
+        changes
+    })
+}
+
+/// Operations still in effect that were made at or after `since` (an
+/// SQLite timestamp), newest first
+pub fn changes_since(since: &str) -> std::result::Result<Vec<ChangeRecord>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, batch_id, operation_type, source_path, destination_path, collision_resolution,
+                    timestamp, is_undone, verified, file_data
+             FROM change_log WHERE timestamp >= ?1 AND is_undone = 0
+             ORDER BY rowid DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![since], change_record_from_row)?;
+        let changes: Result<Vec<ChangeRecord>> = rows.collect();
+        changes
+    })
+}
+
+/// Operations still in effect from `batch_id` onwards, that batch included,
+/// newest first; empty when the batch logged nothing
+pub fn changes_from_batch(batch_id: &str) -> std::result::Result<Vec<ChangeRecord>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, batch_id, operation_type, source_path, destination_path, collision_resolution,
+                    timestamp, is_undone, verified, file_data
+             FROM change_log
+             WHERE rowid >= (SELECT MIN(rowid) FROM change_log WHERE batch_id = ?1) AND is_undone = 0
+             ORDER BY rowid DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![batch_id], change_record_from_row)?;
+        let changes: Result<Vec<ChangeRecord>> = rows.collect();
+        changes
+    })
+}
+
+fn change_record_from_row(row: &rusqlite::Row) -> Result<ChangeRecord> {
+    Ok(ChangeRecord {
+        id: row.get(0)?,
+        batch_id: row.get(1)?,
+        operation_type: row.get(2)?,
+        source_path: row.get(3)?,
+        destination_path: row.get(4)?,
+        collision_resolution: row.get(5)?,
+        verified: row.get::<_, Option<i64>>(8)?.map(|v| v != 0),
+        file_data: row.get(9)?,
+        timestamp: row.get(6)?,
+        is_undone: row.get::<_, i64>(7)? != 0,
+    })
+}
+
+/// Timestamp of a history batch, as SQLite wrote it
+pub fn batch_timestamp(batch_id: &str) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT timestamp FROM history_batches WHERE id = ?1",
+            rusqlite::params![batch_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Save a generated digest
+pub fn store_digest(
+    id: &str,
+    trigger: &str,
+    period_start: &str,
+    period_end: &str,
+    content: &str,
+) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO digests (id, trigger, period_start, period_end, content) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![id, trigger, period_start, period_end, content],
+        )
+    })?;
+    Ok(())
+}
+
+/// Stored digests as JSON, newest first
+pub fn list_digests(limit: usize) -> std::result::Result<Vec<String>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT content FROM digests ORDER BY period_end DESC LIMIT ?1")?;
+        let rows = stmt.query_map(rusqlite::params![limit as i64], |row| row.get(0))?;
+        let digests: Result<Vec<String>> = rows.collect();
+        digests
+    })
+}
+
+/// End of the period covered by the latest digest
+pub fn last_digest_end() -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row("SELECT MAX(period_end) FROM digests", [], |row| row.get(0))
+    })
+}
+
+// A source file and the organized copy made from it
+#[derive(Debug, Clone, Serialize)]
+pub struct CopyMapping {
+    pub source_path: String,
+    pub destination_path: String,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+/// The copy previously made from `source_path`, if any
+pub fn get_copy_mapping(source_path: &str) -> std::result::Result<Option<CopyMapping>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT source_path, destination_path, size, mtime FROM copy_mappings WHERE source_path = ?1",
+            rusqlite::params![source_path],
+            |row| {
+                Ok(CopyMapping {
+                    source_path: row.get(0)?,
+                    destination_path: row.get(1)?,
+                    size: row.get::<_, i64>(2)? as u64,
+                    mtime: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
+
+/// Remember the copy made from a source file at its current size and mtime
+pub fn store_copy_mapping(mapping: &CopyMapping) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO copy_mappings (source_path, destination_path, size, mtime, copied_at)
+             VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)",
+            rusqlite::params![
+                mapping.source_path,
+                mapping.destination_path,
+                mapping.size as i64,
+                mapping.mtime
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+// An imported organization pack and where it is in install -> preview -> activate
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledPack {
+    pub pack: OrganizationPack,
+    pub signature: SignatureStatus,
+    pub installed_at: String,
+    pub previewed_at: Option<String>,
+    pub activated_at: Option<String>,
+}
+
+/// Store a pack and its rules, inactive; re-importing a pack replaces it
+/// and requires a new preview
+pub fn install_pack(
+    pack: &OrganizationPack,
+    signature: SignatureStatus,
+    rules: &[Rule],
+) -> std::result::Result<(), String> {
+    let content = serde_json::to_string(pack).map_err(|e| format!("Failed to serialize pack: {}", e))?;
+    let conditions = rules.iter().map(conditions_json).collect::<std::result::Result<Vec<_>, _>>()?;
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO organization_packs (id, name, version, content, signature_status)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![pack.id, pack.name, pack.version, content, signature.as_str()],
+        )?;
+        tx.execute(
+            "DELETE FROM rules WHERE id LIKE ?1 ESCAPE '\\'",
+            rusqlite::params![pack_rules_pattern(&pack.id)],
+        )?;
+        for (rule, conditions) in rules.iter().zip(&conditions) {
+            tx.execute(
+                "INSERT INTO rules (id, name, description, pattern, pattern_kind, destination, priority, is_active, created_at, conditions)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    rule.id,
+                    rule.name,
+                    rule.description,
+                    rule.pattern,
+                    rule.pattern_kind.as_str(),
+                    rule.destination,
+                    rule.priority,
+                    rule.is_active as i64,
+                    rule.created_at,
+                    conditions
+                ],
+            )?;
+        }
+        tx.commit()
+    })
+}
+
+/// Every imported pack, by name
+pub fn list_packs() -> std::result::Result<Vec<InstalledPack>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT content, signature_status, installed_at, previewed_at, activated_at
+             FROM organization_packs ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let content: String = row.get(0)?;
+            let pack = serde_json::from_str(&content).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+            })?;
+            Ok(InstalledPack {
+                pack,
+                signature: SignatureStatus::parse(&row.get::<_, String>(1)?),
+                installed_at: row.get(2)?,
+                previewed_at: row.get(3)?,
+                activated_at: row.get(4)?,
+            })
+        })?;
+        let packs: Result<Vec<InstalledPack>> = rows.collect();
+        packs
+    })
+}
+
+/// Record that a pack's sandboxed preview ran
+pub fn mark_pack_previewed(pack_id: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "UPDATE organization_packs SET previewed_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            rusqlite::params![pack_id],
+        )
+    })?;
+    Ok(())
+}
+
+/// Turn a pack's rules on or off and record when it was activated
+pub fn set_pack_active(pack_id: &str, active: bool) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "UPDATE rules SET is_active = ?2 WHERE id LIKE ?1 ESCAPE '\\'",
+            rusqlite::params![pack_rules_pattern(pack_id), active as i64],
+        )?;
+        conn.execute(
+            "UPDATE organization_packs SET activated_at = CASE WHEN ?2 THEN CURRENT_TIMESTAMP END WHERE id = ?1",
+            rusqlite::params![pack_id, active],
+        )
+    })?;
+    Ok(())
+}
+
+/// Read a user preference
+pub fn get_preference(key: &str) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT value FROM preferences WHERE key = ?1",
+            rusqlite::params![key],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Store a user preference
+pub fn set_preference(key: &str, value: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO preferences (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            rusqlite::params![key, value],
+        )
+    })?;
+    Ok(())
+}
+
+/// Every preference whose key starts with `prefix`, as (key, value)
+pub fn preferences_with_prefix(prefix: &str) -> std::result::Result<Vec<(String, String)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT key, value FROM preferences WHERE substr(key, 1, length(?1)) = ?1")?;
+        let rows = stmt.query_map(rusqlite::params![prefix], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let preferences: Result<Vec<(String, String)>> = rows.collect();
+        preferences
+    })
+}
+
+/// Remove a user preference
+pub fn delete_preference(key: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| conn.execute("DELETE FROM preferences WHERE key = ?1", rusqlite::params![key]))?;
+    Ok(())
+}
+
+// A file row from the metadata cache
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub name: String,
+    pub file_type: Option<String>,
+    pub size: u64,
+    pub modified_at: String,
+    pub parent_path: Option<String>,
+}
+
+/// Insert or refresh file nodes in the metadata cache
+pub fn index_file_nodes(nodes: &[FileNode]) -> std::result::Result<(), String> {
+    let indexed_at = chrono::Utc::now().to_rfc3339();
+    with_connection(|conn| {
+        let online = online_volumes(conn)?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO files (id, path, name, type, file_type, size, modified_at, created_at, extension, parent_path, indexed_at, volume_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(path) DO UPDATE SET
+                    name = excluded.name,
+                    type = excluded.type,
+                    file_type = excluded.file_type,
+                    size = excluded.size,
+                    modified_at = excluded.modified_at,
+                    created_at = excluded.created_at,
+                    extension = excluded.extension,
+                    parent_path = excluded.parent_path,
+                    indexed_at = excluded.indexed_at,
+                    volume_id = COALESCE(excluded.volume_id, files.volume_id)",
+            )?;
+            for node in nodes {
+                let parent_path = Path::new(&node.path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string());
+                // The drive mounted at the path now, whatever drive was there before
+                let volume_id = online
+                    .iter()
+                    .filter(|(_, mount_point)| Path::new(&node.path).starts_with(mount_point))
+                    .max_by_key(|(_, mount_point)| Path::new(mount_point).components().count())
+                    .map(|(id, _)| id.as_str());
+                stmt.execute(rusqlite::params![
+                    node.id,
+                    node.path,
+                    node.name,
+                    node.node_type,
+                    node.file_type,
+                    node.size as i64,
+                    node.modified_at,
+                    node.created_at,
+                    node.extension,
+                    parent_path,
+                    indexed_at,
+                    volume_id
+                ])?;
+            }
+        }
+        tx.commit()
+    })
+}
+
+// Map a row selected as (path, name, file_type, size, modified_at, parent_path)
+fn indexed_file_from_row(row: &rusqlite::Row) -> Result<IndexedFile> {
+    Ok(IndexedFile {
+        path: row.get(0)?,
+        name: row.get(1)?,
+        file_type: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        modified_at: row.get(4)?,
+        parent_path: row.get(5)?,
+    })
+}
+
+// `value` with LIKE's wildcards and the escape character itself escaped,
+// for patterns used with ESCAPE '\'
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// LIKE pattern, used with ESCAPE '\', for everything below `folder`. A
+// trailing separator is dropped first, so a drive root doesn't turn into
+// "//%" and match nothing.
+fn below_pattern(folder: &str) -> String {
+    let separator = std::path::MAIN_SEPARATOR.to_string();
+    format!(
+        "{}{}%",
+        escape_like(folder.trim_end_matches(std::path::MAIN_SEPARATOR)),
+        escape_like(&separator)
+    )
+}
+
+// LIKE pattern, used with ESCAPE '\', for the ids of a pack's rules
+fn pack_rules_pattern(pack_id: &str) -> String {
+    format!("pack:{}:%", escape_like(pack_id))
+}
+
+/// Remove a path, and everything below it, from the metadata cache
+pub fn remove_indexed_path(path: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "DELETE FROM files WHERE path = ?1 OR path LIKE ?2 ESCAPE '\\'",
+            rusqlite::params![path, below_pattern(path)],
+        )?;
+        conn.execute(
+            "DELETE FROM file_contents WHERE path = ?1 OR path LIKE ?2 ESCAPE '\\'",
+            rusqlite::params![path, below_pattern(path)],
+        )
+    })?;
+    Ok(())
+}
+
+/// Format of SQLite's CURRENT_TIMESTAMP, used by change_log and
+/// history_batches
+pub const SQLITE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// Format of timestamps in the files and file_events tables
+pub const INDEX_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+/// File events older than this are dropped
+const FILE_EVENT_RETENTION_DAYS: i64 = 90;
+
+// A recorded change below a folder
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEvent {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub kind: ChangeKind,
+    pub occurred_at: String,
+}
+
+/// Append changes to the event log, dropping events past the retention period
+pub fn record_file_events(changes: &[FsChange]) -> std::result::Result<(), String> {
+    let now = chrono::Utc::now();
+    let occurred_at = now.format(INDEX_TIME_FORMAT).to_string();
+    let cutoff = (now - chrono::Duration::days(FILE_EVENT_RETENTION_DAYS))
+        .format(INDEX_TIME_FORMAT)
+        .to_string();
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO file_events (path, old_path, kind, occurred_at) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for change in changes {
+                stmt.execute(rusqlite::params![change.path, change.old_path, change.kind.as_str(), occurred_at])?;
+            }
+        }
+        tx.execute("DELETE FROM file_events WHERE occurred_at < ?1", rusqlite::params![cutoff])?;
+        tx.commit()
+    })
+}
+
+/// How long manual moves count towards rule suggestions
+const OBSERVED_MOVE_RETENTION_DAYS: i64 = 90;
+
+/// (source, destination) of every change the app logged in the last
+/// `minutes`, to tell its own moves from the user's
+pub fn recent_logged_moves(
+    minutes: i64,
+) -> std::result::Result<std::collections::HashSet<(String, String)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT source_path, destination_path FROM change_log
+             WHERE destination_path IS NOT NULL AND timestamp >= datetime('now', ?1)",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![format!("-{} minutes", minutes)], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        let moves: Result<std::collections::HashSet<(String, String)>> = rows.collect();
+        moves
+    })
+}
+
+/// Store manual moves seen by the watcher, dropping ones too old to matter
+pub fn record_observed_moves(moves: &[ObservedMove]) -> std::result::Result<(), String> {
+    let now = chrono::Utc::now();
+    let moved_at = now.format(INDEX_TIME_FORMAT).to_string();
+    let cutoff = (now - chrono::Duration::days(OBSERVED_MOVE_RETENTION_DAYS))
+        .format(INDEX_TIME_FORMAT)
+        .to_string();
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO observed_moves (path, old_path, extension, destination_folder, moved_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for m in moves {
+                stmt.execute(rusqlite::params![m.path, m.old_path, m.extension, m.destination_folder, moved_at])?;
+            }
+        }
+        tx.execute("DELETE FROM observed_moves WHERE moved_at < ?1", rusqlite::params![cutoff])?;
+        tx.commit()
+    })
+}
+
+/// Distinct files of `extension` moved by hand into `destination_folder`
+pub fn observed_move_count(extension: &str, destination_folder: &str) -> std::result::Result<i64, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT COUNT(DISTINCT path) FROM observed_moves WHERE extension = ?1 AND destination_folder = ?2",
+            rusqlite::params![extension, destination_folder],
+            |row| row.get(0),
+        )
+    })
+}
+
+fn rule_suggestion_from_row(row: &rusqlite::Row) -> Result<RuleSuggestion> {
+    Ok(RuleSuggestion {
+        id: row.get(0)?,
+        extension: row.get(1)?,
+        destination_folder: row.get(2)?,
+        move_count: row.get(3)?,
+        status: SuggestionStatus::parse(&row.get::<_, String>(4)?),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// The suggestion for moving `extension` files into `destination_folder`, in any status
+pub fn get_rule_suggestion(
+    extension: &str,
+    destination_folder: &str,
+) -> std::result::Result<Option<RuleSuggestion>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT id, extension, destination_folder, move_count, status, created_at, updated_at
+             FROM rule_suggestions WHERE extension = ?1 AND destination_folder = ?2",
+            rusqlite::params![extension, destination_folder],
+            rule_suggestion_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Rule suggestions, optionally only those in `status`, most moves first
+pub fn list_rule_suggestions(
+    status: Option<SuggestionStatus>,
+) -> std::result::Result<Vec<RuleSuggestion>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, extension, destination_folder, move_count, status, created_at, updated_at
+             FROM rule_suggestions WHERE ?1 IS NULL OR status = ?1
+             ORDER BY move_count DESC, updated_at DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![status.map(|s| s.as_str())], rule_suggestion_from_row)?;
+        let suggestions: Result<Vec<RuleSuggestion>> = rows.collect();
+        suggestions
+    })
+}
+
+/// Insert or update a rule suggestion
+pub fn save_rule_suggestion(suggestion: &RuleSuggestion) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO rule_suggestions (id, extension, destination_folder, move_count, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                move_count = excluded.move_count,
+                status = excluded.status,
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                suggestion.id,
+                suggestion.extension,
+                suggestion.destination_folder,
+                suggestion.move_count,
+                suggestion.status.as_str(),
+                suggestion.created_at,
+                suggestion.updated_at
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Events at or below `folder` (including renames out of it) after `since`, oldest first
+pub fn file_events_since(folder: &str, since: &str) -> std::result::Result<Vec<FileEvent>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, old_path, kind, occurred_at FROM file_events
+             WHERE occurred_at > ?2
+               AND (path LIKE ?1 ESCAPE '\\' OR old_path LIKE ?1 ESCAPE '\\')
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![below_pattern(folder), since], |row| {
+            Ok(FileEvent {
+                path: row.get(0)?,
+                old_path: row.get(1)?,
+                kind: ChangeKind::parse(&row.get::<_, String>(2)?),
+                occurred_at: row.get(3)?,
+            })
+        })?;
+        let events: Result<Vec<FileEvent>> = rows.collect();
+        events
+    })
+}
+
+/// Indexed files below `folder` created or modified after `since`, with
+/// whether each was created (rather than only modified) in that window
+pub fn indexed_changes_since(folder: &str, since: &str) -> std::result::Result<Vec<(IndexedFile, bool)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, name, file_type, size, modified_at, parent_path, created_at > ?2 FROM files
+             WHERE type = 'file' AND path LIKE ?1 ESCAPE '\\'
+               AND (modified_at > ?2 OR created_at > ?2)",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![below_pattern(folder), since], |row| {
+            Ok((indexed_file_from_row(row)?, row.get::<_, bool>(6)?))
+        })?;
+        let files: Result<Vec<(IndexedFile, bool)>> = rows.collect();
+        files
+    })
+}
+
+// Index totals for one extension (and the file type it was classified as)
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtensionStats {
+    /// Lowercase extension, empty for files without one
+    pub extension: String,
+    pub file_type: String,
+    pub file_count: u64,
+    pub total_size: u64,
+    pub average_size: u64,
+    pub newest_modified_at: Option<String>,
+    pub oldest_modified_at: Option<String>,
+}
+
+/// Totals per extension of the indexed files below `folder`, largest first
+pub fn extension_stats(folder: &str) -> std::result::Result<Vec<ExtensionStats>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT LOWER(COALESCE(extension, '')), COALESCE(file_type, 'other'),
+                    COUNT(*), SUM(size), MAX(modified_at), MIN(modified_at)
+             FROM files
+             WHERE type = 'file' AND path LIKE ?1 ESCAPE '\\'
+             GROUP BY 1, 2
+             ORDER BY 4 DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![below_pattern(folder)], |row| {
+            let file_count = row.get::<_, i64>(2)? as u64;
+            let total_size = row.get::<_, i64>(3)? as u64;
+            Ok(ExtensionStats {
+                extension: row.get(0)?,
+                file_type: row.get(1)?,
+                file_count,
+                total_size,
+                average_size: total_size / file_count.max(1),
+                newest_modified_at: row.get(4)?,
+                oldest_modified_at: row.get(5)?,
+            })
+        })?;
+        let stats: Result<Vec<ExtensionStats>> = rows.collect();
+        stats
+    })
+}
+
+/// When the user last viewed `folder`
+pub fn folder_last_viewed(folder: &str) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT last_viewed_at FROM folder_visits WHERE path = ?1",
+            rusqlite::params![folder],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Record that the user viewed `folder` now
+pub fn mark_folder_viewed(folder: &str, viewed_at: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO folder_visits (path, last_viewed_at) VALUES (?1, ?2)
+             ON CONFLICT(path) DO UPDATE SET last_viewed_at = excluded.last_viewed_at",
+            rusqlite::params![folder, viewed_at],
+        )
+    })?;
+    Ok(())
+}
+
+/// Recursive size and file count of a folder from the metadata cache
+pub fn indexed_folder_totals(path: &str) -> std::result::Result<(u64, u64), String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT COALESCE(SUM(size), 0), COUNT(*) FROM files
+             WHERE type = 'file' AND path LIKE ?1 ESCAPE '\\'",
+            rusqlite::params![below_pattern(path)],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        )
+    })
+}
+
+/// Cached (size, file_count, computed_at) of a folder
+pub fn get_folder_size(path: &str) -> std::result::Result<Option<(u64, u64, String)>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT size, file_count, computed_at FROM folder_sizes WHERE path = ?1",
+            rusqlite::params![path],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64, row.get(2)?)),
+        )
+        .optional()
+    })
+}
+
+/// Cache a folder's recursive totals
+pub fn store_folder_size(path: &str, size: u64, file_count: u64, computed_at: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO folder_sizes (path, size, file_count, computed_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![path, size as i64, file_count as i64, computed_at],
+        )
+    })?;
+    Ok(())
+}
+
+/// Drop cached totals of every folder containing `path` (and of `path` itself
+/// and anything below it, for folders that moved or were removed)
+pub fn invalidate_folder_sizes(path: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for folder in std::path::Path::new(path).ancestors() {
+            tx.execute(
+                "DELETE FROM folder_sizes WHERE path = ?1",
+                rusqlite::params![folder.to_string_lossy()],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM folder_sizes WHERE path LIKE ?1 ESCAPE '\\'",
+            rusqlite::params![below_pattern(path)],
+        )?;
+        tx.commit()
+    })?;
+    Ok(())
+}
+
+/// Cached content hash for `path`, if it was computed for this size and mtime
+pub fn cached_hash(path: &str, size: u64, mtime: i64) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT content_hash FROM files
+             WHERE path = ?1 AND hashed_size = ?2 AND hashed_mtime = ?3 AND content_hash IS NOT NULL",
+            rusqlite::params![path, size as i64, mtime],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Store a content hash with the size and mtime it was computed for.
+/// The path must already be indexed.
+pub fn store_hash(path: &str, size: u64, mtime: i64, hash: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "UPDATE files SET content_hash = ?2, hashed_size = ?3, hashed_mtime = ?4 WHERE path = ?1",
+            rusqlite::params![path, hash, size as i64, mtime],
+        )
+    })?;
+    Ok(())
+}
+
+/// Files from the metadata cache, optionally limited to paths under `scope`
+pub fn indexed_files(scope: Option<&str>) -> std::result::Result<Vec<IndexedFile>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, name, file_type, size, modified_at, parent_path FROM files
+             WHERE type = 'file' AND (?1 IS NULL OR path = ?1 OR path LIKE ?2 ESCAPE '\\')
+             ORDER BY path",
+        )?;
+        let rows = stmt.query_map(
+            rusqlite::params![scope, scope.map(below_pattern)],
+            indexed_file_from_row,
+        )?;
+        let files: Result<Vec<IndexedFile>> = rows.collect();
+        files
+    })
+}
+
+/// All saved rules, highest priority first
+pub fn list_rules() -> std::result::Result<Vec<Rule>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, pattern, pattern_kind, destination, priority, is_active,
+                    created_at, hit_count, last_matched_at, conditions
+             FROM rules ORDER BY priority DESC, created_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Rule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                pattern: row.get(3)?,
+                pattern_kind: PatternKind::parse(&row.get::<_, String>(4)?),
+                destination: row.get(5)?,
+                priority: row.get(6)?,
+                is_active: row.get::<_, i64>(7)? != 0,
+                created_at: row.get(8)?,
+                hit_count: row.get(9)?,
+                last_matched_at: row.get(10)?,
+                conditions: row
+                    .get::<_, Option<String>>(11)?
+                    .map(|json| serde_json::from_str(&json))
+                    .transpose()
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(11, rusqlite::types::Type::Text, Box::new(e)))?,
+            })
+        })?;
+        let rules: Result<Vec<Rule>> = rows.collect();
+        rules
+    })
+}
+
+/// Insert or update a rule; hit statistics are left untouched
+pub fn save_rule(rule: &Rule) -> std::result::Result<(), String> {
+    let conditions = conditions_json(rule)?;
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO rules (id, name, description, pattern, pattern_kind, destination, priority, is_active, created_at, conditions)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                pattern = excluded.pattern,
+                pattern_kind = excluded.pattern_kind,
+                destination = excluded.destination,
+                priority = excluded.priority,
+                is_active = excluded.is_active,
+                conditions = excluded.conditions",
+            rusqlite::params![
+                rule.id,
+                rule.name,
+                rule.description,
+                rule.pattern,
+                rule.pattern_kind.as_str(),
+                rule.destination,
+                rule.priority,
+                rule.is_active as i64,
+                rule.created_at,
+                conditions
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+// A rule's conditions as stored in the rules table
+fn conditions_json(rule: &Rule) -> std::result::Result<Option<String>, String> {
+    rule.conditions
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| format!("Failed to serialize rule conditions: {}", e))
+}
+
+/// Delete a rule
+pub fn delete_rule(id: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| conn.execute("DELETE FROM rules WHERE id = ?1", rusqlite::params![id]))?;
+    Ok(())
+}
+
+/// Add match counts from a plan run to each rule's statistics
+pub fn record_rule_hits(hits: &std::collections::HashMap<String, i64>) -> std::result::Result<(), String> {
+    if hits.is_empty() {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for (id, count) in hits {
+            tx.execute(
+                "UPDATE rules SET hit_count = hit_count + ?1, last_matched_at = ?2 WHERE id = ?3",
+                rusqlite::params![count, now, id],
+            )?;
+        }
+        tx.commit()
+    })
+}
+
+/// Whether a vector exists for this content and model
+pub fn has_embedding(content_hash: &str, model_id: &str) -> std::result::Result<bool, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT 1 FROM embeddings WHERE content_hash = ?1 AND model_id = ?2",
+            rusqlite::params![content_hash, model_id],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+    })
+}
+
+/// Store a vector for this content and model
+pub fn store_embedding(content_hash: &str, model_id: &str, dims: usize, vector: &[u8]) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO embeddings (content_hash, model_id, dims, vector) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![content_hash, model_id, dims as i64, vector],
+        )
+    })?;
+    Ok(())
+}
+
+/// Drop vectors produced by models other than `model_id`
+pub fn delete_embeddings_except_model(model_id: &str) -> std::result::Result<usize, String> {
+    with_connection(|conn| {
+        conn.execute("DELETE FROM embeddings WHERE model_id != ?1", rusqlite::params![model_id])
+    })
+}
+
+/// All cached vectors for a model as (content_hash, encoded vector)
+pub fn embeddings_for_model(model_id: &str) -> std::result::Result<Vec<(String, Vec<u8>)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT content_hash, vector FROM embeddings WHERE model_id = ?1")?;
+        let rows = stmt.query_map(rusqlite::params![model_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let vectors: Result<Vec<(String, Vec<u8>)>> = rows.collect();
+        vectors
+    })
+}
+
+/// Indexed files with the given content hash
+pub fn files_with_hash(content_hash: &str) -> std::result::Result<Vec<IndexedFile>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, name, file_type, size, modified_at, parent_path FROM files
+             WHERE type = 'file' AND content_hash = ?1 ORDER BY path",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![content_hash], indexed_file_from_row)?;
+        let files: Result<Vec<IndexedFile>> = rows.collect();
+        files
+    })
+}
+
+/// Indexed files of at least `min_size` bytes whose persisted content hash
+/// is shared with another indexed file, as (content_hash, file), largest
+/// first and grouped by hash. Files on devices that are not connected are
+/// included, since their rows stay in the index.
+pub fn duplicate_files(min_size: u64) -> std::result::Result<Vec<(String, IndexedFile)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, name, file_type, size, modified_at, parent_path, content_hash FROM files
+             WHERE type = 'file' AND size >= ?1 AND content_hash IN (
+                 SELECT content_hash FROM files
+                 WHERE type = 'file' AND content_hash IS NOT NULL
+                 GROUP BY content_hash HAVING COUNT(*) > 1
+             )
+             ORDER BY size DESC, content_hash, path",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![min_size as i64], |row| {
+            Ok((row.get(6)?, indexed_file_from_row(row)?))
+        })?;
+        let files: Result<Vec<(String, IndexedFile)>> = rows.collect();
+        files
+    })
+}
+
+// How much of an indexed scope has hashes and vectors
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingCoverage {
+    pub model_id: String,
+    pub indexed_files: u64,
+    pub hashed_files: u64,
+    pub embedded_files: u64,
+}
+
+/// Coverage of the current embedding model for files under `scope`
+pub fn embedding_coverage(scope: Option<&str>, model_id: &str) -> std::result::Result<EmbeddingCoverage, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT COUNT(*),
+                    COUNT(f.content_hash),
+                    COUNT(e.content_hash)
+             FROM files f
+             LEFT JOIN embeddings e ON e.content_hash = f.content_hash AND e.model_id = ?2
+             WHERE f.type = 'file' AND (?1 IS NULL OR f.path = ?1 OR f.path LIKE ?3 ESCAPE '\\')",
+            rusqlite::params![scope, model_id, scope.map(below_pattern)],
+            |row| {
+                Ok(EmbeddingCoverage {
+                    model_id: model_id.to_string(),
+                    indexed_files: row.get::<_, i64>(0)? as u64,
+                    hashed_files: row.get::<_, i64>(1)? as u64,
+                    embedded_files: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )
+    })
+}
+
+/// Insert or replace extracted media metadata
+pub fn store_media_metadata(metadata: &MediaMetadata) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO media_metadata
+                (path, title, artist, album, genre, year, track, duration_ms, bitrate_kbps,
+                 sample_rate, channels, width, height, extracted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, CURRENT_TIMESTAMP)",
+            rusqlite::params![
+                metadata.path,
+                metadata.title,
+                metadata.artist,
+                metadata.album,
+                metadata.genre,
+                metadata.year,
+                metadata.track,
+                metadata.duration_ms.map(|v| v as i64),
+                metadata.bitrate_kbps,
+                metadata.sample_rate,
+                metadata.channels,
+                metadata.width,
+                metadata.height
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Stored media metadata for a path
+pub fn get_media_metadata(path: &str) -> std::result::Result<Option<MediaMetadata>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT path, title, artist, album, genre, year, track, duration_ms, bitrate_kbps,
+                    sample_rate, channels, width, height
+             FROM media_metadata WHERE path = ?1",
+            rusqlite::params![path],
+            |row| {
+                Ok(MediaMetadata {
+                    path: row.get(0)?,
+                    title: row.get(1)?,
+                    artist: row.get(2)?,
+                    album: row.get(3)?,
+                    genre: row.get(4)?,
+                    year: row.get(5)?,
+                    track: row.get(6)?,
+                    duration_ms: row.get::<_, Option<i64>>(7)?.map(|v| v as u64),
+                    bitrate_kbps: row.get(8)?,
+                    sample_rate: row.get(9)?,
+                    channels: row.get(10)?,
+                    width: row.get(11)?,
+                    height: row.get(12)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
+
+/// Whether text for `path` was already extracted at this size and mtime
+pub fn has_fresh_content(path: &str, size: u64, mtime: i64) -> std::result::Result<bool, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT 1 FROM file_contents WHERE path = ?1 AND size = ?2 AND mtime = ?3",
+            rusqlite::params![path, size as i64, mtime],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|row| row.is_some())
+    })
+}
+
+/// Insert or replace the extracted text of a document
+pub fn store_content(path: &str, size: u64, mtime: i64, text: &str, truncated: bool) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO file_contents (path, size, mtime, text, truncated, extracted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, CURRENT_TIMESTAMP)
+             ON CONFLICT(path) DO UPDATE SET
+                size = excluded.size,
+                mtime = excluded.mtime,
+                text = excluded.text,
+                truncated = excluded.truncated,
+                extracted_at = excluded.extracted_at",
+            rusqlite::params![path, size as i64, mtime, text, truncated],
+        )
+    })?;
+    Ok(())
+}
+
+/// Stored document text for a path and whether it was truncated
+pub fn get_content(path: &str) -> std::result::Result<Option<(String, bool)>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT text, truncated FROM file_contents WHERE path = ?1",
+            rusqlite::params![path],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+    })
+}
+
+// WHERE conditions and bound values for structured search filters on `files f`
+fn filter_conditions(filters: &SearchFilters) -> (Vec<String>, Vec<rusqlite::types::Value>) {
+    use rusqlite::types::Value;
+
+    let mut conditions = vec!["f.type = 'file'".to_string()];
+    let mut values: Vec<Value> = Vec::new();
+
+    if !filters.file_types.is_empty() {
+        let file_types = filters.expanded_file_types();
+        let placeholders = vec!["?"; file_types.len()].join(", ");
+        conditions.push(format!("COALESCE(f.file_type, 'other') IN ({})", placeholders));
+        values.extend(file_types.into_iter().map(Value::Text));
+    }
+    if !filters.extensions.is_empty() {
+        let placeholders = vec!["?"; filters.extensions.len()].join(", ");
+        conditions.push(format!("LOWER(f.extension) IN ({})", placeholders));
+        values.extend(
+            filters
+                .extensions
+                .iter()
+                .map(|e| Value::Text(e.trim_start_matches('.').to_lowercase())),
+        );
+    }
+    if let Some(min) = filters.min_size {
+        conditions.push("f.size >= ?".to_string());
+        values.push(Value::Integer(min as i64));
+    }
+    if let Some(max) = filters.max_size {
+        conditions.push("f.size <= ?".to_string());
+        values.push(Value::Integer(max as i64));
+    }
+    if let Some(after) = &filters.modified_after {
+        conditions.push("f.modified_at >= ?".to_string());
+        values.push(Value::Text(after.clone()));
+    }
+    if let Some(before) = &filters.modified_before {
+        conditions.push("f.modified_at < ?".to_string());
+        values.push(Value::Text(before.clone()));
+    }
+    if let Some(prefix) = &filters.path_prefix {
+        conditions.push("substr(f.path, 1, length(?)) = ?".to_string());
+        values.push(Value::Text(prefix.clone()));
+        values.push(Value::Text(prefix.clone()));
+    }
+
+    (conditions, values)
+}
+
+/// Indexed files matching an FTS5 expression (best match first) and the
+/// structured filters; without an expression, newest files first
+pub fn search_indexed_files(
+    fts_expression: Option<&str>,
+    filters: &SearchFilters,
+    limit: usize,
+) -> std::result::Result<Vec<IndexedFile>, String> {
+    use rusqlite::types::Value;
+
+    let mut sql = String::from(
+        "SELECT f.path, f.name, f.file_type, f.size, f.modified_at, f.parent_path FROM files f",
+    );
+    let (mut conditions, mut values) = filter_conditions(filters);
+
+    if let Some(expression) = fts_expression {
+        sql.push_str(" JOIN files_fts ON files_fts.rowid = f.rowid");
+        conditions.push("files_fts MATCH ?".to_string());
+        values.push(Value::Text(expression.to_string()));
+    }
+
+    sql.push_str(" WHERE ");
+    sql.push_str(&conditions.join(" AND "));
+    sql.push_str(if fts_expression.is_some() {
+        " ORDER BY bm25(files_fts)"
+    } else {
+        " ORDER BY f.modified_at DESC"
+    });
+    sql.push_str(" LIMIT ?");
+    values.push(Value::Integer(limit as i64));
+
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), indexed_file_from_row)?;
+        let files: Result<Vec<IndexedFile>> = rows.collect();
+        files
+    })
+}
+
+/// Indexed files whose extracted text matches an FTS5 expression, best match
+/// first, each with a snippet of matching text. Matched terms in the snippet
+/// are wrapped in SNIPPET_OPEN / SNIPPET_CLOSE.
+pub fn search_file_contents(
+    fts_expression: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> std::result::Result<Vec<(IndexedFile, String)>, String> {
+    search_text_table("file_contents", "contents_fts", fts_expression, filters, limit)
+}
+
+/// Like `search_file_contents`, over file comments
+pub fn search_file_comments(
+    fts_expression: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> std::result::Result<Vec<(IndexedFile, String)>, String> {
+    search_text_table("file_comments", "comments_fts", fts_expression, filters, limit)
+}
+
+// Search a path-keyed text table through its external-content FTS index
+fn search_text_table(
+    table: &str,
+    fts_table: &str,
+    fts_expression: &str,
+    filters: &SearchFilters,
+    limit: usize,
+) -> std::result::Result<Vec<(IndexedFile, String)>, String> {
+    use rusqlite::types::Value;
+
+    let (mut conditions, mut values) = filter_conditions(filters);
+    conditions.push(format!("{} MATCH ?", fts_table));
+    values.push(Value::Text(fts_expression.to_string()));
+    values.push(Value::Integer(limit as i64));
+
+    let sql = format!(
+        "SELECT f.path, f.name, f.file_type, f.size, f.modified_at, f.parent_path,
+                snippet({fts}, 0, '{open}', '{close}', '…', 16)
+         FROM files f
+         JOIN {table} t ON t.path = f.path
+         JOIN {fts} ON {fts}.rowid = t.rowid
+         WHERE {conditions} ORDER BY bm25({fts}) LIMIT ?",
+        fts = fts_table,
+        table = table,
+        open = SNIPPET_OPEN,
+        close = SNIPPET_CLOSE,
+        conditions = conditions.join(" AND ")
+    );
+
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok((indexed_file_from_row(row)?, row.get(6)?))
+        })?;
+        let files: Result<Vec<(IndexedFile, String)>> = rows.collect();
+        files
+    })
+}
+
+/// Set or clear (empty text) the comment on a file
+pub fn set_comment(path: &str, comment: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        if comment.trim().is_empty() {
+            conn.execute("DELETE FROM file_comments WHERE path = ?1", rusqlite::params![path])
+        } else {
+            conn.execute(
+                "INSERT INTO file_comments (path, comment, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+                 ON CONFLICT(path) DO UPDATE SET comment = excluded.comment, updated_at = excluded.updated_at",
+                rusqlite::params![path, comment],
+            )
+        }
+    })?;
+    Ok(())
+}
+
+/// The comment on a file, if any
+pub fn get_comment(path: &str) -> std::result::Result<Option<String>, String> {
+    with_connection(|conn| {
+        conn.query_row(
+            "SELECT comment FROM file_comments WHERE path = ?1",
+            rusqlite::params![path],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Re-key per-file records (comments, extracted text, media tags) after a
+/// move, including everything below a moved folder
+pub fn rename_path_records(old_path: &str, new_path: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        for table in PATH_RECORD_TABLES {
+            // A record already at the destination belongs to the overwritten file
+            tx.execute(
+                &format!("DELETE FROM {} WHERE path = ?1", table),
+                rusqlite::params![new_path],
+            )?;
+            tx.execute(
+                &format!(
+                    "UPDATE {} SET path = ?2 || substr(path, length(?1) + 1)
+                     WHERE path = ?1 OR path LIKE ?3 ESCAPE '\\'",
+                    table
+                ),
+                rusqlite::params![old_path, new_path, below_pattern(old_path)],
+            )?;
+        }
+        tx.commit()
+    })
+}
+
+// Tables of per-file records keyed by path, besides files itself
+const PATH_RECORD_TABLES: [&str; 4] = ["file_comments", "file_contents", "media_metadata", "protected_paths"];
+
+fn volume_from_row(row: &rusqlite::Row) -> Result<Volume> {
+    Ok(Volume {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        mount_point: row.get(2)?,
+        file_system: row.get(3)?,
+        removable: row.get::<_, i64>(4)? != 0,
+        total_size: row.get::<_, i64>(5)? as u64,
+        first_seen_at: row.get(6)?,
+        last_seen_at: row.get(7)?,
+    })
+}
+
+/// Every volume ever seen mounted
+pub fn list_volumes() -> std::result::Result<Vec<Volume>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, label, mount_point, file_system, removable, total_size, first_seen_at, last_seen_at
+             FROM volumes ORDER BY label",
+        )?;
+        let rows = stmt.query_map([], volume_from_row)?;
+        let volumes: Result<Vec<Volume>> = rows.collect();
+        volumes
+    })
+}
+
+/// Insert or update a volume
+pub fn save_volume(volume: &Volume) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO volumes
+                (id, label, mount_point, file_system, removable, total_size, first_seen_at, last_seen_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                volume.id,
+                volume.label,
+                volume.mount_point,
+                volume.file_system,
+                volume.removable as i64,
+                volume.total_size as i64,
+                volume.first_seen_at,
+                volume.last_seen_at
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+// (id, mount point) of the volumes mounted at the last refresh
+fn online_volumes(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT id, mount_point FROM volumes WHERE online = 1")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    let volumes: Result<Vec<(String, String)>> = rows.collect();
+    volumes
+}
+
+/// Mark the volumes in `ids` as the ones mounted now
+pub fn set_online_volumes(ids: &std::collections::BTreeSet<String>) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("UPDATE volumes SET online = 0", [])?;
+        for id in ids {
+            tx.execute("UPDATE volumes SET online = 1 WHERE id = ?1", rusqlite::params![id])?;
+        }
+        tx.commit()
+    })
+}
+
+/// Forget a volume along with its catalog of indexed files
+pub fn delete_volume(id: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM files WHERE volume_id = ?1", rusqlite::params![id])?;
+        tx.execute("DELETE FROM volumes WHERE id = ?1", rusqlite::params![id])?;
+        tx.commit()
+    })
+}
+
+// `mount_point` with a trailing separator, so /media/usb doesn't match
+// /media/usb2
+fn mount_prefix(mount_point: &str) -> String {
+    let separator = std::path::MAIN_SEPARATOR;
+    if mount_point.ends_with(separator) {
+        mount_point.to_string()
+    } else {
+        format!("{}{}", mount_point, separator)
+    }
+}
+
+/// Attribute indexed files under `mount_point` that have no volume yet
+pub fn assign_volume(id: &str, mount_point: &str) -> std::result::Result<(), String> {
+    let prefix = mount_prefix(mount_point);
+    with_connection(|conn| {
+        conn.execute(
+            "UPDATE files SET volume_id = ?1
+             WHERE volume_id IS NULL AND (path = ?2 OR substr(path, 1, length(?3)) = ?3)",
+            rusqlite::params![id, mount_point, prefix],
+        )
+    })?;
+    Ok(())
+}
+
+/// Move a volume's indexed files, and the per-file records that go with
+/// them, from the mount point it had to the one it has now
+pub fn relink_volume(id: &str, old_mount_point: &str, new_mount_point: &str) -> std::result::Result<(), String> {
+    let (old_prefix, new_prefix) = (mount_prefix(old_mount_point), mount_prefix(new_mount_point));
+    with_connection(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        // Other tables first, while files still says which paths are the volume's
+        for table in PATH_RECORD_TABLES {
+            tx.execute(
+                &format!(
+                    "UPDATE {} SET path = ?3 || substr(path, length(?2) + 1)
+                     WHERE substr(path, 1, length(?2)) = ?2
+                       AND path IN (SELECT path FROM files WHERE volume_id = ?1)",
+                    table
+                ),
+                rusqlite::params![id, old_prefix, new_prefix],
+            )?;
+        }
+        tx.execute(
+            "UPDATE files SET
+                path = ?3 || substr(path, length(?2) + 1),
+                parent_path = CASE
+                    WHEN substr(parent_path, 1, length(?2)) = ?2 THEN ?3 || substr(parent_path, length(?2) + 1)
+                    WHEN parent_path = ?4 THEN ?5
+                    ELSE parent_path
+                END
+             WHERE volume_id = ?1 AND substr(path, 1, length(?2)) = ?2",
+            rusqlite::params![id, old_prefix, new_prefix, old_mount_point, new_mount_point],
+        )?;
+        tx.execute(
+            "UPDATE files SET path = ?3 WHERE volume_id = ?1 AND path = ?2",
+            rusqlite::params![id, old_mount_point, new_mount_point],
+        )?;
+        tx.commit()
+    })
+}
+
+/// Indexed file count and bytes of each volume
+pub fn volume_totals() -> std::result::Result<std::collections::HashMap<String, (u64, u64)>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT volume_id, COUNT(*), COALESCE(SUM(size), 0) FROM files
+             WHERE type = 'file' AND volume_id IS NOT NULL GROUP BY volume_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, (row.get::<_, i64>(1)? as u64, row.get::<_, i64>(2)? as u64)))
+        })?;
+        rows.collect()
+    })
+}
+
+/// Files and folders the catalog holds directly inside `folder` of a volume,
+/// whether or not the volume is mounted
+pub fn volume_entries(id: &str, folder: &str) -> std::result::Result<Vec<IndexedFile>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, name, file_type, size, modified_at, parent_path FROM files
+             WHERE volume_id = ?1 AND parent_path = ?2 ORDER BY type DESC, name",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![id, folder], indexed_file_from_row)?;
+        let files: Result<Vec<IndexedFile>> = rows.collect();
+        files
+    })
+}
+
+/// Model runs older than this are dropped from the usage log
+const AI_USAGE_RETENTION_DAYS: i64 = 365;
+
+// Model use by one feature over a period
+#[derive(Debug, Clone, Serialize)]
+pub struct AiUsage {
+    pub feature: String,
+    pub runs: u64,
+    /// Tokens generated, or tokens embedded for embedding runs
+    pub tokens: u64,
+    /// Files, names or texts processed
+    pub items: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Log one model run, dropping runs past the retention period
+pub fn record_ai_usage(feature: &str, tokens: u64, items: u64, elapsed_ms: u64) -> std::result::Result<(), String> {
+    let now = chrono::Utc::now();
+    let recorded_at = now.format(INDEX_TIME_FORMAT).to_string();
+    let cutoff = (now - chrono::Duration::days(AI_USAGE_RETENTION_DAYS))
+        .format(INDEX_TIME_FORMAT)
+        .to_string();
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO ai_usage (feature, tokens, items, elapsed_ms, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![feature, tokens as i64, items as i64, elapsed_ms as i64, recorded_at],
+        )?;
+        conn.execute("DELETE FROM ai_usage WHERE recorded_at < ?1", rusqlite::params![cutoff])?;
+        Ok(())
+    })
+}
+
+/// Model use per feature since `since` (INDEX_TIME_FORMAT), heaviest first
+pub fn ai_usage_since(since: &str) -> std::result::Result<Vec<AiUsage>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT feature, COUNT(*), SUM(tokens), SUM(items), SUM(elapsed_ms) FROM ai_usage
+             WHERE recorded_at >= ?1 GROUP BY feature ORDER BY SUM(elapsed_ms) DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![since], |row| {
+            Ok(AiUsage {
+                feature: row.get(0)?,
+                runs: row.get::<_, i64>(1)? as u64,
+                tokens: row.get::<_, i64>(2)? as u64,
+                items: row.get::<_, i64>(3)? as u64,
+                elapsed_ms: row.get::<_, i64>(4)? as u64,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+// A file or folder waiting in the staging area to be deleted
+#[derive(Debug, Clone, Serialize)]
+pub struct StagedFile {
+    pub id: String,
+    pub original_path: String,
+    /// Where it sits until it is restored or purged
+    pub staged_path: String,
+    pub size: u64,
+    pub batch_id: Option<String>,
+    pub staged_at: String,
+    /// When the automatic purge deletes it (INDEX_TIME_FORMAT)
+    pub purge_after: String,
+}
+
+pub fn insert_staged_file(file: &StagedFile) -> std::result::Result<(), String> {
+    with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO staged_files (id, original_path, staged_path, size, batch_id, staged_at, purge_after)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                file.id,
+                file.original_path,
+                file.staged_path,
+                file.size as i64,
+                file.batch_id,
+                file.staged_at,
+                file.purge_after
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Everything in the staging area, next to be purged first
+pub fn list_staged_files() -> std::result::Result<Vec<StagedFile>, String> {
+    staged_files_where("1", rusqlite::params![])
+}
+
+/// Staged entries whose grace period ended before `now` (INDEX_TIME_FORMAT)
+pub fn expired_staged_files(now: &str) -> std::result::Result<Vec<StagedFile>, String> {
+    staged_files_where("purge_after <= ?1", rusqlite::params![now])
+}
+
+pub fn staged_file(id: &str) -> std::result::Result<Option<StagedFile>, String> {
+    Ok(staged_files_where("id = ?1", rusqlite::params![id])?.pop())
+}
+
+pub fn delete_staged_file(id: &str) -> std::result::Result<(), String> {
+    with_connection(|conn| conn.execute("DELETE FROM staged_files WHERE id = ?1", rusqlite::params![id]))?;
+    Ok(())
+}
+
+fn staged_files_where(
+    condition: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> std::result::Result<Vec<StagedFile>, String> {
+    with_connection(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, original_path, staged_path, size, batch_id, staged_at, purge_after FROM staged_files
+             WHERE {} ORDER BY purge_after",
+            condition
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            Ok(StagedFile {
+                id: row.get(0)?,
+                original_path: row.get(1)?,
+                staged_path: row.get(2)?,
+                size: row.get::<_, i64>(3)? as u64,
+                batch_id: row.get(4)?,
+                staged_at: row.get(5)?,
+                purge_after: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_like_escapes_wildcards_and_the_escape_character() {
+        assert_eq!(escape_like("plain"), "plain");
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+    }
+
+    #[test]
+    fn pack_rules_pattern_escapes_the_pack_id() {
+        assert_eq!(pack_rules_pattern("my_pack"), "pack:my\\_pack:%");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn below_pattern_trims_trailing_separators() {
+        assert_eq!(below_pattern("/"), "/%");
+        assert_eq!(below_pattern("/home/a_b/"), "/home/a\\_b/%");
+        assert_eq!(below_pattern("/home/a_b"), "/home/a\\_b/%");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn below_pattern_matches_only_paths_inside_the_folder() {
+        let conn = Connection::open_in_memory().unwrap();
+        let matches = |path: &str, folder: &str| -> bool {
+            conn.query_row("SELECT ?1 LIKE ?2 ESCAPE '\\'", rusqlite::params![path, below_pattern(folder)], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert!(matches("/home/a_b/file.txt", "/home/a_b"));
+        assert!(matches("/home/a_b/sub/file.txt", "/home/a_b/"));
+        assert!(!matches("/home/axb/file.txt", "/home/a_b"));
+        assert!(!matches("/home/a_b2/file.txt", "/home/a_b"));
+        assert!(!matches("/home/100%/file.txt", "/home/100_"));
+        assert!(matches("/media/usb", "/"));
+    }
+}
@@ -0,0 +1,233 @@
+// ============================================================================
+// Storage Sense Module - Folders the OS cleans up or evicts on its own
+// ============================================================================
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::fileops;
+
+// OS feature that deletes or evicts files without us
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupFeature {
+    /// Windows Storage Sense deleting old files
+    StorageSense,
+    /// Storage Sense making unused OneDrive files online-only
+    OneDriveOnDemand,
+    /// macOS Optimize Mac Storage evicting iCloud Drive files
+    OptimizeStorage,
+    /// Finder emptying the Trash after 30 days
+    AutoEmptyTrash,
+}
+
+// What the OS does to a folder it manages
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupAction {
+    /// Files are deleted
+    Delete,
+    /// Local copies are removed; the files stay in the cloud
+    Evict,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagedFolder {
+    pub path: String,
+    pub feature: CleanupFeature,
+    pub action: CleanupAction,
+    /// Age after which files are deleted or evicted, when the OS exposes it
+    pub after_days: Option<u32>,
+    pub explanation: String,
+}
+
+// How the OS treats a path, for library settings and previews
+#[derive(Debug, Clone, Serialize)]
+pub struct CleanupStatus {
+    pub path: String,
+    /// "unmanaged", "os_managed" or "online_only"
+    pub status: String,
+    pub managed_by: Vec<ManagedFolder>,
+    /// The file's contents were evicted; reading it would download it
+    pub dehydrated: bool,
+    pub explanation: Option<String>,
+}
+
+/// Folders the OS currently cleans up or evicts, from its settings
+pub fn managed_folders() -> Vec<ManagedFolder> {
+    if cfg!(windows) {
+        windows_managed_folders()
+    } else if cfg!(target_os = "macos") {
+        macos_managed_folders()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Entries of `folders` that contain `path`
+pub fn managing<'a>(path: &Path, folders: &'a [ManagedFolder]) -> Vec<&'a ManagedFolder> {
+    folders
+        .iter()
+        .filter(|folder| path.starts_with(&folder.path))
+        .collect()
+}
+
+/// How the OS treats `path`, and what we do differently because of it
+pub fn status(path: &Path, folders: &[ManagedFolder]) -> CleanupStatus {
+    let dehydrated = fs::metadata(fileops::long_path(path))
+        .map(|metadata| fileops::is_dehydrated(&metadata))
+        .unwrap_or(false);
+    let managed_by: Vec<ManagedFolder> = managing(path, folders).into_iter().cloned().collect();
+
+    let (status, explanation) = if dehydrated {
+        (
+            "online_only",
+            Some(
+                "This file is stored in the cloud only. It is not hashed or indexed so it isn't \
+                 downloaded again; moves within the same cloud folder keep it online-only."
+                    .to_string(),
+            ),
+        )
+    } else if let Some(folder) = managed_by.first() {
+        ("os_managed", Some(folder.explanation.clone()))
+    } else {
+        ("unmanaged", None)
+    };
+
+    CleanupStatus {
+        path: path.to_string_lossy().to_string(),
+        status: status.to_string(),
+        managed_by,
+        dehydrated,
+        explanation,
+    }
+}
+
+/// Warning for an organize destination the OS will clean up behind our back
+pub fn destination_warning(destination: &Path, folders: &[ManagedFolder]) -> Option<String> {
+    managing(destination, folders)
+        .into_iter()
+        .find(|folder| folder.action == CleanupAction::Delete)
+        .map(|folder| match folder.after_days {
+            Some(days) => format!("The OS deletes files in {} after {} days", folder.path, days),
+            None => format!("The OS deletes files in {} automatically", folder.path),
+        })
+}
+
+// Storage Sense keeps its settings as numbered DWORDs under StoragePolicy:
+// 01 enabled, 32/512 Downloads cleanup and its age in days
+fn windows_managed_folders() -> Vec<ManagedFolder> {
+    let policy = registry_dwords(r"HKCU\Software\Microsoft\Windows\CurrentVersion\StorageSense\Parameters\StoragePolicy");
+    if policy.get("01") != Some(&1) {
+        return Vec::new();
+    }
+
+    let mut folders = Vec::new();
+    let profile = std::env::var_os("USERPROFILE").map(PathBuf::from);
+
+    let downloads_days = policy.get("512").copied().unwrap_or(0);
+    if policy.get("32") == Some(&1) && downloads_days > 0 {
+        if let Some(profile) = &profile {
+            folders.push(ManagedFolder {
+                path: profile.join("Downloads").to_string_lossy().to_string(),
+                feature: CleanupFeature::StorageSense,
+                action: CleanupAction::Delete,
+                after_days: Some(downloads_days),
+                explanation: format!(
+                    "Storage Sense deletes files in Downloads that haven't been opened for {} days. \
+                     Files organized out of this folder are safe from it.",
+                    downloads_days
+                ),
+            });
+        }
+    }
+
+    for variable in ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"] {
+        let Some(root) = std::env::var_os(variable) else {
+            continue;
+        };
+        let root = root.to_string_lossy().to_string();
+        if folders.iter().any(|folder| folder.path == root) {
+            continue;
+        }
+        folders.push(ManagedFolder {
+            path: root,
+            feature: CleanupFeature::OneDriveOnDemand,
+            action: CleanupAction::Evict,
+            after_days: None,
+            explanation: "Storage Sense can make OneDrive files you haven't opened recently online-only. \
+                          Those files are skipped by hashing and indexing so they aren't downloaded again."
+                .to_string(),
+        });
+    }
+
+    folders
+}
+
+// DWORD values of a registry key, by name
+fn registry_dwords(key: &str) -> HashMap<String, u32> {
+    let Ok(output) = Command::new("reg").args(["query", key]).output() else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            if parts.next()? != "REG_DWORD" {
+                return None;
+            }
+            let value = u32::from_str_radix(parts.next()?.trim_start_matches("0x"), 16).ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+fn macos_managed_folders() -> Vec<ManagedFolder> {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let mut folders = Vec::new();
+
+    let icloud = home.join("Library/Mobile Documents/com~apple~CloudDocs");
+    if icloud.is_dir() {
+        let explanation = "With Optimize Mac Storage on, macOS removes local copies of iCloud Drive files \
+                           you haven't opened recently. Those files are skipped by hashing and indexing so \
+                           they aren't downloaded again."
+            .to_string();
+        let mut roots = vec![icloud.clone()];
+        // Desktop & Documents Folders syncing puts both under iCloud Drive
+        if icloud.join("Desktop").is_dir() {
+            roots.push(home.join("Desktop"));
+            roots.push(home.join("Documents"));
+        }
+        for root in roots {
+            folders.push(ManagedFolder {
+                path: root.to_string_lossy().to_string(),
+                feature: CleanupFeature::OptimizeStorage,
+                action: CleanupAction::Evict,
+                after_days: None,
+                explanation: explanation.clone(),
+            });
+        }
+    }
+
+    let empties_trash = Command::new("defaults")
+        .args(["read", "com.apple.finder", "FXRemoveOldTrashItems"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1");
+    if empties_trash {
+        folders.push(ManagedFolder {
+            path: home.join(".Trash").to_string_lossy().to_string(),
+            feature: CleanupFeature::AutoEmptyTrash,
+            action: CleanupAction::Delete,
+            after_days: Some(30),
+            explanation: "Finder deletes items that have been in the Trash for 30 days.".to_string(),
+        });
+    }
+
+    folders
+}
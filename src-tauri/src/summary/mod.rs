@@ -0,0 +1,136 @@
+// ============================================================================
+// Summary Module - One-sentence descriptions of plans and batch results
+// ============================================================================
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::commands::files::get_file_type;
+
+/// Describe files going from sources to destinations, e.g. "Moved 312
+/// images into 12 folders under Pictures/2024". `verb` leads the sentence
+/// ("Moved", "Will copy"); `failed` is appended when non-zero.
+pub fn describe_transfer<'a>(
+    verb: &str,
+    transfers: impl IntoIterator<Item = (&'a str, &'a str)>,
+    failed: usize,
+) -> String {
+    let mut types: BTreeMap<String, usize> = BTreeMap::new();
+    let mut folders: BTreeSet<PathBuf> = BTreeSet::new();
+    for (source, destination) in transfers {
+        let extension = Path::new(source)
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        *types.entry(get_file_type(&extension)).or_insert(0) += 1;
+        if let Some(parent) = Path::new(destination).parent() {
+            folders.insert(parent.to_path_buf());
+        }
+    }
+
+    let count: usize = types.values().sum();
+    let mut sentence = if count == 0 {
+        format!("{} no files", verb)
+    } else {
+        let what = match types.keys().next() {
+            Some(file_type) if types.len() == 1 => count_of(count, file_type),
+            _ => count_of(count, "other"),
+        };
+        format!("{} {} {}", verb, what, destination_phrase(&folders))
+    };
+    if failed > 0 {
+        sentence.push_str(&format!("; {} failed", failed));
+    }
+    sentence
+}
+
+/// Describe a folder mirror, e.g. "Mirrored Documents to Backup: copied 10
+/// new and 3 updated files, removed 2"
+pub fn describe_mirror(
+    source: &str,
+    destination: &str,
+    added: usize,
+    updated: usize,
+    pruned: usize,
+    failed: usize,
+    dry_run: bool,
+) -> String {
+    let (lead, copied, removed) = if dry_run {
+        ("Mirroring would copy", "would copy", "would remove")
+    } else {
+        ("Mirrored", "copied", "removed")
+    };
+    let (source, destination) = (short_path(Path::new(source)), short_path(Path::new(destination)));
+
+    let mut sentence = if added + updated + pruned == 0 {
+        format!("{} is already up to date with {}", destination, source)
+    } else if dry_run {
+        format!("{} {} new and {} updated files from {} to {}", lead, added, updated, source, destination)
+    } else {
+        format!("{} {} to {}: {} {} new and {} updated files", lead, source, destination, copied, added, updated)
+    };
+    if pruned > 0 {
+        let separator = if dry_run { " and" } else { "," };
+        sentence.push_str(&format!("{} {} {}", separator, removed, count_of(pruned, "other")));
+    }
+    if failed > 0 {
+        sentence.push_str(&format!("; {} failed", failed));
+    }
+    sentence
+}
+
+// "into Pictures/2024" or "into 12 folders under Pictures"
+fn destination_phrase(folders: &BTreeSet<PathBuf>) -> String {
+    let mut folder_iter = folders.iter();
+    let Some(first) = folder_iter.next() else {
+        return "in place".to_string();
+    };
+    if folders.len() == 1 {
+        return format!("into {}", short_path(first));
+    }
+
+    let mut common = first.clone();
+    for folder in folder_iter {
+        while !folder.starts_with(&common) {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    if common.as_os_str().is_empty() || common.parent().is_none() {
+        format!("into {} folders", folders.len())
+    } else {
+        format!("into {} folders under {}", folders.len(), short_path(&common))
+    }
+}
+
+// The last two components of a path; full paths are tedious to listen to
+fn short_path(path: &Path) -> String {
+    let mut tail: Vec<_> = path
+        .components()
+        .rev()
+        .take(2)
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    tail.reverse();
+    tail.join("/")
+}
+
+// "1 image", "312 images", "5 files"
+fn count_of(count: usize, file_type: &str) -> String {
+    let (singular, plural) = match file_type {
+        "document" => ("document", "documents"),
+        "pdf" => ("PDF", "PDFs"),
+        "spreadsheet" => ("spreadsheet", "spreadsheets"),
+        "presentation" => ("presentation", "presentations"),
+        "image" => ("image", "images"),
+        "screenshot" => ("screenshot", "screenshots"),
+        "video" => ("video", "videos"),
+        "audio" => ("audio file", "audio files"),
+        "archive" => ("archive", "archives"),
+        "executable" => ("program", "programs"),
+        "code" => ("code file", "code files"),
+        _ => ("file", "files"),
+    };
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
@@ -0,0 +1,152 @@
+// ============================================================================
+// Throttle Module - Throughput caps and foreground priority for I/O
+// ============================================================================
+
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::storage;
+
+const IO_LIMITS_KEY: &str = "io_rate_limits";
+/// How often a paused background job checks whether it may resume
+const BACKGROUND_POLL: Duration = Duration::from_millis(50);
+
+// Interactive operations in flight; background jobs pause while any run
+static FOREGROUND: AtomicUsize = AtomicUsize::new(0);
+
+// Throughput caps in MB/s; None means unlimited
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IoLimits {
+    pub hashing_mbps: Option<f64>,
+    pub copying_mbps: Option<f64>,
+    pub network_mbps: Option<f64>,
+}
+
+// Kinds of I/O that are capped separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoClass {
+    Hashing,
+    Copying,
+    Network,
+}
+
+impl IoLimits {
+    fn bytes_per_second(&self, class: IoClass) -> Option<f64> {
+        let mbps = match class {
+            IoClass::Hashing => self.hashing_mbps,
+            IoClass::Copying => self.copying_mbps,
+            IoClass::Network => self.network_mbps,
+        };
+        mbps.filter(|mbps| *mbps > 0.0).map(|mbps| mbps * 1024.0 * 1024.0)
+    }
+}
+
+static LIMITS: Lazy<RwLock<IoLimits>> = Lazy::new(|| {
+    let limits = storage::get_preference(IO_LIMITS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    RwLock::new(limits)
+});
+
+// When each class may next transfer, shared by all threads so parallel
+// hashing still respects one cap
+static NEXT_SLOT: Lazy<Mutex<[Option<Instant>; 3]>> = Lazy::new(|| Mutex::new([None; 3]));
+
+/// Current limits
+pub fn limits() -> IoLimits {
+    *LIMITS.read()
+}
+
+/// Replace the limits; running operations pick them up on their next chunk
+pub fn set_limits(limits: IoLimits) -> Result<(), String> {
+    let json = serde_json::to_string(&limits).map_err(|e| format!("Failed to serialize limits: {}", e))?;
+    storage::set_preference(IO_LIMITS_KEY, &json)?;
+    *LIMITS.write() = limits;
+    *NEXT_SLOT.lock() = [None; 3];
+    Ok(())
+}
+
+/// Whether `class` currently has a cap
+pub fn is_limited(class: IoClass) -> bool {
+    limits().bytes_per_second(class).is_some()
+}
+
+/// Block until `bytes` more of `class` I/O fit under its cap
+pub fn consume(class: IoClass, bytes: usize) {
+    let wait = reserve(class, bytes);
+    if !wait.is_zero() {
+        std::thread::sleep(wait);
+    }
+}
+
+/// Async form of `consume` for downloads
+pub async fn consume_async(class: IoClass, bytes: usize) {
+    let wait = reserve(class, bytes);
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Book the next free time slot for `bytes` and return how long to wait for it
+fn reserve(class: IoClass, bytes: usize) -> Duration {
+    let Some(rate) = limits().bytes_per_second(class) else {
+        return Duration::ZERO;
+    };
+    let now = Instant::now();
+    let mut slots = NEXT_SLOT.lock();
+    let slot = &mut slots[class as usize];
+    let start = slot.filter(|next| *next > now).unwrap_or(now);
+    *slot = Some(start + Duration::from_secs_f64(bytes as f64 / rate));
+    start - now
+}
+
+// Reader that throttles everything read through it
+pub struct ThrottledReader<R> {
+    inner: R,
+    class: IoClass,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    pub fn new(inner: R, class: IoClass) -> Self {
+        Self { inner, class }
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        consume(self.class, read);
+        Ok(read)
+    }
+}
+
+// Held for the duration of an interactive operation
+pub struct ForegroundGuard(());
+
+impl Drop for ForegroundGuard {
+    fn drop(&mut self) {
+        FOREGROUND.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Mark an interactive operation (plan apply, search) as running until the
+/// guard is dropped
+pub fn foreground() -> ForegroundGuard {
+    FOREGROUND.fetch_add(1, Ordering::SeqCst);
+    ForegroundGuard(())
+}
+
+/// Called by background jobs between items: waits while interactive
+/// operations are running so they get the disk to themselves
+pub fn yield_to_foreground() {
+    while FOREGROUND.load(Ordering::SeqCst) > 0 {
+        std::thread::sleep(BACKGROUND_POLL);
+    }
+}
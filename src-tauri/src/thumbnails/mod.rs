@@ -0,0 +1,154 @@
+// ============================================================================
+// Thumbnails Module - Cached resized previews for image and video files
+// ============================================================================
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::files::get_file_type;
+use crate::fileops;
+use crate::libraries;
+use crate::media;
+use crate::photos;
+
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+const MAX_SCENE_FRAMES: u32 = 12;
+/// Images wider or taller than this aren't decoded; a crafted or huge
+/// image would otherwise need gigabytes of memory for a small preview
+const MAX_DECODE_DIMENSION: u32 = 16_384;
+
+/// Directory holding generated thumbnails
+pub fn thumbnail_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let dir = app_data_dir.join("thumbnails");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create thumbnails dir: {}", e))?;
+    Ok(dir)
+}
+
+/// Whether a thumbnail can be generated for this file
+pub fn is_supported(path: &Path) -> bool {
+    image::ImageFormat::from_path(path).is_ok()
+        || path
+            .extension()
+            .is_some_and(|ext| photos::is_camera_format(&ext.to_string_lossy()))
+        || (is_video(path) && media::can_extract_frames())
+}
+
+fn is_video(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| get_file_type(&ext.to_string_lossy()) == "video")
+}
+
+/// Path of the cached thumbnail for `path`, generating it if needed.
+///
+/// Thumbnails are keyed by path, size and modification time, so edited
+/// files get a new one without the file being read to find out.
+pub fn thumbnail_for(app: &AppHandle, path: &Path, size: u32) -> Result<PathBuf, String> {
+    if !is_supported(path) {
+        return Err(format!("No thumbnail support for {}", path.display()));
+    }
+    check_policy(path)?;
+
+    let size = size.clamp(16, MAX_THUMBNAIL_SIZE);
+    let key = cache_key(path)?;
+    let target = thumbnail_dir(app)?.join(format!("{}_{}.png", key, size));
+    if !target.exists() {
+        fileops::ensure_local(path)?;
+    }
+
+    if !target.exists() && is_video(path) {
+        // Written under another name first, so a failed extraction never
+        // leaves a broken thumbnail in the cache
+        let partial = target.with_extension("partial.png");
+        media::video_frame(path, &partial, size)?;
+        std::fs::rename(&partial, &target).map_err(|e| format!("Failed to write thumbnail: {}", e))?;
+    } else if !target.exists() {
+        let image = decode(path)?;
+        image
+            .thumbnail(size, size)
+            .save_with_format(&target, image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to write thumbnail: {}", e))?;
+    }
+
+    Ok(target)
+}
+
+/// The first frame of each scene of a video, up to `count`, generated on
+/// first request and cached like thumbnails. Videos without cuts give a
+/// single frame.
+pub fn scene_frames_for(app: &AppHandle, path: &Path, count: u32, size: u32) -> Result<Vec<PathBuf>, String> {
+    if !is_video(path) {
+        return Err(format!("Not a video: {}", path.display()));
+    }
+    check_policy(path)?;
+
+    let size = size.clamp(16, MAX_THUMBNAIL_SIZE);
+    let count = count.clamp(1, MAX_SCENE_FRAMES);
+    let key = cache_key(path)?;
+    let dir = thumbnail_dir(app)?;
+    let frame = |n: u32| dir.join(format!("{}_{}_scene{}.png", key, size, n));
+
+    if !frame(1).exists() {
+        fileops::ensure_local(path)?;
+        media::scene_frames(path, &dir.join(format!("{}_{}_scene%d.png", key, size)), count, size)?;
+    }
+
+    let frames: Vec<PathBuf> = (1..=count).map(frame).take_while(|frame| frame.exists()).collect();
+    if frames.is_empty() {
+        return Err(format!("No frames extracted from {}", path.display()));
+    }
+    Ok(frames)
+}
+
+// Previews read the file's contents, so they follow the content policy of
+// the file's library
+fn check_policy(path: &Path) -> Result<(), String> {
+    let size = std::fs::metadata(fileops::long_path(path)).map_or(0, |metadata| metadata.len());
+    if libraries::policy_for(path, &libraries::list()).allows_content(size) {
+        Ok(())
+    } else {
+        Err(format!("Previews are turned off for {}", path.display()))
+    }
+}
+
+// Name of a file's cached previews, from its path, size and modification
+// time
+fn cache_key(path: &Path) -> Result<String, String> {
+    let metadata =
+        std::fs::metadata(fileops::long_path(path)).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let (size, mtime) = fileops::fingerprint(&metadata);
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(size.to_le_bytes());
+    hasher.update(mtime.to_le_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// RAW and HEIF files fall back to the JPEG preview the camera embedded, then
+// to the OS's decoder; AVIF is decoded directly when the image crate can
+fn decode(path: &Path) -> Result<image::DynamicImage, String> {
+    let camera_format = path
+        .extension()
+        .is_some_and(|ext| photos::is_camera_format(&ext.to_string_lossy()));
+    match open_limited(path) {
+        Ok(image) => Ok(image),
+        Err(e) if !camera_format => Err(format!("Failed to decode image: {}", e)),
+        Err(e) => photos::embedded_preview(path)
+            .or_else(|| photos::system_decode(path))
+            .ok_or_else(|| format!("Failed to decode image: {}", e)),
+    }
+}
+
+// image::open, refusing images beyond MAX_DECODE_DIMENSION on either side
+fn open_limited(path: &Path) -> image::ImageResult<image::DynamicImage> {
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_DECODE_DIMENSION);
+    limits.max_image_height = Some(MAX_DECODE_DIMENSION);
+    let mut reader = image::ImageReader::open(fileops::long_path(path))?.with_guessed_format()?;
+    reader.limits(limits);
+    reader.decode()
+}
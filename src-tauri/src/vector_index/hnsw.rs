@@ -0,0 +1,237 @@
+// ============================================================================
+// HNSW - Approximate nearest neighbor graph over normalized vectors
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Node {
+    key: String,
+    vector: Vec<f32>,
+    /// Neighbor ids per layer, layer 0 first
+    neighbors: Vec<Vec<u32>>,
+}
+
+/// Hierarchical navigable small world graph (Malkov & Yashunin).
+///
+/// Vectors are expected to be L2-normalized, so distance is `1 - dot`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Hnsw {
+    m: usize,
+    ef_construction: usize,
+    nodes: Vec<Node>,
+    keys: HashMap<String, u32>,
+    entry: Option<u32>,
+    max_level: usize,
+    rng_state: u64,
+}
+
+// Heap entry ordered by distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: u32,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+impl Hnsw {
+    pub fn new(m: usize, ef_construction: usize) -> Self {
+        Self {
+            m,
+            ef_construction,
+            nodes: Vec::new(),
+            keys: HashMap::new(),
+            entry: None,
+            max_level: 0,
+            rng_state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.contains_key(key)
+    }
+
+    /// Add a vector under `key`; keys already present are ignored
+    pub fn insert(&mut self, key: &str, vector: Vec<f32>) {
+        if self.contains(key) {
+            return;
+        }
+
+        let id = self.nodes.len() as u32;
+        let level = self.random_level();
+        self.nodes.push(Node {
+            key: key.to_string(),
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+        });
+        self.keys.insert(key.to_string(), id);
+
+        let Some(mut entry) = self.entry else {
+            self.entry = Some(id);
+            self.max_level = level;
+            return;
+        };
+
+        let query = self.nodes[id as usize].vector.clone();
+
+        // Greedy descent through the layers above the new node
+        for layer in (level + 1..=self.max_level).rev() {
+            entry = self.search_layer(&query, &[entry], 1, layer)[0].id;
+        }
+
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let candidates = self.search_layer(&query, &[entry], self.ef_construction, layer);
+            let max_connections = self.max_connections(layer);
+            let selected: Vec<u32> = candidates
+                .iter()
+                .take(max_connections)
+                .map(|c| c.id)
+                .collect();
+
+            self.nodes[id as usize].neighbors[layer] = selected.clone();
+            for neighbor in selected {
+                self.nodes[neighbor as usize].neighbors[layer].push(id);
+                if self.nodes[neighbor as usize].neighbors[layer].len() > max_connections {
+                    self.prune(neighbor, layer, max_connections);
+                }
+            }
+            entry = candidates[0].id;
+        }
+
+        if level > self.max_level {
+            self.entry = Some(id);
+            self.max_level = level;
+        }
+    }
+
+    /// Up to `k` nearest keys with their cosine similarity, best first
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(String, f32)> {
+        let Some(mut entry) = self.entry else {
+            return Vec::new();
+        };
+
+        for layer in (1..=self.max_level).rev() {
+            entry = self.search_layer(query, &[entry], 1, layer)[0].id;
+        }
+
+        self.search_layer(query, &[entry], ef.max(k), 0)
+            .into_iter()
+            .take(k)
+            .map(|c| (self.nodes[c.id as usize].key.clone(), 1.0 - c.distance))
+            .collect()
+    }
+
+    fn max_connections(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    fn distance(&self, query: &[f32], id: u32) -> f32 {
+        let vector = &self.nodes[id as usize].vector;
+        1.0 - query.iter().zip(vector).map(|(a, b)| a * b).sum::<f32>()
+    }
+
+    // Best-first search within one layer; returns candidates sorted by distance
+    fn search_layer(&self, query: &[f32], entry_points: &[u32], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates: BinaryHeap<std::cmp::Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &id in entry_points {
+            let candidate = Candidate {
+                distance: self.distance(query, id),
+                id,
+            };
+            candidates.push(std::cmp::Reverse(candidate));
+            results.push(candidate);
+        }
+
+        while let Some(std::cmp::Reverse(current)) = candidates.pop() {
+            let worst = results.peek().map(|c| c.distance).unwrap_or(f32::MAX);
+            if current.distance > worst && results.len() >= ef {
+                break;
+            }
+
+            let neighbors = match self.nodes[current.id as usize].neighbors.get(layer) {
+                Some(neighbors) => neighbors,
+                None => continue,
+            };
+            for &neighbor in neighbors {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance(query, neighbor),
+                    id: neighbor,
+                };
+                let worst = results.peek().map(|c| c.distance).unwrap_or(f32::MAX);
+                if results.len() < ef || candidate.distance < worst {
+                    candidates.push(std::cmp::Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    // Keep only the closest `max_connections` neighbors of `id` on `layer`
+    fn prune(&mut self, id: u32, layer: usize, max_connections: usize) {
+        let vector = self.nodes[id as usize].vector.clone();
+        let mut scored: Vec<Candidate> = self.nodes[id as usize].neighbors[layer]
+            .iter()
+            .map(|&neighbor| Candidate {
+                distance: self.distance(&vector, neighbor),
+                id: neighbor,
+            })
+            .collect();
+        scored.sort();
+        scored.truncate(max_connections);
+        self.nodes[id as usize].neighbors[layer] = scored.into_iter().map(|c| c.id).collect();
+    }
+
+    // Exponentially distributed level with normalization 1/ln(M)
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        let random = self.rng_state.wrapping_mul(0x2545_F491_4F6C_DD1D);
+
+        let uniform = ((random >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let normalization = 1.0 / (self.m.max(2) as f64).ln();
+        ((-uniform.ln() * normalization).floor() as usize).min(16)
+    }
+}
@@ -0,0 +1,93 @@
+// ============================================================================
+// Vector Index Module - Persisted ANN index over cached embeddings
+// ============================================================================
+
+mod hnsw;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::embeddings;
+use crate::storage;
+use hnsw::Hnsw;
+
+const INDEX_FILENAME: &str = "vector_index.bin";
+const HNSW_M: usize = 16;
+const HNSW_EF_CONSTRUCTION: usize = 100;
+const HNSW_EF_SEARCH: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+struct VectorIndex {
+    model_id: String,
+    graph: Hnsw,
+}
+
+static INDEX: Lazy<RwLock<Option<VectorIndex>>> = Lazy::new(|| RwLock::new(None));
+
+fn index_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(app_data_dir.join(INDEX_FILENAME))
+}
+
+fn load_from_disk(path: &Path) -> Option<VectorIndex> {
+    let file = File::open(path).ok()?;
+    bincode::deserialize_from(BufReader::new(file)).ok()
+}
+
+/// Make sure the index is loaded for the current embedding model and holds
+/// every cached vector. Vectors added since the last save are inserted
+/// incrementally; a model switch starts a fresh graph.
+pub fn sync(app: &AppHandle) -> Result<(), String> {
+    let model_id = embeddings::current_model_id();
+    let path = index_path(app)?;
+    let mut guard = INDEX.write();
+
+    let stale = guard.as_ref().map(|index| index.model_id != model_id).unwrap_or(true);
+    if stale {
+        *guard = load_from_disk(&path).filter(|index| index.model_id == model_id);
+    }
+    let index = guard.get_or_insert_with(|| VectorIndex {
+        model_id: model_id.clone(),
+        graph: Hnsw::new(HNSW_M, HNSW_EF_CONSTRUCTION),
+    });
+
+    let mut added = 0;
+    for (content_hash, vector) in storage::embeddings_for_model(&model_id)? {
+        if !index.graph.contains(&content_hash) {
+            index.graph.insert(&content_hash, embeddings::decode_vector(&vector));
+            added += 1;
+        }
+    }
+
+    if added > 0 || stale {
+        let file = File::create(&path).map_err(|e| format!("Failed to write vector index: {}", e))?;
+        bincode::serialize_into(BufWriter::new(file), &*index)
+            .map_err(|e| format!("Failed to write vector index: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Drop the in-memory index, e.g. after the embedding model changed
+pub fn reset() {
+    *INDEX.write() = None;
+}
+
+/// Nearest content hashes to `query` with cosine similarity, best first
+pub fn search(query: &[f32], k: usize) -> Vec<(String, f32)> {
+    INDEX
+        .read()
+        .as_ref()
+        .map(|index| index.graph.search(query, k, HNSW_EF_SEARCH))
+        .unwrap_or_default()
+}
+
+/// Number of vectors in the loaded index
+pub fn len() -> usize {
+    INDEX.read().as_ref().map(|index| index.graph.len()).unwrap_or(0)
+}
@@ -0,0 +1,516 @@
+// ============================================================================
+// Volumes Module - Mounted drives and the catalog kept for each of them
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::network;
+use crate::shutdown;
+use crate::storage;
+
+/// Event emitted when a volume is mounted or unmounted
+pub const VOLUMES_CHANGED_EVENT: &str = "volumes-changed";
+
+/// How often mount points are checked for drives coming and going
+const VOLUME_POLL: Duration = Duration::from_secs(5);
+
+// File systems that are not drives the user keeps files on
+const PSEUDO_FILE_SYSTEMS: &[&str] = &[
+    "squashfs", "overlay", "tmpfs", "devtmpfs", "ramfs", "efivarfs",
+];
+
+// A drive known to the catalog. Its indexed files stay searchable while it
+// is unplugged, and follow it when it comes back at another mount point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Volume {
+    /// Filesystem UUID or serial number, so the drive is recognized
+    /// wherever it is mounted
+    pub id: String,
+    pub label: String,
+    /// Where it is mounted, or was when last seen
+    pub mount_point: String,
+    pub file_system: String,
+    /// USB sticks, card readers, external disks
+    pub removable: bool,
+    pub total_size: u64,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+}
+
+/// FAT file systems, as found on USB sticks, SD cards and camera cards
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FatKind {
+    /// FAT12/16/32: files below 4 GB, times to 2 seconds
+    Fat,
+    /// exFAT: no practical size limit, times to 10 ms
+    ExFat,
+}
+
+impl FatKind {
+    /// Largest file the file system can hold, if it has a limit that matters
+    pub fn max_file_size(&self) -> Option<u64> {
+        match self {
+            FatKind::Fat => Some(u32::MAX as u64),
+            FatKind::ExFat => None,
+        }
+    }
+
+    /// How precisely modification times are kept
+    pub fn time_resolution(&self) -> Duration {
+        match self {
+            FatKind::Fat => Duration::from_secs(2),
+            FatKind::ExFat => Duration::from_millis(10),
+        }
+    }
+}
+
+impl Volume {
+    /// Which FAT file system the volume uses, if any; names vary by OS
+    /// ("FAT32" on Windows, "msdos" on macOS, "vfat" on Linux)
+    pub fn fat_kind(&self) -> Option<FatKind> {
+        match self.file_system.to_ascii_lowercase().as_str() {
+            "exfat" => Some(FatKind::ExFat),
+            "fat" | "fat12" | "fat16" | "fat32" | "vfat" | "msdos" => Some(FatKind::Fat),
+            _ => None,
+        }
+    }
+}
+
+/// The volume `path` is on, among `volumes`: the one with the deepest
+/// mount point containing it
+pub fn volume_of<'a>(path: &Path, volumes: &'a [Volume]) -> Option<&'a Volume> {
+    volumes
+        .iter()
+        .filter(|volume| path.starts_with(&volume.mount_point))
+        .max_by_key(|volume| Path::new(&volume.mount_point).components().count())
+}
+
+/// Volumes the OS reports mounted right now, read without touching the
+/// catalog; empty when the mount table can't be read
+pub fn mounted_now() -> Vec<Volume> {
+    let now = chrono::Utc::now().to_rfc3339();
+    mounted_volumes()
+        .into_iter()
+        .map(|mounted| Volume {
+            id: mounted.id,
+            label: mounted.label,
+            mount_point: mounted.mount_point.to_string_lossy().to_string(),
+            file_system: mounted.file_system,
+            removable: mounted.removable,
+            total_size: mounted.total_size,
+            first_seen_at: now.clone(),
+            last_seen_at: now.clone(),
+        })
+        .collect()
+}
+
+// A volume as the OS reports it right now
+struct MountedVolume {
+    id: String,
+    label: String,
+    mount_point: PathBuf,
+    file_system: String,
+    removable: bool,
+    total_size: u64,
+}
+
+// A known volume with its online state and what the catalog holds for it
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeSummary {
+    #[serde(flatten)]
+    pub volume: Volume,
+    pub online: bool,
+    pub indexed_files: u64,
+    pub indexed_size: u64,
+}
+
+/// Record the volumes mounted now. New drives are added to the catalog,
+/// files indexed under a mount point without a volume are attributed to
+/// it, and a known drive mounted somewhere else has its indexed files
+/// re-linked to the new mount point. Returns the ids of mounted volumes.
+pub fn refresh() -> Result<BTreeSet<String>, String> {
+    let known: HashMap<String, Volume> = storage::list_volumes()?
+        .into_iter()
+        .map(|volume| (volume.id.clone(), volume))
+        .collect();
+    let mut mounted = mounted_volumes();
+    network::forget_mounts();
+    // Deepest first, so a drive mounted inside another claims its own files
+    mounted.sort_by_key(|volume| std::cmp::Reverse(volume.mount_point.components().count()));
+
+    // Paths are unique in the catalog, so files of a drive that moved, and
+    // of a drive last seen where another one is mounted now, are parked
+    // first; drives swapping mount points would otherwise collide
+    for mounted in &mounted {
+        let mount_point = mounted.mount_point.to_string_lossy().to_string();
+        for other in known.values().filter(|other| other.mount_point == mount_point) {
+            if other.id != mounted.id {
+                storage::relink_volume(&other.id, &other.mount_point, &parked_root(&other.id))?;
+            }
+        }
+        if let Some(previous) = known.get(&mounted.id).filter(|previous| previous.mount_point != mount_point) {
+            storage::relink_volume(&mounted.id, &previous.mount_point, &parked_root(&mounted.id))?;
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut ids = BTreeSet::new();
+    for mounted in mounted {
+        let mount_point = mounted.mount_point.to_string_lossy().to_string();
+        let previous = known.get(&mounted.id);
+        storage::relink_volume(&mounted.id, &parked_root(&mounted.id), &mount_point)?;
+        storage::save_volume(&Volume {
+            id: mounted.id.clone(),
+            label: mounted.label,
+            mount_point: mount_point.clone(),
+            file_system: mounted.file_system,
+            removable: mounted.removable,
+            total_size: mounted.total_size,
+            first_seen_at: previous
+                .map_or_else(|| now.clone(), |previous| previous.first_seen_at.clone()),
+            last_seen_at: now.clone(),
+        })?;
+        storage::assign_volume(&mounted.id, &mount_point)?;
+        ids.insert(mounted.id);
+    }
+    storage::set_online_volumes(&ids)?;
+    Ok(ids)
+}
+
+/// Where the catalog keeps the files of a drive while another drive is
+/// mounted where it was last seen; not a real path, so it never collides
+pub fn parked_root(id: &str) -> String {
+    format!("volume:{}", id)
+}
+
+/// Every known volume, mounted ones first
+pub fn summaries(mounted: &BTreeSet<String>) -> Result<Vec<VolumeSummary>, String> {
+    let totals = storage::volume_totals()?;
+    let mut summaries: Vec<VolumeSummary> = storage::list_volumes()?
+        .into_iter()
+        .map(|volume| {
+            let (indexed_files, indexed_size) = totals.get(&volume.id).copied().unwrap_or_default();
+            VolumeSummary {
+                online: mounted.contains(&volume.id),
+                volume,
+                indexed_files,
+                indexed_size,
+            }
+        })
+        .collect();
+    summaries.sort_by(|a, b| {
+        b.online
+            .cmp(&a.online)
+            .then_with(|| a.volume.label.cmp(&b.volume.label))
+    });
+    Ok(summaries)
+}
+
+/// Watch for drives being plugged in and removed, refreshing the catalog
+/// and telling the UI each time the set of mount points changes
+pub fn start_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_mount_points = None;
+        while !shutdown::stopping() {
+            let mount_points = mount_points();
+            if last_mount_points.as_ref() != Some(&mount_points) {
+                match refresh().and_then(|mounted| summaries(&mounted)) {
+                    Ok(summaries) => {
+                        let _ = app.emit(VOLUMES_CHANGED_EVENT, summaries);
+                    }
+                    Err(e) => eprintln!("Volumes: {}", e),
+                }
+                last_mount_points = Some(mount_points);
+            }
+            std::thread::sleep(VOLUME_POLL);
+        }
+    });
+}
+
+// Mount points only, cheap enough to poll
+fn mount_points() -> BTreeSet<PathBuf> {
+    if cfg!(windows) {
+        ('A'..='Z')
+            .map(|letter| PathBuf::from(format!("{}:\\", letter)))
+            .filter(|root| root.exists())
+            .collect()
+    } else if cfg!(target_os = "macos") {
+        mount_points_under_volumes().into_iter().collect()
+    } else {
+        linux_mounts()
+            .into_iter()
+            .map(|(_, mount_point, _)| mount_point)
+            .collect()
+    }
+}
+
+fn mounted_volumes() -> Vec<MountedVolume> {
+    if cfg!(windows) {
+        windows_volumes()
+    } else if cfg!(target_os = "macos") {
+        macos_volumes()
+    } else {
+        linux_volumes()
+    }
+}
+
+// Logical disks from WMI: drive type 2 is removable, 3 local, 4 network
+fn windows_volumes() -> Vec<MountedVolume> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_LogicalDisk | Select-Object DeviceID,DriveType,VolumeSerialNumber,VolumeName,FileSystem,Size | ConvertTo-Json -Compress",
+        ])
+        .output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    let disks = match serde_json::from_slice::<serde_json::Value>(&output.stdout) {
+        Ok(serde_json::Value::Array(disks)) => disks,
+        Ok(disk @ serde_json::Value::Object(_)) => vec![disk],
+        _ => return Vec::new(),
+    };
+
+    disks
+        .iter()
+        .filter_map(|disk| {
+            // Card readers and optical drives with nothing in them have no size
+            let total_size = disk["Size"].as_u64()?;
+            let device = disk["DeviceID"].as_str()?;
+            let drive_type = disk["DriveType"].as_u64().unwrap_or(0);
+            let label = disk["VolumeName"]
+                .as_str()
+                .filter(|name| !name.is_empty())
+                .unwrap_or(device);
+            Some(MountedVolume {
+                id: disk["VolumeSerialNumber"]
+                    .as_str()
+                    .map(|serial| serial.to_string())
+                    .unwrap_or_else(|| fallback_id(label, total_size)),
+                label: label.to_string(),
+                mount_point: PathBuf::from(format!("{}\\", device)),
+                file_system: disk["FileSystem"].as_str().unwrap_or_default().to_string(),
+                removable: drive_type == 2,
+                total_size,
+            })
+        })
+        .collect()
+}
+
+// The startup disk and everything under /Volumes, described by diskutil
+fn macos_volumes() -> Vec<MountedVolume> {
+    let mut roots = vec![PathBuf::from("/")];
+    roots.extend(mount_points_under_volumes());
+
+    roots
+        .into_iter()
+        .filter_map(|mount_point| {
+            // The startup disk also shows up as a link in /Volumes
+            if fs::symlink_metadata(&mount_point).is_ok_and(|m| m.file_type().is_symlink()) {
+                return None;
+            }
+            let output = Command::new("diskutil")
+                .arg("info")
+                .arg("-plist")
+                .arg(&mount_point)
+                .output()
+                .ok()?;
+            let plist = String::from_utf8_lossy(&output.stdout).to_string();
+            let label =
+                plist_value(&plist, "VolumeName").unwrap_or_else(|| display_name(&mount_point));
+            let total_size = plist_value(&plist, "TotalSize")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(0);
+            let internal = plist_value(&plist, "Internal").as_deref() == Some("true");
+            let removable = plist_value(&plist, "RemovableMedia").as_deref() == Some("true");
+            Some(MountedVolume {
+                id: plist_value(&plist, "VolumeUUID")
+                    .unwrap_or_else(|| fallback_id(&label, total_size)),
+                label,
+                file_system: plist_value(&plist, "FilesystemType").unwrap_or_default(),
+                removable: removable || !internal,
+                total_size,
+                mount_point,
+            })
+        })
+        .collect()
+}
+
+fn mount_points_under_volumes() -> Vec<PathBuf> {
+    fs::read_dir("/Volumes")
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Value of a key in diskutil's XML plist: the text of a string or integer,
+// or "true"/"false"
+fn plist_value(plist: &str, key: &str) -> Option<String> {
+    let rest = &plist[plist.find(&format!("<key>{}</key>", key))? + key.len() + 11..];
+    let rest = rest.trim_start();
+    if rest.starts_with("<true/>") {
+        return Some("true".to_string());
+    }
+    if rest.starts_with("<false/>") {
+        return Some("false".to_string());
+    }
+    let start = rest.find('>')? + 1;
+    let end = rest.find("</")?;
+    (start <= end).then(|| rest[start..end].to_string())
+}
+
+// Block devices from /proc/self/mounts, identified by their filesystem UUID
+fn linux_volumes() -> Vec<MountedVolume> {
+    let uuids = device_links("/dev/disk/by-uuid");
+    let labels = device_links("/dev/disk/by-label");
+
+    linux_mounts()
+        .into_iter()
+        .map(|(device, mount_point, file_system)| {
+            let canonical = fs::canonicalize(&device).unwrap_or_else(|_| PathBuf::from(&device));
+            let label = labels
+                .get(&canonical)
+                .cloned()
+                .unwrap_or_else(|| display_name(&mount_point));
+            let total_size = total_size(&mount_point);
+            MountedVolume {
+                id: uuids
+                    .get(&canonical)
+                    .cloned()
+                    .unwrap_or_else(|| fallback_id(&label, total_size)),
+                label,
+                removable: is_removable_device(&canonical),
+                file_system,
+                total_size,
+                mount_point,
+            }
+        })
+        .collect()
+}
+
+// (device, mount point, file system) of each block device, once; bind
+// mounts and pseudo file systems are left out
+fn linux_mounts() -> Vec<(String, PathBuf, String)> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    let mut seen = BTreeSet::new();
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape_mount_field(fields.next()?);
+            let mount_point = PathBuf::from(unescape_mount_field(fields.next()?));
+            let file_system = fields.next()?.to_string();
+            if !device.starts_with("/dev/") || device.starts_with("/dev/loop") {
+                return None;
+            }
+            if PSEUDO_FILE_SYSTEMS.contains(&file_system.as_str())
+                || mount_point.starts_with("/boot")
+            {
+                return None;
+            }
+            seen.insert(device.clone())
+                .then_some((device, mount_point, file_system))
+        })
+        .collect()
+}
+
+/// Undo the octal escapes like \040 that /proc/mounts writes for spaces
+/// and tabs in paths
+pub fn unescape_mount_field(field: &str) -> String {
+    let mut result = String::new();
+    let mut chars = field.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let digits: String = chars.clone().take(3).collect();
+            if let Some(code) = (digits.len() == 3)
+                .then(|| u8::from_str_radix(&digits, 8).ok())
+                .flatten()
+            {
+                result.push(code as char);
+                chars.nth(2);
+                continue;
+            }
+        }
+        result.push(c);
+    }
+    result
+}
+
+// Device path -> link name from a /dev/disk/by-* folder; udev writes
+// spaces in labels as \x20
+fn device_links(folder: &str) -> HashMap<PathBuf, String> {
+    fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|entry| {
+                    let device = fs::canonicalize(entry.path()).ok()?;
+                    let name = entry.file_name().to_string_lossy().replace("\\x20", " ");
+                    Some((device, name))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// USB disks often don't set the removable flag, so the bus counts too
+fn is_removable_device(device: &Path) -> bool {
+    let Some(name) = device.file_name() else {
+        return false;
+    };
+    let Ok(sys_path) = fs::canonicalize(Path::new("/sys/class/block").join(name)) else {
+        return false;
+    };
+    let flag = |path: &Path| {
+        fs::read_to_string(path.join("removable")).is_ok_and(|value| value.trim() == "1")
+    };
+    sys_path.to_string_lossy().contains("/usb")
+        || flag(&sys_path)
+        || sys_path.parent().is_some_and(|disk| flag(disk))
+}
+
+#[cfg(unix)]
+fn total_size(mount_point: &Path) -> u64 {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(c_path) = std::ffi::CString::new(mount_point.as_os_str().as_bytes()) else {
+        return 0;
+    };
+    // SAFETY: statvfs is plain old data, for which all zeroes is a valid value
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is writable
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return 0;
+    }
+    stats.f_blocks as u64 * stats.f_frsize as u64
+}
+
+#[cfg(not(unix))]
+fn total_size(_mount_point: &Path) -> u64 {
+    0
+}
+
+fn display_name(mount_point: &Path) -> String {
+    mount_point
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| mount_point.to_string_lossy().to_string())
+}
+
+// For filesystems without a UUID or serial, e.g. some FAT cards
+fn fallback_id(label: &str, total_size: u64) -> String {
+    format!("{}:{}", label, total_size)
+}
@@ -0,0 +1,594 @@
+// ============================================================================
+// Watcher Module - Keeps the index and UI in sync with filesystem changes
+// ============================================================================
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::backups::BackupDetector;
+use crate::commands::files::create_file_node;
+use crate::events::{self, ChangeKind, FsChange};
+use crate::fileops;
+use crate::learning;
+use crate::libraries::{self, IndexDepth};
+use crate::scan::ScanFilter;
+use crate::staging;
+use crate::storage;
+
+/// How long to collect raw events before emitting one consolidated batch
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Event emitted when a new file has the same contents as one already indexed
+pub const DUPLICATE_ARRIVED_EVENT: &str = "duplicate-arrived";
+
+/// Event emitted once a new file has stopped changing, the point at which
+/// it is safe to organize
+pub const FILE_READY_EVENT: &str = "file-ready";
+
+/// Extensions browsers and download managers write to until a download
+/// completes, then rename away
+const PARTIAL_DOWNLOAD_EXTENSIONS: &[&str] = &["crdownload", "part", "partial", "download", "opdownload"];
+
+const SETTLE_SECONDS_KEY: &str = "watch_settle_seconds";
+/// How long a new file must keep its size and mtime before it counts as complete
+const DEFAULT_SETTLE_SECONDS: u64 = 5;
+/// How often settling files are re-checked when no events arrive
+const SETTLE_POLL: Duration = Duration::from_secs(1);
+
+const DUPLICATE_POLICIES_KEY: &str = "watch_duplicate_policies";
+const WATCHED_PATHS_KEY: &str = "watched_paths";
+
+// What to do automatically when a duplicate arrives in a watched folder
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Keep both copies; the event lets the user decide
+    #[default]
+    Ignore,
+    /// Move the file that just arrived to the staging area
+    TrashNew,
+    /// Keep the new file and move the older copies to the staging area
+    ReplaceOlder,
+}
+
+impl DuplicatePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DuplicatePolicy::Ignore => "ignore",
+            DuplicatePolicy::TrashNew => "trash_new",
+            DuplicatePolicy::ReplaceOlder => "replace_older",
+        }
+    }
+}
+
+// Payload of the "duplicate-arrived" event
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateArrived {
+    pub path: String,
+    /// Indexed files with the same contents
+    pub existing: Vec<String>,
+    pub content_hash: String,
+    pub watched_folder: String,
+    /// Policy that was applied
+    pub policy: DuplicatePolicy,
+    /// Files moved to the staging area by the policy
+    pub trashed: Vec<String>,
+    pub error: Option<String>,
+}
+
+struct WatcherState {
+    watcher: RecommendedWatcher,
+    paths: BTreeSet<PathBuf>,
+}
+
+static WATCHER: Lazy<Mutex<Option<WatcherState>>> = Lazy::new(|| Mutex::new(None));
+
+/// Start watching `path` recursively; the watcher is created on first use
+pub fn watch(app: &AppHandle, path: &Path) -> Result<(), String> {
+    if !path.is_dir() {
+        return Err(format!("Path is not a directory: {}", path.display()));
+    }
+
+    let mut guard = WATCHER.lock();
+    if guard.is_none() {
+        *guard = Some(start(app.clone())?);
+    }
+
+    if let Some(state) = guard.as_mut() {
+        if !state.paths.contains(path) {
+            state
+                .watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+            state.paths.insert(path.to_path_buf());
+            save_watched(&state.paths);
+        }
+    }
+    Ok(())
+}
+
+/// Stop watching `path`; the watcher shuts down once nothing is watched
+pub fn unwatch(path: &Path) -> Result<(), String> {
+    let mut guard = WATCHER.lock();
+    let Some(state) = guard.as_mut() else {
+        return Ok(());
+    };
+
+    if state.paths.remove(path) {
+        state
+            .watcher
+            .unwatch(path)
+            .map_err(|e| format!("Failed to unwatch {}: {}", path.display(), e))?;
+        save_watched(&state.paths);
+    }
+    if state.paths.is_empty() {
+        *guard = None;
+    }
+    Ok(())
+}
+
+/// Stop watching everything
+pub fn unwatch_all() {
+    *WATCHER.lock() = None;
+    save_watched(&BTreeSet::new());
+}
+
+/// Watch the folders that were watched when the app last ran. Folders that
+/// are missing (e.g. on an unplugged drive) stay saved for next time.
+/// Returns the folders that could not be watched.
+pub fn restore(app: &AppHandle) -> Vec<String> {
+    let saved: Vec<String> = storage::get_preference(WATCHED_PATHS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let mut failed = Vec::new();
+    for path in &saved {
+        let folder = Path::new(path);
+        if !folder.is_dir() {
+            continue;
+        }
+        if let Err(e) = watch(app, folder) {
+            eprintln!("Watcher: {}", e);
+            failed.push(path.clone());
+        }
+    }
+    // Each watch() saved only what is watched so far; put the skipped ones back
+    let mut all: BTreeSet<PathBuf> = saved.iter().map(PathBuf::from).collect();
+    if let Some(state) = WATCHER.lock().as_ref() {
+        all.extend(state.paths.iter().cloned());
+    }
+    save_watched(&all);
+    failed
+}
+
+// Remember the watched folders for the next start; the set only changes
+// on user action, so a failure is logged rather than undoing it
+fn save_watched(paths: &BTreeSet<PathBuf>) {
+    let paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let saved = serde_json::to_string(&paths)
+        .map_err(|e| e.to_string())
+        .and_then(|json| storage::set_preference(WATCHED_PATHS_KEY, &json));
+    if let Err(e) = saved {
+        eprintln!("Watcher: failed to save watched folders: {}", e);
+    }
+}
+
+/// Folders currently being watched
+pub fn watched_paths() -> Vec<String> {
+    WATCHER
+        .lock()
+        .as_ref()
+        .map(|state| {
+            state
+                .paths
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn start(app: AppHandle) -> Result<WatcherState, String> {
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            let _ = tx.send(event);
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    // Exits once the watcher (and with it the sender) is dropped
+    std::thread::spawn(move || debounce_loop(app, rx));
+
+    Ok(WatcherState {
+        watcher,
+        paths: BTreeSet::new(),
+    })
+}
+
+// Payload of the "file-ready" event
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReady {
+    pub path: String,
+    pub size: u64,
+}
+
+// New files waiting to stop changing, with the size/mtime last seen and
+// when that was first seen
+#[derive(Default)]
+struct Settling {
+    files: HashMap<PathBuf, ((u64, i64), Instant)>,
+}
+
+impl Settling {
+    fn track(&mut self, changes: &[FsChange]) {
+        for change in changes {
+            let path = PathBuf::from(&change.path);
+            match change.kind {
+                ChangeKind::Created => {
+                    self.files.insert(path, ((0, 0), Instant::now()));
+                }
+                ChangeKind::Removed => {
+                    self.files.remove(&path);
+                }
+                ChangeKind::Renamed | ChangeKind::Modified => {
+                    if let Some(old_path) = &change.old_path {
+                        if let Some(entry) = self.files.remove(Path::new(old_path)) {
+                            self.files.insert(path, entry);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Files whose size and mtime held still for `settle`; they stop being tracked
+    fn take_settled(&mut self, settle: Duration) -> Vec<FileReady> {
+        let mut ready = Vec::new();
+        self.files.retain(|path, (seen, since)| {
+            let Ok(metadata) = std::fs::metadata(fileops::long_path(path)) else {
+                return false;
+            };
+            if !metadata.is_file() {
+                return false;
+            }
+            let current = fileops::fingerprint(&metadata);
+            if current != *seen {
+                *seen = current;
+                *since = Instant::now();
+                return true;
+            }
+            if since.elapsed() < settle {
+                return true;
+            }
+            ready.push(FileReady {
+                path: path.to_string_lossy().to_string(),
+                size: metadata.len(),
+            });
+            false
+        });
+        ready
+    }
+}
+
+/// Whether `path` is a download still in progress (.crdownload, .part, ...)
+pub fn is_partial_download(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| PARTIAL_DOWNLOAD_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Seconds a new file has to stay unchanged before it is reported ready
+pub fn settle_seconds() -> u64 {
+    storage::get_preference(SETTLE_SECONDS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SETTLE_SECONDS)
+}
+
+pub fn set_settle_seconds(seconds: u64) -> Result<(), String> {
+    storage::set_preference(SETTLE_SECONDS_KEY, &seconds.to_string())
+}
+
+// Downloads in progress never reach the index or the UI; the rename to
+// the final name counts as the file arriving
+fn without_partial_downloads(changes: Vec<FsChange>) -> Vec<FsChange> {
+    changes
+        .into_iter()
+        .filter(|change| !is_partial_download(Path::new(&change.path)))
+        .map(|change| match &change.old_path {
+            Some(old_path) if is_partial_download(Path::new(old_path)) => FsChange::created(Path::new(&change.path)),
+            _ => change,
+        })
+        .collect()
+}
+
+fn debounce_loop(app: AppHandle, rx: Receiver<Event>) {
+    let mut settling = Settling::default();
+    let mut settle = Duration::from_secs(settle_seconds());
+
+    loop {
+        // Wake up regularly while files are settling, otherwise sleep until an event
+        let first = if settling.files.is_empty() {
+            match rx.recv() {
+                Ok(event) => Some(event),
+                Err(_) => break,
+            }
+        } else {
+            match rx.recv_timeout(SETTLE_POLL) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        };
+        if let Some(first) = first {
+            if let Some(changes) = collect_batch(&rx, first) {
+                settling.track(&changes);
+                update_index(&changes);
+                learning::observe(&app, &changes);
+                events::emit_fs_changed(&app, changes);
+                settle = Duration::from_secs(settle_seconds());
+            }
+        }
+
+        let ready = settling.take_settled(settle);
+        if !ready.is_empty() {
+            check_duplicates(&app, &ready);
+            for file in ready {
+                let _ = app.emit(FILE_READY_EVENT, file);
+            }
+        }
+    }
+}
+
+// Gather the events of one debounce window into consolidated, filtered changes
+fn collect_batch(rx: &Receiver<Event>, first: Event) -> Option<Vec<FsChange>> {
+    let mut changes = changes_from_event(first);
+    let deadline = Instant::now() + DEBOUNCE;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(event) => changes.extend(changes_from_event(event)),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let filter = match ScanFilter::new(Path::new(""), false) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprintln!("Watcher: {}", e);
+            return None;
+        }
+    };
+    let changes = events::coalesce(changes)
+        .into_iter()
+        .filter(|change| filter.allows_with_ancestors(Path::new(&change.path)))
+        .collect();
+    Some(without_partial_downloads(changes))
+}
+
+fn changes_from_event(event: Event) -> Vec<FsChange> {
+    match event.kind {
+        EventKind::Create(_) => event.paths.iter().map(|p| FsChange::created(p)).collect(),
+        EventKind::Remove(_) => event.paths.iter().map(|p| FsChange::removed(p)).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            vec![FsChange::renamed(&event.paths[0], &event.paths[1])]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            event.paths.iter().map(|p| FsChange::removed(p)).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            event.paths.iter().map(|p| FsChange::created(p)).collect()
+        }
+        EventKind::Modify(_) => event.paths.iter().map(|p| FsChange::modified(p)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Mirror a batch of changes into the files table
+fn update_index(changes: &[FsChange]) {
+    let mut nodes = Vec::new();
+
+    for change in changes {
+        if let Some(old_path) = &change.old_path {
+            if let Err(e) = storage::rename_path_records(old_path, &change.path) {
+                eprintln!("Watcher: failed to carry records to {}: {}", change.path, e);
+            }
+            if let Err(e) = storage::remove_indexed_path(old_path) {
+                eprintln!("Watcher: failed to unindex {}: {}", old_path, e);
+            }
+        }
+
+        match change.kind {
+            ChangeKind::Removed => {
+                if let Err(e) = storage::remove_indexed_path(&change.path) {
+                    eprintln!("Watcher: failed to unindex {}: {}", change.path, e);
+                }
+            }
+            ChangeKind::Created | ChangeKind::Modified | ChangeKind::Renamed => {
+                if let Ok(node) = create_file_node(&PathBuf::from(&change.path)) {
+                    nodes.push(node);
+                }
+            }
+        }
+    }
+
+    if let Err(e) = storage::index_file_nodes(&nodes) {
+        eprintln!("Watcher: failed to index changes: {}", e);
+    }
+}
+
+/// Duplicate policy of every watched folder that has one, by folder
+pub fn duplicate_policies() -> BTreeMap<String, DuplicatePolicy> {
+    storage::get_preference(DUPLICATE_POLICIES_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Set the duplicate policy of a watched folder; `Ignore` removes the entry
+pub fn set_duplicate_policy(folder: &Path, policy: DuplicatePolicy) -> Result<(), String> {
+    let mut policies = duplicate_policies();
+    let key = folder.to_string_lossy().to_string();
+    if policy == DuplicatePolicy::Ignore {
+        policies.remove(&key);
+    } else {
+        policies.insert(key, policy);
+    }
+    let json = serde_json::to_string(&policies).map_err(|e| format!("Failed to serialize policies: {}", e))?;
+    storage::set_preference(DUPLICATE_POLICIES_KEY, &json)
+}
+
+// Look for files among the settled arrivals whose contents are already in
+// the library, apply the watched folder's policy and tell the UI. Backup
+// sets are never touched: files a backup tool writes into a watched folder
+// are skipped, and copies inside backups don't count as existing.
+fn check_duplicates(app: &AppHandle, arrivals: &[FileReady]) {
+    let watched: Vec<PathBuf> = WATCHER
+        .lock()
+        .as_ref()
+        .map(|state| state.paths.iter().cloned().collect())
+        .unwrap_or_default();
+    let policies = duplicate_policies();
+    let libraries = libraries::list();
+    let mut detector = BackupDetector::default();
+
+    for change in arrivals {
+        let path = Path::new(&change.path);
+        if detector.backup_of(path).is_some() {
+            continue;
+        }
+        // Every empty file has the same hash, so an empty file duplicates nothing
+        if fs::metadata(fileops::long_path(path)).map_or(true, |metadata| metadata.len() == 0) {
+            continue;
+        }
+        // Libraries kept to names only are never read
+        if !libraries::policy_for(path, &libraries).allows(IndexDepth::Hashes) {
+            continue;
+        }
+        // Online-only files can't be hashed without downloading them; skip them
+        let Ok((content_hash, _)) = fileops::cached_file_hash(path) else {
+            continue;
+        };
+        let existing: Vec<String> = match storage::files_with_hash(&content_hash) {
+            Ok(files) => files
+                .into_iter()
+                .map(|file| file.path)
+                .filter(|other| other != &change.path && fileops::long_path(Path::new(other)).is_file())
+                .filter(|other| detector.backup_of(Path::new(other)).is_none())
+                .collect(),
+            Err(e) => {
+                eprintln!("Watcher: failed to look up duplicates of {}: {}", change.path, e);
+                continue;
+            }
+        };
+        if existing.is_empty() {
+            continue;
+        }
+
+        // The innermost watched folder decides
+        let watched_folder = watched
+            .iter()
+            .filter(|folder| path.starts_with(folder))
+            .max_by_key(|folder| folder.components().count())
+            .map(|folder| folder.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let policy = policies.get(&watched_folder).copied().unwrap_or_default();
+
+        let mut arrived = DuplicateArrived {
+            path: change.path.clone(),
+            existing,
+            content_hash,
+            watched_folder,
+            policy,
+            trashed: Vec::new(),
+            error: None,
+        };
+        if let Err(e) = apply_duplicate_policy(&mut arrived) {
+            eprintln!("Watcher: duplicate policy failed for {}: {}", arrived.path, e);
+            arrived.error = Some(e);
+        }
+        let _ = app.emit(DUPLICATE_ARRIVED_EVENT, arrived);
+    }
+}
+
+// Stage whichever copies the policy gives up, logging each as a "stage"
+// change, so they can be restored until the staging area is purged. Only
+// copies in the same library as the new file (or its watched folder outside
+// libraries) are considered, each re-hashed first in case it changed since
+// it was indexed, and ReplaceOlder only replaces copies that really are
+// older than the new file.
+fn apply_duplicate_policy(arrived: &mut DuplicateArrived) -> Result<(), String> {
+    if arrived.policy == DuplicatePolicy::Ignore || arrived.watched_folder.is_empty() {
+        return Ok(());
+    }
+    let scope = libraries::library_of(Path::new(&arrived.path), &libraries::list())
+        .map(|library| PathBuf::from(&library.root))
+        .unwrap_or_else(|| PathBuf::from(&arrived.watched_folder));
+    let copies: Vec<String> = arrived
+        .existing
+        .iter()
+        .filter(|other| Path::new(other).starts_with(&scope))
+        .filter(|other| fileops::hash_file_contents(Path::new(other)).is_ok_and(|hash| hash == arrived.content_hash))
+        .cloned()
+        .collect();
+
+    let victims = match arrived.policy {
+        DuplicatePolicy::Ignore => return Ok(()),
+        DuplicatePolicy::TrashNew if copies.is_empty() => return Ok(()),
+        DuplicatePolicy::TrashNew => vec![arrived.path.clone()],
+        DuplicatePolicy::ReplaceOlder => {
+            let Some(arrived_at) = modified(&arrived.path) else {
+                return Ok(());
+            };
+            copies
+                .iter()
+                .filter(|other| modified(other).is_some_and(|mtime| mtime < arrived_at))
+                .cloned()
+                .collect()
+        }
+    };
+    if victims.is_empty() {
+        return Ok(());
+    }
+    let protected = storage::protected_paths()?;
+    if let Some(locked) = victims.iter().find(|path| fileops::is_protected(Path::new(path), &protected)) {
+        return Err(format!("Path is protected: {}", locked));
+    }
+
+    let batch_id = storage::create_history_batch(
+        "Duplicate arrived",
+        &format!("{} ({})", arrived.path, arrived.policy.as_str()),
+    )?;
+    let kept = match arrived.policy {
+        DuplicatePolicy::TrashNew => copies[0].clone(),
+        _ => arrived.path.clone(),
+    };
+    for victim in victims {
+        staging::stage(Path::new(&victim), &batch_id)
+            .map_err(|e| format!("{} (kept {})", e, kept))?;
+        arrived.trashed.push(victim);
+    }
+    Ok(())
+}
+
+// Modification time of `path`, in the index's fingerprint units
+fn modified(path: &str) -> Option<i64> {
+    fs::metadata(fileops::long_path(Path::new(path)))
+        .ok()
+        .map(|metadata| fileops::fingerprint(&metadata).1)
+}
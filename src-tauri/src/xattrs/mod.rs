@@ -0,0 +1,635 @@
+// ============================================================================
+// Extended Attributes Module - Metadata that lives beside a file's contents
+// ============================================================================
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Finder keeps a file's tags here, as a property list of "Name\nColor"
+#[cfg(target_os = "macos")]
+const FINDER_TAGS_ATTRIBUTE: &str = "com.apple.metadata:_kMDItemUserTags";
+
+/// Dolphin and other freedesktop file managers keep comma-separated tags here
+#[cfg(target_os = "linux")]
+const XDG_TAGS_ATTRIBUTE: &str = "user.xdg.tags";
+
+/// Highest Finder label color: 1 gray, 2 green, 3 purple, 4 blue, 5 yellow,
+/// 6 red, 7 orange
+const MAX_TAG_COLOR: u8 = 7;
+
+// A label the user put on a file in their file manager
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileTag {
+    pub name: String,
+    /// Finder label color, 0 for none; other file managers keep no color
+    #[serde(default)]
+    pub color: u8,
+}
+
+/// Copy what a plain copy of the contents leaves behind from `source` to
+/// `target`: extended attributes (Finder tags and comments, quarantine
+/// flags, xdg tags) and timestamps on Unix, alternate data streams such as
+/// the Zone.Identifier that marks downloads on Windows. Attributes the
+/// target refuses, such as security labels without the privileges to set
+/// them, are skipped.
+pub fn copy_metadata(source: &Path, target: &Path) -> Result<(), String> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        for name in list(source)? {
+            if let Some(value) = get(source, &name)? {
+                let _ = set(target, &name, &value);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    copy_times(source, target)?;
+
+    #[cfg(windows)]
+    copy_streams(source, target)?;
+
+    #[cfg(not(any(unix, windows)))]
+    let _ = (source, target);
+
+    Ok(())
+}
+
+// fs::copy leaves the copy's times at "now" on Unix, which would move every
+// file copied across volumes to today's date folder
+#[cfg(unix)]
+fn copy_times(source: &Path, target: &Path) -> Result<(), String> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata =
+        std::fs::metadata(source).map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let c_path = c_string(target)?;
+    let times = [
+        libc::timespec {
+            tv_sec: metadata.atime() as libc::time_t,
+            tv_nsec: metadata.atime_nsec() as _,
+        },
+        libc::timespec {
+            tv_sec: metadata.mtime() as libc::time_t,
+            tv_nsec: metadata.mtime_nsec() as _,
+        },
+    ];
+    // SAFETY: c_path is a valid NUL-terminated string and times holds the
+    // two entries utimensat reads
+    if unsafe { libc::utimensat(libc::AT_FDCWD, c_path.as_ptr(), times.as_ptr(), 0) } != 0 {
+        return Err(format!(
+            "Failed to copy timestamps: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+// Alternate data streams: where a download came from (Zone.Identifier),
+// thumbnails and summaries older Windows versions kept, tags from tools
+// that store them beside the file. fs::copy keeps them, a throttled copy
+// doesn't, so they are copied either way.
+#[cfg(windows)]
+fn copy_streams(source: &Path, target: &Path) -> Result<(), String> {
+    for stream in alternate_streams(source) {
+        let data = std::fs::read(stream_path(source, &stream.name))
+            .map_err(|e| format!("Failed to read stream {}: {}", stream.name, e))?;
+        std::fs::write(stream_path(target, &stream.name), data)
+            .map_err(|e| format!("Failed to copy stream {}: {}", stream.name, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn stream_path(path: &Path, stream: &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(":");
+    name.push(stream);
+    std::path::PathBuf::from(name)
+}
+
+#[cfg(windows)]
+mod ffi {
+    // WIN32_FIND_STREAM_DATA: a LARGE_INTEGER size, then the name
+    #[repr(C)]
+    pub struct FindStreamData {
+        pub stream_size: i64,
+        pub stream_name: [u16; 260 + 36],
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn FindFirstStreamW(
+            file_name: *const u16,
+            info_level: i32,
+            find_stream_data: *mut FindStreamData,
+            flags: u32,
+        ) -> isize;
+        pub fn FindNextStreamW(find_stream: isize, find_stream_data: *mut FindStreamData) -> i32;
+        pub fn FindClose(find_file: isize) -> i32;
+        pub fn GetVolumePathNameW(
+            file_name: *const u16,
+            volume_path_name: *mut u16,
+            buffer_length: u32,
+        ) -> i32;
+        pub fn GetVolumeInformationW(
+            root_path_name: *const u16,
+            volume_name_buffer: *mut u16,
+            volume_name_size: u32,
+            volume_serial_number: *mut u32,
+            maximum_component_length: *mut u32,
+            file_system_flags: *mut u32,
+            file_system_name_buffer: *mut u16,
+            file_system_name_size: u32,
+        ) -> i32;
+    }
+
+    pub const INVALID_HANDLE_VALUE: isize = -1;
+    pub const FIND_STREAM_INFO_STANDARD: i32 = 0;
+    pub const FILE_NAMED_STREAMS: u32 = 0x0004_0000;
+
+    pub fn wide(path: &std::path::Path) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt;
+        path.as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+}
+
+/// A named stream stored beside a file's contents on NTFS
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DataStream {
+    /// Name without the ":$DATA" type, e.g. "Zone.Identifier"
+    pub name: String,
+    pub size: u64,
+}
+
+/// The alternate data streams of `path`; none on other platforms, where
+/// the same information lives in extended attributes
+pub fn alternate_streams(path: &Path) -> Vec<DataStream> {
+    #[cfg(windows)]
+    {
+        let wide_path = ffi::wide(path);
+        // SAFETY: all zeroes is a valid FindStreamData
+        let mut data: ffi::FindStreamData = unsafe { std::mem::zeroed() };
+        // SAFETY: wide_path is NUL-terminated and data is writable
+        let handle = unsafe {
+            ffi::FindFirstStreamW(
+                wide_path.as_ptr(),
+                ffi::FIND_STREAM_INFO_STANDARD,
+                &mut data,
+                0,
+            )
+        };
+        if handle == ffi::INVALID_HANDLE_VALUE {
+            return Vec::new();
+        }
+        let mut streams = Vec::new();
+        loop {
+            let length = data
+                .stream_name
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(data.stream_name.len());
+            let full_name = String::from_utf16_lossy(&data.stream_name[..length]);
+            // "::$DATA" is the file's contents; the rest read ":name:$DATA"
+            let name = full_name.trim_start_matches(':').trim_end_matches(":$DATA");
+            if !name.is_empty() && !full_name.starts_with("::") {
+                streams.push(DataStream {
+                    name: name.to_string(),
+                    size: data.stream_size.max(0) as u64,
+                });
+            }
+            // SAFETY: handle came from FindFirstStreamW and data is writable
+            if unsafe { ffi::FindNextStreamW(handle, &mut data) } == 0 {
+                break;
+            }
+        }
+        // SAFETY: handle came from FindFirstStreamW and is closed once
+        unsafe { ffi::FindClose(handle) };
+        streams
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = path;
+        Vec::new()
+    }
+}
+
+/// Whether the volume `path` is on (or would be created on) can hold
+/// alternate data streams: NTFS and ReFS can, FAT, exFAT and most network
+/// shares can't. Only Windows has the concept, so elsewhere this is true.
+pub fn supports_streams(path: &Path) -> bool {
+    #[cfg(windows)]
+    {
+        let Some(existing) = path.ancestors().find(|folder| folder.exists()) else {
+            return true;
+        };
+        let wide_path = ffi::wide(existing);
+        let mut root = [0u16; 1024];
+        // SAFETY: wide_path is NUL-terminated and root is writable for its length
+        if unsafe {
+            ffi::GetVolumePathNameW(wide_path.as_ptr(), root.as_mut_ptr(), root.len() as u32)
+        } == 0
+        {
+            return true;
+        }
+        let mut flags = 0u32;
+        // SAFETY: root holds a NUL-terminated path; unused buffers are null
+        // with zero sizes, and flags is writable
+        let ok = unsafe {
+            ffi::GetVolumeInformationW(
+                root.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut flags,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        // Unknown volumes aren't reported as lossy
+        ok == 0 || flags & ffi::FILE_NAMED_STREAMS != 0
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = path;
+        true
+    }
+}
+
+/// Tags on `path`: Finder tags on macOS, xdg tags on Linux
+pub fn tags(path: &Path) -> Result<Vec<FileTag>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(get(path, FINDER_TAGS_ATTRIBUTE)?
+            .map(|plist| {
+                parse_plist_strings(&plist)
+                    .iter()
+                    .map(|tag| finder_tag(tag))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Ok(get(path, XDG_TAGS_ATTRIBUTE)?
+            .map(|value| {
+                String::from_utf8_lossy(&value)
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| FileTag {
+                        name: name.to_string(),
+                        color: 0,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = path;
+        Err("File tags are not supported on this platform".to_string())
+    }
+}
+
+/// Replace the tags on `path`; an empty list removes them
+pub fn set_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+    for tag in tags {
+        if tag.name.trim().is_empty() || tag.name.contains(['\n', ',']) {
+            return Err(format!("Invalid tag name: {:?}", tag.name));
+        }
+        if tag.color > MAX_TAG_COLOR {
+            return Err(format!("Invalid tag color: {}", tag.color));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if tags.is_empty() {
+            return remove(path, FINDER_TAGS_ATTRIBUTE);
+        }
+        let names: Vec<String> = tags
+            .iter()
+            .map(|tag| match tag.color {
+                0 => tag.name.clone(),
+                color => format!("{}\n{}", tag.name, color),
+            })
+            .collect();
+        set(path, FINDER_TAGS_ATTRIBUTE, xml_plist(&names).as_bytes())
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if tags.is_empty() {
+            return remove(path, XDG_TAGS_ATTRIBUTE);
+        }
+        let names: Vec<&str> = tags.iter().map(|tag| tag.name.trim()).collect();
+        set(path, XDG_TAGS_ATTRIBUTE, names.join(",").as_bytes())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = path;
+        Err("File tags are not supported on this platform".to_string())
+    }
+}
+
+// "Name\n6" as Finder stores a tag with its color
+#[cfg(target_os = "macos")]
+fn finder_tag(tag: &str) -> FileTag {
+    match tag.rsplit_once('\n') {
+        Some((name, color)) => FileTag {
+            name: name.to_string(),
+            color: color.trim().parse().unwrap_or(0),
+        },
+        None => FileTag {
+            name: tag.to_string(),
+            color: 0,
+        },
+    }
+}
+
+// Finder reads any property list format, so tags are written as XML
+#[cfg(target_os = "macos")]
+fn xml_plist(strings: &[String]) -> String {
+    let mut plist = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">\n<array>\n",
+    );
+    for value in strings {
+        let escaped = value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        plist.push_str(&format!("\t<string>{}</string>\n", escaped));
+    }
+    plist.push_str("</array>\n</plist>\n");
+    plist
+}
+
+/// Strings in a property list holding an array of strings, binary as
+/// Finder writes it or XML as `set_tags` does; anything else gives none
+#[cfg(target_os = "macos")]
+fn parse_plist_strings(data: &[u8]) -> Vec<String> {
+    if data.starts_with(b"bplist00") {
+        return parse_binary_plist_strings(data).unwrap_or_default();
+    }
+    let text = String::from_utf8_lossy(data);
+    text.split("<string>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</string>"))
+        .map(|(value, _)| {
+            value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+// The subset of the binary property list format Finder uses for tags: a
+// top-level array of ASCII or UTF-16 strings
+#[cfg(target_os = "macos")]
+fn parse_binary_plist_strings(data: &[u8]) -> Option<Vec<String>> {
+    let trailer = data.get(data.len().checked_sub(32)?..)?;
+    let offset_size = trailer[6] as usize;
+    let ref_size = trailer[7] as usize;
+    let object_count = read_be(&trailer[8..16]) as usize;
+    let top_object = read_be(&trailer[16..24]) as usize;
+    let offset_table = read_be(&trailer[24..32]) as usize;
+
+    let object_offset = |object: usize| -> Option<usize> {
+        if object >= object_count {
+            return None;
+        }
+        let start = offset_table.checked_add(object.checked_mul(offset_size)?)?;
+        Some(read_be(data.get(start..start.checked_add(offset_size)?)?) as usize)
+    };
+    // Object lengths up to 14 sit in the marker; longer ones follow it as
+    // an integer object
+    let length = |offset: usize| -> Option<(usize, usize)> {
+        let marker = *data.get(offset)?;
+        if marker & 0x0F != 0x0F {
+            return Some(((marker & 0x0F) as usize, offset + 1));
+        }
+        let int_marker = *data.get(offset + 1)?;
+        if int_marker & 0xF0 != 0x10 {
+            return None;
+        }
+        let size = 1usize << (int_marker & 0x0F);
+        let start = offset + 2;
+        Some((
+            read_be(data.get(start..start + size)?) as usize,
+            start + size,
+        ))
+    };
+
+    let array = object_offset(top_object)?;
+    if *data.get(array)? & 0xF0 != 0xA0 {
+        return None;
+    }
+    let (count, refs) = length(array)?;
+    (0..count)
+        .map(|i| {
+            let start = refs + i * ref_size;
+            let object = read_be(data.get(start..start + ref_size)?) as usize;
+            let offset = object_offset(object)?;
+            let (count, start) = length(offset)?;
+            match *data.get(offset)? & 0xF0 {
+                0x50 => Some(String::from_utf8_lossy(data.get(start..start + count)?).to_string()),
+                0x60 => {
+                    let units: Vec<u16> = data
+                        .get(start..start + count * 2)?
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect();
+                    Some(String::from_utf16_lossy(&units))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn read_be(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+#[cfg(unix)]
+fn c_string(value: impl AsRef<std::ffi::OsStr>) -> Result<std::ffi::CString, String> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(value.as_ref().as_bytes())
+        .map_err(|e| format!("Invalid path or name: {}", e))
+}
+
+/// Names of the extended attributes on `path`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn list(path: &Path) -> Result<Vec<String>, String> {
+    let c_path = c_string(path)?;
+    // SAFETY: c_path is a valid NUL-terminated string; a null buffer of
+    // size 0 asks for the size needed
+    #[cfg(target_os = "macos")]
+    let size = unsafe { libc::listxattr(c_path.as_ptr(), std::ptr::null_mut(), 0, 0) };
+    // SAFETY: as above
+    #[cfg(target_os = "linux")]
+    let size = unsafe { libc::listxattr(c_path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut names = vec![0u8; size as usize];
+    // SAFETY: names is writable for its whole length
+    #[cfg(target_os = "macos")]
+    let size =
+        unsafe { libc::listxattr(c_path.as_ptr(), names.as_mut_ptr().cast(), names.len(), 0) };
+    // SAFETY: as above
+    #[cfg(target_os = "linux")]
+    let size = unsafe { libc::listxattr(c_path.as_ptr(), names.as_mut_ptr().cast(), names.len()) };
+    if size < 0 {
+        return Err(format!(
+            "Failed to list extended attributes: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    names.truncate(size as usize);
+    Ok(names
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .collect())
+}
+
+/// Value of the extended attribute `name` on `path`, if it is set
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn get(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let c_path = c_string(path)?;
+    let c_name = c_string(name)?;
+    let read = |buffer: &mut [u8]| {
+        let pointer = if buffer.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            buffer.as_mut_ptr().cast()
+        };
+        // SAFETY: c_path and c_name are valid NUL-terminated strings and
+        // pointer is null or writable for buffer.len() bytes
+        #[cfg(target_os = "macos")]
+        let size = unsafe {
+            libc::getxattr(
+                c_path.as_ptr(),
+                c_name.as_ptr(),
+                pointer,
+                buffer.len(),
+                0,
+                0,
+            )
+        };
+        // SAFETY: as above
+        #[cfg(target_os = "linux")]
+        let size =
+            unsafe { libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), pointer, buffer.len()) };
+        size
+    };
+
+    let size = read(&mut []);
+    if size < 0 {
+        let error = std::io::Error::last_os_error();
+        #[cfg(target_os = "macos")]
+        let missing = error.raw_os_error() == Some(libc::ENOATTR);
+        #[cfg(target_os = "linux")]
+        let missing = error.raw_os_error() == Some(libc::ENODATA);
+        if missing {
+            return Ok(None);
+        }
+        return Err(format!(
+            "Failed to read extended attribute {}: {}",
+            name, error
+        ));
+    }
+    let mut value = vec![0u8; size as usize];
+    let size = read(&mut value);
+    if size < 0 {
+        return Err(format!(
+            "Failed to read extended attribute {}: {}",
+            name,
+            std::io::Error::last_os_error()
+        ));
+    }
+    value.truncate(size as usize);
+    Ok(Some(value))
+}
+
+/// Set the extended attribute `name` on `path`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn set(path: &Path, name: &str, value: &[u8]) -> Result<(), String> {
+    let c_path = c_string(path)?;
+    let c_name = c_string(name)?;
+    // SAFETY: c_path and c_name are valid NUL-terminated strings and value
+    // outlives the call
+    #[cfg(target_os = "macos")]
+    let result = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+            0,
+        )
+    };
+    // SAFETY: as above
+    #[cfg(target_os = "linux")]
+    let result = unsafe {
+        libc::setxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+        )
+    };
+    if result != 0 {
+        return Err(format!(
+            "Failed to set extended attribute {}: {}",
+            name,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// Remove the extended attribute `name` from `path`; removing one that
+/// isn't set is not an error
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn remove(path: &Path, name: &str) -> Result<(), String> {
+    let c_path = c_string(path)?;
+    let c_name = c_string(name)?;
+    // SAFETY: c_path and c_name are valid NUL-terminated strings
+    #[cfg(target_os = "macos")]
+    let result = unsafe { libc::removexattr(c_path.as_ptr(), c_name.as_ptr(), 0) };
+    // SAFETY: as above
+    #[cfg(target_os = "linux")]
+    let result = unsafe { libc::removexattr(c_path.as_ptr(), c_name.as_ptr()) };
+    if result != 0 {
+        let error = std::io::Error::last_os_error();
+        #[cfg(target_os = "macos")]
+        let missing = error.raw_os_error() == Some(libc::ENOATTR);
+        #[cfg(target_os = "linux")]
+        let missing = error.raw_os_error() == Some(libc::ENODATA);
+        if !missing {
+            return Err(format!(
+                "Failed to remove extended attribute {}: {}",
+                name, error
+            ));
+        }
+    }
+    Ok(())
+}
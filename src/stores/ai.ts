@@ -517,8 +517,8 @@ async function generateResponse(prompt: string): Promise<string> {
     }
 
     try {
-      const response = await invoke('generate_response', { prompt });
-      return response as string;
+      const response = (await invoke('generate_response', { prompt })) as { text: string; truncated: boolean };
+      return response.text;
     } catch (error) {
       console.error('Generate response error:', error);
       throw error;